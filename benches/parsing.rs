@@ -0,0 +1,152 @@
+//! Benchmarks for the hot paths of indexers: event parsing, model conversion and unit parsing.
+//!
+//! Run with `cargo bench`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use gevulot_rs::{
+    models::{ByteUnit, CoreUnit, Task, TimeUnit},
+    proto::gevulot::gevulot,
+    Event, GevulotEvent, Height,
+};
+
+fn event(kind: &str, attributes: &[(&str, &str)]) -> Event {
+    Event::new(
+        kind,
+        attributes
+            .iter()
+            .map(|(key, value)| (*key, *value, true))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn bench_event_parsing(c: &mut Criterion) {
+    let height = Height::from(1000u32);
+    let create_pin = event(
+        "create-pin",
+        &[
+            ("cid", "QmYwMXeEc3Z64vqcPXx8p8Y8Y5tE9Y5sYW42FZ1U87Y"),
+            ("id", "pin-1"),
+            ("creator", "gvlt1creator"),
+            ("assigned-workers", "worker1,worker2,worker3"),
+            ("retention-period", "86400"),
+            (
+                "fallback-urls",
+                "https://example.com/a,https://example.com/b",
+            ),
+            ("msg_index", "0"),
+        ],
+    );
+    let finish_task = event(
+        "finish-task",
+        &[
+            ("task-id", "task-1"),
+            ("worker-id", "worker-1"),
+            ("creator", "gvlt1creator"),
+            ("msg_index", "0"),
+        ],
+    );
+    let transfer = event(
+        "transfer",
+        &[
+            ("recipient", "gvlt1recipient"),
+            ("sender", "gvlt1sender"),
+            ("amount", "100ucredit"),
+        ],
+    );
+
+    let mut group = c.benchmark_group("event_parsing");
+    group.bench_function("create_pin", |b| {
+        b.iter(|| GevulotEvent::from_cosmos(black_box(&create_pin), height))
+    });
+    group.bench_function("finish_task", |b| {
+        b.iter(|| GevulotEvent::from_cosmos(black_box(&finish_task), height))
+    });
+    group.bench_function("unknown_kind", |b| {
+        b.iter(|| GevulotEvent::from_cosmos(black_box(&transfer), height))
+    });
+    group.finish();
+}
+
+fn proto_task() -> gevulot::Task {
+    gevulot::Task {
+        metadata: Some(gevulot::Metadata {
+            id: "task-1".to_string(),
+            creator: "gvlt1creator".to_string(),
+            name: "prover".to_string(),
+            desc: "Generates a proof".to_string(),
+            tags: vec!["zk".to_string(), "prover".to_string()],
+            labels: vec![gevulot::Label {
+                key: "env".to_string(),
+                value: "prod".to_string(),
+            }],
+        }),
+        spec: Some(gevulot::TaskSpec {
+            image: "ghcr.io/example/prover:latest".to_string(),
+            command: vec!["prove".to_string()],
+            args: vec!["--input".to_string(), "/input/data".to_string()],
+            input_contexts: vec![gevulot::InputContext {
+                source: "QmYwMXeEc3Z64vqcPXx8p8Y8Y5tE9Y5sYW42FZ1U87Y".to_string(),
+                target: "/input/data".to_string(),
+            }],
+            cpus: 4000,
+            gpus: 1000,
+            memory: 8 * 1024 * 1024 * 1024,
+            time: 3600,
+            ..Default::default()
+        }),
+        status: Some(gevulot::TaskStatus {
+            state: 3,
+            assigned_workers: vec!["worker-1".to_string()],
+            active_worker: "worker-1".to_string(),
+            output_contexts: vec!["QmOutput".to_string()],
+            ..Default::default()
+        }),
+    }
+}
+
+fn bench_model_conversion(c: &mut Criterion) {
+    let task = proto_task();
+    c.bench_function("model_conversion/task", |b| {
+        b.iter_batched(
+            || task.clone(),
+            Task::from,
+            criterion::BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_unit_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("unit_parsing");
+    group.bench_function("byte_unit", |b| {
+        b.iter(|| {
+            black_box("1.5GiB")
+                .parse::<ByteUnit>()
+                .and_then(|unit| unit.bytes())
+        })
+    });
+    group.bench_function("core_unit", |b| {
+        b.iter(|| {
+            black_box("500mcpu")
+                .parse::<CoreUnit>()
+                .and_then(|unit| unit.millicores())
+        })
+    });
+    group.bench_function("time_unit", |b| {
+        b.iter(|| {
+            black_box("1h30m")
+                .parse::<TimeUnit>()
+                .and_then(|unit| unit.seconds())
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_event_parsing,
+    bench_model_conversion,
+    bench_unit_parsing
+);
+criterion_main!(benches);
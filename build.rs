@@ -2,10 +2,64 @@ use prost_build::Config;
 use std::io::Read;
 use std::{env::set_current_dir, path::PathBuf};
 
+// Gevulot types encoded as proto3 JSON, with the types they contain
+#[cfg(feature = "tx-json")]
+const JSON_TYPES: &[&str] = &[
+    "MsgCreateWorker",
+    "MsgUpdateWorker",
+    "MsgDeleteWorker",
+    "MsgAnnounceWorkerExit",
+    "MsgCreateTask",
+    "MsgDeleteTask",
+    "MsgRescheduleTask",
+    "MsgAcceptTask",
+    "MsgDeclineTask",
+    "MsgFinishTask",
+    "MsgCreateWorkflow",
+    "MsgDeleteWorkflow",
+    "MsgCreateProof",
+    "MsgDeleteProof",
+    "MsgCreatePin",
+    "MsgDeletePin",
+    "MsgAckPin",
+    "MsgUpdateParams",
+    "MsgSudoFreezeAccount",
+    "MsgSudoDeleteWorker",
+    "MsgSudoDeletePin",
+    "MsgSudoDeleteTask",
+    "GenesisState",
+    "Params",
+    "Metadata",
+    "Label",
+    "TaskEnv",
+    "InputContext",
+    "OutputContext",
+    "Worker",
+    "WorkerSpec",
+    "WorkerStatus",
+    "Task",
+    "TaskSpec",
+    "TaskStatus",
+    "Workflow",
+    "WorkflowSpec",
+    "WorkflowStatus",
+    "Proof",
+    "ProofSpec",
+    "ProofStatus",
+    "Pin",
+    "PinSpec",
+    "PinStatus",
+    "PinAck",
+];
+
 fn main() {
     let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
     let mut config = Config::new();
     config.enable_type_names();
+    #[cfg(feature = "tx-json")]
+    let descriptor_path = out_dir.join("proto_descriptor.bin");
+    #[cfg(feature = "tx-json")]
+    config.file_descriptor_set_path(&descriptor_path);
 
     set_current_dir("./").unwrap();
 
@@ -30,15 +84,39 @@ fn main() {
 
         let includes = vec!["./proto", "./buf_exported"];
 
-        let tonic_builder = tonic_build::configure().build_client(true).out_dir(out_dir);
+        let tonic_builder = tonic_build::configure()
+            .build_client(cfg!(feature = "query-only"))
+            .build_server(cfg!(feature = "query-only"))
+            .out_dir(out_dir);
         tonic_builder
             .compile_with_config(config, &protos, &includes)
             .unwrap();
     } else {
         tonic_buf_build::compile_from_buf_workspace(
-            tonic_build::configure().build_client(true).out_dir(out_dir),
+            tonic_build::configure()
+                .build_client(cfg!(feature = "query-only"))
+                .build_server(cfg!(feature = "query-only"))
+                .out_dir(out_dir),
             Some(config),
         )
         .unwrap();
     }
+
+    #[cfg(feature = "tx-json")]
+    {
+        // Proto3 JSON for the Gevulot messages and state, used to export and import transactions
+        // and to read genesis files. The query types are left out, their pagination types come
+        // from the Cosmos SDK protos and have no JSON encoding.
+        let types = JSON_TYPES
+            .iter()
+            .map(|name| format!(".gevulot.gevulot.{}", name))
+            .collect::<Vec<_>>();
+        let descriptor_set = std::fs::read(&descriptor_path).unwrap();
+        pbjson_build::Builder::new()
+            .register_descriptors(&descriptor_set)
+            .unwrap()
+            .preserve_proto_field_names()
+            .build(&types)
+            .unwrap();
+    }
 }
@@ -0,0 +1,362 @@
+//! Alerting rules evaluated on snapshots of the chain state.
+//!
+//! An [`AlertEngine`] periodically takes a [`Snapshot`] of the tasks and pins on the chain,
+//! compares it with the previous ones and passes an [`Alert`] to a callback whenever a
+//! [`Rule`] starts to be violated:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use gevulot_rs::{alerts::{AlertEngine, Rule}, GevulotClient};
+//! # fn run(client: &GevulotClient) {
+//! let engine = AlertEngine::new(vec![
+//!     Rule::task_stuck_pending(Duration::from_secs(600)),
+//!     Rule::worker_unacked_pins(5),
+//! ]);
+//! let handle = engine.spawn(client, Duration::from_secs(60), |alert| {
+//!     log::warn!("{}", alert);
+//! });
+//! # }
+//! ```
+//!
+//! An alert is emitted once when its condition starts to hold and again only after the
+//! condition cleared in between.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+
+use tokio::{task::JoinHandle, time::Instant};
+
+use crate::{
+    error::Result,
+    gevulot_client::GevulotClient,
+    pin_client::PinClient,
+    proto::gevulot::gevulot::{task_status::State, Pin, Task},
+    task_client::TaskClient,
+};
+
+/// Condition on the chain state which raises an [`Alert`] when violated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Rule {
+    /// A task has been pending for longer than the duration.
+    TaskStuckPending(Duration),
+    /// A worker has more than the given number of pins assigned which it didn't ack.
+    WorkerUnackedPins(usize),
+}
+
+impl Rule {
+    /// Alerts about tasks pending for longer than `duration`.
+    ///
+    /// The time is counted from the first snapshot the task was pending in.
+    pub fn task_stuck_pending(duration: Duration) -> Self {
+        Self::TaskStuckPending(duration)
+    }
+
+    /// Alerts about workers with more than `max` assigned pins they didn't ack.
+    pub fn worker_unacked_pins(max: usize) -> Self {
+        Self::WorkerUnackedPins(max)
+    }
+}
+
+/// Violation of a [`Rule`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Alert {
+    TaskStuckPending {
+        task_id: String,
+        /// How long the task has been pending so far.
+        pending_for: Duration,
+    },
+    WorkerUnackedPins {
+        worker_id: String,
+        /// IDs of the pins the worker didn't ack.
+        pin_ids: Vec<String>,
+    },
+}
+
+impl Alert {
+    // Identifies the condition of the alert across snapshots
+    fn key(&self) -> (&'static str, &str) {
+        match self {
+            Self::TaskStuckPending { task_id, .. } => ("task-stuck-pending", task_id),
+            Self::WorkerUnackedPins { worker_id, .. } => ("worker-unacked-pins", worker_id),
+        }
+    }
+}
+
+impl std::fmt::Display for Alert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TaskStuckPending {
+                task_id,
+                pending_for,
+            } => write!(
+                f,
+                "task {} has been pending for {}",
+                task_id,
+                humantime::format_duration(*pending_for)
+            ),
+            Self::WorkerUnackedPins { worker_id, pin_ids } => write!(
+                f,
+                "worker {} didn't ack {} pins: {}",
+                worker_id,
+                pin_ids.len(),
+                pin_ids.join(", ")
+            ),
+        }
+    }
+}
+
+/// State of the chain at one point in time.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub tasks: Vec<Task>,
+    pub pins: Vec<Pin>,
+    pub taken_at: Instant,
+}
+
+impl Snapshot {
+    /// Takes a snapshot of all tasks and pins.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a request to the Gevulot client fails.
+    pub async fn take(tasks: &mut TaskClient, pins: &mut PinClient) -> Result<Self> {
+        Ok(Self {
+            tasks: tasks.list().await?,
+            pins: pins.list().await?,
+            taken_at: Instant::now(),
+        })
+    }
+}
+
+/// Evaluates [`Rule`]s on consecutive [`Snapshot`]s.
+#[derive(Debug)]
+pub struct AlertEngine {
+    rules: Vec<Rule>,
+    // Time each pending task was first seen pending
+    pending_since: HashMap<String, Instant>,
+    // Keys of the alerts whose condition still holds
+    active: HashSet<(&'static str, String)>,
+}
+
+impl AlertEngine {
+    /// Creates an engine evaluating the given rules.
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            pending_since: HashMap::new(),
+            active: HashSet::new(),
+        }
+    }
+
+    /// Evaluates the rules on a snapshot, taken after the ones evaluated before.
+    ///
+    /// # Returns
+    ///
+    /// The alerts whose condition started to hold with this snapshot.
+    pub fn evaluate(&mut self, snapshot: &Snapshot) -> Vec<Alert> {
+        let pending: HashSet<&str> = snapshot
+            .tasks
+            .iter()
+            .filter(|task| {
+                task.status
+                    .as_ref()
+                    .map_or(true, |status| status.state() == State::Pending)
+            })
+            .filter_map(|task| task.metadata.as_ref().map(|m| m.id.as_str()))
+            .collect();
+        self.pending_since
+            .retain(|task_id, _| pending.contains(task_id.as_str()));
+        for task_id in &pending {
+            self.pending_since
+                .entry(task_id.to_string())
+                .or_insert(snapshot.taken_at);
+        }
+
+        let mut alerts = Vec::new();
+        for rule in &self.rules {
+            match rule {
+                Rule::TaskStuckPending(max) => {
+                    for (task_id, since) in &self.pending_since {
+                        let pending_for = snapshot.taken_at.saturating_duration_since(*since);
+                        if pending_for > *max {
+                            alerts.push(Alert::TaskStuckPending {
+                                task_id: task_id.clone(),
+                                pending_for,
+                            });
+                        }
+                    }
+                }
+                Rule::WorkerUnackedPins(max) => {
+                    for (worker_id, pin_ids) in unacked_pins(&snapshot.pins) {
+                        if pin_ids.len() > *max {
+                            alerts.push(Alert::WorkerUnackedPins { worker_id, pin_ids });
+                        }
+                    }
+                }
+            }
+        }
+
+        let previous = std::mem::take(&mut self.active);
+        self.active = alerts
+            .iter()
+            .map(|alert| {
+                let (rule, id) = alert.key();
+                (rule, id.to_string())
+            })
+            .collect();
+        alerts.retain(|alert| {
+            let (rule, id) = alert.key();
+            !previous.contains(&(rule, id.to_string()))
+        });
+        alerts.sort_by(|a, b| a.key().cmp(&b.key()));
+        alerts
+    }
+
+    /// Evaluates the rules on a snapshot taken every `interval` in a background task.
+    ///
+    /// If taking a snapshot fails the error is logged and the rules are evaluated again on the
+    /// next tick.
+    pub fn spawn<F>(
+        mut self,
+        client: &GevulotClient,
+        interval: Duration,
+        mut callback: F,
+    ) -> JoinHandle<()>
+    where
+        F: FnMut(Alert) + Send + 'static,
+    {
+        let mut tasks = client.tasks.clone();
+        let mut pins = client.pins.clone();
+        let base_client = client.base_client.clone();
+        crate::background::spawn_named("gevulot-alerts", async move {
+            let timer = base_client.read().await.timer();
+            loop {
+                match Snapshot::take(&mut tasks, &mut pins).await {
+                    Ok(mut snapshot) => {
+                        snapshot.taken_at = timer.now();
+                        for alert in self.evaluate(&snapshot) {
+                            callback(alert);
+                        }
+                    }
+                    Err(e) => log::warn!("Failed to take a snapshot for alerting: {}", e),
+                }
+                timer.sleep(interval).await;
+            }
+        })
+    }
+}
+
+// Pins assigned to each worker which the worker didn't ack
+fn unacked_pins(pins: &[Pin]) -> BTreeMap<String, Vec<String>> {
+    let mut unacked = BTreeMap::<String, Vec<String>>::new();
+    for pin in pins {
+        let Some(status) = &pin.status else {
+            continue;
+        };
+        let pin_id = pin
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.id.clone())
+            .unwrap_or_else(|| status.cid.clone());
+        for worker in &status.assigned_workers {
+            if !status.worker_acks.iter().any(|ack| &ack.worker == worker) {
+                unacked
+                    .entry(worker.clone())
+                    .or_default()
+                    .push(pin_id.clone());
+            }
+        }
+    }
+    unacked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::gevulot::gevulot::{Metadata, PinAck, PinStatus, TaskStatus};
+
+    fn task(id: &str, state: State) -> Task {
+        Task {
+            metadata: Some(Metadata {
+                id: id.to_string(),
+                ..Default::default()
+            }),
+            status: Some(TaskStatus {
+                state: state as i32,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn pin(id: &str, assigned: &[&str], acked: &[&str]) -> Pin {
+        Pin {
+            metadata: Some(Metadata {
+                id: id.to_string(),
+                ..Default::default()
+            }),
+            status: Some(PinStatus {
+                assigned_workers: assigned.iter().map(|w| w.to_string()).collect(),
+                worker_acks: acked
+                    .iter()
+                    .map(|worker| PinAck {
+                        worker: worker.to_string(),
+                        success: true,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_alert_engine() {
+        let mut engine = AlertEngine::new(vec![
+            Rule::task_stuck_pending(Duration::from_secs(60)),
+            Rule::worker_unacked_pins(1),
+        ]);
+        let start = Instant::now();
+        let snapshot = |tasks, pins, secs| Snapshot {
+            tasks,
+            pins,
+            taken_at: start + Duration::from_secs(secs),
+        };
+
+        let pins = vec![pin("p1", &["w1", "w2"], &["w2"]), pin("p2", &["w1"], &[])];
+        let alerts = engine.evaluate(&snapshot(
+            vec![task("t1", State::Pending), task("t2", State::Running)],
+            pins.clone(),
+            0,
+        ));
+        assert_eq!(
+            alerts,
+            vec![Alert::WorkerUnackedPins {
+                worker_id: "w1".to_string(),
+                pin_ids: vec!["p1".to_string(), "p2".to_string()],
+            }]
+        );
+
+        // Alerts are only emitted when their condition starts to hold
+        let alerts = engine.evaluate(&snapshot(vec![task("t1", State::Pending)], pins, 90));
+        assert_eq!(
+            alerts,
+            vec![Alert::TaskStuckPending {
+                task_id: "t1".to_string(),
+                pending_for: Duration::from_secs(90),
+            }]
+        );
+        assert_eq!(alerts[0].to_string(), "task t1 has been pending for 1m 30s");
+
+        // Once cleared, conditions alert again
+        let alerts = engine.evaluate(&snapshot(vec![task("t1", State::Running)], vec![], 100));
+        assert!(alerts.is_empty());
+        let alerts = engine.evaluate(&snapshot(vec![task("t1", State::Pending)], vec![], 110));
+        assert!(alerts.is_empty());
+        let alerts = engine.evaluate(&snapshot(vec![task("t1", State::Pending)], vec![], 200));
+        assert_eq!(alerts.len(), 1);
+    }
+}
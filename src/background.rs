@@ -0,0 +1,481 @@
+//! Supervision of the background tasks spawned by the SDK.
+//!
+//! All tasks spawned by the SDK, e.g. by [`Outbox::spawn`](crate::outbox::Outbox::spawn) or
+//! [`EventBus::attach`](crate::event_bus::EventBus::attach), are named with a `gevulot-` prefix
+//! and return their join handle. Task names are visible in tokio-console when the crate is built
+//! with the `task-names` feature and `--cfg tokio_unstable`.
+//!
+//! Services embedding the SDK can run their loops under a [`BackgroundTasks`] supervisor to
+//! restart them on failure and monitor them in one place:
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use gevulot_rs::{background::{BackgroundTasks, RestartPolicy}, event_bus::EventBus};
+//! # async fn run(bus: EventBus) {
+//! let tasks = BackgroundTasks::new();
+//! tasks
+//!     .spawn("heartbeat", RestartPolicy::on_failure(Duration::from_secs(5)), || async {
+//!         // Send a heartbeat
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! tasks.supervise("event-bus", bus.attach(())).unwrap();
+//! for failure in tasks.failures() {
+//!     println!("{}", failure);
+//! }
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    panic::AssertUnwindSafe,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::FutureExt;
+use tokio::task::{AbortHandle, JoinHandle};
+
+use crate::error::{Error, Result};
+
+/// Spawns a future as a named tokio task.
+///
+/// The name is only attached to the task when built with the `task-names` feature and
+/// `--cfg tokio_unstable`, otherwise it is only logged.
+pub fn spawn_named<F>(name: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    log::debug!("Spawning background task {}", name);
+    #[cfg(all(tokio_unstable, feature = "task-names"))]
+    {
+        tokio::task::Builder::new()
+            .name(name)
+            .spawn(future)
+            .expect("failed to spawn task")
+    }
+    #[cfg(not(all(tokio_unstable, feature = "task-names")))]
+    {
+        tokio::spawn(future)
+    }
+}
+
+/// When a supervised task is restarted after it ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// The task is never restarted.
+    Never,
+    /// The task is restarted after it failed, at most max_restarts times if set.
+    OnFailure {
+        delay: Duration,
+        max_restarts: Option<u32>,
+    },
+    /// The task is restarted whenever it ended, whether it failed or not.
+    Always { delay: Duration },
+}
+
+impl RestartPolicy {
+    /// Restarts the task after every failure once the delay passed.
+    pub fn on_failure(delay: Duration) -> Self {
+        RestartPolicy::OnFailure {
+            delay,
+            max_restarts: None,
+        }
+    }
+
+    // Returns the delay before the next restart, None if the task isn't restarted
+    fn restart_delay(&self, failed: bool, restarts: u32) -> Option<Duration> {
+        match *self {
+            RestartPolicy::Never => None,
+            RestartPolicy::OnFailure {
+                delay,
+                max_restarts,
+            } => (failed && max_restarts.map_or(true, |max| restarts < max)).then_some(delay),
+            RestartPolicy::Always { delay } => Some(delay),
+        }
+    }
+}
+
+/// State of a supervised task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SupervisedState {
+    Running,
+    /// The task ended and is restarted once the delay of its restart policy passed.
+    Restarting,
+    /// The task ended successfully and isn't restarted.
+    Finished,
+    /// The task failed and isn't restarted.
+    Failed,
+    /// The task was aborted.
+    Aborted,
+}
+
+/// Status of a supervised task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SupervisedTask {
+    pub name: String,
+    pub state: SupervisedState,
+    /// Number of times the task was restarted.
+    pub restarts: u32,
+    /// The error of the most recent failure, if any.
+    pub last_error: Option<String>,
+}
+
+/// A failure of a supervised task.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("background task {name} failed: {error}")]
+pub struct TaskFailure {
+    pub name: String,
+    /// The error returned by the task, or the panic message if it panicked.
+    pub error: String,
+    /// Number of restarts before the failure.
+    pub restarts: u32,
+}
+
+#[derive(Debug)]
+struct Entry {
+    status: SupervisedTask,
+    handle: Option<JoinHandle<()>>,
+    // Aborts the supervising task and the supervised one if it was spawned elsewhere
+    aborts: Vec<AbortHandle>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    tasks: BTreeMap<String, Entry>,
+    failures: Vec<TaskFailure>,
+}
+
+/// Supervisor of named background tasks with restart policies and aggregated error reporting.
+///
+/// Clones share the same tasks.
+#[derive(Clone, Debug, Default)]
+pub struct BackgroundTasks {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl BackgroundTasks {
+    /// Creates a supervisor without tasks.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Spawns a task restarted according to the restart policy.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the task, unique among the running tasks of the supervisor.
+    /// * `policy` - When the task is restarted.
+    /// * `factory` - Creates the future of the task, called again for every restart.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a task with the same name is still running.
+    pub fn spawn<F, Fut>(&self, name: &str, policy: RestartPolicy, mut factory: F) -> Result<()>
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let supervisor = self.clone();
+        let task_name = name.to_string();
+        self.insert(name, None, move || {
+            spawn_named(&format!("gevulot-{}", task_name), async move {
+                let mut restarts = 0;
+                loop {
+                    let error = match AssertUnwindSafe(factory()).catch_unwind().await {
+                        Ok(Ok(())) => None,
+                        Ok(Err(e)) => Some(e.to_string()),
+                        Err(panic) => Some(panic_message(panic)),
+                    };
+                    let failed = error.is_some();
+                    let delay = policy.restart_delay(failed, restarts);
+                    supervisor.record(&task_name, error, restarts, delay.is_some());
+                    match delay {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => return,
+                    }
+                    restarts += 1;
+                    supervisor.update(&task_name, |status| {
+                        status.state = SupervisedState::Running;
+                        status.restarts = restarts;
+                    });
+                }
+            })
+        })
+    }
+
+    /// Monitors an already spawned task, e.g. one returned by the SDK.
+    ///
+    /// The task can't be restarted since the supervisor can't recreate it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a task with the same name is still running.
+    pub fn supervise(&self, name: &str, handle: JoinHandle<Result<()>>) -> Result<()> {
+        let supervisor = self.clone();
+        let task_name = name.to_string();
+        let abort = handle.abort_handle();
+        self.insert(name, Some(abort), move || {
+            spawn_named(&format!("gevulot-supervise-{}", task_name), async move {
+                let error = match handle.await {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(e.to_string()),
+                    Err(e) if e.is_cancelled() => {
+                        supervisor.update(&task_name, |status| {
+                            status.state = SupervisedState::Aborted;
+                        });
+                        return;
+                    }
+                    Err(e) => Some(panic_message(e.into_panic())),
+                };
+                supervisor.record(&task_name, error, 0, false);
+            })
+        })
+    }
+
+    // Registers a task, spawn is called with the lock held so the task can't update its status
+    // before it was registered
+    fn insert(
+        &self,
+        name: &str,
+        supervised: Option<AbortHandle>,
+        spawn: impl FnOnce() -> JoinHandle<()>,
+    ) -> Result<()> {
+        let mut inner = self.lock();
+        if let Some(entry) = inner.tasks.get(name) {
+            if matches!(
+                entry.status.state,
+                SupervisedState::Running | SupervisedState::Restarting
+            ) {
+                return Err(Error::Unknown(format!(
+                    "background task {} is already running",
+                    name
+                )));
+            }
+        }
+        let handle = spawn();
+        let mut aborts = vec![handle.abort_handle()];
+        aborts.extend(supervised);
+        inner.tasks.insert(
+            name.to_string(),
+            Entry {
+                status: SupervisedTask {
+                    name: name.to_string(),
+                    state: SupervisedState::Running,
+                    restarts: 0,
+                    last_error: None,
+                },
+                handle: Some(handle),
+                aborts,
+            },
+        );
+        Ok(())
+    }
+
+    fn update(&self, name: &str, f: impl FnOnce(&mut SupervisedTask)) {
+        if let Some(entry) = self.lock().tasks.get_mut(name) {
+            f(&mut entry.status);
+        }
+    }
+
+    // Records the end of a run of a task
+    fn record(&self, name: &str, error: Option<String>, restarts: u32, restarting: bool) {
+        let mut inner = self.lock();
+        let Some(entry) = inner.tasks.get_mut(name) else {
+            return;
+        };
+        entry.status.state = match (&error, restarting) {
+            (_, true) => SupervisedState::Restarting,
+            (None, false) => SupervisedState::Finished,
+            (Some(_), false) => SupervisedState::Failed,
+        };
+        if let Some(error) = error {
+            log::error!("Background task {} failed: {}", name, error);
+            entry.status.last_error = Some(error.clone());
+            inner.failures.push(TaskFailure {
+                name: name.to_string(),
+                error,
+                restarts,
+            });
+        }
+    }
+
+    /// Returns the status of all tasks ordered by name.
+    pub fn status(&self) -> Vec<SupervisedTask> {
+        self.lock()
+            .tasks
+            .values()
+            .map(|entry| entry.status.clone())
+            .collect()
+    }
+
+    /// Returns all failures of the tasks in the order they happened, including failures after
+    /// which the task was restarted.
+    pub fn failures(&self) -> Vec<TaskFailure> {
+        self.lock().failures.clone()
+    }
+
+    /// Returns true if no task ended with a failure.
+    pub fn is_healthy(&self) -> bool {
+        self.lock()
+            .tasks
+            .values()
+            .all(|entry| entry.status.state != SupervisedState::Failed)
+    }
+
+    /// Aborts a task, returns false if there is no task with the name.
+    pub fn abort(&self, name: &str) -> bool {
+        let mut inner = self.lock();
+        let Some(entry) = inner.tasks.get_mut(name) else {
+            return false;
+        };
+        for abort in &entry.aborts {
+            abort.abort();
+        }
+        if matches!(
+            entry.status.state,
+            SupervisedState::Running | SupervisedState::Restarting
+        ) {
+            entry.status.state = SupervisedState::Aborted;
+        }
+        true
+    }
+
+    /// Aborts all tasks.
+    pub fn abort_all(&self) {
+        let names: Vec<_> = self.lock().tasks.keys().cloned().collect();
+        for name in names {
+            self.abort(&name);
+        }
+    }
+
+    /// Waits until all tasks ended without being restarted.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error listing all failures of the tasks, including failures
+    /// after which the task was restarted.
+    pub async fn join(&self) -> Result<()> {
+        let handles: Vec<_> = self
+            .lock()
+            .tasks
+            .values_mut()
+            .filter_map(|entry| entry.handle.take())
+            .collect();
+        for handle in handles {
+            // Failures are recorded by the task itself
+            let _ = handle.await;
+        }
+        let failures = self.failures();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::BackgroundTasks(failures))
+        }
+    }
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    format!("panicked: {}", message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_restart_policy() {
+        assert_eq!(RestartPolicy::Never.restart_delay(true, 0), None);
+        let policy = RestartPolicy::OnFailure {
+            delay: Duration::from_secs(1),
+            max_restarts: Some(2),
+        };
+        assert_eq!(policy.restart_delay(true, 1), Some(Duration::from_secs(1)));
+        assert_eq!(policy.restart_delay(true, 2), None);
+        assert_eq!(policy.restart_delay(false, 0), None);
+        let policy = RestartPolicy::Always {
+            delay: Duration::ZERO,
+        };
+        assert_eq!(policy.restart_delay(false, 100), Some(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn test_restart_on_failure() {
+        let tasks = BackgroundTasks::new();
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = runs.clone();
+        let policy = RestartPolicy::OnFailure {
+            delay: Duration::ZERO,
+            max_restarts: Some(2),
+        };
+        tasks
+            .spawn("flaky", policy, move || {
+                let run = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    match run {
+                        0 => Err(Error::Unknown("first".to_string())),
+                        1 => panic!("second"),
+                        _ => Ok(()),
+                    }
+                }
+            })
+            .unwrap();
+        assert!(tasks.spawn("flaky", policy, || async { Ok(()) }).is_err());
+
+        let Err(Error::BackgroundTasks(failures)) = tasks.join().await else {
+            panic!("expected failures");
+        };
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert_eq!(failures.len(), 2);
+        assert_eq!(
+            failures[0].to_string(),
+            "background task flaky failed: unknown error: first"
+        );
+        assert_eq!(failures[1].error, "panicked: second");
+        assert_eq!(failures[1].restarts, 1);
+
+        let status = tasks.status();
+        assert_eq!(status[0].state, SupervisedState::Finished);
+        assert_eq!(status[0].restarts, 2);
+        assert!(tasks.is_healthy());
+    }
+
+    #[tokio::test]
+    async fn test_supervise() {
+        let tasks = BackgroundTasks::new();
+        tasks
+            .supervise(
+                "failing",
+                tokio::spawn(async { Err(Error::Unknown("boom".to_string())) }),
+            )
+            .unwrap();
+        tasks
+            .spawn("endless", RestartPolicy::Never, || {
+                futures::future::pending::<Result<()>>()
+            })
+            .unwrap();
+        assert!(tasks.abort("endless"));
+        assert!(!tasks.abort("missing"));
+
+        assert!(tasks.join().await.is_err());
+        let status = tasks.status();
+        assert_eq!(status[0].name, "endless");
+        assert_eq!(status[0].state, SupervisedState::Aborted);
+        assert_eq!(status[1].state, SupervisedState::Failed);
+        assert_eq!(status[1].last_error.as_deref(), Some("unknown error: boom"));
+        assert!(!tasks.is_healthy());
+    }
+}
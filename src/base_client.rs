@@ -3,36 +3,108 @@ use cosmos_sdk_proto::cosmos::tx::v1beta1::{SimulateResponse, Tx};
 use cosmos_sdk_proto::prost::{Message, Name};
 use cosmos_sdk_proto::tendermint::types::Block;
 use cosmrs::{auth::BaseAccount, Coin};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
 use tonic::transport::{Channel, ClientTlsConfig};
 
-use crate::error::{Error, Result};
-use crate::signer::GevulotSigner;
+use crate::builders::{MsgBatch, MsgBatchResponses};
+use crate::chain_clock::ChainClock;
+use crate::compression::{CompressionConfig, CompressionStats, MeteredChannel};
+use crate::concurrency::{ConcurrencyLimit, ConcurrencyStats};
+use crate::gas_cache::{GasCache, GasOverride};
+use crate::health::{ChainStatus, NodeInfo};
+use crate::query_retry::QueryRetryPolicy;
+
+use crate::error::{Error, Result, TxError};
+use crate::failover::{EndpointHealth, FailoverChannel};
+use crate::registry::DecodedMsg;
+use crate::signer::{GevulotSigner, HdPath, DEFAULT_ADDRESS_PREFIX};
+use crate::timer::{Timer, TokioTimer};
+use crate::tx_receipt::TxReceipt;
 
 // Type aliases for various clients used in the BaseClient
 type AuthQueryClient<T> = cosmrs::proto::cosmos::auth::v1beta1::query_client::QueryClient<T>;
 type BankQueryClient<T> = cosmrs::proto::cosmos::bank::v1beta1::query_client::QueryClient<T>;
 type GovQueryClient<T> = cosmrs::proto::cosmos::gov::v1beta1::query_client::QueryClient<T>;
 type GevulotQueryClient<T> = crate::proto::gevulot::gevulot::query_client::QueryClient<T>;
-type TxServiceClient<T> = cosmrs::proto::cosmos::tx::v1beta1::service_client::ServiceClient<T>;
+pub(crate) type TxServiceClient<T> =
+    cosmrs::proto::cosmos::tx::v1beta1::service_client::ServiceClient<T>;
 type TendermintClient<T> =
     cosmrs::proto::cosmos::base::tendermint::v1beta1::service_client::ServiceClient<T>;
 
+/// Chain ID transactions are signed for unless set otherwise.
+pub const DEFAULT_CHAIN_ID: &str = "gevulot";
+
+/// Denomination fees and balances are in unless set otherwise.
+pub const DEFAULT_DENOM: &str = "ucredit";
+
+/// Retries of transactions rejected because of a wrong account sequence unless set otherwise.
+pub const DEFAULT_SEQUENCE_RETRIES: u32 = 3;
+
+/// Limits the chain enforces on transactions, from its consensus params.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ChainLimits {
+    /// Largest transaction in bytes, bounded by the maximum block size. None if unlimited.
+    pub max_tx_bytes: Option<u64>,
+    /// Most gas a block and therefore a single transaction may use. None if unlimited.
+    pub max_block_gas: Option<u64>,
+}
+
+impl ChainLimits {
+    /// Checks the size and gas limit of a transaction against the limits.
+    pub fn check(&self, tx_bytes: u64, gas: u64) -> Result<()> {
+        for (limit, value, max) in [
+            ("tx bytes", tx_bytes, self.max_tx_bytes),
+            ("block gas", gas, self.max_block_gas),
+        ] {
+            if let Some(max) = max.filter(|max| value > *max) {
+                return Err(Error::ExceedsChainLimits { limit, value, max });
+            }
+        }
+        Ok(())
+    }
+}
+
+// The consensus module isn't part of cosmos-sdk-proto, only its params query is needed
+#[derive(Clone, PartialEq, prost::Message)]
+struct QueryConsensusParamsRequest {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct QueryConsensusParamsResponse {
+    #[prost(message, optional, tag = "1")]
+    params: Option<cosmos_sdk_proto::tendermint::types::ConsensusParams>,
+}
+
+/// Transaction signed by the client, ready to be broadcast.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignedTx {
+    pub tx_bytes: Vec<u8>,
+    /// Upper case hex encoded SHA-256 hash of the transaction, as used by the chain.
+    pub hash: String,
+    /// Account sequence the transaction was signed with.
+    pub sequence: u64,
+}
+
 /// BaseClient is a struct that provides various functionalities to interact with the blockchain.
 #[derive(derivative::Derivative)]
 #[derivative(Debug)]
 pub struct BaseClient {
     // Query clients
-    pub auth_client: AuthQueryClient<Channel>,
-    pub bank_client: BankQueryClient<Channel>,
-    pub gevulot_client: GevulotQueryClient<Channel>,
-    pub gov_client: GovQueryClient<Channel>,
-    pub tendermint_client: TendermintClient<Channel>,
+    pub auth_client: AuthQueryClient<MeteredChannel>,
+    pub bank_client: BankQueryClient<MeteredChannel>,
+    pub gevulot_client: GevulotQueryClient<MeteredChannel>,
+    pub gov_client: GovQueryClient<MeteredChannel>,
+    pub tendermint_client: TendermintClient<MeteredChannel>,
     // Message client
-    pub tx_client: TxServiceClient<Channel>,
+    pub tx_client: TxServiceClient<MeteredChannel>,
+
+    channel: MeteredChannel,
 
     gas_price: f64,
     denom: String,
     gas_multiplier: f64,
+    chain_id: cosmrs::tendermint::chain::Id,
+    address_prefix: String,
 
     // Data from signer
     pub address: Option<String>,
@@ -42,6 +114,20 @@ pub struct BaseClient {
 
     // Latest account sequence
     pub account_sequence: Option<u64>,
+    // How often transactions are signed again after an account sequence mismatch
+    sequence_retries: u32,
+
+    // Limits from the consensus params, fetched on first use
+    chain_limits: Option<ChainLimits>,
+
+    // Clock used when waiting for blocks and transactions
+    timer: Arc<dyn Timer>,
+
+    // Estimate of the chain time, for comparisons with timestamps on the chain
+    chain_clock: ChainClock,
+
+    // Gas used by recent simulations, for transactions skipping the simulation
+    gas_cache: GasCache,
 }
 
 impl BaseClient {
@@ -49,7 +135,7 @@ impl BaseClient {
     ///
     /// # Arguments
     ///
-    /// * `endpoint` - The endpoint URL to connect to.
+    /// * `endpoint` - The endpoint URL to connect to, `unix:///path/to/socket` connects over a Unix domain socket.
     /// * `gas_price` - The gas price to be used.
     /// * `gas_multiplier` - The gas multiplier to be used.
     ///
@@ -65,13 +151,7 @@ impl BaseClient {
 
         // Attempt to create a channel with retries and exponential backoff
         let channel = loop {
-            match Channel::from_shared(endpoint.to_owned())
-                .map_err(|e| crate::error::Error::RpcConnectionError(e.to_string()))?
-                .tls_config(ClientTlsConfig::new().with_native_roots())
-                .map_err(|e| crate::error::Error::RpcConnectionError(e.to_string()))?
-                .connect()
-                .await
-            {
+            match Self::connect(endpoint, false).await? {
                 Ok(channel) => break channel,
                 Err(_) if retries > 0 => {
                     retries -= 1;
@@ -83,31 +163,430 @@ impl BaseClient {
             }
         };
 
-        // Initialize the BaseClient with the created channel
+        let channel = FailoverChannel::new(vec![(endpoint.to_owned(), channel)]);
+        Self::from_channel(MeteredChannel::new(channel), gas_price, gas_multiplier)
+    }
+
+    /// Creates a new instance of BaseClient failing over between several endpoints.
+    ///
+    /// All endpoints are health checked and the first healthy one becomes active. When a
+    /// request to the active endpoint fails with a transport error the next one becomes active,
+    /// see [`crate::failover`].
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoints` - The endpoint URLs of nodes of the same chain, see [`BaseClient::new`].
+    /// * `gas_price` - The gas price to be used.
+    /// * `gas_multiplier` - The gas multiplier to be used.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new instance of BaseClient or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no endpoint is given, an endpoint is invalid or
+    /// none of the endpoints is healthy.
+    pub async fn with_endpoints(
+        endpoints: &[&str],
+        gas_price: f64,
+        gas_multiplier: f64,
+    ) -> Result<Self> {
+        if let [endpoint] = endpoints {
+            return Self::new(endpoint, gas_price, gas_multiplier).await;
+        }
+        if endpoints.is_empty() {
+            return Err(Error::RpcConnectionError("no endpoints given".to_string()));
+        }
+        let mut channels = Vec::with_capacity(endpoints.len());
+        for endpoint in endpoints {
+            // Lazy channels connect on first use and reconnect after failures
+            let channel = Self::connect(endpoint, true).await??;
+            channels.push((endpoint.to_string(), channel));
+        }
+        let channel = MeteredChannel::new(FailoverChannel::new(channels));
+        let mut client = Self::from_channel(channel, gas_price, gas_multiplier)?;
+        let health = client.check_endpoints().await;
+        if !health.iter().any(EndpointHealth::is_healthy) {
+            return Err(Error::RpcConnectionError(format!(
+                "no healthy endpoint: {}",
+                health
+                    .iter()
+                    .map(|h| format!("{}: {}", h.endpoint, h.error.as_deref().unwrap_or_default()))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        Ok(client)
+    }
+
+    // Initializes the BaseClient with the created channel
+    fn from_channel(channel: MeteredChannel, gas_price: f64, gas_multiplier: f64) -> Result<Self> {
         Ok(Self {
             auth_client: AuthQueryClient::new(channel.clone()),
             bank_client: BankQueryClient::new(channel.clone()),
             gevulot_client: GevulotQueryClient::new(channel.clone()),
             gov_client: GovQueryClient::new(channel.clone()),
             tendermint_client: TendermintClient::new(channel.clone()),
-            tx_client: TxServiceClient::new(channel),
-            denom: "ucredit".to_owned(),
+            tx_client: TxServiceClient::new(channel.clone()),
+            channel,
+            denom: DEFAULT_DENOM.to_owned(),
+            chain_id: DEFAULT_CHAIN_ID
+                .parse()
+                .map_err(|e| Error::Parse(format!("invalid chain id: {}", e)))?,
+            address_prefix: DEFAULT_ADDRESS_PREFIX.to_owned(),
             gas_price,
             gas_multiplier,
             address: None,
             pub_key: None,
             priv_key: None,
             account_sequence: None,
+            sequence_retries: DEFAULT_SEQUENCE_RETRIES,
+            chain_limits: None,
+            timer: Arc::new(TokioTimer),
+            chain_clock: ChainClock::default(),
+            gas_cache: GasCache::default(),
         })
     }
 
+    /// Connects to the endpoint, either over TCP (`http://` and `https://` URLs) or over a
+    /// Unix domain socket (`unix:///path/to/socket`). Lazy channels connect on first use.
+    ///
+    /// The outer Result holds errors in the endpoint itself, the inner one connection errors
+    /// which are worth retrying.
+    async fn connect(
+        endpoint: &str,
+        lazy: bool,
+    ) -> Result<std::result::Result<Channel, tonic::transport::Error>> {
+        if let Some(path) = endpoint.strip_prefix("unix://") {
+            return Self::connect_unix(path, lazy).await;
+        }
+
+        let endpoint = Channel::from_shared(endpoint.to_owned())
+            .map_err(|e| crate::error::Error::RpcConnectionError(e.to_string()))?
+            .tls_config(ClientTlsConfig::new().with_native_roots())
+            .map_err(|e| crate::error::Error::RpcConnectionError(e.to_string()))?;
+        if lazy {
+            return Ok(Ok(endpoint.connect_lazy()));
+        }
+        Ok(endpoint.connect().await)
+    }
+
+    #[cfg(unix)]
+    async fn connect_unix(
+        path: &str,
+        lazy: bool,
+    ) -> Result<std::result::Result<Channel, tonic::transport::Error>> {
+        let path = std::path::PathBuf::from(path);
+        // The URI is required by tonic but ignored by the connector
+        let endpoint = tonic::transport::Endpoint::from_static("http://localhost");
+        let connector = tower::service_fn(move |_: http::Uri| {
+            let path = path.clone();
+            async move {
+                Ok::<_, std::io::Error>(hyper_util::rt::TokioIo::new(
+                    tokio::net::UnixStream::connect(path).await?,
+                ))
+            }
+        });
+        if lazy {
+            return Ok(Ok(endpoint.connect_with_connector_lazy(connector)));
+        }
+        Ok(endpoint.connect_with_connector(connector).await)
+    }
+
+    #[cfg(not(unix))]
+    async fn connect_unix(
+        _path: &str,
+        _lazy: bool,
+    ) -> Result<std::result::Result<Channel, tonic::transport::Error>> {
+        Err(Error::RpcConnectionError(
+            "Unix domain sockets are not supported on this platform".to_string(),
+        ))
+    }
+
+    /// Checks the health of all endpoints and activates the first healthy one if the active
+    /// endpoint is unhealthy.
+    ///
+    /// An endpoint is healthy if it answers and isn't catching up with the chain.
+    ///
+    /// # Returns
+    ///
+    /// The health of every endpoint, in the order they were given.
+    pub async fn check_endpoints(&mut self) -> Vec<EndpointHealth> {
+        let failover = self.channel.failover();
+        let mut health = Vec::new();
+        for (endpoint, channel) in failover.endpoints() {
+            let request = cosmrs::proto::cosmos::base::tendermint::v1beta1::GetSyncingRequest {};
+            let error = match TendermintClient::new(channel.clone())
+                .get_syncing(request)
+                .await
+            {
+                Ok(response) if response.get_ref().syncing => {
+                    Some("node is catching up".to_string())
+                }
+                Ok(_) => None,
+                Err(status) => Some(Error::from(status).to_string()),
+            };
+            health.push(EndpointHealth {
+                endpoint: endpoint.clone(),
+                active: false,
+                error,
+            });
+        }
+        let active = failover.active();
+        if !health[active].is_healthy() {
+            if let Some(healthy) = health.iter().position(EndpointHealth::is_healthy) {
+                failover.set_active(healthy);
+            }
+        }
+        let active = failover.active();
+        health[active].active = true;
+        health
+    }
+
+    /// Configures compression of gRPC messages for all clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The compression to use for requests and the compressions accepted for responses.
+    pub fn set_compression(&mut self, config: &CompressionConfig) {
+        macro_rules! configure {
+            ($client:expr) => {{
+                let mut client = $client.clone();
+                if let Some(send) = config.send {
+                    client = client.send_compressed(send.into());
+                }
+                for accept in config.accept.iter() {
+                    client = client.accept_compressed((*accept).into());
+                }
+                $client = client;
+            }};
+        }
+        configure!(self.auth_client);
+        configure!(self.bank_client);
+        configure!(self.gevulot_client);
+        configure!(self.gov_client);
+        configure!(self.tendermint_client);
+        configure!(self.tx_client);
+    }
+
+    /// Returns the number of gRPC messages and bytes sent and received, compressed and raw.
+    pub fn compression_stats(&self) -> CompressionStats {
+        self.channel.metrics().snapshot()
+    }
+
+    /// Limits the number of gRPC requests in flight at the same time over all clients.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The maximum number of concurrent requests, None removes the limit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is `Some(0)`.
+    pub fn set_concurrency_limit(&mut self, limit: Option<usize>) {
+        self.channel
+            .set_concurrency_limit(limit.map(ConcurrencyLimit::new));
+    }
+
+    /// Sets the retry policy of read-only gRPC calls failing with transient errors, see
+    /// [`crate::query_retry`].
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - The retry policy, None disables retries.
+    pub fn set_query_retry(&mut self, policy: Option<QueryRetryPolicy>) {
+        self.channel.set_retry_policy(policy);
+    }
+
+    /// Returns how many requests are in flight and how often requests had to wait for a slot,
+    /// None if no concurrency limit is set.
+    pub fn concurrency_stats(&self) -> Option<ConcurrencyStats> {
+        self.channel.concurrency_limit().map(|limit| limit.stats())
+    }
+
+    /// Sets how often a transaction is signed again after the chain rejected it because of a
+    /// wrong account sequence, 0 disables the retries.
+    ///
+    /// # Arguments
+    ///
+    /// * `retries` - The number of retries, [`DEFAULT_SEQUENCE_RETRIES`] by default.
+    pub fn set_sequence_retries(&mut self, retries: u32) {
+        self.sequence_retries = retries;
+    }
+
+    /// Returns how often a transaction is signed again after an account sequence mismatch.
+    pub fn sequence_retries(&self) -> u32 {
+        self.sequence_retries
+    }
+
+    /// Sets the factor applied to gas limits estimated from recent simulations, see
+    /// [`crate::gas_cache`].
+    ///
+    /// # Arguments
+    ///
+    /// * `multiplier` - The factor,
+    ///   [`DEFAULT_SAFETY_MULTIPLIER`](crate::gas_cache::DEFAULT_SAFETY_MULTIPLIER) by default.
+    pub fn set_gas_safety_multiplier(&mut self, multiplier: f64) {
+        self.gas_cache.set_safety_multiplier(multiplier);
+    }
+
+    /// Returns the gas used by recent simulations.
+    pub fn gas_cache(&self) -> &GasCache {
+        &self.gas_cache
+    }
+
+    /// Forgets the gas used by recent simulations, e.g. after a chain upgrade changed gas
+    /// costs.
+    pub fn clear_gas_cache(&mut self) {
+        self.gas_cache.clear();
+    }
+
+    /// Sets the skew of the local clock from the chain time which is ignored, see
+    /// [`crate::chain_clock`].
+    ///
+    /// # Arguments
+    ///
+    /// * `tolerance` - The tolerated skew.
+    pub fn set_clock_skew_tolerance(&mut self, tolerance: std::time::Duration) {
+        self.chain_clock.set_tolerance(tolerance);
+    }
+
+    /// Returns the estimate of the chain time.
+    pub fn chain_clock(&self) -> &ChainClock {
+        &self.chain_clock
+    }
+
+    /// Updates the estimate of the chain time from the header of the latest block.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the time of the latest block or an error.
+    pub async fn sync_chain_clock(&mut self) -> Result<std::time::SystemTime> {
+        let block = self.current_block().await?;
+        let local_time = std::time::SystemTime::now();
+        let time = block
+            .header
+            .and_then(|header| header.time)
+            .ok_or("Block time not found")?;
+        let block_time = Self::system_time(&time)?;
+        self.chain_clock
+            .record(block_time, local_time, self.timer.now());
+        Ok(block_time)
+    }
+
+    /// Returns the current chain time, estimated from recent block headers.
+    ///
+    /// A new header is fetched if the estimate is outdated, otherwise no request is sent.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the chain time or an error.
+    pub async fn chain_time(&mut self) -> Result<std::time::SystemTime> {
+        if self.chain_clock.needs_sync(self.timer.now()) {
+            self.sync_chain_clock().await?;
+        }
+        Ok(self.chain_clock.chain_time(std::time::SystemTime::now()))
+    }
+
+    /// Sets the timer used when waiting, see [`crate::timer`].
+    ///
+    /// # Arguments
+    ///
+    /// * `timer` - The timer to be used.
+    pub fn set_timer(&mut self, timer: Arc<dyn Timer>) {
+        self.timer = timer;
+    }
+
+    /// Returns the timer used when waiting.
+    pub fn timer(&self) -> Arc<dyn Timer> {
+        self.timer.clone()
+    }
+
+    /// Sets the chain ID transactions are signed for, [`DEFAULT_CHAIN_ID`] by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `chain_id` - The chain ID of the network, e.g. `gevulot-testnet`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the chain ID is invalid.
+    pub fn set_chain_id(&mut self, chain_id: &str) -> Result<()> {
+        self.chain_id = chain_id
+            .parse()
+            .map_err(|e| Error::Parse(format!("invalid chain id {}: {}", chain_id, e)))?;
+        Ok(())
+    }
+
+    /// Returns the chain ID transactions are signed for.
+    pub fn chain_id(&self) -> &str {
+        self.chain_id.as_str()
+    }
+
+    /// Sets the denomination of fees and balances, [`DEFAULT_DENOM`] by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `denom` - The denomination of the network's fee token.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the denomination is invalid.
+    pub fn set_denom(&mut self, denom: &str) -> Result<()> {
+        denom
+            .parse::<cosmrs::Denom>()
+            .map_err(|e| Error::Parse(format!("invalid denom {}: {}", denom, e)))?;
+        self.denom = denom.to_owned();
+        Ok(())
+    }
+
+    /// Returns the denomination of fees and balances.
+    pub fn denom(&self) -> &str {
+        &self.denom
+    }
+
+    /// Sets the bech32 prefix of account addresses, [`DEFAULT_ADDRESS_PREFIX`] by default.
+    ///
+    /// The address of a signer already set is derived again with the new prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The bech32 prefix of the network's addresses.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the prefix is invalid.
+    pub fn set_address_prefix(&mut self, prefix: &str) -> Result<()> {
+        // Validates the prefix
+        cosmrs::AccountId::new(prefix, &[0; 20])
+            .map_err(|e| Error::Parse(format!("invalid address prefix {}: {}", prefix, e)))?;
+        self.address_prefix = prefix.to_owned();
+        if let Some(pub_key) = self.pub_key {
+            self.address = Some(pub_key.account_id(prefix)?.to_string());
+        }
+        Ok(())
+    }
+
+    /// Returns the bech32 prefix of account addresses.
+    pub fn address_prefix(&self) -> &str {
+        &self.address_prefix
+    }
+
     /// Sets the signer for the client.
     ///
+    /// The address of the signer is derived with the address prefix of the client.
+    ///
     /// # Arguments
     ///
     /// * `signer` - The GevulotSigner to be set.
     pub fn set_signer(&mut self, signer: GevulotSigner) {
-        self.address = Some(signer.0.public_address.to_string());
+        let address = signer
+            .0
+            .public_key
+            .account_id(&self.address_prefix)
+            .unwrap_or(signer.0.public_address);
+        self.address = Some(address.to_string());
         self.pub_key = Some(signer.0.public_key);
         self.priv_key = Some(signer.0.private_key);
     }
@@ -127,6 +606,27 @@ impl BaseClient {
         Ok(())
     }
 
+    /// Sets the mnemonic for the client and initializes the signer of a derivation path.
+    ///
+    /// # Arguments
+    ///
+    /// * `mnemonic` - The mnemonic string to be used.
+    /// * `path` - The derivation path of the key.
+    ///
+    /// # Returns
+    ///
+    /// A Result indicating success or failure.
+    pub fn set_mnemonic_with_path(
+        &mut self,
+        mnemonic: &str,
+        password: Option<&str>,
+        path: &HdPath,
+    ) -> Result<()> {
+        let signer = GevulotSigner::from_mnemonic_with_path(mnemonic, password, path)?;
+        self.set_signer(signer);
+        Ok(())
+    }
+
     /// Retrieves the account information for a given address.
     ///
     /// # Arguments
@@ -164,7 +664,7 @@ impl BaseClient {
     pub async fn get_account_balance(&mut self, address: &str) -> Result<Coin> {
         let request = cosmrs::proto::cosmos::bank::v1beta1::QueryBalanceRequest {
             address: address.to_string(),
-            denom: String::from("ucredit"),
+            denom: self.denom.clone(),
         };
         let response = self.bank_client.balance(request).await?;
 
@@ -250,11 +750,24 @@ impl BaseClient {
         sequence: u64,
     ) -> Result<SimulateResponse> {
         let msg = cosmrs::Any::from_msg(&msg)?;
+        self.simulate_anys(vec![msg], memo, account_number, sequence)
+            .await
+    }
+
+    /// Simulates a transaction with the given messages to estimate gas usage.
+    async fn simulate_anys(
+        &mut self,
+        msgs: Vec<cosmrs::Any>,
+        memo: &str,
+        account_number: u64,
+        sequence: u64,
+    ) -> Result<SimulateResponse> {
         let gas = 100_000u64;
-        let chain_id: cosmrs::tendermint::chain::Id = "gevulot"
-            .parse()
-            .map_err(|_| Error::Parse("fail".to_string()))?;
-        let tx_body = cosmrs::tx::BodyBuilder::new().msg(msg).memo(memo).finish();
+        let chain_id = self.chain_id.clone();
+        let tx_body = cosmrs::tx::BodyBuilder::new()
+            .msgs(msgs)
+            .memo(memo)
+            .finish();
         let signer_info = cosmrs::tx::SignerInfo::single_direct(self.pub_key, sequence);
         let gas_per_ucredit = (1.0 / self.gas_price).floor() as u128;
         let fee = cosmrs::tx::Fee::from_amount_and_gas(
@@ -293,14 +806,184 @@ impl BaseClient {
         msg: M,
         memo: &str,
     ) -> Result<String> {
-        // Use simulate_msg to estimate gas
+        let msg = cosmrs::Any::from_msg(&msg)?;
+        self.send_anys(vec![msg], memo).await
+    }
+
+    /// Sends a message with the gas limit determined as given and returns the transaction hash.
+    ///
+    /// High-throughput submitters knowing the typical gas of their messages can skip the
+    /// simulation of each transaction with [`GasOverride::Cached`] or [`GasOverride::Limit`].
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to be sent.
+    /// * `memo` - The memo to be included in the transaction.
+    /// * `gas` - How the gas limit is determined.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the transaction hash or an error.
+    pub async fn send_msg_with_gas<M: Message + Name>(
+        &mut self,
+        msg: M,
+        memo: &str,
+        gas: GasOverride,
+    ) -> Result<String> {
+        let msg = cosmrs::Any::from_msg(&msg)?;
+        self.send_anys_with_gas(vec![msg], memo, gas).await
+    }
+
+    /// Sends several messages in a single transaction and returns the transaction hash.
+    ///
+    /// The messages are executed in order and atomically, if one of them fails the whole
+    /// transaction fails.
+    ///
+    /// # Arguments
+    ///
+    /// * `msgs` - The messages to be sent.
+    /// * `memo` - The memo to be included in the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the transaction hash or an error.
+    pub async fn send_msgs<M: Message + Name>(&mut self, msgs: &[M], memo: &str) -> Result<String> {
+        if msgs.is_empty() {
+            return Err(Error::Unknown("no messages to send".to_string()));
+        }
+        let msgs = msgs
+            .iter()
+            .map(cosmrs::Any::from_msg)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        self.send_anys(msgs, memo).await
+    }
+
+    /// Sends a batch of messages of different types in a single transaction and returns the
+    /// transaction hash.
+    ///
+    /// The messages are executed in order and atomically. The gas limit is estimated by
+    /// simulating the whole batch.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The messages to be sent.
+    /// * `memo` - The memo to be included in the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the transaction hash or an error.
+    pub async fn send_batch(&mut self, batch: MsgBatch, memo: &str) -> Result<String> {
+        if batch.is_empty() {
+            return Err(Error::Unknown("no messages to send".to_string()));
+        }
+        self.send_anys(batch.into_anys(), memo).await
+    }
+
+    /// Sends a batch of messages of different types in a single transaction and waits for it
+    /// to be included in a block.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The messages to be sent.
+    /// * `memo` - The memo to be included in the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the responses in the order of the sent messages or an error.
+    pub async fn send_batch_sync(
+        &mut self,
+        batch: MsgBatch,
+        memo: &str,
+    ) -> Result<MsgBatchResponses> {
+        if batch.is_empty() {
+            return Err(Error::Unknown("no messages to send".to_string()));
+        }
+        let responses = self.send_anys_sync(batch.into_anys(), memo).await?;
+        Ok(MsgBatchResponses::new(responses))
+    }
+
+    /// Signs and broadcasts a transaction with the given messages.
+    ///
+    /// If the chain rejects the transaction because of a wrong account sequence, e.g. since
+    /// another transaction of the account raced it, the sequence is queried again and the
+    /// transaction signed again, up to the number of retries set with
+    /// [`BaseClient::set_sequence_retries`].
+    async fn send_anys(&mut self, msgs: Vec<cosmrs::Any>, memo: &str) -> Result<String> {
+        self.send_anys_with_gas(msgs, memo, GasOverride::Simulate)
+            .await
+    }
+
+    /// Signs and broadcasts a transaction with the given messages and gas limit, see
+    /// [`BaseClient::send_anys`].
+    async fn send_anys_with_gas(
+        &mut self,
+        msgs: Vec<cosmrs::Any>,
+        memo: &str,
+        gas: GasOverride,
+    ) -> Result<String> {
+        let mut retry = 0;
+        loop {
+            let result = match self.sign_anys_with_gas(msgs.clone(), memo, gas).await {
+                Ok(tx) => self.broadcast_signed(&tx).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Err(e) if e.is_sequence_mismatch() && retry < self.sequence_retries => {
+                    retry += 1;
+                    log::warn!(
+                        "Account sequence mismatch, retrying ({}/{}): {}",
+                        retry,
+                        self.sequence_retries,
+                        e
+                    );
+                    // Without the expected sequence, the next signing queries the account
+                    self.account_sequence = e.expected_sequence();
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Signs a transaction with the given messages at the current account sequence.
+    ///
+    /// The gas limit is estimated by simulating the transaction.
+    pub(crate) async fn sign_anys(
+        &mut self,
+        msgs: Vec<cosmrs::Any>,
+        memo: &str,
+    ) -> Result<SignedTx> {
+        self.sign_anys_with_gas(msgs, memo, GasOverride::Simulate)
+            .await
+    }
+
+    /// Signs a transaction with the given messages at the current account sequence, with the
+    /// gas limit determined as given.
+    pub(crate) async fn sign_anys_with_gas(
+        &mut self,
+        msgs: Vec<cosmrs::Any>,
+        memo: &str,
+        gas: GasOverride,
+    ) -> Result<SignedTx> {
         let (account_number, sequence) = self.get_account_details().await?;
-        let simulate_response = self
-            .simulate_msg(msg.clone(), memo, account_number, sequence)
-            .await?;
-        log::debug!("simulate_response: {:#?}", simulate_response);
-        let gas_info = simulate_response.gas_info.ok_or("Failed to get gas info")?;
-        let gas_limit = (gas_info.gas_used * ((self.gas_multiplier * 10000.0) as u64)) / 10000; // Adjust gas limit based on simulation
+        let gas_limit = match gas {
+            GasOverride::Limit(gas_limit) => Some(gas_limit),
+            GasOverride::Cached => self.gas_cache.estimate(&msgs),
+            GasOverride::Simulate => None,
+        };
+        let gas_limit = match gas_limit {
+            Some(gas_limit) => gas_limit,
+            None => {
+                // Use simulate_anys to estimate gas
+                let simulate_response = self
+                    .simulate_anys(msgs.clone(), memo, account_number, sequence)
+                    .await?;
+                log::debug!("simulate_response: {:#?}", simulate_response);
+                let gas_info = simulate_response.gas_info.ok_or("Failed to get gas info")?;
+                self.gas_cache.record(&msgs, gas_info.gas_used);
+                // Adjust gas limit based on simulation
+                (gas_info.gas_used * ((self.gas_multiplier * 10000.0) as u64)) / 10000
+            }
+        };
         let gas_per_ucredit = (1.0 / self.gas_price).floor() as u128;
         let fee = cosmrs::tx::Fee::from_amount_and_gas(
             Coin {
@@ -312,31 +995,223 @@ impl BaseClient {
 
         log::debug!("fee: {:?}", fee);
 
-        let msg = cosmrs::Any::from_msg(&msg)?;
-        let chain_id: cosmrs::tendermint::chain::Id = "gevulot"
-            .parse()
-            .map_err(|_| Error::Parse("fail".to_string()))?;
-        let tx_body = cosmrs::tx::BodyBuilder::new().msg(msg).memo(memo).finish();
+        let chain_id = self.chain_id.clone();
+        let tx_body = cosmrs::tx::BodyBuilder::new()
+            .msgs(msgs)
+            .memo(memo)
+            .finish();
         let signer_info = cosmrs::tx::SignerInfo::single_direct(self.pub_key, sequence);
         let auth_info = signer_info.auth_info(fee);
         let sign_doc = cosmrs::tx::SignDoc::new(&tx_body, &auth_info, &chain_id, account_number)?;
         let tx_raw = sign_doc.sign(self.priv_key.as_ref().ok_or("Private key not set")?)?;
         let tx_bytes = tx_raw.to_bytes()?;
+        self.check_chain_limits(tx_bytes.len() as u64, gas_limit)
+            .await?;
 
-        let request = cosmos_sdk_proto::cosmos::tx::v1beta1::BroadcastTxRequest {
+        Ok(SignedTx {
+            hash: hex::encode_upper(Sha256::digest(&tx_bytes)),
             tx_bytes,
+            sequence,
+        })
+    }
+
+    /// Broadcasts a signed transaction and returns its hash.
+    pub(crate) async fn broadcast_signed(&mut self, tx: &SignedTx) -> Result<String> {
+        let hash = Self::broadcast_with(&mut self.tx_client, tx).await?;
+
+        // Bump up the local account sequence after successful tx.
+        self.account_sequence = Some(tx.sequence + 1);
+        Ok(hash)
+    }
+
+    /// Broadcasts a signed transaction with the given client and returns its hash.
+    ///
+    /// Unlike [`BaseClient::broadcast_signed`], the account sequence is left as is, so that
+    /// broadcasts don't need to hold the client.
+    pub(crate) async fn broadcast_with(
+        tx_client: &mut TxServiceClient<MeteredChannel>,
+        tx: &SignedTx,
+    ) -> Result<String> {
+        let request = cosmos_sdk_proto::cosmos::tx::v1beta1::BroadcastTxRequest {
+            tx_bytes: tx.tx_bytes.clone(),
             mode: 2, // BROADCAST_MODE_SYNC -> Wait for the tx to be processed, but not in-block
         };
-        let resp = self.tx_client.broadcast_tx(request).await?;
+        let resp = tx_client.broadcast_tx(request).await?;
         let resp = resp.into_inner();
         log::debug!("broadcast_tx response: {:#?}", resp);
         let tx_response = resp.tx_response.ok_or("Tx response not found")?;
         Self::assert_tx_success(&tx_response)?;
+        Ok(tx_response.txhash)
+    }
 
-        // Bump up the local account sequence after successful tx.
-        self.account_sequence = Some(sequence + 1);
-        let hash = tx_response.txhash;
-        Ok(hash)
+    /// Prepares an unsigned transaction for offline signing, e.g. by cosigners.
+    ///
+    /// The signer of the client, if set, is added as the only signer at the current account
+    /// sequence. The fee is calculated from the gas limit and the gas price.
+    ///
+    /// # Arguments
+    ///
+    /// * `msgs` - The messages of the transaction.
+    /// * `memo` - The memo to be included in the transaction.
+    /// * `gas_limit` - The gas limit of the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the unsigned transaction or an error.
+    pub async fn prepare_tx(
+        &mut self,
+        msgs: Vec<cosmrs::Any>,
+        memo: &str,
+        gas_limit: u64,
+    ) -> Result<Tx> {
+        let tx_body = cosmrs::tx::BodyBuilder::new()
+            .msgs(msgs)
+            .memo(memo)
+            .finish();
+        let gas_per_ucredit = (1.0 / self.gas_price).floor() as u128;
+        let fee = cosmrs::tx::Fee::from_amount_and_gas(
+            Coin {
+                denom: self.denom.parse()?,
+                amount: (gas_limit as u128 / gas_per_ucredit) + 1,
+            },
+            gas_limit,
+        );
+        let signer_infos = match self.pub_key {
+            Some(pub_key) => {
+                let (_, sequence) = self.get_account_details().await?;
+                vec![cosmrs::tx::SignerInfo::single_direct(
+                    Some(pub_key),
+                    sequence,
+                )]
+            }
+            None => Vec::new(),
+        };
+        let auth_info = cosmrs::tx::AuthInfo { signer_infos, fee };
+        Ok(Tx {
+            body: Some(tx_body.into_proto()),
+            auth_info: Some(auth_info.into_proto()),
+            signatures: Vec::new(),
+        })
+    }
+
+    /// Adds the signature of the client's signer to a transaction.
+    ///
+    /// The signer has to be listed in the signer infos of the transaction, signatures are kept
+    /// in the order of the signer infos.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The transaction, e.g. prepared with [`BaseClient::prepare_tx`] or imported.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the signed transaction or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no signer is set or if it's not a signer of the
+    /// transaction.
+    pub async fn sign_tx(&mut self, mut tx: Tx) -> Result<Tx> {
+        let pub_key = self.pub_key.ok_or("Public key not set")?;
+        let pub_key_any = pub_key.to_any()?;
+        let auth_info = tx.auth_info.clone().unwrap_or_default();
+        let index = auth_info
+            .signer_infos
+            .iter()
+            .position(|info| info.public_key.as_ref() == Some(&pub_key_any))
+            .ok_or_else(|| Error::Unknown("signer is not a signer of the tx".to_string()))?;
+        let (account_number, _) = self.get_account_details().await?;
+        let chain_id = self.chain_id.clone();
+        let sign_doc = cosmrs::tx::SignDoc {
+            body_bytes: tx.body.clone().unwrap_or_default().encode_to_vec(),
+            auth_info_bytes: auth_info.encode_to_vec(),
+            chain_id: chain_id.to_string(),
+            account_number,
+        };
+        let tx_raw = sign_doc.sign(self.priv_key.as_ref().ok_or("Private key not set")?)?;
+        let tx_raw =
+            cosmos_sdk_proto::cosmos::tx::v1beta1::TxRaw::decode(tx_raw.to_bytes()?.as_slice())?;
+        if tx.signatures.len() <= index {
+            tx.signatures.resize(index + 1, Vec::new());
+        }
+        tx.signatures[index] = tx_raw.signatures.into_iter().next().unwrap_or_default();
+        Ok(tx)
+    }
+
+    /// Broadcasts a transaction signed offline and returns its hash.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The signed transaction, e.g. imported from JSON.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the transaction hash or an error.
+    pub async fn broadcast_tx(&mut self, tx: &Tx) -> Result<String> {
+        let tx_raw = cosmos_sdk_proto::cosmos::tx::v1beta1::TxRaw {
+            body_bytes: tx.body.clone().unwrap_or_default().encode_to_vec(),
+            auth_info_bytes: tx.auth_info.clone().unwrap_or_default().encode_to_vec(),
+            signatures: tx.signatures.clone(),
+        };
+        let tx_bytes = tx_raw.encode_to_vec();
+        let sequence = self.account_sequence;
+        let signed = SignedTx {
+            hash: hex::encode_upper(Sha256::digest(&tx_bytes)),
+            tx_bytes,
+            sequence: 0,
+        };
+        let hash = self.broadcast_signed(&signed).await;
+        // The transaction isn't necessarily signed by this client
+        self.account_sequence = sequence;
+        hash
+    }
+
+    /// Returns the transaction limits from the consensus params of the chain.
+    ///
+    /// The limits are fetched once and cached for the lifetime of the client.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the limits or an error.
+    pub async fn chain_limits(&mut self) -> Result<ChainLimits> {
+        if let Some(limits) = self.chain_limits {
+            return Ok(limits);
+        }
+        let mut grpc = tonic::client::Grpc::new(self.channel.clone());
+        grpc.ready()
+            .await
+            .map_err(|e| Error::RpcConnectionError(e.to_string()))?;
+        let response: tonic::Response<QueryConsensusParamsResponse> = grpc
+            .unary(
+                tonic::Request::new(QueryConsensusParamsRequest {}),
+                http::uri::PathAndQuery::from_static("/cosmos.consensus.v1.Query/Params"),
+                tonic::codec::ProstCodec::default(),
+            )
+            .await?;
+        let block = response.into_inner().params.and_then(|params| params.block);
+        // Non-positive values mean unlimited
+        let positive = |value: i64| u64::try_from(value).ok().filter(|value| *value > 0);
+        let limits = ChainLimits {
+            max_tx_bytes: block.as_ref().and_then(|block| positive(block.max_bytes)),
+            max_block_gas: block.as_ref().and_then(|block| positive(block.max_gas)),
+        };
+        self.chain_limits = Some(limits);
+        Ok(limits)
+    }
+
+    // Checks a transaction against the chain limits before broadcasting it. Chains which don't
+    // expose their consensus params aren't checked.
+    async fn check_chain_limits(&mut self, tx_bytes: u64, gas: u64) -> Result<()> {
+        match self.chain_limits().await {
+            Ok(limits) => limits.check(tx_bytes, gas),
+            Err(e) => {
+                log::debug!("skipping chain limit checks: {}", e);
+                if !e.is_retryable() {
+                    self.chain_limits = Some(ChainLimits::default());
+                }
+                Ok(())
+            }
+        }
     }
 
     /// Sends a message and waits for the transaction to be included in a block.
@@ -355,19 +1230,79 @@ impl BaseClient {
         memo: &str,
     ) -> Result<R> {
         let hash = self.send_msg(msg, memo).await?;
-        self.wait_for_tx(&hash, Some(tokio::time::Duration::from_secs(10)))
+        let msg_responses = self.wait_for_msg_responses(&hash).await?;
+        match msg_responses.first() {
+            Some(msg_response) => Ok(R::decode(&msg_response.value[..])?),
+            None => Err(Error::Unknown("no response message".to_string())),
+        }
+    }
+
+    /// Sends several messages in a single transaction and waits for it to be included in a
+    /// block.
+    ///
+    /// # Arguments
+    ///
+    /// * `msgs` - The messages to be sent.
+    /// * `memo` - The memo to be included in the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the response messages in the order of the sent messages or an error.
+    pub async fn send_msgs_sync<M: Message + Name, R: Message + Default>(
+        &mut self,
+        msgs: &[M],
+        memo: &str,
+    ) -> Result<Vec<R>> {
+        let hash = self.send_msgs(msgs, memo).await?;
+        let msg_responses = self.wait_for_msg_responses(&hash).await?;
+        if msg_responses.len() != msgs.len() {
+            return Err(Error::Unknown(format!(
+                "expected {} response messages, got {}",
+                msgs.len(),
+                msg_responses.len()
+            )));
+        }
+        msg_responses
+            .iter()
+            .map(|msg_response| Ok(R::decode(&msg_response.value[..])?))
+            .collect()
+    }
+
+    /// Sends messages of different types in a single transaction and waits for it to be
+    /// included in a block.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the encoded response messages in the order of the sent messages or
+    /// an error.
+    pub(crate) async fn send_anys_sync(
+        &mut self,
+        msgs: Vec<cosmrs::Any>,
+        memo: &str,
+    ) -> Result<Vec<cosmrs::Any>> {
+        let count = msgs.len();
+        let hash = self.send_anys(msgs, memo).await?;
+        let msg_responses = self.wait_for_msg_responses(&hash).await?;
+        if msg_responses.len() != count {
+            return Err(Error::Unknown(format!(
+                "expected {} response messages, got {}",
+                count,
+                msg_responses.len()
+            )));
+        }
+        Ok(msg_responses)
+    }
+
+    /// Waits for a transaction to be included in a block and returns its response messages.
+    async fn wait_for_msg_responses(&mut self, hash: &str) -> Result<Vec<cosmrs::Any>> {
+        self.wait_for_tx(hash, Some(tokio::time::Duration::from_secs(10)))
             .await?;
-        let tx_response: TxResponse = self.get_tx_response(&hash).await?;
+        let tx_response: TxResponse = self.get_tx_response(hash).await?;
         Self::assert_tx_success(&tx_response)?;
         let tx_msg_data = cosmos_sdk_proto::cosmos::base::abci::v1beta1::TxMsgData::decode(
             &*hex::decode(tx_response.data)?,
         )?;
-        if tx_msg_data.msg_responses.is_empty() {
-            Err(Error::Unknown("no response message".to_string()))
-        } else {
-            let msg_response = &tx_msg_data.msg_responses[0];
-            Ok(R::decode(&msg_response.value[..])?)
-        }
+        Ok(tx_msg_data.msg_responses)
     }
 
     /// Checks if Tx did not failed with non-zero code.
@@ -379,14 +1314,9 @@ impl BaseClient {
     /// # Returns
     ///
     /// An empty Result or a Tx error.
-    fn assert_tx_success(tx_response: &TxResponse) -> Result<()> {
-        let (tx_hash, tx_code, raw_log) = (
-            tx_response.txhash.to_owned(),
-            tx_response.code,
-            tx_response.raw_log.to_owned(),
-        );
-        if tx_code != 0 {
-            return Err(Error::Tx(tx_hash, tx_code, raw_log));
+    pub(crate) fn assert_tx_success(tx_response: &TxResponse) -> Result<()> {
+        if tx_response.code != 0 {
+            return Err(Error::Tx(Box::new(TxError::from_response(tx_response))));
         }
 
         Ok(())
@@ -404,6 +1334,77 @@ impl BaseClient {
         Ok(block)
     }
 
+    /// Retrieves the identity and software versions of the node.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the NodeInfo or an error.
+    pub async fn node_info(&mut self) -> Result<NodeInfo> {
+        let request = cosmrs::proto::cosmos::base::tendermint::v1beta1::GetNodeInfoRequest {};
+        let response = self
+            .tendermint_client
+            .get_node_info(request)
+            .await?
+            .into_inner();
+        let node_info = response.default_node_info.unwrap_or_default();
+        let version = response.application_version.unwrap_or_default();
+        Ok(NodeInfo {
+            node_id: node_info.default_node_id,
+            moniker: node_info.moniker,
+            network: node_info.network,
+            version: node_info.version,
+            app_name: version.app_name,
+            app_version: version.version,
+            cosmos_sdk_version: version.cosmos_sdk_version,
+        })
+    }
+
+    /// Checks whether the node is still syncing blocks from its peers.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing true if the node is catching up or an error.
+    pub async fn syncing(&mut self) -> Result<bool> {
+        let request = cosmrs::proto::cosmos::base::tendermint::v1beta1::GetSyncingRequest {};
+        let response = self.tendermint_client.get_syncing(request).await?;
+        Ok(response.into_inner().syncing)
+    }
+
+    /// Retrieves the latest block known to the node and whether it is catching up.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the ChainStatus or an error.
+    pub async fn chain_status(&mut self) -> Result<ChainStatus> {
+        let block = self.current_block().await?;
+        Self::chain_status_of(block, self.syncing().await?)
+    }
+
+    // Describes the chain by its latest block
+    pub(crate) fn chain_status_of(block: Block, catching_up: bool) -> Result<ChainStatus> {
+        let header = block.header.ok_or("Block header not found")?;
+        let time = header.time.ok_or("Block time not found")?;
+        Ok(ChainStatus {
+            chain_id: header.chain_id,
+            latest_height: header.height,
+            latest_block_time: Self::system_time(&time)?,
+            catching_up,
+        })
+    }
+
+    // Converts a timestamp of the chain into a system time
+    fn system_time(
+        time: &cosmos_sdk_proto::tendermint::google::protobuf::Timestamp,
+    ) -> Result<std::time::SystemTime> {
+        u64::try_from(time.seconds)
+            .ok()
+            .map(|seconds| {
+                std::time::UNIX_EPOCH
+                    + std::time::Duration::new(seconds, u32::try_from(time.nanos).unwrap_or(0))
+            })
+            .ok_or_else(|| Error::Parse(format!("invalid block time {}", time.seconds)))
+    }
+
     /// Retrieves a block by its height.
     ///
     /// # Arguments
@@ -438,7 +1439,7 @@ impl BaseClient {
             .ok_or("Header not found")?
             .height;
         while current_height < height {
-            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            self.timer.sleep(tokio::time::Duration::from_secs(1)).await;
             current_block = self.current_block().await?;
             current_height = current_block
                 .header
@@ -467,6 +1468,28 @@ impl BaseClient {
         Ok(tx)
     }
 
+    /// Retrieves a transaction by its hash and decodes its messages.
+    ///
+    /// Messages are decoded with the decoders in the [`registry`](crate::registry), messages
+    /// of unregistered types are returned undecoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx_hash` - The hash of the transaction to be retrieved.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the messages of the transaction or an error.
+    pub async fn get_tx_decoded(&mut self, tx_hash: &str) -> Result<Vec<DecodedMsg>> {
+        let tx = self.get_tx(tx_hash).await?;
+        tx.body
+            .map(|body| body.messages)
+            .unwrap_or_default()
+            .iter()
+            .map(|msg| crate::registry::decode_msg(&msg.type_url, &msg.value))
+            .collect()
+    }
+
     /// Retrieves the transaction respotransport::httpnse by its hash.
     ///
     /// # Arguments
@@ -488,6 +1511,22 @@ impl BaseClient {
         Ok(tx_response)
     }
 
+    /// Retrieves the receipt of a transaction, with the fee charged for it.
+    ///
+    /// Failed transactions have a receipt too, as their fee is charged as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx_hash` - The hash of the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the TxReceipt or an error.
+    pub async fn get_tx_receipt(&mut self, tx_hash: &str) -> Result<TxReceipt> {
+        let tx_response = self.get_tx_response(tx_hash).await?;
+        TxReceipt::from_response(&tx_response)
+    }
+
     /// Waits for a transaction to be included in a block.
     ///
     /// # Arguments
@@ -503,17 +1542,17 @@ impl BaseClient {
         tx_hash: &str,
         timeout: Option<tokio::time::Duration>,
     ) -> Result<Tx> {
-        let start = std::time::Instant::now();
+        let start = self.timer.now();
         loop {
             let tx = match self.get_tx(tx_hash).await {
                 Ok(tx) => tx,
                 Err(e) => {
                     if let Some(timeout) = timeout {
-                        if start.elapsed() > timeout {
+                        if self.timer.now() - start > timeout {
                             return Err(e);
                         }
                     }
-                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    self.timer.sleep(tokio::time::Duration::from_secs(1)).await;
                     continue;
                 }
             };
@@ -521,3 +1560,22 @@ impl BaseClient {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_limits() {
+        let limits = ChainLimits {
+            max_tx_bytes: Some(1000),
+            max_block_gas: None,
+        };
+        assert!(limits.check(1000, u64::MAX).is_ok());
+        let Err(Error::ExceedsChainLimits { limit, value, max }) = limits.check(1001, 0) else {
+            panic!("tx should exceed the limits");
+        };
+        assert_eq!((limit, value, max), ("tx bytes", 1001, 1000));
+        assert!(ChainLimits::default().check(u64::MAX, u64::MAX).is_ok());
+    }
+}
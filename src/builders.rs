@@ -1,8 +1,11 @@
+use cosmos_sdk_proto::prost::{Message, Name};
 use derive_builder::Builder;
 
 use crate::{
     error::{Error, Result},
+    models::{unsigned, TaskValidator},
     proto::gevulot::gevulot::{self, InputContext, Label, OutputContext, TaskEnv},
+    runtime_config::{self, RuntimeConfig},
 };
 
 /// Enum representing different units of bytes.
@@ -68,6 +71,103 @@ impl From<(u64, ByteUnit)> for ByteSize {
     }
 }
 
+impl<K: Into<String>, V: Into<String>> From<(K, V)> for Label {
+    /// Converts a key-value pair to a Label.
+    fn from((key, value): (K, V)) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+        }
+    }
+}
+
+/// Label storage of a message builder, a list of labels or a map from key to value.
+trait LabelSet: Default {
+    /// Sets the value of the label with the given key.
+    fn set(&mut self, key: String, value: String);
+}
+
+impl LabelSet for Vec<Label> {
+    fn set(&mut self, key: String, value: String) {
+        match self.iter_mut().find(|label| label.key == key) {
+            Some(label) => label.value = value,
+            None => self.push(Label { key, value }),
+        }
+    }
+}
+
+impl LabelSet for std::collections::HashMap<String, String> {
+    fn set(&mut self, key: String, value: String) {
+        self.insert(key, value);
+    }
+}
+
+/// Implements the label and tag setters shared by the builders of messages with metadata.
+macro_rules! metadata_setters {
+    ($($builder:ty),* $(,)?) => {
+        $(
+            impl $builder {
+                /// Sets the labels from labels or key-value pairs, replacing the labels set
+                /// before.
+                pub fn labels<I, L>(&mut self, labels: I) -> &mut Self
+                where
+                    I: IntoIterator<Item = L>,
+                    L: Into<Label>,
+                {
+                    self.labels = Some(Default::default());
+                    for label in labels {
+                        let Label { key, value } = label.into();
+                        self.label(key, value);
+                    }
+                    self
+                }
+
+                /// Adds a label, replacing the value of a label with the same key.
+                pub fn label(
+                    &mut self,
+                    key: impl Into<String>,
+                    value: impl Into<String>,
+                ) -> &mut Self {
+                    self.labels
+                        .get_or_insert_with(Default::default)
+                        .set(key.into(), value.into());
+                    self
+                }
+
+                /// Sets the tags, replacing the tags set before.
+                pub fn tags<I, T>(&mut self, tags: I) -> &mut Self
+                where
+                    I: IntoIterator<Item = T>,
+                    T: Into<String>,
+                {
+                    self.tags = Some(Vec::new());
+                    for tag in tags {
+                        self.tag(tag);
+                    }
+                    self
+                }
+
+                /// Adds a tag unless it is set already.
+                pub fn tag(&mut self, tag: impl Into<String>) -> &mut Self {
+                    let tag = tag.into();
+                    let tags = self.tags.get_or_insert_with(Vec::new);
+                    if !tags.contains(&tag) {
+                        tags.push(tag);
+                    }
+                    self
+                }
+            }
+        )*
+    };
+}
+
+metadata_setters!(
+    MsgCreateTaskBuilder,
+    MsgCreatePinBuilder,
+    MsgCreateWorkerBuilder,
+    MsgUpdateWorkerBuilder,
+);
+
 #[derive(Builder)]
 pub struct MsgCreateTask {
     pub creator: String,
@@ -94,18 +194,79 @@ pub struct MsgCreateTask {
     pub store_stdout: bool,
     #[builder(default = "true")]
     pub store_stderr: bool,
-    #[builder(default = "std::collections::HashMap::new()")]
+    #[builder(default, setter(custom))]
     pub labels: std::collections::HashMap<String, String>,
-    #[builder(default = "Vec::new()")]
+    #[builder(default, setter(custom))]
     pub tags: Vec<String>,
+    /// Runtime configuration of the VM, sent in the task environment.
+    #[builder(default = "None", setter(strip_option))]
+    pub runtime_config: Option<RuntimeConfig>,
 }
 
 impl MsgCreateTaskBuilder {
+    /// Creates a builder pre-filled from a declarative task spec, resolving its resource units.
+    pub fn from_spec(creator: &str, spec: &crate::models::TaskSpec) -> Result<Self> {
+        let resources = &spec.resources;
+        let mut builder = Self::default();
+        builder
+            .creator(creator.to_string())
+            .image(spec.image.clone())
+            .command(spec.command.clone())
+            .args(spec.args.clone())
+            .env(
+                spec.env
+                    .iter()
+                    .map(|e| (e.name.clone(), e.value.clone()))
+                    .collect(),
+            )
+            .input_contexts(
+                spec.input_contexts
+                    .iter()
+                    .map(|ic| (ic.source.clone(), ic.target.clone()))
+                    .collect(),
+            )
+            .output_contexts(
+                spec.output_contexts
+                    .iter()
+                    .map(|oc| {
+                        let retention_period =
+                            unsigned("retentionPeriod", Ok(oc.retention_period))?;
+                        Ok((oc.source.clone(), retention_period))
+                    })
+                    .collect::<Result<_>>()?,
+            )
+            .cpus(unsigned("cpus", resources.cpus.millicores())?)
+            .gpus(unsigned("gpus", resources.gpus.millicores())?)
+            .memory(ByteSize::new(
+                unsigned("memory", resources.memory.bytes())?,
+                ByteUnit::Byte,
+            ))
+            .time(unsigned("time", resources.time.seconds())?)
+            .store_stdout(spec.store_stdout)
+            .store_stderr(spec.store_stderr);
+        if let Some(runtime_config) = &spec.runtime_config {
+            builder.runtime_config(runtime_config.clone());
+        }
+        Ok(builder)
+    }
+
+    /// Builds the message and validates it with the default [`TaskValidator`].
     pub fn into_message(&self) -> Result<gevulot::MsgCreateTask> {
-        let msg = self
+        self.into_message_with(&TaskValidator::default())
+    }
+
+    /// Builds the message and validates it with the given validator.
+    pub fn into_message_with(&self, validator: &TaskValidator) -> Result<gevulot::MsgCreateTask> {
+        let mut msg = self
             .build()
             .map_err(|e| Error::EncodeError(e.to_string()))?;
-        Ok(gevulot::MsgCreateTask {
+        if let Some(runtime_config) = &msg.runtime_config {
+            msg.env.insert(
+                runtime_config::TASK_ENV.to_string(),
+                runtime_config.to_env_value()?,
+            );
+        }
+        let msg = gevulot::MsgCreateTask {
             creator: msg.creator,
             image: msg.image,
             command: msg.command,
@@ -143,7 +304,9 @@ impl MsgCreateTaskBuilder {
                 .into_iter()
                 .map(|(k, v)| Label { key: k, value: v })
                 .collect(),
-        })
+        };
+        validator.validate_msg(&msg)?;
+        Ok(msg)
     }
 }
 
@@ -157,7 +320,9 @@ pub struct MsgCreatePin {
     pub time: u64,
     pub description: String,
     pub fallback_urls: Vec<String>,
+    #[builder(default, setter(custom))]
     pub tags: Vec<String>,
+    #[builder(default, setter(custom))]
     pub labels: Vec<Label>,
 }
 
@@ -179,6 +344,30 @@ impl MsgCreatePinBuilder {
             labels: msg.labels,
         })
     }
+
+    /// Builds the message after checking its fallback URLs with HEAD requests.
+    ///
+    /// Every URL has to answer with a success status and, if it reports a Content-Length, serve
+    /// exactly the declared number of bytes.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::FallbackUrls` with all problems found, or an error if
+    /// the message can't be built.
+    #[cfg(feature = "fallback-check")]
+    pub async fn into_message_checked(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<gevulot::MsgCreatePin> {
+        let msg = self.into_message()?;
+        let errors =
+            crate::fallback_check::check_fallback_urls(&msg.fallback_urls, msg.bytes, timeout)
+                .await;
+        if !errors.is_empty() {
+            return Err(Error::FallbackUrls(errors));
+        }
+        Ok(msg)
+    }
 }
 
 #[derive(Builder)]
@@ -201,6 +390,55 @@ impl MsgDeletePinBuilder {
     }
 }
 
+/// Extension of the retention of an existing pin.
+///
+/// The chain has no message changing a pin, so the pin is re-created: the messages delete the
+/// pin and create it again with the same spec and metadata but the new time. Send both in a
+/// single transaction so the data is never left unpinned.
+#[derive(Builder)]
+pub struct MsgExtendPin {
+    pub creator: String,
+    /// The pin to extend.
+    pub pin: gevulot::Pin,
+    /// Retention of the re-created pin in seconds, counted from its creation.
+    pub time: u64,
+}
+
+impl MsgExtendPinBuilder {
+    pub fn into_messages(&self) -> Result<(gevulot::MsgDeletePin, gevulot::MsgCreatePin)> {
+        let msg = self
+            .build()
+            .map_err(|e| Error::EncodeError(e.to_string()))?;
+        let metadata = msg.pin.metadata.unwrap_or_default();
+        let spec = msg.pin.spec.unwrap_or_default();
+        let cid = msg
+            .pin
+            .status
+            .map(|status| status.cid)
+            .filter(|cid| !cid.is_empty())
+            .unwrap_or_else(|| metadata.id.clone());
+        Ok((
+            gevulot::MsgDeletePin {
+                creator: msg.creator.clone(),
+                cid: cid.clone(),
+                id: metadata.id,
+            },
+            gevulot::MsgCreatePin {
+                creator: msg.creator,
+                cid,
+                bytes: spec.bytes,
+                name: metadata.name,
+                redundancy: spec.redundancy,
+                time: msg.time,
+                description: metadata.desc,
+                fallback_urls: spec.fallback_urls,
+                tags: metadata.tags,
+                labels: metadata.labels,
+            },
+        ))
+    }
+}
+
 #[derive(Builder)]
 pub struct MsgCreateWorker {
     pub creator: String,
@@ -210,11 +448,45 @@ pub struct MsgCreateWorker {
     pub gpus: u64,
     pub memory: ByteSize,
     pub disk: ByteSize,
+    #[builder(default, setter(custom))]
     pub labels: Vec<Label>,
+    #[builder(default, setter(custom))]
     pub tags: Vec<String>,
 }
 
 impl MsgCreateWorkerBuilder {
+    /// Creates a builder pre-filled from a declarative worker spec and its metadata, resolving
+    /// the resource units.
+    pub fn from_spec(
+        creator: &str,
+        spec: &crate::models::WorkerSpec,
+        metadata: &crate::models::Metadata,
+    ) -> Result<Self> {
+        let mut builder = Self::default();
+        builder
+            .creator(creator.to_string())
+            .name(metadata.name.clone())
+            .description(metadata.description.clone())
+            .cpus(unsigned("cpus", spec.cpus.millicores())?)
+            .gpus(unsigned("gpus", spec.gpus.millicores())?)
+            .memory(ByteSize::new(
+                unsigned("memory", spec.memory.bytes())?,
+                ByteUnit::Byte,
+            ))
+            .disk(ByteSize::new(
+                unsigned("disk", spec.disk.bytes())?,
+                ByteUnit::Byte,
+            ))
+            .labels(
+                metadata
+                    .labels
+                    .iter()
+                    .map(|label| (label.key.as_str(), label.value.as_str())),
+            )
+            .tags(&metadata.tags);
+        Ok(builder)
+    }
+
     pub fn into_message(&self) -> Result<gevulot::MsgCreateWorker> {
         let msg = self
             .build()
@@ -233,6 +505,68 @@ impl MsgCreateWorkerBuilder {
     }
 }
 
+#[derive(Builder)]
+pub struct MsgUpdateWorker {
+    pub creator: String,
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub cpus: u64,
+    pub gpus: u64,
+    pub memory: ByteSize,
+    pub disk: ByteSize,
+    #[builder(default, setter(custom))]
+    pub labels: Vec<Label>,
+    #[builder(default, setter(custom))]
+    pub tags: Vec<String>,
+}
+
+impl MsgUpdateWorkerBuilder {
+    /// Creates a builder pre-filled from a declarative worker spec and its metadata, resolving
+    /// the resource units.
+    pub fn from_spec(
+        creator: &str,
+        id: &str,
+        spec: &crate::models::WorkerSpec,
+        metadata: &crate::models::Metadata,
+    ) -> Result<Self> {
+        let create = MsgCreateWorkerBuilder::from_spec(creator, spec, metadata)?
+            .build()
+            .map_err(|e| Error::EncodeError(e.to_string()))?;
+        let mut builder = Self::default();
+        builder
+            .creator(create.creator)
+            .id(id.to_string())
+            .name(create.name)
+            .description(create.description)
+            .cpus(create.cpus)
+            .gpus(create.gpus)
+            .memory(create.memory)
+            .disk(create.disk)
+            .labels(create.labels)
+            .tags(create.tags);
+        Ok(builder)
+    }
+
+    pub fn into_message(&self) -> Result<gevulot::MsgUpdateWorker> {
+        let msg = self
+            .build()
+            .map_err(|e| Error::EncodeError(e.to_string()))?;
+        Ok(gevulot::MsgUpdateWorker {
+            creator: msg.creator,
+            id: msg.id,
+            name: msg.name,
+            description: msg.description,
+            cpus: msg.cpus,
+            gpus: msg.gpus,
+            memory: msg.memory.to_bytes(),
+            disk: msg.disk.to_bytes(),
+            labels: msg.labels,
+            tags: msg.tags,
+        })
+    }
+}
+
 #[derive(Builder)]
 pub struct MsgDeleteWorker {
     pub creator: String,
@@ -472,3 +806,201 @@ impl MsgDeleteTaskBuilder {
         })
     }
 }
+
+/// Messages of different types sent atomically in a single transaction.
+///
+/// The messages are executed in the order they were added. If one of them fails, none of them
+/// takes effect, e.g. a task is only created together with the pin of its input:
+///
+/// ```no_run
+/// # use gevulot_rs::builders::MsgBatch;
+/// # use gevulot_rs::proto::gevulot::gevulot::{MsgCreatePin, MsgCreateTask};
+/// # async fn run(
+/// #     client: &mut gevulot_rs::base_client::BaseClient,
+/// #     pin: MsgCreatePin,
+/// #     task: MsgCreateTask,
+/// # ) -> gevulot_rs::Result<()> {
+/// let batch = MsgBatch::new().msg(&pin)?.msg(&task)?;
+/// let responses = client.send_batch_sync(batch, "").await?;
+/// let task_id = responses
+///     .decode::<gevulot_rs::proto::gevulot::gevulot::MsgCreateTaskResponse>(1)?
+///     .id;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MsgBatch {
+    msgs: Vec<cosmrs::Any>,
+}
+
+impl MsgBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a message to the batch.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the message can't be encoded.
+    pub fn msg<M: Message + Name>(mut self, msg: &M) -> Result<Self> {
+        self.msgs.push(cosmrs::Any::from_msg(msg)?);
+        Ok(self)
+    }
+
+    /// Adds an already encoded message to the batch.
+    pub fn any(mut self, msg: cosmrs::Any) -> Self {
+        self.msgs.push(msg);
+        self
+    }
+
+    /// Returns the number of messages in the batch.
+    pub fn len(&self) -> usize {
+        self.msgs.len()
+    }
+
+    /// Returns true if the batch has no messages.
+    pub fn is_empty(&self) -> bool {
+        self.msgs.is_empty()
+    }
+
+    /// Returns the encoded messages in order.
+    pub fn into_anys(self) -> Vec<cosmrs::Any> {
+        self.msgs
+    }
+}
+
+impl From<Vec<cosmrs::Any>> for MsgBatch {
+    fn from(msgs: Vec<cosmrs::Any>) -> Self {
+        Self { msgs }
+    }
+}
+
+/// Responses to the messages of a [`MsgBatch`], in the order of the messages.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MsgBatchResponses {
+    responses: Vec<cosmrs::Any>,
+}
+
+impl MsgBatchResponses {
+    pub(crate) fn new(responses: Vec<cosmrs::Any>) -> Self {
+        Self { responses }
+    }
+
+    /// Decodes the response to the message with the given index.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if there is no response with the index, or if it is
+    /// of another type.
+    pub fn decode<R: Message + Name + Default>(&self, index: usize) -> Result<R> {
+        let response = self.responses.get(index).ok_or_else(|| {
+            Error::DecodeError(format!(
+                "no response to message {} of {}",
+                index,
+                self.responses.len()
+            ))
+        })?;
+        if response.type_url != R::type_url() {
+            return Err(Error::DecodeError(format!(
+                "expected {} in response to message {}, got {}",
+                R::type_url(),
+                index,
+                response.type_url
+            )));
+        }
+        Ok(R::decode(&response.value[..])?)
+    }
+
+    /// Returns the number of responses.
+    pub fn len(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Returns true if there are no responses.
+    pub fn is_empty(&self) -> bool {
+        self.responses.is_empty()
+    }
+
+    /// Returns the encoded responses in order.
+    pub fn into_anys(self) -> Vec<cosmrs::Any> {
+        self.responses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_setters() {
+        let msg = MsgCreateTaskBuilder::default()
+            .creator("alice".to_string())
+            .image("prover:latest".to_string())
+            .labels([("team", "zk"), ("env", "dev")])
+            .label("env", "prod")
+            .tags(vec!["gpu".to_string()])
+            .tag("fast")
+            .tag("gpu")
+            .into_message()
+            .unwrap();
+        let mut labels: Vec<_> = msg
+            .labels
+            .iter()
+            .map(|label| (label.key.as_str(), label.value.as_str()))
+            .collect();
+        labels.sort();
+        assert_eq!(labels, vec![("env", "prod"), ("team", "zk")]);
+        assert_eq!(msg.tags, vec!["gpu", "fast"]);
+
+        let labels = vec![Label {
+            key: "region".to_string(),
+            value: "eu".to_string(),
+        }];
+        let msg = MsgCreatePinBuilder::default()
+            .creator("alice".to_string())
+            .cid(None)
+            .bytes((1, ByteUnit::Megabyte).into())
+            .name("data".to_string())
+            .redundancy(1)
+            .time(3600)
+            .description(String::new())
+            .fallback_urls(Vec::new())
+            .labels(labels)
+            .into_message()
+            .unwrap();
+        assert_eq!(msg.labels[0].key, "region");
+        assert!(msg.tags.is_empty());
+    }
+
+    #[test]
+    fn test_msg_batch() {
+        let delete = MsgDeleteTaskBuilder::default()
+            .creator("alice".to_string())
+            .id("task".to_string())
+            .into_message()
+            .unwrap();
+        let batch = MsgBatch::new()
+            .msg(&delete)
+            .unwrap()
+            .any(cosmrs::Any::from_msg(&gevulot::MsgDeletePin::default()).unwrap());
+        assert_eq!(batch.len(), 2);
+        let anys = batch.into_anys();
+        assert_eq!(anys[0].type_url, "/gevulot.gevulot.MsgDeleteTask");
+        assert_eq!(anys[1].type_url, "/gevulot.gevulot.MsgDeletePin");
+
+        let response = gevulot::MsgCreateTaskResponse {
+            id: "task".to_string(),
+        };
+        let responses = MsgBatchResponses::new(vec![cosmrs::Any::from_msg(&response).unwrap()]);
+        let decoded: gevulot::MsgCreateTaskResponse = responses.decode(0).unwrap();
+        assert_eq!(decoded.id, "task");
+        assert!(responses
+            .decode::<gevulot::MsgCreatePinResponse>(0)
+            .is_err());
+        assert!(responses
+            .decode::<gevulot::MsgCreateTaskResponse>(1)
+            .is_err());
+    }
+}
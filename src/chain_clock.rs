@@ -0,0 +1,171 @@
+//! Estimation of the chain time from recent block headers.
+//!
+//! Timestamps on the chain, e.g. when a task was created or a pin was acked, are block times
+//! set by the validators. Comparing them with the local clock goes wrong when the local clock
+//! is skewed. The [`ChainClock`] of the [`BaseClient`](crate::base_client::BaseClient) estimates
+//! the chain time from recent block headers and is used by all helpers comparing chain
+//! timestamps with the current time, e.g. `PinClient::list_expiring_within` and
+//! `WorkerClient::find_available`.
+//!
+//! The latest block is at most one block interval old when its header is fetched, so the
+//! smallest difference between the local time and the block time over recent headers
+//! approximates the skew of the local clock. Skews within the tolerance are ignored and the
+//! local time is used as is.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+use tokio::time::Instant;
+
+/// Skew of the local clock ignored unless set otherwise.
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// Age after which the estimate is updated from a new header unless set otherwise.
+pub const DEFAULT_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+// Number of recent headers the skew is estimated from
+const SAMPLES: usize = 8;
+
+/// Estimate of the chain time from recent block headers.
+#[derive(Clone, Debug)]
+pub struct ChainClock {
+    tolerance: Duration,
+    sync_interval: Duration,
+    // Local time minus block time in milliseconds, for the latest headers
+    offsets: VecDeque<i64>,
+    synced_at: Option<Instant>,
+}
+
+impl Default for ChainClock {
+    fn default() -> Self {
+        Self::new(DEFAULT_TOLERANCE)
+    }
+}
+
+impl ChainClock {
+    /// Creates a clock ignoring skews up to the tolerance.
+    pub fn new(tolerance: Duration) -> Self {
+        Self {
+            tolerance,
+            sync_interval: DEFAULT_SYNC_INTERVAL,
+            offsets: VecDeque::with_capacity(SAMPLES),
+            synced_at: None,
+        }
+    }
+
+    /// Sets the skew of the local clock which is ignored.
+    pub fn set_tolerance(&mut self, tolerance: Duration) {
+        self.tolerance = tolerance;
+    }
+
+    /// Returns the skew of the local clock which is ignored.
+    pub fn tolerance(&self) -> Duration {
+        self.tolerance
+    }
+
+    /// Sets the age after which the estimate is updated from a new header.
+    pub fn set_sync_interval(&mut self, interval: Duration) {
+        self.sync_interval = interval;
+    }
+
+    /// Returns true if no header was recorded yet or the last one is older than the sync
+    /// interval.
+    pub fn needs_sync(&self, now: Instant) -> bool {
+        self.synced_at.map_or(true, |synced_at| {
+            now.duration_since(synced_at) >= self.sync_interval
+        })
+    }
+
+    /// Records the time of a block header fetched at the local time.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_time` - The time in the header.
+    /// * `local_time` - The local time the header was fetched at.
+    /// * `now` - The instant the header was fetched at, to tell when to sync again.
+    pub fn record(&mut self, block_time: SystemTime, local_time: SystemTime, now: Instant) {
+        let offset = match local_time.duration_since(block_time) {
+            Ok(ahead) => millis(ahead),
+            Err(behind) => -millis(behind.duration()),
+        };
+        if self.offsets.len() == SAMPLES {
+            self.offsets.pop_front();
+        }
+        self.offsets.push_back(offset);
+        self.synced_at = Some(now);
+    }
+
+    /// Returns how far the local clock is ahead of the chain in milliseconds, negative if it is
+    /// behind, None if no header was recorded yet.
+    pub fn skew_millis(&self) -> Option<i64> {
+        self.offsets.iter().copied().min()
+    }
+
+    /// Returns true if the local clock is skewed by more than the tolerance.
+    pub fn is_skewed(&self) -> bool {
+        self.skew_millis()
+            .is_some_and(|skew| skew.unsigned_abs() > millis(self.tolerance) as u64)
+    }
+
+    /// Estimates the chain time at the given local time.
+    ///
+    /// The local time is returned as is if the skew is within the tolerance or unknown.
+    pub fn chain_time(&self, local_time: SystemTime) -> SystemTime {
+        match self.skew_millis() {
+            Some(skew) if self.is_skewed() => {
+                let skew_duration = Duration::from_millis(skew.unsigned_abs());
+                if skew > 0 {
+                    local_time - skew_duration
+                } else {
+                    local_time + skew_duration
+                }
+            }
+            _ => local_time,
+        }
+    }
+}
+
+fn millis(duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_clock() {
+        let mut clock = ChainClock::default();
+        let now = Instant::now();
+        let local = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert!(clock.needs_sync(now));
+        assert_eq!(clock.skew_millis(), None);
+        assert_eq!(clock.chain_time(local), local);
+
+        // The local clock is 10s ahead, the headers are up to 6s old
+        for age in [3, 6, 0, 1] {
+            clock.record(local - Duration::from_secs(10 + age), local, now);
+        }
+        assert_eq!(clock.skew_millis(), Some(10_000));
+        assert!(clock.is_skewed());
+        assert_eq!(clock.chain_time(local), local - Duration::from_secs(10));
+        assert!(!clock.needs_sync(now + Duration::from_secs(30)));
+        assert!(clock.needs_sync(now + DEFAULT_SYNC_INTERVAL));
+
+        // Skews within the tolerance are ignored
+        clock.set_tolerance(Duration::from_secs(15));
+        assert!(!clock.is_skewed());
+        assert_eq!(clock.chain_time(local), local);
+
+        // Behind the chain, old headers are dropped
+        let mut clock = ChainClock::default();
+        clock.record(local + Duration::from_secs(60), local, now);
+        for _ in 0..SAMPLES {
+            clock.record(local + Duration::from_secs(5), local, now);
+        }
+        assert_eq!(clock.skew_millis(), Some(-5_000));
+        assert_eq!(clock.chain_time(local), local + Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,431 @@
+//! Compression of gRPC messages and metrics about the bytes saved by it.
+//!
+//! Compression is negotiated with the server: requests are only compressed when a send
+//! compression is configured, responses only when the server supports one of the accepted
+//! encodings. Every gRPC message passing through a [`MeteredChannel`] is counted with its size on
+//! the wire and its uncompressed size.
+
+use std::{
+    future::Future,
+    io::Read,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use http_body::{Body, Frame, SizeHint};
+use tonic::{body::BoxBody, codec::CompressionEncoding};
+use tower::{Service, ServiceExt};
+
+use crate::concurrency::{ConcurrencyLimit, ConcurrencyPermit};
+use crate::failover::FailoverChannel;
+use crate::query_retry::{self, BufferedBody, QueryRetryPolicy};
+
+/// Compression algorithms supported for gRPC messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression for a `grpc-encoding` header value.
+    pub fn from_header(value: &str) -> Option<Self> {
+        match value {
+            "gzip" => Some(Compression::Gzip),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Returns the size of a message compressed with this algorithm once decompressed.
+    fn decompressed_len(&self, data: &[u8]) -> std::io::Result<u64> {
+        let mut reader: Box<dyn Read + '_> = match self {
+            Compression::Gzip => Box::new(flate2::read::GzDecoder::new(data)),
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(data)?),
+        };
+        std::io::copy(&mut reader, &mut std::io::sink())
+    }
+}
+
+impl From<Compression> for CompressionEncoding {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::Gzip => CompressionEncoding::Gzip,
+            Compression::Zstd => CompressionEncoding::Zstd,
+        }
+    }
+}
+
+/// Compression settings applied to all gRPC clients.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompressionConfig {
+    /// Compression used for requests, None sends requests uncompressed.
+    pub send: Option<Compression>,
+    /// Compressions the server may use for responses.
+    pub accept: Vec<Compression>,
+}
+
+/// Snapshot of the compression metrics.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    pub messages_sent: u64,
+    /// Bytes of sent messages as transferred, after compression.
+    pub wire_bytes_sent: u64,
+    /// Bytes of sent messages before compression.
+    pub raw_bytes_sent: u64,
+    pub messages_received: u64,
+    /// Bytes of received messages as transferred, before decompression.
+    pub wire_bytes_received: u64,
+    /// Bytes of received messages after decompression.
+    pub raw_bytes_received: u64,
+}
+
+impl CompressionStats {
+    /// Ratio of wire bytes to raw bytes over both directions, 1.0 if nothing was compressed.
+    pub fn ratio(&self) -> f64 {
+        let raw = self.raw_bytes_sent + self.raw_bytes_received;
+        if raw == 0 {
+            return 1.0;
+        }
+        (self.wire_bytes_sent + self.wire_bytes_received) as f64 / raw as f64
+    }
+}
+
+#[derive(Debug, Default)]
+struct DirectionCounters {
+    messages: AtomicU64,
+    wire_bytes: AtomicU64,
+    raw_bytes: AtomicU64,
+}
+
+/// Shared counters of the bytes sent and received through a [`MeteredChannel`].
+#[derive(Clone, Debug, Default)]
+pub struct CompressionMetrics {
+    sent: Arc<DirectionCounters>,
+    received: Arc<DirectionCounters>,
+}
+
+impl CompressionMetrics {
+    /// Returns the current values of the counters.
+    pub fn snapshot(&self) -> CompressionStats {
+        CompressionStats {
+            messages_sent: self.sent.messages.load(Ordering::Relaxed),
+            wire_bytes_sent: self.sent.wire_bytes.load(Ordering::Relaxed),
+            raw_bytes_sent: self.sent.raw_bytes.load(Ordering::Relaxed),
+            messages_received: self.received.messages.load(Ordering::Relaxed),
+            wire_bytes_received: self.received.wire_bytes.load(Ordering::Relaxed),
+            raw_bytes_received: self.received.raw_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Splits a stream of body chunks into gRPC messages and counts them.
+///
+/// Every gRPC message is prefixed by a compressed flag byte and a 4 byte big endian length.
+struct MessageCounter {
+    encoding: Option<Compression>,
+    counters: Arc<DirectionCounters>,
+    header: [u8; 5],
+    header_len: usize,
+    remaining: usize,
+    payload: Vec<u8>,
+}
+
+impl MessageCounter {
+    fn new(encoding: Option<Compression>, counters: Arc<DirectionCounters>) -> Self {
+        Self {
+            encoding,
+            counters,
+            header: [0; 5],
+            header_len: 0,
+            remaining: 0,
+            payload: Vec::new(),
+        }
+    }
+
+    fn compressed(&self) -> bool {
+        self.header[0] == 1
+    }
+
+    fn message_len(&self) -> u32 {
+        u32::from_be_bytes([
+            self.header[1],
+            self.header[2],
+            self.header[3],
+            self.header[4],
+        ])
+    }
+
+    fn feed(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.header_len < self.header.len() {
+                let take = (self.header.len() - self.header_len).min(data.len());
+                self.header[self.header_len..self.header_len + take].copy_from_slice(&data[..take]);
+                self.header_len += take;
+                data = &data[take..];
+                if self.header_len == self.header.len() {
+                    self.remaining = self.message_len() as usize;
+                    self.payload.clear();
+                    if self.remaining == 0 {
+                        self.finish_message();
+                    }
+                }
+                continue;
+            }
+
+            let take = self.remaining.min(data.len());
+            // Only compressed messages need to be kept around to find out their raw size
+            if self.compressed() {
+                self.payload.extend_from_slice(&data[..take]);
+            }
+            self.remaining -= take;
+            data = &data[take..];
+            if self.remaining == 0 {
+                self.finish_message();
+            }
+        }
+    }
+
+    fn finish_message(&mut self) {
+        let wire_len = self.message_len() as u64;
+        let raw_len = match (self.compressed(), self.encoding) {
+            (true, Some(encoding)) => encoding.decompressed_len(&self.payload).unwrap_or(wire_len),
+            _ => wire_len,
+        };
+        self.counters.messages.fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .wire_bytes
+            .fetch_add(wire_len, Ordering::Relaxed);
+        self.counters
+            .raw_bytes
+            .fetch_add(raw_len, Ordering::Relaxed);
+        self.header_len = 0;
+        self.payload.clear();
+    }
+}
+
+/// HTTP body counting the gRPC messages passing through it.
+struct MeteredBody {
+    inner: BoxBody,
+    counter: MessageCounter,
+    // Keeps the request's concurrency slot until the response was read completely
+    _permit: Option<ConcurrencyPermit>,
+}
+
+impl Body for MeteredBody {
+    type Data = Bytes;
+    type Error = tonic::Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = &mut *self;
+        let result = Pin::new(&mut this.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &result {
+            if let Some(data) = frame.data_ref() {
+                this.counter.feed(data);
+            }
+        }
+        result
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+fn encoding<T>(message: &http::HeaderMap<T>) -> Option<Compression>
+where
+    T: AsRef<[u8]>,
+{
+    message
+        .get("grpc-encoding")
+        .and_then(|value| std::str::from_utf8(value.as_ref()).ok())
+        .and_then(Compression::from_header)
+}
+
+/// A tonic [`Channel`](tonic::transport::Channel) counting the gRPC messages sent and received
+/// through it.
+///
+/// Also enforces the concurrency limit of the connection and retries calls according to its
+/// retry policy, both are shared by all clones. Requests go to the active endpoint of the
+/// [`FailoverChannel`] it wraps.
+#[doc(hidden)]
+#[derive(Clone, Debug)]
+pub struct MeteredChannel {
+    inner: FailoverChannel,
+    metrics: CompressionMetrics,
+    concurrency_limit: Arc<std::sync::RwLock<Option<ConcurrencyLimit>>>,
+    retry_policy: Arc<std::sync::RwLock<Option<QueryRetryPolicy>>>,
+}
+
+impl MeteredChannel {
+    /// Wraps a channel.
+    pub fn new(inner: impl Into<FailoverChannel>) -> Self {
+        Self {
+            inner: inner.into(),
+            metrics: CompressionMetrics::default(),
+            concurrency_limit: Arc::default(),
+            retry_policy: Arc::default(),
+        }
+    }
+
+    /// Returns the endpoints of the channel.
+    pub fn failover(&self) -> &FailoverChannel {
+        &self.inner
+    }
+
+    /// Returns the metrics of the channel.
+    pub fn metrics(&self) -> &CompressionMetrics {
+        &self.metrics
+    }
+
+    /// Sets the concurrency limit for this channel and all its clones.
+    pub fn set_concurrency_limit(&self, limit: Option<ConcurrencyLimit>) {
+        *self
+            .concurrency_limit
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = limit;
+    }
+
+    /// Returns the concurrency limit of the channel.
+    pub fn concurrency_limit(&self) -> Option<ConcurrencyLimit> {
+        self.concurrency_limit
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Sets the retry policy of calls for this channel and all its clones.
+    pub fn set_retry_policy(&self, policy: Option<QueryRetryPolicy>) {
+        *self.retry_policy.write().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
+    /// Returns the retry policy of calls of the channel.
+    pub fn retry_policy(&self) -> Option<QueryRetryPolicy> {
+        *self.retry_policy.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Service<http::Request<BoxBody>> for MeteredChannel {
+    type Response = http::Response<BoxBody>;
+    type Error = tonic::transport::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        #[cfg(feature = "otel")]
+        let mut request = request;
+        #[cfg(feature = "otel")]
+        crate::otel::inject_trace_context(request.headers_mut());
+
+        let counter = MessageCounter::new(encoding(request.headers()), self.metrics.sent.clone());
+        let request = request.map(|inner| {
+            tonic::body::boxed(MeteredBody {
+                inner,
+                counter,
+                _permit: None,
+            })
+        });
+        let received = self.metrics.received.clone();
+        let concurrency_limit = self.concurrency_limit();
+        let retry_policy = self
+            .retry_policy()
+            .filter(|policy| policy.applies_to(request.uri().path()));
+        // Take the channel which was polled ready, leaving a fresh clone behind
+        let fresh = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, fresh);
+
+        Box::pin(async move {
+            let permit = match concurrency_limit {
+                Some(limit) => Some(limit.acquire().await),
+                None => None,
+            };
+            let response = match retry_policy {
+                None => inner.call(request).await?,
+                Some(policy) => {
+                    // The body is read once, through the counter, and sent again on retries
+                    let path = request.uri().path().to_owned();
+                    let (parts, body) = request.into_parts();
+                    match BufferedBody::read(body).await {
+                        Ok(data) => {
+                            query_retry::call_with_retry(&policy, &path, || {
+                                let request = http::Request::from_parts(
+                                    parts.clone(),
+                                    BufferedBody::new(data.clone()),
+                                );
+                                let mut channel = inner.clone();
+                                async move { channel.ready().await?.call(request).await }
+                            })
+                            .await?
+                        }
+                        Err(status) => {
+                            let request =
+                                http::Request::from_parts(parts, BufferedBody::failed(status));
+                            inner.call(request).await?
+                        }
+                    }
+                }
+            };
+            let counter = MessageCounter::new(encoding(response.headers()), received);
+            Ok(response.map(|inner| {
+                tonic::body::boxed(MeteredBody {
+                    inner,
+                    counter,
+                    _permit: permit,
+                })
+            }))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn frame(compressed: bool, payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![compressed as u8];
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[test]
+    fn test_message_counter() {
+        let raw = vec![7u8; 1000];
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&raw).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut data = frame(false, b"hello");
+        data.extend(frame(true, &compressed));
+
+        let metrics = CompressionMetrics::default();
+        let mut counter = MessageCounter::new(Some(Compression::Gzip), metrics.received.clone());
+        // Feed in small chunks to split headers and payloads across chunks
+        for chunk in data.chunks(3) {
+            counter.feed(chunk);
+        }
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.messages_received, 2);
+        assert_eq!(stats.wire_bytes_received, 5 + compressed.len() as u64);
+        assert_eq!(stats.raw_bytes_received, 5 + 1000);
+        assert!(stats.ratio() < 1.0);
+    }
+}
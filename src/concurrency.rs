@@ -0,0 +1,158 @@
+//! Limiting of concurrent gRPC requests.
+//!
+//! Bulk operations can easily start hundreds of requests at once, which trips the limits of
+//! most nodes. A [`ConcurrencyLimit`] caps the number of requests in flight over all clients
+//! sharing a connection and keeps metrics about how often requests had to wait for a slot.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Snapshot of the metrics of a [`ConcurrencyLimit`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConcurrencyStats {
+    /// Maximum number of requests in flight at the same time.
+    pub limit: usize,
+    /// Number of requests currently in flight.
+    pub in_flight: u64,
+    /// Highest number of requests in flight at the same time so far.
+    pub max_in_flight: u64,
+    /// Number of requests started so far.
+    pub requests: u64,
+    /// Number of requests which had to wait because the limit was reached.
+    pub saturated_requests: u64,
+    /// Total time requests spent waiting for a slot.
+    pub total_wait: Duration,
+}
+
+#[derive(Debug)]
+struct Inner {
+    semaphore: Arc<Semaphore>,
+    limit: usize,
+    in_flight: AtomicU64,
+    max_in_flight: AtomicU64,
+    requests: AtomicU64,
+    saturated_requests: AtomicU64,
+    wait_nanos: AtomicU64,
+}
+
+/// Shared budget of concurrent requests.
+#[derive(Clone, Debug)]
+pub struct ConcurrencyLimit {
+    inner: Arc<Inner>,
+}
+
+impl ConcurrencyLimit {
+    /// Creates a budget allowing `limit` requests in flight at the same time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is 0.
+    pub fn new(limit: usize) -> Self {
+        assert!(limit > 0, "concurrency limit must be at least 1");
+        Self {
+            inner: Arc::new(Inner {
+                semaphore: Arc::new(Semaphore::new(limit)),
+                limit,
+                in_flight: AtomicU64::new(0),
+                max_in_flight: AtomicU64::new(0),
+                requests: AtomicU64::new(0),
+                saturated_requests: AtomicU64::new(0),
+                wait_nanos: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Waits for a free slot. The slot is released when the returned permit is dropped.
+    pub(crate) async fn acquire(&self) -> ConcurrencyPermit {
+        let inner = &self.inner;
+        let permit = match inner.semaphore.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                inner.saturated_requests.fetch_add(1, Ordering::Relaxed);
+                let start = tokio::time::Instant::now();
+                let permit = inner
+                    .semaphore
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .expect("semaphore is never closed");
+                inner
+                    .wait_nanos
+                    .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                permit
+            }
+        };
+        inner.requests.fetch_add(1, Ordering::Relaxed);
+        let in_flight = inner.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+        inner.max_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+        ConcurrencyPermit {
+            _permit: permit,
+            inner: self.inner.clone(),
+        }
+    }
+
+    /// Returns the current values of the metrics.
+    pub fn stats(&self) -> ConcurrencyStats {
+        let inner = &self.inner;
+        ConcurrencyStats {
+            limit: inner.limit,
+            in_flight: inner.in_flight.load(Ordering::Relaxed),
+            max_in_flight: inner.max_in_flight.load(Ordering::Relaxed),
+            requests: inner.requests.load(Ordering::Relaxed),
+            saturated_requests: inner.saturated_requests.load(Ordering::Relaxed),
+            total_wait: Duration::from_nanos(inner.wait_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A slot of a [`ConcurrencyLimit`], held while a request is in flight.
+#[derive(Debug)]
+pub(crate) struct ConcurrencyPermit {
+    _permit: OwnedSemaphorePermit,
+    inner: Arc<Inner>,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.inner.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_concurrency_limit() {
+        let limit = ConcurrencyLimit::new(2);
+        let first = limit.acquire().await;
+        let second = limit.acquire().await;
+        assert_eq!(limit.stats().in_flight, 2);
+
+        let waiting = tokio::spawn({
+            let limit = limit.clone();
+            async move { limit.acquire().await }
+        });
+        tokio::task::yield_now().await;
+        drop(first);
+        let third = waiting.await.unwrap();
+
+        let stats = limit.stats();
+        assert_eq!(stats.limit, 2);
+        assert_eq!(stats.in_flight, 2);
+        assert_eq!(stats.max_in_flight, 2);
+        assert_eq!(stats.requests, 3);
+        assert_eq!(stats.saturated_requests, 1);
+
+        drop(second);
+        drop(third);
+        assert_eq!(limit.stats().in_flight, 0);
+    }
+}
@@ -10,26 +10,408 @@ pub enum Error {
     InvalidEventAttribute(&'static str),
     #[error("unknown event kind: {0}")]
     UnknownEventKind(String),
+    #[error("malformed event: {0}")]
+    MalformedEvent(Box<Error>),
     #[error("rpc connection error: {0}")]
     RpcConnectionError(String),
     #[error("decode error: {0}")]
     DecodeError(String),
     #[error("encode error: {0}")]
     EncodeError(String),
-    #[error("not found")]
-    NotFound,
+    #[error("not found{}", .0.as_ref().map(|s| format!(": {}", s.message())).unwrap_or_default())]
+    NotFound(Option<Box<tonic::Status>>),
+    #[error("permission denied: {}", .0.message())]
+    PermissionDenied(Box<tonic::Status>),
+    #[error("service unavailable: {}", .0.message())]
+    Unavailable(Box<tonic::Status>),
+    #[error("deadline exceeded: {}", .0.message())]
+    DeadlineExceeded(Box<tonic::Status>),
+    #[error("resource exhausted: {}", .0.message())]
+    ResourceExhausted(Box<tonic::Status>),
+    #[error("rpc error: {0}")]
+    Status(Box<tonic::Status>),
     #[error("parse error: {0}")]
     Parse(String),
+    #[error("invalid task spec: {}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))]
+    Validation(Vec<crate::models::ValidationError>),
+    #[error("invalid runtime config: {}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))]
+    RuntimeConfig(Vec<crate::runtime_config::ConfigError>),
+    #[error("invalid fallback urls: {}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))]
+    FallbackUrls(Vec<crate::fallback_check::FallbackUrlError>),
+    #[error("{}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))]
+    BackgroundTasks(Vec<crate::background::TaskFailure>),
+    #[error("invalid workflow dag: {0}")]
+    Dag(crate::workflow_dag::DagError),
     #[error("tendermint error: {0}")]
     Tendermint(#[from] tendermint::Error),
-    #[error("tx {0} failed with code {1}: {2}")]
-    Tx(String, u32, String),
+    #[error("tx {} failed with code {}: {}", .0.tx_hash, .0.code, .0.raw_log)]
+    Tx(Box<TxError>),
+    #[error("{limit} of {value} exceeds the chain limit of {max}")]
+    ExceedsChainLimits {
+        /// The exceeded limit, `tx bytes` or `block gas`.
+        limit: &'static str,
+        value: u64,
+        max: u64,
+    },
+    #[error("message of {size} bytes exceeds the limit of {limit} bytes")]
+    TooLarge { size: usize, limit: usize },
+    #[error("cannot cancel task in state {}", .0.as_str_name())]
+    CannotCancel(crate::proto::gevulot::gevulot::task_status::State),
+    #[cfg(feature = "query-only")]
+    #[error("{0}")]
+    PinReplication(Box<crate::pin_client::PinReplicationError>),
+    #[error("timed out: {0}")]
+    Timeout(String),
+    #[error("keychain error: {0}")]
+    Keychain(String),
     #[error("unknown error: {0}")]
     Unknown(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Codespace of the errors of the Cosmos SDK itself.
+pub const SDK_CODESPACE: &str = "sdk";
+
+/// Code of the Cosmos SDK error for transactions signed with a wrong account sequence.
+pub const WRONG_SEQUENCE_CODE: u32 = 32;
+
+// Start of the log of a wrong account sequence, e.g.
+// `account sequence mismatch, expected 5, got 4: incorrect account sequence`
+const SEQUENCE_MISMATCH_LOG: &str = "account sequence mismatch";
+
+// Codes of Cosmos SDK errors classified for end users
+const UNAUTHORIZED_CODE: u32 = 4;
+const INSUFFICIENT_FUNDS_CODE: u32 = 5;
+
+/// Failure situation of a [`GevulotError`], for CLIs and other tools to react on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorCode {
+    /// The account can't pay for the transaction or the resources it requests.
+    InsufficientFunds,
+    /// The transaction was signed for another chain than the node's.
+    WrongChainId,
+    /// The node can't be reached or didn't answer in time.
+    NodeUnreachable,
+    /// The account isn't the creator of the entity it tried to change.
+    NotCreator,
+    /// Any other failure.
+    Other,
+}
+
+impl ErrorCode {
+    /// Returns the machine-readable name of the code, e.g. `insufficient_funds`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::InsufficientFunds => "insufficient_funds",
+            Self::WrongChainId => "wrong_chain_id",
+            Self::NodeUnreachable => "node_unreachable",
+            Self::NotCreator => "not_creator",
+            Self::Other => "other",
+        }
+    }
+
+    /// Returns a hint how the user can resolve the failure, None for [`ErrorCode::Other`].
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::InsufficientFunds => Some(
+                "Fund the account with enough tokens for the fees and the requested resources.",
+            ),
+            Self::WrongChainId => {
+                Some("Set the chain ID of the network the node belongs to, e.g. with --chain-id.")
+            }
+            Self::NodeUnreachable => {
+                Some("Check the endpoint of the node and that it is running and reachable.")
+            }
+            Self::NotCreator => {
+                Some("Use the key of the account which created the entity to change it.")
+            }
+            Self::Other => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// [`Error`] classified for end users, with a machine-readable code and a remediation hint.
+///
+/// CLIs can show the error together with its hint without mapping errors themselves:
+///
+/// ```
+/// # use gevulot_rs::error::{Error, ErrorCode, GevulotError};
+/// let error = GevulotError::from(Error::RpcConnectionError("connection refused".to_string()));
+/// assert_eq!(error.code(), ErrorCode::NodeUnreachable);
+/// eprintln!("error: {}", error);
+/// if let Some(hint) = error.hint() {
+///     eprintln!("hint: {}", hint);
+/// }
+/// ```
+#[derive(Debug, thiserror::Error)]
+#[error("{source}")]
+pub struct GevulotError {
+    code: ErrorCode,
+    source: Error,
+}
+
+impl GevulotError {
+    /// Returns the failure situation of the error.
+    pub fn code(&self) -> ErrorCode {
+        self.code
+    }
+
+    /// Returns a hint how the user can resolve the error, if known.
+    pub fn hint(&self) -> Option<&'static str> {
+        self.code.hint()
+    }
+
+    /// Returns the underlying error.
+    pub fn into_inner(self) -> Error {
+        self.source
+    }
+}
+
+impl From<Error> for GevulotError {
+    fn from(error: Error) -> Self {
+        Self {
+            code: error.code(),
+            source: error,
+        }
+    }
+}
+
+/// Details of a failed transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxError {
+    pub tx_hash: String,
+    pub code: u32,
+    /// Module the error code belongs to, e.g. `gevulot` or `sdk`.
+    pub codespace: String,
+    pub raw_log: String,
+    /// Log entries parsed from the raw log, usually a single one for the failed message.
+    pub log_entries: Vec<AbciLogEntry>,
+    /// Events emitted before the transaction failed, e.g. by the fee deduction.
+    pub events: Vec<crate::Event>,
+}
+
+impl TxError {
+    /// Collects the details of a failed transaction from its response.
+    pub(crate) fn from_response(
+        tx_response: &cosmos_sdk_proto::cosmos::base::abci::v1beta1::TxResponse,
+    ) -> Self {
+        let log_entries = if tx_response.logs.is_empty() {
+            AbciLogEntry::parse_raw_log(&tx_response.raw_log)
+        } else {
+            tx_response.logs.iter().map(AbciLogEntry::from).collect()
+        };
+        Self {
+            tx_hash: tx_response.txhash.clone(),
+            code: tx_response.code,
+            codespace: tx_response.codespace.clone(),
+            raw_log: tx_response.raw_log.clone(),
+            log_entries,
+            events: tx_response
+                .events
+                .iter()
+                .cloned()
+                .filter_map(|event| crate::Event::try_from(event).ok())
+                .collect(),
+        }
+    }
+
+    /// Returns true if the transaction was signed with another account sequence than the chain
+    /// expected, e.g. because another transaction of the account was sent concurrently.
+    pub fn is_sequence_mismatch(&self) -> bool {
+        self.codespace == SDK_CODESPACE && self.code == WRONG_SEQUENCE_CODE
+    }
+
+    /// Returns the log entry of the message which made the transaction fail, if known.
+    pub fn failed_message(&self) -> Option<&AbciLogEntry> {
+        self.log_entries
+            .iter()
+            .find(|entry| entry.msg_index.is_some())
+    }
+}
+
+/// Entry of the ABCI log of a transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct AbciLogEntry {
+    /// Index of the message the entry belongs to, None if the log doesn't say.
+    #[serde(default)]
+    pub msg_index: Option<u32>,
+    /// The log message, for failed messages the reason of the failure.
+    #[serde(default)]
+    pub log: String,
+    #[serde(default)]
+    pub events: Vec<AbciLogEvent>,
+}
+
+/// Event contained in an ABCI log entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct AbciLogEvent {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub attributes: Vec<AbciLogAttribute>,
+}
+
+/// Attribute of an event contained in an ABCI log entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub struct AbciLogAttribute {
+    pub key: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+impl AbciLogEntry {
+    /// Parses the raw log of a transaction.
+    ///
+    /// Understands JSON logs as well as the plain text logs of failed transactions of the form
+    /// `failed to execute message; message index: 0: <reason>`. Other logs are returned as a
+    /// single entry without message index.
+    pub fn parse_raw_log(raw_log: &str) -> Vec<AbciLogEntry> {
+        const FAILED_PREFIX: &str = "failed to execute message; message index: ";
+
+        let raw_log = raw_log.trim();
+        if raw_log.is_empty() {
+            return Vec::new();
+        }
+        if let Ok(entries) = serde_json::from_str::<Vec<AbciLogEntry>>(raw_log) {
+            return entries;
+        }
+        if let Some((index, log)) = raw_log
+            .strip_prefix(FAILED_PREFIX)
+            .and_then(|rest| rest.split_once(": "))
+        {
+            if let Ok(index) = index.parse() {
+                return vec![AbciLogEntry {
+                    msg_index: Some(index),
+                    log: log.to_string(),
+                    events: Vec::new(),
+                }];
+            }
+        }
+        vec![AbciLogEntry {
+            log: raw_log.to_string(),
+            ..Default::default()
+        }]
+    }
+}
+
+impl From<&cosmos_sdk_proto::cosmos::base::abci::v1beta1::AbciMessageLog> for AbciLogEntry {
+    fn from(log: &cosmos_sdk_proto::cosmos::base::abci::v1beta1::AbciMessageLog) -> Self {
+        Self {
+            msg_index: Some(log.msg_index),
+            log: log.log.clone(),
+            events: log
+                .events
+                .iter()
+                .map(|event| AbciLogEvent {
+                    kind: event.r#type.clone(),
+                    attributes: event
+                        .attributes
+                        .iter()
+                        .map(|attr| AbciLogAttribute {
+                            key: attr.key.clone(),
+                            value: attr.value.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Error {
+    /// Returns the gRPC status the error was created from, if any.
+    pub fn status(&self) -> Option<&tonic::Status> {
+        match self {
+            Error::NotFound(status) => status.as_deref(),
+            Error::PermissionDenied(status)
+            | Error::Unavailable(status)
+            | Error::DeadlineExceeded(status)
+            | Error::ResourceExhausted(status)
+            | Error::Status(status) => Some(status),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the error is transient and the request may succeed when retried.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Error::Unavailable(_)
+                | Error::DeadlineExceeded(_)
+                | Error::ResourceExhausted(_)
+                | Error::RpcConnectionError(_)
+        )
+    }
+}
+
+impl Error {
+    /// Returns true if a transaction was rejected because of a wrong account sequence, when
+    /// broadcasting it or already when simulating it.
+    pub fn is_sequence_mismatch(&self) -> bool {
+        match self {
+            Error::Tx(tx_error) => tx_error.is_sequence_mismatch(),
+            _ => self
+                .status()
+                .is_some_and(|status| status.message().contains(SEQUENCE_MISMATCH_LOG)),
+        }
+    }
+
+    /// Returns the account sequence the chain expected if the error is a sequence mismatch
+    /// which tells it.
+    pub fn expected_sequence(&self) -> Option<u64> {
+        if !self.is_sequence_mismatch() {
+            return None;
+        }
+        let log = match self {
+            Error::Tx(tx_error) => tx_error.raw_log.as_str(),
+            _ => self.status()?.message(),
+        };
+        let (_, rest) = log.split_once("expected ")?;
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        rest[..end].parse().ok()
+    }
+}
+
+impl Error {
+    /// Classifies the error into the failure situations of [`GevulotError`].
+    pub fn code(&self) -> ErrorCode {
+        let log = match self {
+            Error::Tx(tx_error) => tx_error.raw_log.to_lowercase(),
+            _ => self
+                .status()
+                .map(|status| status.message().to_lowercase())
+                .unwrap_or_default(),
+        };
+        match self {
+            Error::RpcConnectionError(_) | Error::Unavailable(_) | Error::DeadlineExceeded(_) => {
+                ErrorCode::NodeUnreachable
+            }
+            Error::Tx(tx_error) if tx_error.codespace == SDK_CODESPACE => match tx_error.code {
+                INSUFFICIENT_FUNDS_CODE => ErrorCode::InsufficientFunds,
+                UNAUTHORIZED_CODE if log.contains("chain-id") => ErrorCode::WrongChainId,
+                _ => ErrorCode::Other,
+            },
+            // Accounts which never received tokens don't exist on the chain
+            _ if log.contains("insufficient funds")
+                || (matches!(self, Error::NotFound(_)) && log.starts_with("account ")) =>
+            {
+                ErrorCode::InsufficientFunds
+            }
+            _ if log.contains("creator") => ErrorCode::NotCreator,
+            _ => ErrorCode::Other,
+        }
+    }
+}
+
 impl From<Box<dyn std::error::Error>> for Error {
     fn from(error: Box<dyn std::error::Error>) -> Self {
         Error::Unknown(error.to_string())
@@ -50,7 +432,17 @@ impl From<cosmrs::rpc::error::Error> for Error {
 
 impl From<tonic::Status> for Error {
     fn from(error: tonic::Status) -> Self {
-        Error::RpcConnectionError(error.to_string())
+        let status = Box::new(error);
+        match status.code() {
+            tonic::Code::NotFound => Error::NotFound(Some(status)),
+            tonic::Code::PermissionDenied | tonic::Code::Unauthenticated => {
+                Error::PermissionDenied(status)
+            }
+            tonic::Code::Unavailable => Error::Unavailable(status),
+            tonic::Code::DeadlineExceeded => Error::DeadlineExceeded(status),
+            tonic::Code::ResourceExhausted => Error::ResourceExhausted(status),
+            _ => Error::Status(status),
+        }
     }
 }
 
@@ -66,12 +458,14 @@ impl From<DecodeError> for Error {
     }
 }
 
+#[cfg(feature = "query-only")]
 impl From<tonic::transport::Error> for Error {
     fn from(error: tonic::transport::Error) -> Self {
         Error::RpcConnectionError(error.to_string())
     }
 }
 
+#[cfg(feature = "query-only")]
 impl From<bip32::Error> for Error {
     fn from(error: bip32::Error) -> Self {
         Error::Parse(error.to_string())
@@ -95,3 +489,143 @@ impl From<&str> for Error {
         Error::Unknown(error.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_tonic_status() {
+        let error = Error::from(tonic::Status::not_found("task t1 not found"));
+        assert!(matches!(error, Error::NotFound(Some(_))));
+        assert_eq!(error.to_string(), "not found: task t1 not found");
+        assert_eq!(error.status().unwrap().message(), "task t1 not found");
+        assert!(!error.is_retryable());
+
+        let error = Error::from(tonic::Status::unavailable("node down"));
+        assert!(matches!(error, Error::Unavailable(_)));
+        assert!(error.is_retryable());
+
+        let error = Error::from(tonic::Status::permission_denied("nope"));
+        assert!(matches!(error, Error::PermissionDenied(_)));
+
+        let error = Error::from(tonic::Status::invalid_argument("bad id"));
+        assert_eq!(error.status().unwrap().code(), tonic::Code::InvalidArgument);
+        assert_eq!(Error::NotFound(None).to_string(), "not found");
+    }
+
+    #[test]
+    fn test_sequence_mismatch() {
+        let tx_error = |codespace: &str, code, raw_log: &str| {
+            Error::Tx(Box::new(TxError {
+                tx_hash: "AB12".to_string(),
+                code,
+                codespace: codespace.to_string(),
+                raw_log: raw_log.to_string(),
+                log_entries: Vec::new(),
+                events: Vec::new(),
+            }))
+        };
+        let error = tx_error(
+            "sdk",
+            32,
+            "account sequence mismatch, expected 5, got 4: incorrect account sequence",
+        );
+        assert!(error.is_sequence_mismatch());
+        assert_eq!(error.expected_sequence(), Some(5));
+        let error = tx_error("sdk", 32, "incorrect account sequence");
+        assert!(error.is_sequence_mismatch());
+        assert_eq!(error.expected_sequence(), None);
+        assert!(!tx_error("gevulot", 32, "expected 5").is_sequence_mismatch());
+
+        // Simulations fail with a status instead
+        let error = Error::from(tonic::Status::unknown(
+            "account sequence mismatch, expected 12, got 11: incorrect account sequence",
+        ));
+        assert!(error.is_sequence_mismatch());
+        assert_eq!(error.expected_sequence(), Some(12));
+        assert!(!Error::from(tonic::Status::unknown("out of gas")).is_sequence_mismatch());
+    }
+
+    #[test]
+    fn test_error_code() {
+        let tx_error = |codespace: &str, code, raw_log: &str| {
+            Error::Tx(Box::new(TxError {
+                tx_hash: "AB12".to_string(),
+                code,
+                codespace: codespace.to_string(),
+                raw_log: raw_log.to_string(),
+                log_entries: Vec::new(),
+                events: Vec::new(),
+            }))
+        };
+        assert_eq!(
+            tx_error(
+                "sdk",
+                5,
+                "spendable balance 10ucredit is smaller than 2500ucredit: insufficient funds"
+            )
+            .code(),
+            ErrorCode::InsufficientFunds
+        );
+        assert_eq!(
+            tx_error(
+                "sdk",
+                4,
+                "signature verification failed; please verify account number (7), sequence (3) and chain-id (gevulot-devnet): unauthorized",
+            )
+            .code(),
+            ErrorCode::WrongChainId
+        );
+        assert_eq!(
+            tx_error("gevulot", 1105, "only the creator can delete the task").code(),
+            ErrorCode::NotCreator
+        );
+        assert_eq!(
+            Error::from(tonic::Status::not_found("account gvlt1abc not found")).code(),
+            ErrorCode::InsufficientFunds
+        );
+        assert_eq!(
+            Error::from(tonic::Status::unavailable("node down")).code(),
+            ErrorCode::NodeUnreachable
+        );
+        assert_eq!(
+            Error::from(tonic::Status::not_found("task t1 not found")).code(),
+            ErrorCode::Other
+        );
+
+        let error = GevulotError::from(Error::RpcConnectionError("refused".to_string()));
+        assert_eq!(error.code().to_string(), "node_unreachable");
+        assert_eq!(error.to_string(), "rpc connection error: refused");
+        assert!(error.hint().is_some());
+        assert!(GevulotError::from(Error::Parse("x".to_string()))
+            .hint()
+            .is_none());
+    }
+
+    #[test]
+    fn test_parse_raw_log() {
+        let entries = AbciLogEntry::parse_raw_log(
+            "failed to execute message; message index: 1: not enough workers available: invalid request",
+        );
+        assert_eq!(
+            entries,
+            vec![AbciLogEntry {
+                msg_index: Some(1),
+                log: "not enough workers available: invalid request".to_string(),
+                events: Vec::new(),
+            }]
+        );
+
+        let entries = AbciLogEntry::parse_raw_log(
+            r#"[{"msg_index":0,"events":[{"type":"create-task","attributes":[{"key":"task-id","value":"t1"}]}]}]"#,
+        );
+        assert_eq!(entries[0].msg_index, Some(0));
+        assert_eq!(entries[0].events[0].kind, "create-task");
+        assert_eq!(entries[0].events[0].attributes[0].value, "t1");
+
+        let entries = AbciLogEntry::parse_raw_log("out of gas in location: WriteFlat");
+        assert_eq!(entries[0].msg_index, None);
+        assert!(AbciLogEntry::parse_raw_log("").is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+//! In-process distribution of chain events.
+//!
+//! Every [`EventFetcher`](crate::EventFetcher) polls the node on its own. Instead of running a
+//! fetcher per subsystem, register a single [`EventBus`] with one fetcher and let caches,
+//! notifiers and watchers subscribe to the bus:
+//!
+//! ```no_run
+//! # use gevulot_rs::{event_bus::EventBus, task_client::TaskNotifier};
+//! # async fn run() {
+//! let bus = EventBus::new();
+//! // Register a clone of the bus with an EventFetcher, then attach handlers or subscribe
+//! let notifier = TaskNotifier::new();
+//! bus.attach(notifier.clone());
+//! let mut subscription = bus.subscribe();
+//! while let Some(message) = subscription.recv().await {
+//!     println!("{:?}", message);
+//! }
+//! # }
+//! ```
+//!
+//! Subscribers which fall behind by more than the capacity of the bus miss the oldest
+//! messages, see [`EventSubscription::lagged`].
+
+use std::sync::Arc;
+
+use futures::Stream;
+use tokio::{sync::broadcast, task::JoinHandle};
+
+use crate::{
+    error::Result,
+    event_fetcher::{BlockInfo, ChainNotification, EventContext, EventHandler},
+    events::GevulotEvent,
+};
+
+/// Default number of messages a subscriber may fall behind before missing messages.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+/// A message published on an [`EventBus`].
+#[derive(Clone, Debug)]
+pub enum BusMessage {
+    /// An event emitted by the chain.
+    Event {
+        /// The event as emitted by the chain.
+        raw: crate::Event,
+        /// The parsed event, None if it isn't a Gevulot event or couldn't be parsed.
+        parsed: Option<GevulotEvent>,
+        context: EventContext,
+    },
+    /// A notification about the chain itself.
+    Notification(ChainNotification),
+    /// All events of the block were published.
+    BlockEnd(BlockInfo),
+}
+
+/// Event handler publishing the events it receives to all subscribers.
+///
+/// Clones share the same subscribers.
+#[derive(Clone, Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<Arc<BusMessage>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    /// Creates a bus with the [`DEFAULT_CAPACITY`].
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Creates a bus buffering up to the given number of messages for slow subscribers.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Subscribes to all messages published from now on.
+    pub fn subscribe(&self) -> EventSubscription {
+        EventSubscription {
+            receiver: self.sender.subscribe(),
+            lagged: 0,
+        }
+    }
+
+    /// Returns the number of current subscribers.
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Publishes a message to all subscribers.
+    pub fn publish(&self, message: BusMessage) {
+        // No subscribers is fine
+        let _ = self.sender.send(Arc::new(message));
+    }
+
+    /// Passes all messages published from now on to an event handler in a background task.
+    ///
+    /// The task ends when the handler returns an error, which is returned by the task, or when
+    /// all clones of the bus were dropped.
+    pub fn attach<H>(&self, mut handler: H) -> JoinHandle<Result<()>>
+    where
+        H: EventHandler + 'static,
+    {
+        let mut subscription = self.subscribe();
+        crate::background::spawn_named("gevulot-event-bus", async move {
+            while let Some(message) = subscription.recv().await {
+                match &*message {
+                    BusMessage::Event { raw, context, .. } => {
+                        handler.handle_event_with_context(raw, context).await?
+                    }
+                    BusMessage::Notification(notification) => {
+                        handler.handle_notification(notification).await?
+                    }
+                    BusMessage::BlockEnd(block) => handler.handle_block_end(block).await?,
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+impl EventHandler for EventBus {
+    async fn handle_event(
+        &mut self,
+        event: &crate::Event,
+        block_height: crate::Height,
+    ) -> Result<()> {
+        self.handle_event_with_context(event, &EventContext::new(block_height))
+            .await
+    }
+
+    async fn handle_event_with_context(
+        &mut self,
+        event: &crate::Event,
+        context: &EventContext,
+    ) -> Result<()> {
+        self.publish(BusMessage::Event {
+            raw: event.clone(),
+            parsed: GevulotEvent::from_cosmos(event, context.block_height).ok(),
+            context: context.clone(),
+        });
+        Ok(())
+    }
+
+    async fn handle_notification(&mut self, notification: &ChainNotification) -> Result<()> {
+        self.publish(BusMessage::Notification(notification.clone()));
+        Ok(())
+    }
+
+    async fn handle_block_end(&mut self, block: &BlockInfo) -> Result<()> {
+        self.publish(BusMessage::BlockEnd(block.clone()));
+        Ok(())
+    }
+}
+
+/// Receiver of the messages of an [`EventBus`].
+#[derive(Debug)]
+pub struct EventSubscription {
+    receiver: broadcast::Receiver<Arc<BusMessage>>,
+    lagged: u64,
+}
+
+impl EventSubscription {
+    /// Waits for the next message, None once all clones of the bus were dropped.
+    ///
+    /// Messages missed because the subscriber fell behind are skipped and counted.
+    pub async fn recv(&mut self) -> Option<Arc<BusMessage>> {
+        loop {
+            match self.receiver.recv().await {
+                Ok(message) => return Some(message),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    log::warn!("Event bus subscriber missed {} messages", missed);
+                    self.lagged += missed;
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    /// Returns the number of messages missed because the subscriber fell behind.
+    pub fn lagged(&self) -> u64 {
+        self.lagged
+    }
+
+    /// Turns the subscription into a stream of the parsed Gevulot events.
+    pub fn into_events(self) -> impl Stream<Item = (GevulotEvent, EventContext)> {
+        futures::stream::unfold(self, |mut subscription| async move {
+            loop {
+                let message = subscription.recv().await?;
+                if let BusMessage::Event {
+                    parsed: Some(event),
+                    context,
+                    ..
+                } = &*message
+                {
+                    return Some(((event.clone(), context.clone()), subscription));
+                }
+            }
+        })
+    }
+}
+
+#[cfg(all(test, feature = "query-only"))]
+mod tests {
+    use cosmrs::{rpc::dialect::v0_34::EventAttribute, tendermint::abci::Event};
+    use futures::StreamExt;
+
+    use super::*;
+    use crate::task_client::TaskNotifier;
+
+    fn event(kind: &str) -> Event {
+        let attribute = |key: &[u8], value: &[u8]| EventAttribute {
+            index: true,
+            key: key.to_vec(),
+            value: value.to_vec(),
+        };
+        Event::new(
+            kind,
+            vec![
+                attribute(b"task-id", b"task1"),
+                attribute(b"worker-id", b"worker1"),
+            ],
+        )
+    }
+
+    #[tokio::test]
+    async fn test_event_bus() {
+        let mut bus = EventBus::new();
+        let mut first = bus.subscribe();
+        let events = bus.subscribe().into_events();
+        assert_eq!(bus.subscriber_count(), 2);
+
+        let height = crate::Height::from(1u32);
+        bus.handle_event(&event("transfer"), height).await.unwrap();
+        bus.handle_event(&event("accept-task"), height)
+            .await
+            .unwrap();
+        bus.handle_block_end(&BlockInfo {
+            height,
+            time: None,
+            hash: None,
+        })
+        .await
+        .unwrap();
+        drop(bus);
+
+        let mut kinds = Vec::new();
+        while let Some(message) = first.recv().await {
+            kinds.push(match &*message {
+                BusMessage::Event { raw, parsed, .. } => {
+                    assert_eq!(parsed.is_some(), raw.kind == "accept-task");
+                    raw.kind.clone()
+                }
+                BusMessage::Notification(_) => "notification".to_string(),
+                BusMessage::BlockEnd(_) => "block-end".to_string(),
+            });
+        }
+        assert_eq!(kinds, vec!["transfer", "accept-task", "block-end"]);
+        assert_eq!(first.lagged(), 0);
+
+        let events: Vec<_> = events.collect().await;
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0].0, GevulotEvent::Task(_)));
+    }
+
+    #[tokio::test]
+    async fn test_attach() {
+        let mut bus = EventBus::with_capacity(16);
+        let notifier = TaskNotifier::new();
+        let mut task_ids = notifier.subscribe();
+        let handle = bus.attach(notifier);
+
+        bus.handle_event(&event("accept-task"), crate::Height::from(1u32))
+            .await
+            .unwrap();
+        assert_eq!(task_ids.recv().await.unwrap(), "task1");
+
+        drop(bus);
+        handle.await.unwrap().unwrap();
+    }
+}
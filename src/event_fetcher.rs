@@ -1,12 +1,84 @@
+use std::collections::BTreeMap;
 use std::time::Duration;
 
-use backon::{ExponentialBuilder, Retryable};
+use backon::{BackoffBuilder, ExponentialBuilder, Retryable};
 use cosmrs::{
     rpc::{self, endpoint::block_results::Response as BlockResults, Client},
-    tendermint::block::Height,
+    tendermint::{account, block::Height, Block, Hash, Time},
 };
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
 
-use crate::error::Result;
+use crate::error::{Error, Result};
+use crate::event_filter::EventFilter;
+use crate::events::GevulotEvent;
+
+// Block and transaction metadata delivered alongside each event. The block time, proposer and
+// transaction hash cost an extra request per block and are only filled in when the fetcher is
+// built with with_block_metadata(true).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventContext {
+    pub block_height: Height,
+    // Block timestamp, only available with block metadata
+    pub block_time: Option<Time>,
+    // Address of the block proposer, only available with block metadata
+    pub proposer_address: Option<account::Id>,
+    // Hash of the transaction which emitted the event, None for block level events. Only
+    // available with block metadata
+    pub tx_hash: Option<String>,
+    // Index of the message within the transaction which emitted the event, if the chain reports it
+    pub msg_index: Option<u32>,
+    // Index of the event within all events of the block
+    pub event_index: usize,
+}
+
+impl EventContext {
+    // Creates a context carrying only the block height
+    pub fn new(block_height: Height) -> Self {
+        Self {
+            block_height,
+            block_time: None,
+            proposer_address: None,
+            tx_hash: None,
+            msg_index: None,
+            event_index: 0,
+        }
+    }
+
+    // Identifier which is unique for every event on the chain, useful for deduplication
+    pub fn event_id(&self) -> String {
+        format!("{}-{}", self.block_height, self.event_index)
+    }
+}
+
+// Returns the index of the message which emitted an event, from the msg_index attribute
+fn msg_index(event: &crate::Event) -> Option<u32> {
+    event
+        .attributes
+        .iter()
+        .find(|attr| attr.key_bytes() == b"msg_index")
+        .and_then(|attr| attr.value_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+// Metadata of a block, delivered once all events of the block were handled
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockInfo {
+    pub height: Height,
+    // Block timestamp, only available with block metadata
+    pub time: Option<Time>,
+    // Block hash, only available when the fetcher fetches block metadata or detects reorgs
+    pub hash: Option<Hash>,
+}
+
+// Notifications about the chain itself, delivered to handlers next to events
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChainNotification {
+    // A previously delivered block was replaced. Events delivered for this height and all
+    // heights above it are invalid, the new blocks will be delivered again.
+    Rollback(Height),
+}
 
 // Trait for handling events asynchronously
 pub trait EventHandler: Send + Sync {
@@ -16,6 +88,254 @@ pub trait EventHandler: Send + Sync {
         event: &crate::Event,
         block_height: crate::Height,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    // Asynchronously handles an event together with its block and transaction metadata.
+    // Defaults to handle_event, override it to make use of the context.
+    fn handle_event_with_context(
+        &mut self,
+        event: &crate::Event,
+        context: &EventContext,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        self.handle_event(event, context.block_height)
+    }
+
+    // Asynchronously handles a chain notification. Ignores notifications by default.
+    fn handle_notification(
+        &mut self,
+        _notification: &ChainNotification,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+
+    // Called once all events of a block were handled, for every block including those without
+    // events. Does nothing by default.
+    fn handle_block_end(
+        &mut self,
+        _block: &BlockInfo,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+// Trait for handling all parsed Gevulot events of a block in a single call, e.g. to write them
+// to a database in one transaction. Use it with the EventFetcher through the BlockBatched adapter.
+pub trait BlockEventHandler: Send + Sync {
+    // Asynchronously handles the Gevulot events of a block in the order they were emitted.
+    // Called for every block, also those without Gevulot events, so the handler can record its
+    // progress together with the events.
+    fn handle_block(
+        &mut self,
+        block: &BlockInfo,
+        events: Vec<GevulotEvent>,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    // Asynchronously handles a chain notification. Ignores notifications by default.
+    fn handle_notification(
+        &mut self,
+        _notification: &ChainNotification,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        async { Ok(()) }
+    }
+}
+
+// Adapter turning a BlockEventHandler into an EventHandler usable by the EventFetcher.
+// Parsed events are collected until the end of the block and then delivered together.
+// Events which are not Gevulot events are skipped, malformed Gevulot events are skipped and
+// passed to the fetcher's dead letter callback. The handler's error policy applies to single
+// events only, a failing handle_block always stops the fetcher.
+pub struct BlockBatched<H: BlockEventHandler> {
+    pub handler: H,
+    events: Vec<GevulotEvent>,
+}
+
+impl<H> BlockBatched<H>
+where
+    H: BlockEventHandler,
+{
+    // Wraps a BlockEventHandler
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl<H> EventHandler for BlockBatched<H>
+where
+    H: BlockEventHandler,
+{
+    async fn handle_event(
+        &mut self,
+        event: &crate::Event,
+        block_height: crate::Height,
+    ) -> Result<()> {
+        match GevulotEvent::from_cosmos(event, block_height) {
+            Ok(parsed) => self.events.push(parsed),
+            Err(Error::UnknownEventKind(_)) => {}
+            Err(e) => return Err(Error::MalformedEvent(Box::new(e))),
+        }
+        Ok(())
+    }
+
+    async fn handle_notification(&mut self, notification: &ChainNotification) -> Result<()> {
+        self.events.clear();
+        self.handler.handle_notification(notification).await
+    }
+
+    async fn handle_block_end(&mut self, block: &BlockInfo) -> Result<()> {
+        let events = std::mem::take(&mut self.events);
+        self.handler.handle_block(block, events).await
+    }
+}
+
+// Computes the hash of a raw transaction the way it is displayed by the chain (uppercase hex SHA256)
+pub fn tx_hash(tx: &[u8]) -> String {
+    hex::encode_upper(
+        <tendermint::crypto::default::Sha256 as tendermint::crypto::Sha256>::digest(tx),
+    )
+}
+
+// What the EventFetcher does when the handler fails to handle an event
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum HandlerErrorPolicy {
+    // Stop fetching and return the error from start_fetching
+    #[default]
+    Stop,
+    // Log the error, pass the event to the dead letter callback and continue
+    Skip,
+    // Retry the event with exponential backoff. If all retries fail the event is skipped
+    // when skip_on_failure is set, otherwise fetching stops.
+    Retry {
+        max_retries: usize,
+        skip_on_failure: bool,
+    },
+}
+
+// Callback receiving events the handler failed to handle or which are malformed, with the error
+pub type DeadLetterCallback =
+    Box<dyn FnMut(&crate::Event, &EventContext, &Error) + Send + Sync + 'static>;
+
+// Handler ignoring all events, useful together with EventFetcher::into_stream
+impl EventHandler for () {
+    async fn handle_event(&mut self, _event: &crate::Event, _block_height: Height) -> Result<()> {
+        Ok(())
+    }
+}
+
+// Handler passing events to an inner handler and forwarding parsed Gevulot events to a channel
+struct StreamForwarder<H: EventHandler> {
+    inner: H,
+    sender: mpsc::Sender<Result<(GevulotEvent, Height)>>,
+    forward_unknown_events: bool,
+}
+
+impl<H> EventHandler for StreamForwarder<H>
+where
+    H: EventHandler,
+{
+    async fn handle_event(&mut self, event: &crate::Event, block_height: Height) -> Result<()> {
+        self.handle_event_with_context(event, &EventContext::new(block_height))
+            .await
+    }
+
+    async fn handle_event_with_context(
+        &mut self,
+        event: &crate::Event,
+        context: &EventContext,
+    ) -> Result<()> {
+        self.inner.handle_event_with_context(event, context).await?;
+        let parsed = if self.forward_unknown_events {
+            GevulotEvent::from_cosmos_or_unknown(event, context.block_height)
+        } else {
+            GevulotEvent::from_cosmos(event, context.block_height)
+        };
+        let item = match parsed {
+            Ok(parsed) => Ok((parsed, context.block_height)),
+            Err(Error::UnknownEventKind(_)) => return Ok(()),
+            Err(e) => Err(e),
+        };
+        self.sender
+            .send(item)
+            .await
+            .map_err(|_| Error::Unknown("event stream was dropped".to_string()))
+    }
+
+    async fn handle_notification(&mut self, notification: &ChainNotification) -> Result<()> {
+        self.inner.handle_notification(notification).await
+    }
+
+    async fn handle_block_end(&mut self, block: &BlockInfo) -> Result<()> {
+        self.inner.handle_block_end(block).await
+    }
+}
+
+// Trait for handling parsed Gevulot events together with the raw event they were parsed from
+pub trait RawAndParsedEventHandler: Send + Sync {
+    // Asynchronously handles a parsed event, the raw event carries any extra attributes
+    fn handle_event(
+        &mut self,
+        event: &GevulotEvent,
+        raw_event: &crate::Event,
+        block_height: crate::Height,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    // Asynchronously handles a parsed event together with its block and transaction metadata.
+    // Defaults to handle_event, override it to make use of the context.
+    fn handle_event_with_context(
+        &mut self,
+        event: &GevulotEvent,
+        raw_event: &crate::Event,
+        context: &EventContext,
+    ) -> impl std::future::Future<Output = Result<()>> + Send {
+        self.handle_event(event, raw_event, context.block_height)
+    }
+
+    // Whether events of unknown kinds are handed to the handler as GevulotEvent::Unknown
+    // instead of being skipped. Defaults to false.
+    fn forward_unknown_events(&self) -> bool {
+        false
+    }
+}
+
+// Adapter turning a RawAndParsedEventHandler into an EventHandler usable by the EventFetcher.
+// Events which are not Gevulot events are skipped, malformed Gevulot events are skipped and passed
+// to the fetcher's dead letter callback.
+pub struct RawAndParsed<H: RawAndParsedEventHandler>(pub H);
+
+impl<H> EventHandler for RawAndParsed<H>
+where
+    H: RawAndParsedEventHandler,
+{
+    async fn handle_event(
+        &mut self,
+        event: &crate::Event,
+        block_height: crate::Height,
+    ) -> Result<()> {
+        self.handle_event_with_context(event, &EventContext::new(block_height))
+            .await
+    }
+
+    async fn handle_event_with_context(
+        &mut self,
+        event: &crate::Event,
+        context: &EventContext,
+    ) -> Result<()> {
+        let parsed = if self.0.forward_unknown_events() {
+            GevulotEvent::from_cosmos_or_unknown(event, context.block_height)
+        } else {
+            GevulotEvent::from_cosmos(event, context.block_height)
+        };
+        match parsed {
+            Ok(parsed) => {
+                self.0
+                    .handle_event_with_context(&parsed, event, context)
+                    .await
+            }
+            Err(Error::UnknownEventKind(_)) => Ok(()),
+            Err(e) => Err(Error::MalformedEvent(Box::new(e))),
+        }
+    }
 }
 
 // Fetches events from the blockchain and processes them using the provided handler
@@ -25,6 +345,65 @@ pub struct EventFetcher<H: EventHandler> {
     pub start_height: Option<Height>,
     pub sleep_time: Duration,
     pub max_retries: usize,
+    // Whether to fetch block headers and transactions to fill in the EventContext, see
+    // with_block_metadata
+    pub fetch_block_metadata: bool,
+    // Events not matching the filter are not handed to the handler. The filter's block range
+    // limits which blocks are fetched at all.
+    pub filter: EventFilter,
+    // Number of recent block hashes kept to detect reorgs, 0 disables reorg detection
+    pub reorg_window: usize,
+    // Number of blocks fetched in parallel when catching up with the chain
+    pub backfill_concurrency: usize,
+    // What to do when the handler returns an error
+    pub error_policy: HandlerErrorPolicy,
+    // Receives events which were skipped because the handler failed to handle them or because
+    // they are malformed
+    pub dead_letter: Option<DeadLetterCallback>,
+    // Whether the stream returned by into_stream yields events of unknown kinds as
+    // GevulotEvent::Unknown instead of skipping them
+    pub forward_unknown_events: bool,
+    block_hashes: BlockHashes,
+}
+
+// A fetched block which is ready to be indexed
+struct BlockData {
+    block_results: BlockResults,
+    block: Option<Block>,
+    hash: Option<Hash>,
+}
+
+// Hashes of the most recently indexed blocks
+#[derive(Debug, Default)]
+struct BlockHashes {
+    hashes: BTreeMap<u64, Hash>,
+}
+
+impl BlockHashes {
+    // Records the hash of a block, forgetting blocks which fell out of the window
+    fn insert(&mut self, height: Height, hash: Hash, window: usize) {
+        self.hashes.insert(height.value(), hash);
+        while self.hashes.len() > window {
+            self.hashes.pop_first();
+        }
+    }
+
+    // Forgets all blocks at or above the given height
+    fn truncate(&mut self, from_height: u64) {
+        self.hashes.split_off(&from_height);
+    }
+
+    // Recorded blocks from the highest to the lowest
+    fn iter_rev(&self) -> impl Iterator<Item = (u64, Hash)> + '_ {
+        self.hashes
+            .iter()
+            .rev()
+            .map(|(height, hash)| (*height, *hash))
+    }
+
+    fn first_height(&self) -> Option<u64> {
+        self.hashes.keys().next().copied()
+    }
 }
 
 impl<H> EventFetcher<H>
@@ -44,9 +423,117 @@ where
             start_height,
             sleep_time,
             max_retries: 3,
+            fetch_block_metadata: false,
+            filter: EventFilter::default(),
+            reorg_window: 0,
+            backfill_concurrency: 1,
+            error_policy: HandlerErrorPolicy::default(),
+            dead_letter: None,
+            forward_unknown_events: false,
+            block_hashes: BlockHashes::default(),
         }
     }
 
+    fn map_handler<H2: EventHandler>(self, f: impl FnOnce(H) -> H2) -> EventFetcher<H2> {
+        EventFetcher {
+            handler: f(self.handler),
+            rpc_url: self.rpc_url,
+            start_height: self.start_height,
+            sleep_time: self.sleep_time,
+            max_retries: self.max_retries,
+            fetch_block_metadata: self.fetch_block_metadata,
+            filter: self.filter,
+            reorg_window: self.reorg_window,
+            backfill_concurrency: self.backfill_concurrency,
+            error_policy: self.error_policy,
+            dead_letter: self.dead_letter,
+            forward_unknown_events: self.forward_unknown_events,
+            block_hashes: self.block_hashes,
+        }
+    }
+
+    // Turns the fetcher into a stream of parsed Gevulot events. Fetching runs on a background
+    // task which stops when the stream is dropped. Events are still passed to the handler
+    // first, use `()` as the handler if only the stream is needed. Events which are not Gevulot
+    // events are skipped unless forward_unknown_events is set, an error from fetching ends the
+    // stream.
+    pub fn into_stream(self) -> impl Stream<Item = Result<(GevulotEvent, Height)>>
+    where
+        H: 'static,
+    {
+        let (sender, receiver) = mpsc::channel(128);
+        let error_sender = sender.clone();
+        let forward_unknown_events = self.forward_unknown_events;
+        let mut fetcher = self.map_handler(|inner| StreamForwarder {
+            inner,
+            sender,
+            forward_unknown_events,
+        });
+        crate::background::spawn_named("gevulot-event-stream", async move {
+            tokio::select! {
+                result = fetcher.start_fetching() => {
+                    if let Err(e) = result {
+                        // Fails only if the stream was dropped, in which case nobody is interested
+                        let _ = error_sender.send(Err(e)).await;
+                    }
+                }
+                // Stop fetching once the stream is dropped, whatever the error policy
+                _ = error_sender.closed() => {}
+            }
+        });
+
+        stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|item| (item, receiver))
+        })
+    }
+
+    // Fetches the block of each height to fill in the block time, proposer and transaction
+    // hashes of the EventContext. Off by default as it costs an extra request per block.
+    pub fn with_block_metadata(mut self, fetch_block_metadata: bool) -> Self {
+        self.fetch_block_metadata = fetch_block_metadata;
+        self
+    }
+
+    // Sets the filter applied to events before they are handed to the handler
+    pub fn with_filter(mut self, filter: EventFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    // Sets the number of recent block hashes kept to detect reorgs, 0 disables reorg detection
+    pub fn with_reorg_window(mut self, reorg_window: usize) -> Self {
+        self.reorg_window = reorg_window;
+        self
+    }
+
+    // Sets the number of blocks fetched in parallel when catching up with the chain, at least 1
+    pub fn with_backfill_concurrency(mut self, backfill_concurrency: usize) -> Self {
+        self.backfill_concurrency = backfill_concurrency;
+        self
+    }
+
+    // Sets what to do when the handler fails to handle an event
+    pub fn with_error_policy(mut self, error_policy: HandlerErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    // Sets the callback receiving events the handler failed to handle or which are malformed
+    pub fn with_dead_letter(
+        mut self,
+        dead_letter: impl FnMut(&crate::Event, &EventContext, &Error) + Send + Sync + 'static,
+    ) -> Self {
+        self.dead_letter = Some(Box::new(dead_letter));
+        self
+    }
+
+    // Makes the stream returned by into_stream yield events of unknown kinds as
+    // GevulotEvent::Unknown, e.g. to log event kinds added in newer chain versions
+    pub fn with_unknown_events(mut self, forward_unknown_events: bool) -> Self {
+        self.forward_unknown_events = forward_unknown_events;
+        self
+    }
+
     async fn fetch_latest_block_number_no_retry(
         &self,
         rpc_client: &rpc::HttpClient,
@@ -104,36 +591,299 @@ where
             })
     }
 
-    async fn process_block_results(&mut self, block_results: &BlockResults) -> Result<()> {
+    async fn fetch_block_no_retry(
+        &self,
+        rpc_client: &rpc::HttpClient,
+        height: Height,
+    ) -> Result<Block> {
+        Ok(rpc_client.block(height).await?.block)
+    }
+
+    async fn fetch_block(&self, rpc_client: &rpc::HttpClient, height: Height) -> Result<Block> {
+        let backoff = ExponentialBuilder::default()
+            .with_max_times(self.max_retries)
+            .with_jitter();
+
+        (|| async { self.fetch_block_no_retry(rpc_client, height).await })
+            .retry(backoff)
+            .await
+            .map_err(|e| {
+                log::error!(
+                    "Error fetching block for height {} after {} retries: {:?}",
+                    height,
+                    self.max_retries,
+                    e
+                );
+                e
+            })
+    }
+
+    async fn fetch_block_hash_no_retry(
+        &self,
+        rpc_client: &rpc::HttpClient,
+        height: Height,
+    ) -> Result<Hash> {
+        Ok(rpc_client.header(height).await?.header.hash())
+    }
+
+    async fn fetch_block_hash(&self, rpc_client: &rpc::HttpClient, height: Height) -> Result<Hash> {
+        let backoff = ExponentialBuilder::default()
+            .with_max_times(self.max_retries)
+            .with_jitter();
+
+        (|| async { self.fetch_block_hash_no_retry(rpc_client, height).await })
+            .retry(backoff)
+            .await
+            .map_err(|e| {
+                log::error!(
+                    "Error fetching block header for height {} after {} retries: {:?}",
+                    height,
+                    self.max_retries,
+                    e
+                );
+                e
+            })
+    }
+
+    // Checks the recorded block hashes against the chain. If blocks were replaced, notifies the
+    // handler and returns the last height which is still valid.
+    async fn detect_rollback(
+        &mut self,
+        rpc_client: &rpc::HttpClient,
+        latest_block: Height,
+    ) -> Result<Option<Height>> {
+        let mut fork_height = None;
+        let mut rolled_back = false;
+        for (height, hash) in self.block_hashes.iter_rev() {
+            if height <= latest_block.value()
+                && self
+                    .fetch_block_hash(rpc_client, Height::try_from(height)?)
+                    .await?
+                    == hash
+            {
+                fork_height = Some(height);
+                break;
+            }
+            rolled_back = true;
+        }
+        if !rolled_back {
+            return Ok(None);
+        }
+
+        // If none of the recorded blocks is still valid everything in the window is rolled back
+        let from_height = match fork_height {
+            Some(height) => height + 1,
+            None => self.block_hashes.first_height().unwrap_or_default(),
+        };
+        log::warn!("Detected chain reorganization from height {}", from_height);
+        self.block_hashes.truncate(from_height);
+        self.handler
+            .handle_notification(&ChainNotification::Rollback(Height::try_from(from_height)?))
+            .await?;
+        Ok(Some(Height::try_from(from_height.saturating_sub(1))?))
+    }
+
+    // Fetches everything needed to index a block
+    async fn fetch_block_data(
+        &self,
+        rpc_client: &rpc::HttpClient,
+        height: Height,
+    ) -> Result<BlockData> {
+        let block_results = self.fetch_block_results(rpc_client, height).await?;
+        let block = if self.fetch_block_metadata {
+            Some(self.fetch_block(rpc_client, height).await?)
+        } else {
+            None
+        };
+        let hash = match (&block, self.reorg_window) {
+            (_, 0) => None,
+            (Some(block), _) => Some(block.header.hash()),
+            (None, _) => Some(self.fetch_block_hash(rpc_client, height).await?),
+        };
+        Ok(BlockData {
+            block_results,
+            block,
+            hash,
+        })
+    }
+
+    async fn index_block(&mut self, data: BlockData) -> Result<()> {
+        log::debug!(
+            "Processing block results for height {}",
+            data.block_results.height
+        );
+        self.process_block_results(&data.block_results, data.block.as_ref())
+            .await?;
+        let block_info = BlockInfo {
+            height: data.block_results.height,
+            time: data.block.as_ref().map(|b| b.header.time),
+            hash: data
+                .hash
+                .or_else(|| data.block.as_ref().map(|b| b.header.hash())),
+        };
+        self.handler.handle_block_end(&block_info).await?;
+        if let Some(hash) = data.hash {
+            self.block_hashes
+                .insert(data.block_results.height, hash, self.reorg_window);
+        }
+        Ok(())
+    }
+
+    // Indexes all blocks in the range, fetching up to backfill_concurrency blocks in parallel.
+    // Events are still delivered in height order.
+    async fn index_blocks(
+        &mut self,
+        rpc_client: &rpc::HttpClient,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<()> {
+        let batch_size = self.backfill_concurrency.max(1) as u64;
+        let mut batch_start = from_height;
+        while batch_start <= to_height {
+            let batch_end = to_height.min(batch_start + batch_size - 1);
+            let heights = (batch_start..=batch_end)
+                .map(Height::try_from)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let batch: Vec<BlockData> = stream::iter(heights)
+                .map(|height| self.fetch_block_data(rpc_client, height))
+                .buffered(self.backfill_concurrency.max(1))
+                .try_collect()
+                .await?;
+            for data in batch {
+                self.index_block(data).await?;
+            }
+            batch_start = batch_end + 1;
+        }
+        Ok(())
+    }
+
+    async fn process_block_results(
+        &mut self,
+        block_results: &BlockResults,
+        block: Option<&Block>,
+    ) -> Result<()> {
+        let context = EventContext {
+            block_height: block_results.height,
+            block_time: block.map(|b| b.header.time),
+            proposer_address: block.map(|b| b.header.proposer_address),
+            ..EventContext::new(block_results.height)
+        };
+        // Events are numbered in the order they are delivered across the whole block
+        let mut event_index = 0;
+
         if let Some(events) = &block_results.begin_block_events {
             for event in events.iter() {
-                self.handler
-                    .handle_event(event, block_results.height)
-                    .await?;
+                self.dispatch_at(event, &context, &mut event_index).await?;
             }
         }
         if let Some(txs_results) = &block_results.txs_results {
-            for event in txs_results.iter().flat_map(|tx| tx.events.iter()) {
-                self.handler
-                    .handle_event(event, block_results.height)
-                    .await?;
+            for (index, tx) in txs_results.iter().enumerate() {
+                // Transaction results are in the same order as the transactions in the block
+                let tx_context = EventContext {
+                    tx_hash: block
+                        .and_then(|b| b.data.get(index))
+                        .map(|raw_tx| tx_hash(raw_tx)),
+                    ..context.clone()
+                };
+                for event in tx.events.iter() {
+                    self.dispatch_at(event, &tx_context, &mut event_index)
+                        .await?;
+                }
             }
         }
         if let Some(events) = &block_results.end_block_events {
             for event in events.iter() {
-                self.handler
-                    .handle_event(event, block_results.height)
-                    .await?;
+                self.dispatch_at(event, &context, &mut event_index).await?;
             }
         }
         for event in block_results.finalize_block_events.iter() {
-            self.handler
-                .handle_event(event, block_results.height)
-                .await?;
+            self.dispatch_at(event, &context, &mut event_index).await?;
         }
         Ok(())
     }
 
+    // Dispatches an event at the given position in the block and advances the position
+    async fn dispatch_at(
+        &mut self,
+        event: &crate::Event,
+        context: &EventContext,
+        event_index: &mut usize,
+    ) -> Result<()> {
+        let context = EventContext {
+            msg_index: msg_index(event),
+            event_index: *event_index,
+            ..context.clone()
+        };
+        *event_index += 1;
+        self.dispatch(event, &context).await
+    }
+
+    async fn dispatch(&mut self, event: &crate::Event, context: &EventContext) -> Result<()> {
+        if !self.filter.matches(event, context.block_height) {
+            return Ok(());
+        }
+        let mut result = self.handler.handle_event_with_context(event, context).await;
+        // Retrying won't fix a malformed event, so it is skipped whatever the error policy
+        if let Err(e @ Error::MalformedEvent(_)) = result {
+            log::warn!(
+                "Skipping malformed {} event at height {}: {:?}",
+                event.kind,
+                context.block_height,
+                e
+            );
+            self.send_to_dead_letter(event, context, &e);
+            return Ok(());
+        }
+
+        let (max_retries, skip_on_failure) = match self.error_policy {
+            HandlerErrorPolicy::Stop => (0, false),
+            HandlerErrorPolicy::Skip => (0, true),
+            HandlerErrorPolicy::Retry {
+                max_retries,
+                skip_on_failure,
+            } => (max_retries, skip_on_failure),
+        };
+        let mut delays = ExponentialBuilder::default()
+            .with_max_times(max_retries)
+            .with_jitter()
+            .build();
+        while result.is_err() {
+            let Some(delay) = delays.next() else {
+                break;
+            };
+            log::warn!(
+                "Retrying {} event at height {} in {:?}: {:?}",
+                event.kind,
+                context.block_height,
+                delay,
+                result
+            );
+            tokio::time::sleep(delay).await;
+            result = self.handler.handle_event_with_context(event, context).await;
+        }
+
+        match result {
+            Err(e) if skip_on_failure => {
+                log::error!(
+                    "Skipping {} event at height {} after handler error: {:?}",
+                    event.kind,
+                    context.block_height,
+                    e
+                );
+                self.send_to_dead_letter(event, context, &e);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    // Passes a skipped event to the dead letter callback, if any
+    fn send_to_dead_letter(&mut self, event: &crate::Event, context: &EventContext, error: &Error) {
+        if let Some(dead_letter) = self.dead_letter.as_mut() {
+            dead_letter(event, context, error);
+        }
+    }
+
     // Starts fetching events from the blockchain
     pub async fn start_fetching(&mut self) -> Result<()> {
         let rpc_client = rpc::HttpClient::new(self.rpc_url.as_str())?;
@@ -142,21 +892,328 @@ where
         } else {
             self.fetch_latest_block_number(&rpc_client).await?
         };
+        // Blocks before the filter's range don't need to be fetched
+        if let Some(from_height) = self.filter.from_height {
+            let before_range = Height::try_from(from_height.value().saturating_sub(1))?;
+            if before_range > last_indexed_block {
+                last_indexed_block = before_range;
+            }
+        }
 
         loop {
-            let latest_block = self.fetch_latest_block_number(&rpc_client).await?;
+            let mut latest_block = self.fetch_latest_block_number(&rpc_client).await?;
+            if let Some(to_height) = self.filter.to_height {
+                if last_indexed_block >= to_height {
+                    return Ok(());
+                }
+                latest_block = latest_block.min(to_height);
+            }
 
-            if latest_block > last_indexed_block {
-                for height in (last_indexed_block.value() + 1)..=latest_block.value() {
-                    let block_results = self
-                        .fetch_block_results(&rpc_client, Height::from(height as u32))
-                        .await?;
-                    log::debug!("Processing block results for height {}", height);
-                    self.process_block_results(&block_results).await?;
-                    last_indexed_block = Height::from(height as u32);
+            if self.reorg_window > 0 {
+                if let Some(valid_height) = self.detect_rollback(&rpc_client, latest_block).await? {
+                    last_indexed_block = valid_height;
                 }
             }
+
+            if latest_block > last_indexed_block {
+                self.index_blocks(
+                    &rpc_client,
+                    last_indexed_block.value() + 1,
+                    latest_block.value(),
+                )
+                .await?;
+                last_indexed_block = latest_block;
+            }
             tokio::time::sleep(self.sleep_time).await;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmrs::{rpc::dialect::v0_34::EventAttribute, tendermint::abci::Event};
+
+    #[derive(Default)]
+    struct Collector {
+        received: Vec<(GevulotEvent, Event)>,
+    }
+
+    impl RawAndParsedEventHandler for Collector {
+        async fn handle_event(
+            &mut self,
+            event: &GevulotEvent,
+            raw_event: &Event,
+            _block_height: Height,
+        ) -> Result<()> {
+            self.received.push((event.clone(), raw_event.clone()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_raw_and_parsed_delivers_both() {
+        let event = Event::new(
+            "create-worker",
+            vec![
+                EventAttribute {
+                    index: true,
+                    key: b"worker-id".to_vec(),
+                    value: b"worker1".to_vec(),
+                },
+                EventAttribute {
+                    index: true,
+                    key: b"extra".to_vec(),
+                    value: b"value".to_vec(),
+                },
+            ],
+        );
+        let unknown = Event::new("coin_spent", Vec::<EventAttribute>::new());
+
+        let mut handler = RawAndParsed(Collector::default());
+        handler
+            .handle_event(&event, Height::from(10u32))
+            .await
+            .unwrap();
+        handler
+            .handle_event(&unknown, Height::from(10u32))
+            .await
+            .unwrap();
+
+        assert_eq!(handler.0.received.len(), 1);
+        let (parsed, raw) = &handler.0.received[0];
+        assert!(matches!(parsed, GevulotEvent::Worker(_)));
+        assert!(raw.attributes.iter().any(|a| a.key_bytes() == b"extra"));
+    }
+
+    #[tokio::test]
+    async fn test_stream_forwarder_sends_parsed_events() {
+        let event = Event::new(
+            "delete-worker",
+            vec![
+                EventAttribute {
+                    index: true,
+                    key: b"worker-id".to_vec(),
+                    value: b"worker1".to_vec(),
+                },
+                EventAttribute {
+                    index: true,
+                    key: b"creator".to_vec(),
+                    value: b"alice".to_vec(),
+                },
+            ],
+        );
+        let unknown = Event::new("coin_spent", Vec::<EventAttribute>::new());
+
+        let (sender, mut receiver) = mpsc::channel(8);
+        let mut forwarder = StreamForwarder {
+            inner: (),
+            sender,
+            forward_unknown_events: false,
+        };
+        forwarder
+            .handle_event(&unknown, Height::from(3u32))
+            .await
+            .unwrap();
+        forwarder
+            .handle_event(&event, Height::from(3u32))
+            .await
+            .unwrap();
+        drop(forwarder);
+
+        let (parsed, height) = receiver.recv().await.unwrap().unwrap();
+        assert!(matches!(parsed, GevulotEvent::Worker(_)));
+        assert_eq!(height, Height::from(3u32));
+        assert!(receiver.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stream_forwarder_forwards_unknown_events() {
+        let unknown = Event::new("coin_spent", Vec::<EventAttribute>::new());
+
+        let (sender, mut receiver) = mpsc::channel(8);
+        let mut forwarder = StreamForwarder {
+            inner: (),
+            sender,
+            forward_unknown_events: true,
+        };
+        forwarder
+            .handle_event(&unknown, Height::from(3u32))
+            .await
+            .unwrap();
+        drop(forwarder);
+
+        let (parsed, _) = receiver.recv().await.unwrap().unwrap();
+        assert_eq!(parsed.kind_str(), "coin_spent");
+        assert!(parsed.is_unknown());
+    }
+
+    #[derive(Default)]
+    struct BlockCollector {
+        blocks: Vec<(BlockInfo, Vec<GevulotEvent>)>,
+    }
+
+    impl BlockEventHandler for BlockCollector {
+        async fn handle_block(
+            &mut self,
+            block: &BlockInfo,
+            events: Vec<GevulotEvent>,
+        ) -> Result<()> {
+            self.blocks.push((block.clone(), events));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_batched_delivers_events_per_block() {
+        let event = Event::new(
+            "create-worker",
+            vec![EventAttribute {
+                index: true,
+                key: b"worker-id".to_vec(),
+                value: b"worker1".to_vec(),
+            }],
+        );
+        let unknown = Event::new("coin_spent", Vec::<EventAttribute>::new());
+        let block = |height: u32| BlockInfo {
+            height: Height::from(height),
+            time: None,
+            hash: None,
+        };
+
+        let mut handler = BlockBatched::new(BlockCollector::default());
+        for e in [&event, &unknown, &event] {
+            handler.handle_event(e, Height::from(1u32)).await.unwrap();
+        }
+        handler.handle_block_end(&block(1)).await.unwrap();
+        handler.handle_block_end(&block(2)).await.unwrap();
+
+        let blocks = &handler.handler.blocks;
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].0, block(1));
+        assert_eq!(blocks[0].1.len(), 2);
+        assert_eq!(blocks[1].0, block(2));
+        assert!(blocks[1].1.is_empty());
+    }
+
+    struct Failing {
+        attempts: usize,
+    }
+
+    impl EventHandler for Failing {
+        async fn handle_event(&mut self, _event: &Event, _block_height: Height) -> Result<()> {
+            self.attempts += 1;
+            Err(Error::Unknown("handler failed".to_string()))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_error_policy() {
+        let event = Event::new("coin_spent", Vec::<EventAttribute>::new());
+        let context = EventContext::new(Height::from(7u32));
+
+        let mut fetcher = EventFetcher::new(
+            "http://localhost",
+            None,
+            Duration::ZERO,
+            Failing { attempts: 0 },
+        );
+        assert!(fetcher.dispatch(&event, &context).await.is_err());
+        assert_eq!(fetcher.handler.attempts, 1);
+
+        let dead_letters = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let collected = dead_letters.clone();
+        let mut fetcher = EventFetcher::new(
+            "http://localhost",
+            None,
+            Duration::ZERO,
+            Failing { attempts: 0 },
+        )
+        .with_error_policy(HandlerErrorPolicy::Retry {
+            max_retries: 2,
+            skip_on_failure: true,
+        })
+        .with_dead_letter(move |event, context, _error| {
+            collected
+                .lock()
+                .unwrap()
+                .push((event.kind.clone(), context.block_height))
+        });
+        fetcher.dispatch(&event, &context).await.unwrap();
+        assert_eq!(fetcher.handler.attempts, 3);
+        assert_eq!(
+            *dead_letters.lock().unwrap(),
+            vec![("coin_spent".to_string(), Height::from(7u32))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_malformed_event_goes_to_dead_letter() {
+        let malformed = Event::new("create-worker", Vec::<EventAttribute>::new());
+        let context = EventContext::new(Height::from(3u32));
+
+        let dead_letters = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let collected = dead_letters.clone();
+        let mut fetcher = EventFetcher::new(
+            "http://localhost",
+            None,
+            Duration::ZERO,
+            BlockBatched::new(BlockCollector::default()),
+        )
+        .with_dead_letter(move |event, _context, error| {
+            collected.lock().unwrap().push((
+                event.kind.clone(),
+                matches!(error, Error::MalformedEvent(_)),
+            ))
+        });
+        fetcher.dispatch(&malformed, &context).await.unwrap();
+        assert_eq!(
+            *dead_letters.lock().unwrap(),
+            vec![("create-worker".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn test_block_hashes_window() {
+        let mut hashes = BlockHashes::default();
+        for height in 1u32..=5 {
+            hashes.insert(Height::from(height), Hash::Sha256([height as u8; 32]), 3);
+        }
+        assert_eq!(hashes.first_height(), Some(3));
+        assert_eq!(
+            hashes.iter_rev().map(|(h, _)| h).collect::<Vec<_>>(),
+            vec![5, 4, 3]
+        );
+
+        hashes.truncate(4);
+        assert_eq!(
+            hashes.iter_rev().map(|(h, _)| h).collect::<Vec<_>>(),
+            vec![3]
+        );
+    }
+
+    #[test]
+    fn test_msg_index() {
+        let event = Event::new(
+            "create-task",
+            vec![EventAttribute {
+                index: true,
+                key: b"msg_index".to_vec(),
+                value: b"2".to_vec(),
+            }],
+        );
+        assert_eq!(msg_index(&event), Some(2));
+        assert_eq!(
+            msg_index(&Event::new("create-task", Vec::<EventAttribute>::new())),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tx_hash() {
+        assert_eq!(
+            tx_hash(b"hello"),
+            "2CF24DBA5FB0A30E26E83B2AC5B9E29E1B161E5C1FA7425E73043362938B9824"
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use cosmrs::tendermint::block::Height;
+
+use crate::events::keys::{attr, kind};
+
+// Attribute keys carrying the id of the entity an event refers to
+const ENTITY_ID_KEYS: [&str; 5] = [
+    attr::TASK_ID,
+    attr::WORKER_ID,
+    attr::WORKFLOW_ID,
+    attr::CID,
+    attr::ID,
+];
+
+// Filter applied to events before they are handed to an EventHandler.
+// Empty lists match everything, all set criteria have to match for an event to pass. Filtering
+// happens client-side, only the block range limits which blocks are fetched from the node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    // Event kinds to accept, e.g. "create-task"
+    pub kinds: Vec<String>,
+    // Entity ids to accept, matched against task-id, worker-id, workflow-id, cid and id attributes
+    pub entity_ids: Vec<String>,
+    // Creator addresses to accept
+    pub creators: Vec<String>,
+    // First block height to accept (inclusive)
+    pub from_height: Option<Height>,
+    // Last block height to accept (inclusive)
+    pub to_height: Option<Height>,
+}
+
+impl EventFilter {
+    // Creates a filter matching every event
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Accepts events of the given kind
+    pub fn kind(mut self, kind: impl Into<String>) -> Self {
+        self.kinds.push(kind.into());
+        self
+    }
+
+    // Accepts task events only
+    pub fn tasks(self) -> Self {
+        [
+            kind::CREATE_TASK,
+            kind::DELETE_TASK,
+            kind::ACCEPT_TASK,
+            kind::DECLINE_TASK,
+            kind::FINISH_TASK,
+        ]
+        .into_iter()
+        .fold(self, |filter, kind| filter.kind(kind))
+    }
+
+    // Accepts worker events only
+    pub fn workers(self) -> Self {
+        [
+            kind::CREATE_WORKER,
+            kind::UPDATE_WORKER,
+            kind::DELETE_WORKER,
+            kind::ANNOUNCE_WORKER_EXIT,
+        ]
+        .into_iter()
+        .fold(self, |filter, kind| filter.kind(kind))
+    }
+
+    // Accepts workflow events only
+    pub fn workflows(self) -> Self {
+        [
+            kind::CREATE_WORKFLOW,
+            kind::DELETE_WORKFLOW,
+            kind::PROGRESS_WORKFLOW,
+            kind::FINISH_WORKFLOW,
+        ]
+        .into_iter()
+        .fold(self, |filter, kind| filter.kind(kind))
+    }
+
+    // Accepts pin events only
+    pub fn pins(self) -> Self {
+        [kind::CREATE_PIN, kind::DELETE_PIN, kind::ACK_PIN]
+            .into_iter()
+            .fold(self, |filter, kind| filter.kind(kind))
+    }
+
+    // Accepts events referring to the given entity id
+    pub fn entity_id(mut self, id: impl Into<String>) -> Self {
+        self.entity_ids.push(id.into());
+        self
+    }
+
+    // Accepts events created by the given address
+    pub fn creator(mut self, creator: impl Into<String>) -> Self {
+        self.creators.push(creator.into());
+        self
+    }
+
+    // Accepts events from the given block height onwards
+    pub fn from_height(mut self, height: Height) -> Self {
+        self.from_height = Some(height);
+        self
+    }
+
+    // Accepts events up to and including the given block height
+    pub fn to_height(mut self, height: Height) -> Self {
+        self.to_height = Some(height);
+        self
+    }
+
+    // Returns true if the height lies within the filter's block range
+    pub fn matches_height(&self, height: Height) -> bool {
+        self.from_height.map_or(true, |from| height >= from)
+            && self.to_height.map_or(true, |to| height <= to)
+    }
+
+    // Returns true if the event passes the filter
+    pub fn matches(&self, event: &crate::Event, height: Height) -> bool {
+        if !self.matches_height(height) {
+            return false;
+        }
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind) {
+            return false;
+        }
+        if !self.creators.is_empty()
+            && !event.attributes.iter().any(|attr| {
+                attr.key_bytes() == attr::CREATOR.as_bytes()
+                    && self
+                        .creators
+                        .iter()
+                        .any(|creator| attr.value_bytes() == creator.as_bytes())
+            })
+        {
+            return false;
+        }
+        if !self.entity_ids.is_empty()
+            && !event.attributes.iter().any(|attr| {
+                ENTITY_ID_KEYS
+                    .iter()
+                    .any(|key| attr.key_bytes() == key.as_bytes())
+                    && self
+                        .entity_ids
+                        .iter()
+                        .any(|id| attr.value_bytes() == id.as_bytes())
+            })
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmrs::{rpc::dialect::v0_34::EventAttribute, tendermint::abci::Event};
+
+    fn task_event(kind: &str, task_id: &str, creator: &str) -> Event {
+        Event::new(
+            kind,
+            vec![
+                EventAttribute {
+                    index: true,
+                    key: b"task-id".to_vec(),
+                    value: task_id.as_bytes().to_vec(),
+                },
+                EventAttribute {
+                    index: true,
+                    key: b"creator".to_vec(),
+                    value: creator.as_bytes().to_vec(),
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_empty_filter_matches_everything() {
+        let event = task_event("create-task", "task1", "alice");
+        assert!(EventFilter::new().matches(&event, Height::from(1u32)));
+    }
+
+    #[test]
+    fn test_filter_by_kind_creator_and_id() {
+        let filter = EventFilter::new().tasks().creator("alice");
+        let height = Height::from(5u32);
+        assert!(filter.matches(&task_event("create-task", "task1", "alice"), height));
+        assert!(!filter.matches(&task_event("create-task", "task1", "bob"), height));
+        assert!(!filter.matches(&task_event("create-workflow", "wf1", "alice"), height));
+
+        let filter = EventFilter::new().entity_id("task2");
+        assert!(filter.matches(&task_event("finish-task", "task2", "bob"), height));
+        assert!(!filter.matches(&task_event("finish-task", "task1", "bob"), height));
+    }
+
+    #[test]
+    fn test_filter_by_height() {
+        let filter = EventFilter::new()
+            .from_height(Height::from(10u32))
+            .to_height(Height::from(20u32));
+        let event = task_event("create-task", "task1", "alice");
+        assert!(!filter.matches(&event, Height::from(9u32)));
+        assert!(filter.matches(&event, Height::from(10u32)));
+        assert!(filter.matches(&event, Height::from(20u32)));
+        assert!(!filter.matches(&event, Height::from(21u32)));
+    }
+}
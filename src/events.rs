@@ -1,503 +1,515 @@
 use cosmrs::tendermint::block::Height;
+use serde::{Deserialize, Serialize};
 
 use crate::error::Error;
 
-#[derive(Clone, Debug)]
+use keys::attr;
+
+/// Event kinds and attribute keys emitted by the Gevulot module.
+pub mod keys {
+    /// Event kinds.
+    pub mod kind {
+        pub const CREATE_WORKER: &str = "create-worker";
+        pub const UPDATE_WORKER: &str = "update-worker";
+        pub const DELETE_WORKER: &str = "delete-worker";
+        pub const ANNOUNCE_WORKER_EXIT: &str = "announce-worker-exit";
+        pub const CREATE_TASK: &str = "create-task";
+        pub const DELETE_TASK: &str = "delete-task";
+        pub const FINISH_TASK: &str = "finish-task";
+        pub const DECLINE_TASK: &str = "decline-task";
+        pub const ACCEPT_TASK: &str = "accept-task";
+        pub const CREATE_WORKFLOW: &str = "create-workflow";
+        pub const DELETE_WORKFLOW: &str = "delete-workflow";
+        pub const FINISH_WORKFLOW: &str = "finish-workflow";
+        pub const PROGRESS_WORKFLOW: &str = "progress-workflow";
+        pub const CREATE_PIN: &str = "create-pin";
+        pub const DELETE_PIN: &str = "delete-pin";
+        pub const ACK_PIN: &str = "ack-pin";
+    }
+
+    /// Attribute keys.
+    pub mod attr {
+        pub const WORKER_ID: &str = "worker-id";
+        pub const CREATOR: &str = "creator";
+        pub const TASK_ID: &str = "task-id";
+        pub const WORKFLOW_ID: &str = "workflow-id";
+        pub const CID: &str = "cid";
+        pub const ASSIGNED_WORKERS: &str = "assigned-workers";
+        pub const RETENTION_PERIOD: &str = "retention-period";
+        pub const FALLBACK_URLS: &str = "fallback-urls";
+        pub const ID: &str = "id";
+        pub const SUCCESS: &str = "success";
+        pub const CURRENT_STAGE: &str = "current-stage";
+        pub const FINISHED_TASKS: &str = "finished-tasks";
+        pub const FAILED_TASK_IDS: &str = "failed-task-ids";
+    }
+
+    /// Typed Gevulot event kind.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub enum EventKind {
+        CreateWorker,
+        UpdateWorker,
+        DeleteWorker,
+        AnnounceWorkerExit,
+        CreateTask,
+        DeleteTask,
+        AcceptTask,
+        DeclineTask,
+        FinishTask,
+        CreateWorkflow,
+        DeleteWorkflow,
+        ProgressWorkflow,
+        FinishWorkflow,
+        CreatePin,
+        DeletePin,
+        AckPin,
+    }
+
+    impl EventKind {
+        /// All event kinds emitted by the Gevulot module.
+        pub const ALL: [EventKind; 16] = [
+            EventKind::CreateWorker,
+            EventKind::UpdateWorker,
+            EventKind::DeleteWorker,
+            EventKind::AnnounceWorkerExit,
+            EventKind::CreateTask,
+            EventKind::DeleteTask,
+            EventKind::AcceptTask,
+            EventKind::DeclineTask,
+            EventKind::FinishTask,
+            EventKind::CreateWorkflow,
+            EventKind::DeleteWorkflow,
+            EventKind::ProgressWorkflow,
+            EventKind::FinishWorkflow,
+            EventKind::CreatePin,
+            EventKind::DeletePin,
+            EventKind::AckPin,
+        ];
+
+        /// Returns the event kind as it appears in events.
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                EventKind::CreateWorker => kind::CREATE_WORKER,
+                EventKind::UpdateWorker => kind::UPDATE_WORKER,
+                EventKind::DeleteWorker => kind::DELETE_WORKER,
+                EventKind::AnnounceWorkerExit => kind::ANNOUNCE_WORKER_EXIT,
+                EventKind::CreateTask => kind::CREATE_TASK,
+                EventKind::DeleteTask => kind::DELETE_TASK,
+                EventKind::AcceptTask => kind::ACCEPT_TASK,
+                EventKind::DeclineTask => kind::DECLINE_TASK,
+                EventKind::FinishTask => kind::FINISH_TASK,
+                EventKind::CreateWorkflow => kind::CREATE_WORKFLOW,
+                EventKind::DeleteWorkflow => kind::DELETE_WORKFLOW,
+                EventKind::ProgressWorkflow => kind::PROGRESS_WORKFLOW,
+                EventKind::FinishWorkflow => kind::FINISH_WORKFLOW,
+                EventKind::CreatePin => kind::CREATE_PIN,
+                EventKind::DeletePin => kind::DELETE_PIN,
+                EventKind::AckPin => kind::ACK_PIN,
+            }
+        }
+    }
+
+    impl std::fmt::Display for EventKind {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(self.as_str())
+        }
+    }
+
+    impl std::str::FromStr for EventKind {
+        type Err = crate::error::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                kind::CREATE_WORKER => Ok(EventKind::CreateWorker),
+                kind::UPDATE_WORKER => Ok(EventKind::UpdateWorker),
+                kind::DELETE_WORKER => Ok(EventKind::DeleteWorker),
+                kind::ANNOUNCE_WORKER_EXIT => Ok(EventKind::AnnounceWorkerExit),
+                kind::CREATE_TASK => Ok(EventKind::CreateTask),
+                kind::DELETE_TASK => Ok(EventKind::DeleteTask),
+                kind::ACCEPT_TASK => Ok(EventKind::AcceptTask),
+                kind::DECLINE_TASK => Ok(EventKind::DeclineTask),
+                kind::FINISH_TASK => Ok(EventKind::FinishTask),
+                kind::CREATE_WORKFLOW => Ok(EventKind::CreateWorkflow),
+                kind::DELETE_WORKFLOW => Ok(EventKind::DeleteWorkflow),
+                kind::PROGRESS_WORKFLOW => Ok(EventKind::ProgressWorkflow),
+                kind::FINISH_WORKFLOW => Ok(EventKind::FinishWorkflow),
+                kind::CREATE_PIN => Ok(EventKind::CreatePin),
+                kind::DELETE_PIN => Ok(EventKind::DeletePin),
+                kind::ACK_PIN => Ok(EventKind::AckPin),
+                _ => Err(crate::error::Error::UnknownEventKind(s.to_string())),
+            }
+        }
+    }
+}
+
+/// A parsed Gevulot event.
+///
+/// Serializes to a flat JSON object tagged with the `module` the event belongs to and the
+/// event `kind` as emitted by the chain, e.g.
+/// `{"module":"task","kind":"create-task","block_height":"10","task_id":"...",...}`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "module", rename_all = "kebab-case")]
 pub enum GevulotEvent {
     Pin(PinEvent),
     Task(TaskEvent),
     Worker(WorkerEvent),
     Workflow(WorkflowEvent),
+    /// An event of a kind this crate doesn't know yet, e.g. one added in a newer chain version.
+    ///
+    /// Only produced by [`GevulotEvent::from_cosmos_or_unknown`], [`GevulotEvent::from_cosmos`]
+    /// returns [`Error::UnknownEventKind`] for such events.
+    Unknown {
+        block_height: Height,
+        kind: String,
+        attributes: Vec<(String, String)>,
+    },
+    /// An event of a chain extension, decoded by a decoder registered with
+    /// [`register_event`](crate::registry::register_event).
+    Extension {
+        block_height: Height,
+        kind: String,
+        value: serde_json::Value,
+    },
 }
 
 impl GevulotEvent {
-    pub fn from_cosmos(
-        event: &cosmrs::tendermint::abci::Event,
-        block_height: Height,
-    ) -> crate::error::Result<Self> {
-        match event.kind.as_str() {
-            "create-worker" => {
-                let worker_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"worker-id")
-                    .ok_or(Error::MissingEventAttribute("worker-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                Ok(GevulotEvent::Worker(WorkerEvent::Create(
-                    WorkerCreateEvent {
-                        block_height,
-                        worker_id,
-                        creator,
-                    },
-                )))
-            }
-            "update-worker" => {
-                let worker_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"worker-id")
-                    .ok_or(Error::MissingEventAttribute("worker-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                Ok(GevulotEvent::Worker(WorkerEvent::Update(
-                    WorkerUpdateEvent {
-                        block_height,
-                        worker_id,
-                        creator,
-                    },
-                )))
-            }
-            "delete-worker" => {
-                let worker_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"worker-id")
-                    .ok_or(Error::MissingEventAttribute("worker-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                Ok(GevulotEvent::Worker(WorkerEvent::Delete(
-                    WorkerDeleteEvent {
-                        block_height,
-                        worker_id,
-                        creator,
-                    },
-                )))
-            }
-            "announce-worker-exit" => {
-                let worker_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"worker-id")
-                    .ok_or(Error::MissingEventAttribute("worker-id"))?
-                    .value_str()?
-                    .to_string();
+    /// Returns the kind of the event, None for unknown and extension events.
+    pub fn kind(&self) -> Option<keys::EventKind> {
+        use keys::EventKind;
+        Some(match self {
+            GevulotEvent::Pin(PinEvent::Create(_)) => EventKind::CreatePin,
+            GevulotEvent::Pin(PinEvent::Delete(_)) => EventKind::DeletePin,
+            GevulotEvent::Pin(PinEvent::Ack(_)) => EventKind::AckPin,
+            GevulotEvent::Task(TaskEvent::Create(_)) => EventKind::CreateTask,
+            GevulotEvent::Task(TaskEvent::Delete(_)) => EventKind::DeleteTask,
+            GevulotEvent::Task(TaskEvent::Accept(_)) => EventKind::AcceptTask,
+            GevulotEvent::Task(TaskEvent::Decline(_)) => EventKind::DeclineTask,
+            GevulotEvent::Task(TaskEvent::Finish(_)) => EventKind::FinishTask,
+            GevulotEvent::Worker(WorkerEvent::Create(_)) => EventKind::CreateWorker,
+            GevulotEvent::Worker(WorkerEvent::Update(_)) => EventKind::UpdateWorker,
+            GevulotEvent::Worker(WorkerEvent::Delete(_)) => EventKind::DeleteWorker,
+            GevulotEvent::Worker(WorkerEvent::AnnounceExit(_)) => EventKind::AnnounceWorkerExit,
+            GevulotEvent::Workflow(WorkflowEvent::Create(_)) => EventKind::CreateWorkflow,
+            GevulotEvent::Workflow(WorkflowEvent::Delete(_)) => EventKind::DeleteWorkflow,
+            GevulotEvent::Workflow(WorkflowEvent::Progress(_)) => EventKind::ProgressWorkflow,
+            GevulotEvent::Workflow(WorkflowEvent::Finish(_)) => EventKind::FinishWorkflow,
+            GevulotEvent::Unknown { .. } | GevulotEvent::Extension { .. } => return None,
+        })
+    }
 
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                Ok(GevulotEvent::Worker(WorkerEvent::AnnounceExit(
-                    WorkerAnnounceExitEvent {
-                        block_height,
-                        worker_id,
-                        creator,
-                    },
-                )))
-            }
-            "create-task" => {
-                let task_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"task-id")
-                    .ok_or(Error::MissingEventAttribute("task-id"))?
-                    .value_str()?
-                    .to_string();
+    /// Returns the kind of the event as emitted by the chain, e.g. `create-task`.
+    pub fn kind_str(&self) -> &str {
+        match self {
+            GevulotEvent::Unknown { kind, .. } | GevulotEvent::Extension { kind, .. } => kind,
+            event => event.kind().map(|kind| kind.as_str()).unwrap_or_default(),
+        }
+    }
 
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
+    /// Returns true if the event is of a kind this crate doesn't know.
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, GevulotEvent::Unknown { .. })
+    }
 
-                let assigned_workers = event
+    /// Parses an event like [`GevulotEvent::from_cosmos`], but returns events of unknown kinds
+    /// as [`GevulotEvent::Unknown`] instead of failing.
+    ///
+    /// Note that this includes events emitted by other modules of the chain, e.g. `transfer`.
+    pub fn from_cosmos_or_unknown(
+        event: &cosmrs::tendermint::abci::Event,
+        block_height: Height,
+    ) -> crate::error::Result<Self> {
+        match Self::from_cosmos(event, block_height) {
+            Err(Error::UnknownEventKind(kind)) => Ok(GevulotEvent::Unknown {
+                block_height,
+                kind,
+                attributes: event
                     .attributes
                     .iter()
-                    .filter(|attr| attr.key_bytes() == b"worker-id")
-                    .flat_map(|attr| {
-                        attr.value_str()
-                            .map(|s| {
-                                s.split(',')
-                                    .map(|x| x.trim().to_string())
-                                    .collect::<Vec<_>>()
-                            })
-                            .unwrap_or_default()
+                    .map(|attr| {
+                        (
+                            String::from_utf8_lossy(attr.key_bytes()).into_owned(),
+                            String::from_utf8_lossy(attr.value_bytes()).into_owned(),
+                        )
                     })
-                    .collect::<Vec<String>>();
+                    .collect(),
+            }),
+            result => result,
+        }
+    }
 
-                Ok(GevulotEvent::Task(TaskEvent::Create(TaskCreateEvent {
+    pub fn from_cosmos(
+        event: &cosmrs::tendermint::abci::Event,
+        block_height: Height,
+    ) -> crate::error::Result<Self> {
+        // Most events in a block belong to other modules, reject them before indexing attributes
+        let Ok(event_kind) = event.kind.parse::<keys::EventKind>() else {
+            return match crate::registry::decode_event(event, block_height) {
+                Some(value) => Ok(GevulotEvent::Extension {
                     block_height,
-                    task_id,
-                    creator,
-                    assigned_workers,
-                })))
-            }
-            "delete-task" => {
-                let task_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"task-id")
-                    .ok_or(Error::MissingEventAttribute("task-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                Ok(GevulotEvent::Task(TaskEvent::Delete(TaskDeleteEvent {
+                    kind: event.kind.clone(),
+                    value: value?,
+                }),
+                None => Err(Error::UnknownEventKind(event.kind.clone())),
+            };
+        };
+        let attrs = Attributes::new(event);
+
+        use keys::EventKind;
+        match event_kind {
+            EventKind::CreateWorker => Ok(GevulotEvent::Worker(WorkerEvent::Create(
+                WorkerCreateEvent {
                     block_height,
-                    task_id,
-                    creator,
-                })))
-            }
-            "finish-task" => {
-                let task_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"task-id")
-                    .ok_or(Error::MissingEventAttribute("task-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let worker_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"worker-id")
-                    .ok_or(Error::MissingEventAttribute("worker-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-                Ok(GevulotEvent::Task(TaskEvent::Finish(TaskFinishEvent {
+                    worker_id: attrs.required(attr::WORKER_ID)?,
+                    creator: attrs.optional(attr::CREATOR),
+                },
+            ))),
+            EventKind::UpdateWorker => Ok(GevulotEvent::Worker(WorkerEvent::Update(
+                WorkerUpdateEvent {
                     block_height,
-                    task_id,
-                    worker_id,
-                    creator,
-                })))
-            }
-            "decline-task" => {
-                let task_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"task-id")
-                    .ok_or(Error::MissingEventAttribute("task-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                let worker_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"worker-id")
-                    .ok_or(Error::MissingEventAttribute("worker-id"))?
-                    .value_str()?
-                    .to_string();
+                    worker_id: attrs.required(attr::WORKER_ID)?,
+                    creator: attrs.optional(attr::CREATOR),
+                },
+            ))),
+            EventKind::DeleteWorker => Ok(GevulotEvent::Worker(WorkerEvent::Delete(
+                WorkerDeleteEvent {
+                    block_height,
+                    worker_id: attrs.required(attr::WORKER_ID)?,
+                    creator: attrs.optional(attr::CREATOR),
+                },
+            ))),
+            EventKind::AnnounceWorkerExit => Ok(GevulotEvent::Worker(WorkerEvent::AnnounceExit(
+                WorkerAnnounceExitEvent {
+                    block_height,
+                    worker_id: attrs.required(attr::WORKER_ID)?,
+                    creator: attrs.optional(attr::CREATOR),
+                },
+            ))),
+            EventKind::CreateTask => Ok(GevulotEvent::Task(TaskEvent::Create(TaskCreateEvent {
+                block_height,
+                task_id: attrs.required(attr::TASK_ID)?,
+                creator: attrs.optional(attr::CREATOR),
+                assigned_workers: attrs.list(attr::WORKER_ID).collect(),
+            }))),
+            EventKind::DeleteTask => Ok(GevulotEvent::Task(TaskEvent::Delete(TaskDeleteEvent {
+                block_height,
+                task_id: attrs.required(attr::TASK_ID)?,
+                creator: attrs.optional(attr::CREATOR),
+            }))),
+            EventKind::FinishTask => Ok(GevulotEvent::Task(TaskEvent::Finish(TaskFinishEvent {
+                block_height,
+                task_id: attrs.required(attr::TASK_ID)?,
+                worker_id: attrs.required(attr::WORKER_ID)?,
+                creator: attrs.optional(attr::CREATOR),
+            }))),
+            EventKind::DeclineTask => {
                 Ok(GevulotEvent::Task(TaskEvent::Decline(TaskDeclineEvent {
                     block_height,
-                    task_id,
-                    creator,
-                    worker_id,
+                    task_id: attrs.required(attr::TASK_ID)?,
+                    creator: attrs.optional(attr::CREATOR),
+                    worker_id: attrs.required(attr::WORKER_ID)?,
                 })))
             }
-            "accept-task" => {
-                let task_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"task-id")
-                    .ok_or(Error::MissingEventAttribute("task-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                let worker_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"worker-id")
-                    .ok_or(Error::MissingEventAttribute("worker-id"))?
-                    .value_str()?
-                    .to_string();
-                Ok(GevulotEvent::Task(TaskEvent::Accept(TaskAcceptEvent {
+            EventKind::AcceptTask => Ok(GevulotEvent::Task(TaskEvent::Accept(TaskAcceptEvent {
+                block_height,
+                task_id: attrs.required(attr::TASK_ID)?,
+                creator: attrs.optional(attr::CREATOR),
+                worker_id: attrs.required(attr::WORKER_ID)?,
+            }))),
+            EventKind::CreateWorkflow => Ok(GevulotEvent::Workflow(WorkflowEvent::Create(
+                WorkflowCreateEvent {
                     block_height,
-                    task_id,
-                    creator,
-                    worker_id,
-                })))
-            }
-            "create-workflow" => {
-                let workflow_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"workflow-id")
-                    .ok_or(Error::MissingEventAttribute("workflow-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                Ok(GevulotEvent::Workflow(WorkflowEvent::Create(
-                    WorkflowCreateEvent {
-                        block_height,
-                        workflow_id,
-                        creator,
-                    },
-                )))
-            }
-            "delete-workflow" => {
-                let workflow_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"workflow-id")
-                    .ok_or(Error::MissingEventAttribute("workflow-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                Ok(GevulotEvent::Workflow(WorkflowEvent::Delete(
-                    WorkflowDeleteEvent {
-                        block_height,
-                        workflow_id,
-                        creator,
-                    },
-                )))
-            }
-            "finish-workflow" => {
-                let workflow_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"workflow-id")
-                    .ok_or(Error::MissingEventAttribute("workflow-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_default();
-
-                Ok(GevulotEvent::Workflow(WorkflowEvent::Finish(
-                    WorkflowFinishEvent {
-                        block_height,
-                        workflow_id,
-                        creator,
-                    },
-                )))
-            }
-            "progress-workflow" => {
-                let workflow_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"workflow-id")
-                    .ok_or(Error::MissingEventAttribute("workflow-id"))?
-                    .value_str()?
-                    .to_string();
-
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .ok_or(Error::MissingEventAttribute("creator"))?
-                    .value_str()?
-                    .to_string();
-
-                Ok(GevulotEvent::Workflow(WorkflowEvent::Progress(
-                    WorkflowProgressEvent {
-                        block_height,
-                        workflow_id,
-                        creator,
-                    },
-                )))
-            }
-            "create-pin" => {
-                let cid = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"cid")
-                    .ok_or(Error::MissingEventAttribute("cid"))?
-                    .value_str()?
-                    .to_string();
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .ok_or(Error::MissingEventAttribute("creator"))?
-                    .value_str()?
-                    .to_string();
-                let assigned_workers = event
-                    .attributes
-                    .iter()
-                    .filter(|attr| attr.key_bytes() == b"assigned-workers")
-                    .flat_map(|attr| {
-                        attr.value_str()
-                            .map(|s| {
-                                s.split(',')
-                                    .map(|x| x.trim().to_string())
-                                    .collect::<Vec<_>>()
-                            })
-                            .unwrap_or_default()
-                    })
-                    .collect::<Vec<String>>();
-                let retention_period = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"retention-period")
-                    .ok_or(Error::MissingEventAttribute("retention-period"))?
-                    .value_str()?
-                    .parse()
-                    .map_err(|_| Error::InvalidEventAttribute("retention-period"))?;
-                let fallback_urls = event
-                    .attributes
-                    .iter()
-                    .filter(|attr| attr.key_bytes() == b"fallback-urls")
-                    .flat_map(|attr| {
-                        attr.value_str()
-                            .map(|s| {
-                                s.split(',')
-                                    .map(|x| x.trim().to_string())
-                                    .collect::<Vec<_>>()
-                            })
-                            .unwrap_or_default()
-                    })
-                    .filter(|url| !url.is_empty())
-                    .collect::<Vec<String>>();
-                let id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"id")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_else(|| cid.clone());
-
+                    workflow_id: attrs.required(attr::WORKFLOW_ID)?,
+                    creator: attrs.optional(attr::CREATOR),
+                },
+            ))),
+            EventKind::DeleteWorkflow => Ok(GevulotEvent::Workflow(WorkflowEvent::Delete(
+                WorkflowDeleteEvent {
+                    block_height,
+                    workflow_id: attrs.required(attr::WORKFLOW_ID)?,
+                    creator: attrs.optional(attr::CREATOR),
+                },
+            ))),
+            EventKind::FinishWorkflow => Ok(GevulotEvent::Workflow(WorkflowEvent::Finish(
+                WorkflowFinishEvent {
+                    block_height,
+                    workflow_id: attrs.required(attr::WORKFLOW_ID)?,
+                    creator: attrs.optional(attr::CREATOR),
+                },
+            ))),
+            EventKind::ProgressWorkflow => Ok(GevulotEvent::Workflow(WorkflowEvent::Progress(
+                WorkflowProgressEvent {
+                    block_height,
+                    workflow_id: attrs.required(attr::WORKFLOW_ID)?,
+                    creator: attrs.required(attr::CREATOR)?,
+                    current_stage: attrs.parse(attr::CURRENT_STAGE)?,
+                    finished_tasks: attrs.parse(attr::FINISHED_TASKS)?,
+                    failed_task_ids: attrs
+                        .list(attr::FAILED_TASK_IDS)
+                        .filter(|x| !x.is_empty())
+                        .collect(),
+                },
+            ))),
+            EventKind::CreatePin => {
+                let cid = attrs.required(attr::CID)?;
                 Ok(GevulotEvent::Pin(PinEvent::Create(PinCreateEvent {
                     block_height,
+                    creator: attrs.required(attr::CREATOR)?,
+                    assigned_workers: attrs.list(attr::ASSIGNED_WORKERS).collect(),
+                    retention_period: attrs
+                        .parse(attr::RETENTION_PERIOD)?
+                        .ok_or(Error::MissingEventAttribute(attr::RETENTION_PERIOD))?,
+                    fallback_urls: attrs
+                        .list(attr::FALLBACK_URLS)
+                        .filter(|url| !url.is_empty())
+                        .collect(),
+                    id: attrs.id_or(&cid),
                     cid,
-                    creator,
-                    assigned_workers,
-                    retention_period,
-                    fallback_urls,
-                    id,
                 })))
             }
-            "delete-pin" => {
-                let cid = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"cid")
-                    .ok_or(Error::MissingEventAttribute("cid"))?
-                    .value_str()?
-                    .to_string();
-                let creator = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"creator")
-                    .ok_or(Error::MissingEventAttribute("creator"))?
-                    .value_str()?
-                    .to_string();
-                let id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"id")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_else(|| cid.clone());
-
+            EventKind::DeletePin => {
+                let cid = attrs.required(attr::CID)?;
                 Ok(GevulotEvent::Pin(PinEvent::Delete(PinDeleteEvent {
                     block_height,
+                    creator: attrs.required(attr::CREATOR)?,
+                    id: attrs.id_or(&cid),
                     cid,
-                    creator,
-                    id,
                 })))
             }
-            "ack-pin" => {
-                let cid = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"cid")
-                    .ok_or(Error::MissingEventAttribute("cid"))?
-                    .value_str()?
-                    .to_string();
-                let worker_id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"worker-id")
-                    .ok_or(Error::MissingEventAttribute("worker-id"))?
-                    .value_str()?
-                    .to_string();
-                let success = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"success")
-                    .map(|attr| attr.value_str().unwrap_or("true").parse().unwrap_or(true))
-                    .unwrap_or(true);
-                let id = event
-                    .attributes
-                    .iter()
-                    .find(|attr| attr.key_bytes() == b"id")
-                    .map(|attr| attr.value_str().unwrap_or_default().to_string())
-                    .unwrap_or_else(|| cid.clone());
+            EventKind::AckPin => {
+                let cid = attrs.required(attr::CID)?;
                 Ok(GevulotEvent::Pin(PinEvent::Ack(PinAckEvent {
                     block_height,
+                    worker_id: attrs.required(attr::WORKER_ID)?,
+                    success: attrs
+                        .get(attr::SUCCESS)
+                        .map(|attr| attr.value_str().unwrap_or("true").parse().unwrap_or(true))
+                        .unwrap_or(true),
+                    id: attrs.id_or(&cid),
                     cid,
-                    worker_id,
-                    success,
-                    id,
                 })))
             }
-            _ => Err(Error::UnknownEventKind(event.kind.clone())),
         }
     }
 }
 
-#[derive(Clone, Debug)]
+// Number of attribute keys indexed by Attributes
+const INDEXED_KEYS: usize = 13;
+
+// Returns the slot of an attribute key in Attributes
+fn key_index(key: &[u8]) -> Option<usize> {
+    let index = match std::str::from_utf8(key).ok()? {
+        attr::WORKER_ID => 0,
+        attr::CREATOR => 1,
+        attr::TASK_ID => 2,
+        attr::WORKFLOW_ID => 3,
+        attr::CID => 4,
+        attr::ASSIGNED_WORKERS => 5,
+        attr::RETENTION_PERIOD => 6,
+        attr::FALLBACK_URLS => 7,
+        attr::ID => 8,
+        attr::SUCCESS => 9,
+        attr::CURRENT_STAGE => 10,
+        attr::FINISHED_TASKS => 11,
+        attr::FAILED_TASK_IDS => 12,
+        _ => return None,
+    };
+    Some(index)
+}
+
+// Attributes of an event indexed by key in a single pass, so looking up an attribute doesn't
+// scan all attributes of the event again. Keeps the first attribute for every known key.
+struct Attributes<'a> {
+    event: &'a cosmrs::tendermint::abci::Event,
+    first: [Option<&'a cosmrs::tendermint::abci::EventAttribute>; INDEXED_KEYS],
+    // Whether a key occurs more than once, only then list values need another pass
+    repeated: [bool; INDEXED_KEYS],
+}
+
+impl<'a> Attributes<'a> {
+    fn new(event: &'a cosmrs::tendermint::abci::Event) -> Self {
+        let mut first = [None; INDEXED_KEYS];
+        let mut repeated = [false; INDEXED_KEYS];
+        for attr in event.attributes.iter() {
+            if let Some(index) = key_index(attr.key_bytes()) {
+                if first[index].is_some() {
+                    repeated[index] = true;
+                } else {
+                    first[index] = Some(attr);
+                }
+            }
+        }
+        Self {
+            event,
+            first,
+            repeated,
+        }
+    }
+
+    fn get(&self, key: &'static str) -> Option<&'a cosmrs::tendermint::abci::EventAttribute> {
+        key_index(key.as_bytes()).and_then(|index| self.first[index])
+    }
+
+    // Value of an attribute which has to be present
+    fn required(&self, key: &'static str) -> crate::error::Result<String> {
+        Ok(self
+            .get(key)
+            .ok_or(Error::MissingEventAttribute(key))?
+            .value_str()?
+            .to_string())
+    }
+
+    // Value of an attribute, empty if it is missing or not valid UTF-8
+    fn optional(&self, key: &'static str) -> String {
+        self.get(key)
+            .map(|attr| attr.value_str().unwrap_or_default().to_string())
+            .unwrap_or_default()
+    }
+
+    // Parsed value of an optional attribute
+    fn parse<T: std::str::FromStr>(&self, key: &'static str) -> crate::error::Result<Option<T>> {
+        self.get(key)
+            .map(|attr| {
+                attr.value_str()?
+                    .parse()
+                    .map_err(|_| Error::InvalidEventAttribute(key))
+            })
+            .transpose()
+    }
+
+    // Value of the id attribute, falling back to the given value
+    fn id_or(&self, fallback: &str) -> String {
+        self.get(attr::ID)
+            .map(|attr| attr.value_str().unwrap_or_default().to_string())
+            .unwrap_or_else(|| fallback.to_string())
+    }
+
+    // Comma separated values of all attributes with the given key
+    fn list(&self, key: &'static str) -> impl Iterator<Item = String> + 'a {
+        let index = key_index(key.as_bytes()).expect("indexed attribute key");
+        // Without repeated keys the indexed attribute is all there is
+        let (single, all): (_, &'a [cosmrs::tendermint::abci::EventAttribute]) =
+            if self.repeated[index] {
+                (None, &self.event.attributes)
+            } else {
+                (self.first[index], &[])
+            };
+        let attrs = single.into_iter().chain(
+            all.iter()
+                .filter(move |attr| attr.key_bytes() == key.as_bytes()),
+        );
+        attrs.flat_map(|attr| {
+            attr.value_str()
+                .map(|s| {
+                    s.split(',')
+                        .map(|x| x.trim().to_string())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PinCreateEvent {
     pub block_height: Height,
     pub cid: String,
@@ -508,7 +520,7 @@ pub struct PinCreateEvent {
     pub fallback_urls: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PinDeleteEvent {
     pub block_height: Height,
     pub cid: String,
@@ -516,7 +528,7 @@ pub struct PinDeleteEvent {
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct PinAckEvent {
     pub block_height: Height,
     pub cid: String,
@@ -525,14 +537,18 @@ pub struct PinAckEvent {
     pub success: bool,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
 pub enum PinEvent {
+    #[serde(rename = "create-pin")]
     Create(PinCreateEvent),
+    #[serde(rename = "delete-pin")]
     Delete(PinDeleteEvent),
+    #[serde(rename = "ack-pin")]
     Ack(PinAckEvent),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TaskCreateEvent {
     pub block_height: Height,
     pub task_id: String,
@@ -540,14 +556,14 @@ pub struct TaskCreateEvent {
     pub assigned_workers: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TaskDeleteEvent {
     pub block_height: Height,
     pub task_id: String,
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TaskAcceptEvent {
     pub block_height: Height,
     pub task_id: String,
@@ -555,7 +571,7 @@ pub struct TaskAcceptEvent {
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TaskDeclineEvent {
     pub block_height: Height,
     pub task_id: String,
@@ -563,7 +579,7 @@ pub struct TaskDeclineEvent {
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TaskFinishEvent {
     pub block_height: Height,
     pub task_id: String,
@@ -571,84 +587,122 @@ pub struct TaskFinishEvent {
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
 pub enum TaskEvent {
+    #[serde(rename = "create-task")]
     Create(TaskCreateEvent),
+    #[serde(rename = "delete-task")]
     Delete(TaskDeleteEvent),
+    #[serde(rename = "accept-task")]
     Accept(TaskAcceptEvent),
+    #[serde(rename = "decline-task")]
     Decline(TaskDeclineEvent),
+    #[serde(rename = "finish-task")]
     Finish(TaskFinishEvent),
 }
 
-#[derive(Clone, Debug)]
+impl TaskEvent {
+    /// Returns the ID of the task the event refers to.
+    pub fn task_id(&self) -> &str {
+        match self {
+            TaskEvent::Create(event) => &event.task_id,
+            TaskEvent::Delete(event) => &event.task_id,
+            TaskEvent::Accept(event) => &event.task_id,
+            TaskEvent::Decline(event) => &event.task_id,
+            TaskEvent::Finish(event) => &event.task_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkerCreateEvent {
     pub block_height: Height,
     pub worker_id: String,
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkerUpdateEvent {
     pub block_height: Height,
     pub worker_id: String,
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkerDeleteEvent {
     pub block_height: Height,
     pub worker_id: String,
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkerAnnounceExitEvent {
     pub block_height: Height,
     pub worker_id: String,
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
 pub enum WorkerEvent {
+    #[serde(rename = "create-worker")]
     Create(WorkerCreateEvent),
+    #[serde(rename = "update-worker")]
     Update(WorkerUpdateEvent),
+    #[serde(rename = "delete-worker")]
     Delete(WorkerDeleteEvent),
+    #[serde(rename = "announce-worker-exit")]
     AnnounceExit(WorkerAnnounceExitEvent),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkflowCreateEvent {
     pub block_height: Height,
     pub workflow_id: String,
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkflowDeleteEvent {
     pub block_height: Height,
     pub workflow_id: String,
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkflowProgressEvent {
     pub block_height: Height,
     pub workflow_id: String,
     pub creator: String,
+    /// Index of the stage the workflow is currently in, if reported by the chain.
+    #[serde(default)]
+    pub current_stage: Option<u64>,
+    /// Number of finished tasks in the current stage, if reported by the chain.
+    #[serde(default)]
+    pub finished_tasks: Option<u64>,
+    /// IDs of tasks in the current stage which failed.
+    #[serde(default)]
+    pub failed_task_ids: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WorkflowFinishEvent {
     pub block_height: Height,
     pub workflow_id: String,
     pub creator: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
 pub enum WorkflowEvent {
+    #[serde(rename = "create-workflow")]
     Create(WorkflowCreateEvent),
+    #[serde(rename = "delete-workflow")]
     Delete(WorkflowDeleteEvent),
+    #[serde(rename = "progress-workflow")]
     Progress(WorkflowProgressEvent),
+    #[serde(rename = "finish-workflow")]
     Finish(WorkflowFinishEvent),
 }
 
@@ -657,6 +711,7 @@ mod tests {
 
     use super::*;
     use cosmrs::{rpc::dialect::v0_34::EventAttribute, tendermint::abci::Event};
+    use std::str::FromStr;
 
     #[test]
     fn test_from_cosmos_create_pin() {
@@ -1141,6 +1196,52 @@ mod tests {
                 event.creator,
                 "cosmos1fl48vsnmsdzcv85q5d2q4z5ajdha8yu34mf0eh"
             );
+            assert_eq!(event.current_stage, None);
+            assert!(event.failed_task_ids.is_empty());
+        } else {
+            panic!("Unexpected event type");
+        }
+    }
+
+    #[test]
+    fn test_from_cosmos_progress_workflow_details() {
+        let event = Event::new(
+            "progress-workflow",
+            vec![
+                EventAttribute {
+                    index: true,
+                    key: b"workflow-id".to_vec(),
+                    value: b"workflow1".to_vec(),
+                },
+                EventAttribute {
+                    index: true,
+                    key: b"creator".to_vec(),
+                    value: b"cosmos1fl48vsnmsdzcv85q5d2q4z5ajdha8yu34mf0eh".to_vec(),
+                },
+                EventAttribute {
+                    index: true,
+                    key: b"current-stage".to_vec(),
+                    value: b"2".to_vec(),
+                },
+                EventAttribute {
+                    index: true,
+                    key: b"finished-tasks".to_vec(),
+                    value: b"5".to_vec(),
+                },
+                EventAttribute {
+                    index: true,
+                    key: b"failed-task-ids".to_vec(),
+                    value: b"task1, task2".to_vec(),
+                },
+            ],
+        );
+
+        let parsed = GevulotEvent::from_cosmos(&event, Height::from(1000u32));
+
+        if let Ok(GevulotEvent::Workflow(WorkflowEvent::Progress(event))) = parsed {
+            assert_eq!(event.current_stage, Some(2));
+            assert_eq!(event.finished_tasks, Some(5));
+            assert_eq!(event.failed_task_ids, vec!["task1", "task2"]);
         } else {
             panic!("Unexpected event type");
         }
@@ -1256,4 +1357,86 @@ mod tests {
             panic!("Unexpected event type");
         }
     }
+
+    #[test]
+    fn test_event_kind_round_trip() {
+        for kind in keys::EventKind::ALL {
+            assert_eq!(keys::EventKind::from_str(kind.as_str()).unwrap(), kind);
+        }
+        assert!(keys::EventKind::from_str("coin_spent").is_err());
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let event = GevulotEvent::Task(TaskEvent::Accept(TaskAcceptEvent {
+            block_height: Height::from(42u32),
+            task_id: "task1".to_string(),
+            worker_id: "worker1".to_string(),
+            creator: "creator1".to_string(),
+        }));
+        assert_eq!(event.kind_str(), "accept-task");
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "module": "task",
+                "kind": "accept-task",
+                "block_height": "42",
+                "task_id": "task1",
+                "worker_id": "worker1",
+                "creator": "creator1",
+            })
+        );
+        let parsed: GevulotEvent = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, event);
+    }
+
+    #[test]
+    fn test_from_cosmos_or_unknown() {
+        let event = Event::new(
+            "rotate-worker-key",
+            vec![EventAttribute {
+                index: true,
+                key: b"worker-id".to_vec(),
+                value: b"worker1".to_vec(),
+            }],
+        );
+        assert!(matches!(
+            GevulotEvent::from_cosmos(&event, Height::from(5u32)),
+            Err(Error::UnknownEventKind(_))
+        ));
+
+        let parsed = GevulotEvent::from_cosmos_or_unknown(&event, Height::from(5u32)).unwrap();
+        assert!(parsed.is_unknown());
+        assert_eq!(parsed.kind(), None);
+        assert_eq!(parsed.kind_str(), "rotate-worker-key");
+        assert_eq!(
+            parsed,
+            GevulotEvent::Unknown {
+                block_height: Height::from(5u32),
+                kind: "rotate-worker-key".to_string(),
+                attributes: vec![("worker-id".to_string(), "worker1".to_string())],
+            }
+        );
+    }
+
+    #[test]
+    fn test_from_cosmos_extension() {
+        crate::registry::register_event("create-bounty", |event, _| {
+            Ok(serde_json::json!({ "attributes": event.attributes.len() }))
+        });
+        let event = Event::new("create-bounty", Vec::<EventAttribute>::new());
+        let parsed = GevulotEvent::from_cosmos(&event, Height::from(5u32)).unwrap();
+        assert_eq!(parsed.kind(), None);
+        assert_eq!(parsed.kind_str(), "create-bounty");
+        assert_eq!(
+            parsed,
+            GevulotEvent::Extension {
+                block_height: Height::from(5u32),
+                kind: "create-bounty".to_string(),
+                value: serde_json::json!({ "attributes": 0 }),
+            }
+        );
+    }
 }
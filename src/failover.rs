@@ -0,0 +1,206 @@
+//! Failover between several gRPC endpoints of the same chain.
+//!
+//! A [`FailoverChannel`] sends all requests to its active endpoint. When a request fails with
+//! a transport error, e.g. because the node went down, the next endpoint becomes active for
+//! all following requests. Read-only requests are retried on it according to the
+//! [`QueryRetryPolicy`](crate::query_retry::QueryRetryPolicy) of the connection.
+//!
+//! [`BaseClient::check_endpoints`](crate::base_client::BaseClient::check_endpoints) checks the
+//! health of all endpoints and activates a healthy one.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use tonic::{body::BoxBody, transport::Channel};
+use tower::Service;
+
+/// Health of an endpoint of a [`FailoverChannel`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EndpointHealth {
+    pub endpoint: String,
+    /// Whether requests are currently sent to the endpoint.
+    pub active: bool,
+    /// Why the endpoint is unhealthy, None if it is healthy.
+    pub error: Option<String>,
+}
+
+impl EndpointHealth {
+    /// Returns true if the endpoint is reachable and in sync with the chain.
+    pub fn is_healthy(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    endpoints: Vec<(String, Channel)>,
+    active: AtomicUsize,
+}
+
+impl Shared {
+    // Activates the endpoint after the failed one, unless another request did already
+    fn fail_over(&self, failed: usize) {
+        if self.endpoints.len() < 2 {
+            return;
+        }
+        let next = (failed + 1) % self.endpoints.len();
+        if self
+            .active
+            .compare_exchange(failed, next, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            log::warn!(
+                "gRPC endpoint {} failed, failing over to {}",
+                self.endpoints[failed].0,
+                self.endpoints[next].0
+            );
+        }
+    }
+}
+
+/// A tonic [`Channel`] failing over between several endpoints.
+///
+/// Clones share the endpoints and which of them is active.
+#[doc(hidden)]
+#[derive(Clone, Debug)]
+pub struct FailoverChannel {
+    shared: Arc<Shared>,
+    // The channel of the active endpoint when the channel was last polled
+    index: usize,
+    channel: Channel,
+}
+
+impl From<Channel> for FailoverChannel {
+    fn from(channel: Channel) -> Self {
+        Self::new(vec![(String::new(), channel)])
+    }
+}
+
+impl FailoverChannel {
+    /// Creates a channel from the endpoints and their channels, the first one is active.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `endpoints` is empty.
+    pub fn new(endpoints: Vec<(String, Channel)>) -> Self {
+        let channel = endpoints.first().expect("at least one endpoint").1.clone();
+        Self {
+            shared: Arc::new(Shared {
+                endpoints,
+                active: AtomicUsize::new(0),
+            }),
+            index: 0,
+            channel,
+        }
+    }
+
+    /// Returns the endpoints with their channels.
+    pub fn endpoints(&self) -> &[(String, Channel)] {
+        &self.shared.endpoints
+    }
+
+    /// Returns the index of the active endpoint.
+    pub fn active(&self) -> usize {
+        self.shared.active.load(Ordering::Acquire)
+    }
+
+    /// Sends all following requests to the endpoint with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn set_active(&self, index: usize) {
+        assert!(
+            index < self.shared.endpoints.len(),
+            "endpoint out of bounds"
+        );
+        let previous = self.shared.active.swap(index, Ordering::AcqRel);
+        if previous != index {
+            log::info!(
+                "Switching gRPC endpoint from {} to {}",
+                self.shared.endpoints[previous].0,
+                self.shared.endpoints[index].0
+            );
+        }
+    }
+}
+
+impl Service<http::Request<BoxBody>> for FailoverChannel {
+    type Response = http::Response<BoxBody>;
+    type Error = tonic::transport::Error;
+    type Future =
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + 'static>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let active = self.active();
+        if active != self.index {
+            self.index = active;
+            self.channel = self.shared.endpoints[active].1.clone();
+        }
+        let result = self.channel.poll_ready(cx);
+        if let Poll::Ready(Err(_)) = &result {
+            self.shared.fail_over(self.index);
+        }
+        result
+    }
+
+    fn call(&mut self, request: http::Request<BoxBody>) -> Self::Future {
+        // Take the channel which was polled ready, leaving a fresh clone behind
+        let fresh = self.channel.clone();
+        let mut channel = std::mem::replace(&mut self.channel, fresh);
+        let shared = self.shared.clone();
+        let index = self.index;
+        Box::pin(async move {
+            let result = channel.call(request).await;
+            if result.is_err() {
+                shared.fail_over(index);
+            }
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fail_over() {
+        let lazy = |url: &'static str| {
+            (
+                url.to_string(),
+                tonic::transport::Endpoint::from_static(url).connect_lazy(),
+            )
+        };
+        let channel = FailoverChannel::new(vec![
+            lazy("http://node1:9090"),
+            lazy("http://node2:9090"),
+            lazy("http://node3:9090"),
+        ]);
+        assert_eq!(channel.active(), 0);
+
+        channel.shared.fail_over(0);
+        assert_eq!(channel.active(), 1);
+        // Requests which failed on an endpoint no longer active don't fail over again
+        channel.shared.fail_over(0);
+        assert_eq!(channel.active(), 1);
+        channel.shared.fail_over(1);
+        channel.shared.fail_over(2);
+        assert_eq!(channel.active(), 0);
+
+        channel.set_active(2);
+        assert_eq!(channel.clone().active(), 2);
+
+        // A single endpoint stays active
+        let single = FailoverChannel::new(vec![lazy("http://node1:9090")]);
+        single.shared.fail_over(0);
+        assert_eq!(single.active(), 0);
+    }
+}
@@ -0,0 +1,134 @@
+//! Pre-flight checks of the fallback URLs of pins.
+//!
+//! Workers download pinned data from the fallback URLs of a pin. A typo in a URL or a file of
+//! the wrong size only shows up once the workers fail to acknowledge the pin. Checking the URLs
+//! before creating the pin catches these mistakes before the transaction is broadcast.
+
+/// Problem with a fallback URL.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum FallbackUrlError {
+    /// The URL couldn't be requested at all.
+    #[error("{url} is unreachable: {reason}")]
+    Unreachable { url: String, reason: String },
+    /// The server answered with a status other than success.
+    #[error("{url} returned status {status}")]
+    Status { url: String, status: u16 },
+    /// The server reported a size other than the size declared by the pin.
+    #[error("{url} serves {actual} bytes but the pin declares {expected} bytes")]
+    SizeMismatch {
+        url: String,
+        expected: u64,
+        actual: u64,
+    },
+}
+
+/// Checks the response to a HEAD request of a fallback URL.
+///
+/// Responses without a Content-Length can't be checked against the declared size and are
+/// accepted.
+///
+/// # Arguments
+///
+/// * `url` - The requested URL.
+/// * `status` - The HTTP status of the response.
+/// * `content_length` - The Content-Length of the response, if any.
+/// * `expected` - The size of the data declared by the pin in bytes.
+pub fn check_response(
+    url: &str,
+    status: u16,
+    content_length: Option<u64>,
+    expected: u64,
+) -> Option<FallbackUrlError> {
+    if !(200..300).contains(&status) {
+        return Some(FallbackUrlError::Status {
+            url: url.to_string(),
+            status,
+        });
+    }
+    match content_length {
+        Some(actual) if actual != expected => Some(FallbackUrlError::SizeMismatch {
+            url: url.to_string(),
+            expected,
+            actual,
+        }),
+        _ => None,
+    }
+}
+
+/// Issues a HEAD request to every fallback URL and checks the responses.
+///
+/// # Arguments
+///
+/// * `urls` - The fallback URLs.
+/// * `expected` - The size of the data declared by the pin in bytes.
+/// * `timeout` - How long to wait for each response at most.
+///
+/// # Returns
+///
+/// The problems found, empty if all URLs are fine.
+#[cfg(feature = "fallback-check")]
+pub async fn check_fallback_urls(
+    urls: &[String],
+    expected: u64,
+    timeout: std::time::Duration,
+) -> Vec<FallbackUrlError> {
+    let client = match reqwest::Client::builder().timeout(timeout).build() {
+        Ok(client) => client,
+        Err(e) => {
+            return urls
+                .iter()
+                .map(|url| FallbackUrlError::Unreachable {
+                    url: url.clone(),
+                    reason: e.to_string(),
+                })
+                .collect()
+        }
+    };
+    let checks = urls.iter().map(|url| {
+        let client = &client;
+        async move {
+            match client.head(url).send().await {
+                Ok(response) => check_response(
+                    url,
+                    response.status().as_u16(),
+                    response.content_length(),
+                    expected,
+                ),
+                Err(e) => Some(FallbackUrlError::Unreachable {
+                    url: url.clone(),
+                    reason: e.to_string(),
+                }),
+            }
+        }
+    });
+    futures::future::join_all(checks)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_response() {
+        let url = "https://example.com/data.bin";
+        assert_eq!(check_response(url, 200, Some(1024), 1024), None);
+        assert_eq!(check_response(url, 204, None, 1024), None);
+        assert_eq!(
+            check_response(url, 200, Some(512), 1024),
+            Some(FallbackUrlError::SizeMismatch {
+                url: url.to_string(),
+                expected: 1024,
+                actual: 512,
+            })
+        );
+        let err = check_response(url, 404, Some(1024), 1024).unwrap();
+        assert_eq!(
+            err.to_string(),
+            "https://example.com/data.bin returned status 404"
+        );
+    }
+}
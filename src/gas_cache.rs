@@ -0,0 +1,148 @@
+//! Estimation of gas limits from the gas used by recent transactions.
+//!
+//! By default the gas limit of a transaction is estimated by simulating it, which takes a
+//! round trip to the node per transaction. The [`BaseClient`](crate::base_client::BaseClient)
+//! records the gas used by each simulation per message type in its [`GasCache`], so that
+//! submitters sending many similar transactions can skip the simulation with
+//! [`GasOverride::Cached`], or set the gas limit themselves with [`GasOverride::Limit`]:
+//!
+//! ```no_run
+//! # use gevulot_rs::{gas_cache::GasOverride, proto::gevulot::gevulot::MsgAckPin};
+//! # async fn run(client: &mut gevulot_rs::base_client::BaseClient, ack: MsgAckPin) -> gevulot_rs::Result<()> {
+//! client.send_msg_with_gas(ack, "", GasOverride::Cached).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+
+/// Factor applied to cached gas estimates unless set otherwise.
+pub const DEFAULT_SAFETY_MULTIPLIER: f64 = 1.3;
+
+// Number of recent simulations kept per message type
+const SAMPLES: usize = 16;
+
+/// How the gas limit of a transaction is determined.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GasOverride {
+    /// Simulate the transaction and apply the gas multiplier of the client.
+    #[default]
+    Simulate,
+    /// Estimate the gas from recent simulations of the same message types, simulating only if
+    /// one of them wasn't simulated yet.
+    Cached,
+    /// Use the given gas limit as is.
+    Limit(u64),
+}
+
+/// Gas used by recent simulations per message type.
+#[derive(Clone, Debug)]
+pub struct GasCache {
+    safety_multiplier: f64,
+    // Gas used per message, for the latest simulations of each type URL
+    samples: HashMap<String, VecDeque<u64>>,
+}
+
+impl Default for GasCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAFETY_MULTIPLIER)
+    }
+}
+
+impl GasCache {
+    /// Creates an empty cache applying the safety multiplier to its estimates.
+    pub fn new(safety_multiplier: f64) -> Self {
+        Self {
+            safety_multiplier,
+            samples: HashMap::new(),
+        }
+    }
+
+    /// Sets the factor applied to the estimates.
+    pub fn set_safety_multiplier(&mut self, multiplier: f64) {
+        self.safety_multiplier = multiplier;
+    }
+
+    /// Records the gas used by the simulation of a transaction with the given messages.
+    ///
+    /// The gas is only attributable to a message type if all messages are of the same type,
+    /// simulations of mixed transactions are ignored.
+    pub fn record(&mut self, msgs: &[cosmrs::Any], gas_used: u64) {
+        let Some(first) = msgs.first() else {
+            return;
+        };
+        if msgs.iter().any(|msg| msg.type_url != first.type_url) {
+            return;
+        }
+        let samples = self.samples.entry(first.type_url.clone()).or_default();
+        if samples.len() == SAMPLES {
+            samples.pop_front();
+        }
+        samples.push_back(gas_used.div_ceil(msgs.len() as u64));
+    }
+
+    /// Estimates the gas limit of a transaction with the given messages.
+    ///
+    /// Each message is assumed to use as much gas as the most expensive recent simulation of
+    /// its type, the sum is multiplied by the safety multiplier.
+    ///
+    /// # Returns
+    ///
+    /// The gas limit, None if a message type wasn't simulated yet.
+    pub fn estimate(&self, msgs: &[cosmrs::Any]) -> Option<u64> {
+        if msgs.is_empty() {
+            return None;
+        }
+        let mut gas = 0u64;
+        for msg in msgs {
+            let max = self.samples.get(&msg.type_url)?.iter().max()?;
+            gas = gas.saturating_add(*max);
+        }
+        Some((gas as f64 * self.safety_multiplier).ceil() as u64)
+    }
+
+    /// Forgets all recorded simulations, e.g. after a chain upgrade changed gas costs.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(type_url: &str) -> cosmrs::Any {
+        cosmrs::Any {
+            type_url: type_url.to_string(),
+            value: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_gas_cache() {
+        let ack = msg("/gevulot.gevulot.MsgAckPin");
+        let create = msg("/gevulot.gevulot.MsgCreateTask");
+        let mut cache = GasCache::new(1.5);
+        assert_eq!(cache.estimate(&[ack.clone()]), None);
+        assert_eq!(cache.estimate(&[]), None);
+
+        cache.record(&[ack.clone(), ack.clone()], 100_000);
+        cache.record(&[ack.clone()], 40_000);
+        // Mixed transactions can't be attributed
+        cache.record(&[ack.clone(), create.clone()], 500_000);
+        assert_eq!(cache.estimate(&[ack.clone()]), Some(75_000));
+        assert_eq!(cache.estimate(&[ack.clone(), create.clone()]), None);
+
+        cache.record(&[create.clone()], 200_000);
+        assert_eq!(cache.estimate(&[ack.clone(), create]), Some(375_000));
+
+        // Old simulations are dropped
+        for _ in 0..SAMPLES {
+            cache.record(&[ack.clone()], 20_000);
+        }
+        assert_eq!(cache.estimate(&[ack.clone()]), Some(30_000));
+
+        cache.clear();
+        assert_eq!(cache.estimate(&[ack]), None);
+    }
+}
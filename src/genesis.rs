@@ -0,0 +1,181 @@
+//! Reading the state of the Gevulot module from genesis files.
+//!
+//! Both the genesis file a chain starts from and the one `gevulotd export` writes from a
+//! running chain hold the state of every module under `app_state`. [`parse`] extracts the
+//! workers, tasks, pins and workflows of the Gevulot module into the [`models`](crate::models),
+//! e.g. to migrate them to another network or analyse a snapshot of a network offline:
+//!
+//! ```no_run
+//! let genesis = gevulot_rs::genesis::parse("exported.json")?;
+//! println!("{} workers, {} tasks", genesis.workers.len(), genesis.tasks.len());
+//! # Ok::<(), gevulot_rs::Error>(())
+//! ```
+//!
+//! The module state is decoded as proto3 JSON, which requires the `genesis` feature.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::{
+    error::{Error, Result},
+    models,
+    proto::gevulot::gevulot,
+};
+
+/// Name of the Gevulot module in the `app_state` of genesis files.
+pub const MODULE_NAME: &str = "gevulot";
+
+/// State of the Gevulot module from a genesis file.
+#[derive(Debug)]
+pub struct Genesis {
+    /// Chain ID, None if only the module state was given.
+    pub chain_id: Option<String>,
+    /// Time of the genesis block in RFC 3339, None if only the module state was given.
+    pub genesis_time: Option<String>,
+    /// Parameters of the module.
+    pub params: Option<gevulot::Params>,
+    pub workers: Vec<models::Worker>,
+    pub tasks: Vec<models::Task>,
+    pub pins: Vec<models::Pin>,
+    pub workflows: Vec<models::Workflow>,
+    /// Proofs, which have no model.
+    pub proofs: Vec<gevulot::Proof>,
+}
+
+/// Reads the state of the Gevulot module from a genesis file.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or doesn't contain valid module state.
+pub fn parse(path: impl AsRef<Path>) -> Result<Genesis> {
+    let path = path.as_ref();
+    let json = std::fs::read_to_string(path).map_err(|e| {
+        Error::Unknown(format!("can't read genesis file {}: {}", path.display(), e))
+    })?;
+    from_json(&json)
+}
+
+/// Reads the state of the Gevulot module from the JSON of a genesis file.
+///
+/// Besides full genesis files, the state of the module alone, i.e. the value of
+/// `app_state.gevulot`, is accepted as well.
+///
+/// # Errors
+///
+/// Returns an error if the JSON doesn't contain valid module state.
+pub fn from_json(json: &str) -> Result<Genesis> {
+    let mut value: Value = serde_json::from_str(json).map_err(|e| Error::Parse(e.to_string()))?;
+    let string =
+        |value: &Value, key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+    let chain_id = string(&value, "chain_id");
+    let genesis_time = string(&value, "genesis_time");
+
+    let state = match value.get_mut("app_state") {
+        Some(app_state) => app_state
+            .get_mut(MODULE_NAME)
+            .map(Value::take)
+            .ok_or_else(|| Error::Parse(format!("no {} module in app_state", MODULE_NAME)))?,
+        None => value,
+    };
+    let state: gevulot::GenesisState = serde_json::from_value(state)
+        .map_err(|e| Error::Parse(format!("invalid {} genesis state: {}", MODULE_NAME, e)))?;
+
+    Ok(Genesis {
+        chain_id,
+        genesis_time,
+        params: state.params,
+        workers: convert("worker", state.worker_list, |worker| worker.spec.is_some())?,
+        tasks: convert("task", state.task_list, |task| task.spec.is_some())?,
+        pins: convert("pin", state.pin_list, |pin| pin.spec.is_some())?,
+        workflows: convert("workflow", state.workflow_list, |workflow| {
+            workflow.spec.is_some()
+        })?,
+        proofs: state.proof_list,
+    })
+}
+
+// Converts protobuf entries into models, which requires them to have a spec
+fn convert<P, M>(kind: &str, entries: Vec<P>, has_spec: impl Fn(&P) -> bool) -> Result<Vec<M>>
+where
+    M: From<P>,
+{
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            if has_spec(&entry) {
+                Ok(M::from(entry))
+            } else {
+                Err(Error::Parse(format!("{} {} has no spec", kind, index)))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENESIS: &str = r#"{
+        "genesis_time": "2024-11-04T10:00:00Z",
+        "chain_id": "gevulot-testnet",
+        "initial_height": "1",
+        "app_state": {
+            "bank": {"balances": []},
+            "gevulot": {
+                "params": {},
+                "workerList": [{
+                    "metadata": {"id": "w1", "creator": "gvlt1creator", "name": "worker"},
+                    "spec": {"cpus": "4000", "gpus": "0", "memory": "8589934592", "disk": "0"},
+                    "status": {"cpusUsed": "1000"}
+                }],
+                "taskList": [{
+                    "metadata": {"id": "t1", "creator": "gvlt1creator"},
+                    "spec": {"image": "prover:latest", "cpus": "1000", "memory": "1073741824"},
+                    "status": {"state": "RUNNING", "assignedWorkers": ["w1"]}
+                }],
+                "workflowList": [],
+                "proofList": [],
+                "pinList": [{
+                    "metadata": {"id": "p1"},
+                    "spec": {"bytes": "1024", "time": "3600", "redundancy": "1"},
+                    "status": {"assignedWorkers": ["w1"], "cid": "bafybeig"}
+                }]
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_from_json() {
+        let genesis = from_json(GENESIS).unwrap();
+        assert_eq!(genesis.chain_id.as_deref(), Some("gevulot-testnet"));
+        assert_eq!(
+            genesis.genesis_time.as_deref(),
+            Some("2024-11-04T10:00:00Z")
+        );
+        assert!(genesis.params.is_some());
+        assert_eq!(genesis.workers.len(), 1);
+        assert_eq!(genesis.workers[0].metadata.id.as_deref(), Some("w1"));
+        assert_eq!(genesis.tasks.len(), 1);
+        assert_eq!(genesis.tasks[0].spec.image, "prover:latest");
+        assert_eq!(genesis.pins.len(), 1);
+        assert_eq!(genesis.pins[0].metadata.id.as_deref(), Some("p1"));
+        assert!(genesis.workflows.is_empty());
+
+        // The module state alone
+        let value: Value = serde_json::from_str(GENESIS).unwrap();
+        let state = value["app_state"]["gevulot"].to_string();
+        let genesis = from_json(&state).unwrap();
+        assert_eq!(genesis.chain_id, None);
+        assert_eq!(genesis.tasks.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(from_json("not json").is_err());
+        assert!(from_json(r#"{"app_state": {"bank": {}}}"#).is_err());
+        assert!(from_json(r#"{"workerList": [{"metadata": {"id": "w1"}}]}"#).is_err());
+        assert!(parse("/nonexistent/genesis.json").is_err());
+    }
+}
@@ -1,22 +1,38 @@
 use crate::base_client::BaseClient;
-use crate::error::Result;
+use crate::compression::{Compression, CompressionConfig};
+use crate::error::{Error, Result};
 use crate::gov_client::GovClient;
+use crate::health::{Health, DEFAULT_MAX_BLOCK_AGE};
+use crate::origin::Origin;
 use crate::pin_client::PinClient;
+use crate::proof_client::ProofClient;
+use crate::proto::gevulot::gevulot::QueryParamsRequest;
+use crate::query::Query;
+use crate::query_retry::QueryRetryPolicy;
+use crate::quotas::Quotas;
+use crate::signer::{GevulotSigner, HdPath};
+use crate::spec_defaults::SpecDefaults;
 use crate::sudo_client::SudoClient;
 use crate::task_client::TaskClient;
 use crate::worker_client::WorkerClient;
 use crate::workflow_client::WorkflowClient;
+#[cfg(feature = "full")]
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use zeroize::Zeroizing;
 
 /// GevulotClient exposes all gevulot specific functionality
 /// * pins
+/// * proofs
 /// * tasks
 /// * workers
 /// * workflows
 #[derive(Debug, Clone)]
 pub struct GevulotClient {
     pub pins: PinClient,
+    pub proofs: ProofClient,
     pub tasks: TaskClient,
     pub workflows: WorkflowClient,
     pub workers: WorkerClient,
@@ -26,28 +42,189 @@ pub struct GevulotClient {
     pub base_client: Arc<RwLock<BaseClient>>,
 }
 
+impl GevulotClient {
+    /// Starts a query combining several entities, see [`crate::query`].
+    pub fn query(&self) -> Query {
+        Query::new(self.tasks.clone(), self.workers.clone())
+    }
+
+    /// Checks that the node answers, belongs to the chain the client signs for and follows
+    /// the chain, see [`crate::health`].
+    ///
+    /// The latest block may be up to [`DEFAULT_MAX_BLOCK_AGE`] old, use
+    /// [`GevulotClient::healthcheck_with`] to allow another age.
+    ///
+    /// # Returns
+    ///
+    /// The health of the node, with the problems found. Failing requests are reported as
+    /// problems rather than errors.
+    pub async fn healthcheck(&self) -> Health {
+        self.healthcheck_with(DEFAULT_MAX_BLOCK_AGE).await
+    }
+
+    /// Checks the health of the node like [`GevulotClient::healthcheck`], with a custom
+    /// maximum age of the latest block.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_block_age` - Age of the latest block above which the node is considered stale.
+    ///
+    /// # Returns
+    ///
+    /// The health of the node, with the problems found. Failing requests are reported as
+    /// problems rather than errors.
+    pub async fn healthcheck_with(&self, max_block_age: std::time::Duration) -> Health {
+        // The lock is taken per request so other users of the client aren't blocked meanwhile
+        let node_info = self.base_client.write().await.node_info().await;
+        let latest_block = self.base_client.write().await.current_block().await;
+        let chain_status = match latest_block {
+            Ok(block) => self
+                .base_client
+                .write()
+                .await
+                .syncing()
+                .await
+                .and_then(|catching_up| BaseClient::chain_status_of(block, catching_up)),
+            Err(e) => Err(e),
+        };
+        Health::new(
+            self.base_client.read().await.chain_id(),
+            node_info,
+            chain_status,
+            std::time::SystemTime::now(),
+            max_block_age,
+        )
+    }
+
+    /// Returns the limits and current usage of the signing account.
+    ///
+    /// Lists all tasks, workflows and pins, so this is expensive on busy chains.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the quotas or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no signer is set or if a request to the Gevulot
+    /// client fails.
+    pub async fn quotas(&mut self) -> Result<Quotas> {
+        let (account, balance, params) = {
+            let mut base_client = self.base_client.write().await;
+            let account = base_client
+                .address
+                .clone()
+                .ok_or_else(|| Error::Unknown("no signer set".to_string()))?;
+            let balance = base_client.get_account_balance(&account).await?.amount;
+            let params = base_client
+                .gevulot_client
+                .params(QueryParamsRequest {})
+                .await?
+                .into_inner()
+                .params
+                .unwrap_or_default();
+            (account, balance, params)
+        };
+        let tasks = self.tasks.list_by_creator(&account).await?;
+        let workflows = self.workflows.list().await?;
+        let pins = self.pins.list().await?;
+        Ok(Quotas::new(
+            &account, balance, &params, &tasks, &workflows, &pins,
+        ))
+    }
+}
+
 /// Builder for GevulotClient
 pub struct GevulotClientBuilder {
-    endpoint: String,
+    endpoints: Vec<String>,
     gas_price: f64,
     gas_multiplier: f64,
-    mnemonic: Option<String>,
-    password: Option<String>,
+    mnemonic: Option<Zeroizing<String>>,
+    password: Option<Zeroizing<String>>,
+    hd_path: HdPath,
+    chain_id: Option<String>,
+    denom: Option<String>,
+    address_prefix: Option<String>,
+    private_key: Option<Zeroizing<String>>,
+    #[cfg(feature = "full")]
+    keyfile: Option<(PathBuf, Option<Zeroizing<String>>)>,
+    #[cfg(feature = "keychain")]
+    keychain_account: Option<(crate::keychain::Keychain, String)>,
+    signer: Option<GevulotSigner>,
+    compression: CompressionConfig,
+    max_concurrent_requests: Option<usize>,
+    query_retry: Option<QueryRetryPolicy>,
+    sequence_retries: Option<u32>,
+    clock_skew_tolerance: Option<Duration>,
+    spec_defaults: Option<SpecDefaults>,
+    origin: Option<Origin>,
 }
 
 impl Default for GevulotClientBuilder {
     /// Provides default values for GevulotClientBuilder
     fn default() -> Self {
         Self {
-            endpoint: "http://127.0.0.1:9090".to_string(),
+            endpoints: vec!["http://127.0.0.1:9090".to_string()],
             gas_price: 0.025,
             gas_multiplier: 1.2,
             mnemonic: None,
             password: None,
+            hd_path: HdPath::default(),
+            chain_id: None,
+            denom: None,
+            address_prefix: None,
+            private_key: None,
+            #[cfg(feature = "full")]
+            keyfile: None,
+            #[cfg(feature = "keychain")]
+            keychain_account: None,
+            signer: None,
+            compression: CompressionConfig::default(),
+            max_concurrent_requests: None,
+            query_retry: Some(QueryRetryPolicy::default()),
+            sequence_retries: None,
+            clock_skew_tolerance: None,
+            spec_defaults: None,
+            origin: None,
         }
     }
 }
 
+impl std::fmt::Debug for GevulotClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redacted = |secret: &Option<Zeroizing<String>>| secret.as_ref().map(|_| "<redacted>");
+        let mut debug = f.debug_struct("GevulotClientBuilder");
+        debug
+            .field("endpoints", &self.endpoints)
+            .field("gas_price", &self.gas_price)
+            .field("gas_multiplier", &self.gas_multiplier)
+            .field("mnemonic", &redacted(&self.mnemonic))
+            .field("password", &redacted(&self.password))
+            .field("hd_path", &self.hd_path)
+            .field("chain_id", &self.chain_id)
+            .field("denom", &self.denom)
+            .field("address_prefix", &self.address_prefix)
+            .field("private_key", &redacted(&self.private_key));
+        #[cfg(feature = "full")]
+        debug.field(
+            "keyfile",
+            &self
+                .keyfile
+                .as_ref()
+                .map(|(path, passphrase)| (path, redacted(passphrase))),
+        );
+        debug
+            .field(
+                "signer",
+                &self
+                    .signer
+                    .as_ref()
+                    .map(|signer| signer.0.public_address.to_string()),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
 impl GevulotClientBuilder {
     /// Creates a new GevulotClientBuilder with default values
     pub fn new() -> Self {
@@ -55,8 +232,24 @@ impl GevulotClientBuilder {
     }
 
     /// Sets the endpoint for the GevulotClient
+    ///
+    /// Besides `http://` and `https://` URLs this accepts `unix:///path/to/socket` to connect
+    /// to a co-located node over a Unix domain socket.
     pub fn endpoint(mut self, endpoint: &str) -> Self {
-        self.endpoint = endpoint.to_string();
+        self.endpoints = vec![endpoint.to_string()];
+        self
+    }
+
+    /// Sets several endpoints of the same chain for the GevulotClient to fail over between
+    ///
+    /// The first healthy endpoint is used until a request to it fails with a transport error,
+    /// then the next one takes over, see [`crate::failover`].
+    pub fn endpoints<I, S>(mut self, endpoints: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.endpoints = endpoints.into_iter().map(Into::into).collect();
         self
     }
 
@@ -74,37 +267,259 @@ impl GevulotClientBuilder {
 
     /// Sets the mnemonic for the GevulotClient
     pub fn mnemonic(mut self, mnemonic: &str) -> Self {
-        self.mnemonic = Some(mnemonic.to_string());
+        self.mnemonic = Some(Zeroizing::new(mnemonic.to_string()));
         self
     }
 
     /// Sets the password for the GevulotClient
     pub fn password(mut self, password: &str) -> Self {
-        self.password = Some(password.to_string());
+        self.password = Some(Zeroizing::new(password.to_string()));
+        self
+    }
+
+    /// Sets the derivation path of the key derived from the mnemonic
+    ///
+    /// Defaults to `m/44'/118'/0'/0/0`.
+    pub fn hd_path(mut self, hd_path: HdPath) -> Self {
+        self.hd_path = hd_path;
+        self
+    }
+
+    /// Sets the chain ID transactions are signed for
+    ///
+    /// Defaults to [`DEFAULT_CHAIN_ID`](crate::base_client::DEFAULT_CHAIN_ID).
+    pub fn chain_id(mut self, chain_id: &str) -> Self {
+        self.chain_id = Some(chain_id.to_string());
+        self
+    }
+
+    /// Sets the denomination of fees and balances
+    ///
+    /// Defaults to [`DEFAULT_DENOM`](crate::base_client::DEFAULT_DENOM).
+    pub fn denom(mut self, denom: &str) -> Self {
+        self.denom = Some(denom.to_string());
+        self
+    }
+
+    /// Sets the bech32 prefix of account addresses, the signer's address is derived with it
+    ///
+    /// Defaults to [`DEFAULT_ADDRESS_PREFIX`](crate::signer::DEFAULT_ADDRESS_PREFIX).
+    pub fn address_prefix(mut self, prefix: &str) -> Self {
+        self.address_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Sets the signer's private key as hex
+    ///
+    /// Takes precedence over a mnemonic.
+    pub fn private_key(mut self, private_key: &str) -> Self {
+        self.private_key = Some(Zeroizing::new(private_key.to_string()));
+        self
+    }
+
+    /// Loads the signer's private key from a file exported by another wallet
+    ///
+    /// The file may hold any format supported by [`GevulotSigner::import`], encrypted PKCS #8
+    /// keys are decrypted with the passphrase. The key is loaded when the GevulotClient is built
+    /// and takes precedence over a mnemonic and a private key.
+    #[cfg(feature = "full")]
+    pub fn keyfile(mut self, path: impl Into<PathBuf>, passphrase: Option<&str>) -> Self {
+        self.keyfile = Some((
+            path.into(),
+            passphrase.map(|passphrase| Zeroizing::new(passphrase.to_string())),
+        ));
+        self
+    }
+
+    /// Loads the signer's private key from the OS keychain
+    ///
+    /// The key is loaded when the GevulotClient is built and takes precedence over a mnemonic,
+    /// a private key and a keyfile.
+    #[cfg(feature = "keychain")]
+    pub fn keychain_account(mut self, keychain: crate::keychain::Keychain, account: &str) -> Self {
+        self.keychain_account = Some((keychain, account.to_string()));
+        self
+    }
+
+    /// Sets the signer, e.g. one created with [`GevulotSigner::import`]
+    ///
+    /// Takes precedence over all other key sources.
+    pub fn signer(mut self, signer: GevulotSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    /// Sets the compression used for requests sent by the GevulotClient
+    pub fn send_compression(mut self, compression: Compression) -> Self {
+        self.compression.send = Some(compression);
+        self
+    }
+
+    /// Adds a compression the server may use for responses to the GevulotClient
+    pub fn accept_compression(mut self, compression: Compression) -> Self {
+        if !self.compression.accept.contains(&compression) {
+            self.compression.accept.push(compression);
+        }
+        self
+    }
+
+    /// Limits the number of gRPC requests the GevulotClient has in flight at the same time
+    ///
+    /// The limit is shared by all clients of the GevulotClient and its clones, requests over
+    /// the limit wait for a free slot. Unlimited by default.
+    pub fn max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.max_concurrent_requests = Some(limit);
+        self
+    }
+
+    /// Sets the retry policy of read-only calls failing with transient errors
+    ///
+    /// Queries are retried with the default [`QueryRetryPolicy`] unless set otherwise, None
+    /// disables retries. Broadcasts are only retried if the policy opts in to it.
+    pub fn query_retry(mut self, policy: Option<QueryRetryPolicy>) -> Self {
+        self.query_retry = policy;
+        self
+    }
+
+    /// Sets how often transactions are signed again after an account sequence mismatch
+    ///
+    /// Transactions racing other transactions of the same account are rejected by the chain.
+    /// They are retried with the sequence the chain expects, up to
+    /// [`DEFAULT_SEQUENCE_RETRIES`](crate::base_client::DEFAULT_SEQUENCE_RETRIES) times unless
+    /// set otherwise, 0 disables the retries.
+    pub fn sequence_retries(mut self, retries: u32) -> Self {
+        self.sequence_retries = Some(retries);
+        self
+    }
+
+    /// Sets the skew of the local clock from the chain time which is ignored
+    ///
+    /// Larger skews are corrected with an estimate of the chain time from recent block headers
+    /// when comparing timestamps on the chain with the current time, see
+    /// [`crate::chain_clock`]. Defaults to
+    /// [`DEFAULT_TOLERANCE`](crate::chain_clock::DEFAULT_TOLERANCE).
+    pub fn clock_skew_tolerance(mut self, tolerance: Duration) -> Self {
+        self.clock_skew_tolerance = Some(tolerance);
+        self
+    }
+
+    /// Sets defaults merged into every task and workflow created by the GevulotClient
+    ///
+    /// See [`SpecDefaults`] for how defaults and explicitly set values are combined.
+    pub fn spec_defaults(mut self, spec_defaults: SpecDefaults) -> Self {
+        self.spec_defaults = Some(spec_defaults);
+        self
+    }
+
+    /// Labels every task, pin, worker and proof created by the GevulotClient with its origin
+    ///
+    /// Adds the SDK version, a hash of the host name and the given application name, see
+    /// [`Origin`]. Off by default.
+    pub fn origin(mut self, app_name: &str) -> Self {
+        self.origin = Some(Origin::new(app_name));
         self
     }
 
     /// Builds the GevulotClient with the provided configuration
     pub async fn build(self) -> Result<GevulotClient> {
-        // Create a new BaseClient with the provided endpoint, gas price, and gas multiplier
+        if self.max_concurrent_requests == Some(0) {
+            return Err(Error::Unknown(
+                "max_concurrent_requests must be at least 1".to_string(),
+            ));
+        }
+        // Create a new BaseClient with the provided endpoints, gas price, and gas multiplier
+        let endpoints: Vec<&str> = self.endpoints.iter().map(String::as_str).collect();
         let base_client = Arc::new(RwLock::new(
-            BaseClient::new(&self.endpoint, self.gas_price, self.gas_multiplier).await?,
+            BaseClient::with_endpoints(&endpoints, self.gas_price, self.gas_multiplier).await?,
         ));
 
-        // If a mnemonic is provided, set it in the BaseClient
-        if let Some(mnemonic) = self.mnemonic {
+        base_client.write().await.set_compression(&self.compression);
+        base_client
+            .write()
+            .await
+            .set_concurrency_limit(self.max_concurrent_requests);
+        base_client.write().await.set_query_retry(self.query_retry);
+        if let Some(retries) = self.sequence_retries {
+            base_client.write().await.set_sequence_retries(retries);
+        }
+        if let Some(tolerance) = self.clock_skew_tolerance {
             base_client
                 .write()
                 .await
-                .set_mnemonic(&mnemonic, self.password.as_deref())?;
+                .set_clock_skew_tolerance(tolerance);
         }
 
+        // Network settings go first, the signer's address depends on the prefix
+        {
+            let mut base_client = base_client.write().await;
+            if let Some(chain_id) = &self.chain_id {
+                base_client.set_chain_id(chain_id)?;
+            }
+            if let Some(denom) = &self.denom {
+                base_client.set_denom(denom)?;
+            }
+            if let Some(prefix) = &self.address_prefix {
+                base_client.set_address_prefix(prefix)?;
+            }
+        }
+
+        // If a mnemonic is provided, set it in the BaseClient
+        if let Some(mnemonic) = self.mnemonic {
+            base_client.write().await.set_mnemonic_with_path(
+                &mnemonic,
+                self.password.as_ref().map(|password| password.as_str()),
+                &self.hd_path,
+            )?;
+        }
+
+        if let Some(private_key) = self.private_key {
+            let signer = GevulotSigner::from_private_key(&private_key)?;
+            base_client.write().await.set_signer(signer);
+        }
+
+        #[cfg(feature = "full")]
+        if let Some((path, passphrase)) = self.keyfile {
+            let data = Zeroizing::new(std::fs::read(&path).map_err(|e| {
+                Error::Unknown(format!("can't read keyfile {}: {}", path.display(), e))
+            })?);
+            let signer = GevulotSigner::import_encrypted(
+                &data,
+                passphrase.as_ref().map(|passphrase| passphrase.as_str()),
+            )?;
+            base_client.write().await.set_signer(signer);
+        }
+
+        #[cfg(feature = "keychain")]
+        if let Some((keychain, account)) = self.keychain_account {
+            let signer = keychain.load_signer(&account)?;
+            base_client.write().await.set_signer(signer);
+        }
+
+        if let Some(signer) = self.signer {
+            base_client.write().await.set_signer(signer);
+        }
+
+        let spec_defaults = self.spec_defaults.map(Arc::new);
+        let origin = self.origin.map(Arc::new);
+        let mut tasks = TaskClient::new(base_client.clone());
+        tasks.set_spec_defaults(spec_defaults.clone());
+        tasks.set_origin(origin.clone());
+        let mut workflows = WorkflowClient::new(base_client.clone());
+        workflows.set_spec_defaults(spec_defaults);
+        let mut pins = PinClient::new(base_client.clone());
+        pins.set_origin(origin.clone());
+        let mut proofs = ProofClient::new(base_client.clone());
+        proofs.set_origin(origin.clone());
+        let mut workers = WorkerClient::new(base_client.clone());
+        workers.set_origin(origin);
+
         // Create and return the GevulotClient with the initialized clients
         Ok(GevulotClient {
-            pins: PinClient::new(base_client.clone()),
-            tasks: TaskClient::new(base_client.clone()),
-            workflows: WorkflowClient::new(base_client.clone()),
-            workers: WorkerClient::new(base_client.clone()),
+            pins,
+            proofs,
+            tasks,
+            workflows,
+            workers,
             gov: GovClient::new(base_client.clone()),
             sudo: SudoClient::new(base_client.clone()),
             base_client,
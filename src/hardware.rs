@@ -0,0 +1,135 @@
+//! Detection of the hardware resources of the local machine.
+//!
+//! Worker binaries can fill their [`WorkerSpec`] from the machine they run on instead of
+//! hard-coding it:
+//!
+//! ```no_run
+//! use gevulot_rs::models::WorkerSpec;
+//!
+//! let spec = WorkerSpec::detect("/var/lib/worker").unwrap();
+//! ```
+//!
+//! GPUs are counted through the NVIDIA driver's entries in `/proc` and `/dev`, so no driver
+//! library has to be linked. Other GPUs aren't detected and have to be set explicitly.
+
+use std::path::Path;
+
+use crate::{
+    error::{Error, Result},
+    models::{ByteUnit, CoreUnit, WorkerSpec},
+};
+
+/// Returns the number of CPU cores available to this process.
+///
+/// # Errors
+///
+/// This function will return an error if the number of cores can't be determined.
+pub fn cpu_cores() -> Result<u64> {
+    std::thread::available_parallelism()
+        .map(|cores| cores.get() as u64)
+        .map_err(|e| detect_error("cpu cores", e))
+}
+
+/// Returns the total physical memory in bytes.
+///
+/// # Errors
+///
+/// This function will return an error if the memory size can't be determined.
+pub fn total_memory() -> Result<u64> {
+    // SAFETY: sysconf only reads system configuration values
+    let (pages, page_size) = unsafe {
+        (
+            libc::sysconf(libc::_SC_PHYS_PAGES),
+            libc::sysconf(libc::_SC_PAGESIZE),
+        )
+    };
+    if pages <= 0 || page_size <= 0 {
+        return Err(detect_error("memory", std::io::Error::last_os_error()));
+    }
+    Ok(pages as u64 * page_size as u64)
+}
+
+/// Returns the disk space in bytes available to unprivileged users on the file system
+/// containing a path.
+///
+/// # Errors
+///
+/// This function will return an error if the path doesn't exist or isn't accessible.
+pub fn available_disk(path: impl AsRef<Path>) -> Result<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(path.as_ref().as_os_str().as_bytes())
+        .map_err(|e| detect_error("disk space", e))?;
+    // SAFETY: statvfs is plain old data and only written by the call below
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: path is a valid C string and stat a valid pointer
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(detect_error("disk space", std::io::Error::last_os_error()));
+    }
+    #[allow(clippy::unnecessary_cast)] // The field types differ between platforms
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+/// Returns the number of NVIDIA GPUs, 0 if the driver isn't loaded.
+pub fn gpu_count() -> u64 {
+    if let Ok(gpus) = std::fs::read_dir("/proc/driver/nvidia/gpus") {
+        return gpus.count() as u64;
+    }
+    std::fs::read_dir("/dev")
+        .map(|devices| {
+            devices
+                .filter_map(|device| device.ok())
+                .filter(|device| is_nvidia_device(&device.file_name().to_string_lossy()))
+                .count() as u64
+        })
+        .unwrap_or(0)
+}
+
+/// Returns true for device names like `nvidia0`, but not `nvidiactl` or `nvidia-uvm`.
+fn is_nvidia_device(name: &str) -> bool {
+    name.strip_prefix("nvidia")
+        .is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn detect_error(resource: &str, error: impl std::fmt::Display) -> Error {
+    Error::Unknown(format!("failed to detect {resource}: {error}"))
+}
+
+impl WorkerSpec {
+    /// Detects the resources of the local machine.
+    ///
+    /// # Arguments
+    ///
+    /// * `disk_path` - A path on the file system the worker stores its data on.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a resource can't be determined.
+    pub fn detect(disk_path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            cpus: CoreUnit::from_millicores(cpu_cores()?.saturating_mul(1000)),
+            gpus: CoreUnit::from_millicores(gpu_count().saturating_mul(1000)),
+            memory: ByteUnit::from_bytes(total_memory()?),
+            disk: ByteUnit::from_bytes(available_disk(disk_path)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        let spec = WorkerSpec::detect(std::env::temp_dir()).unwrap();
+        assert!(spec.cpus.millicores().unwrap() >= 1000);
+        assert!(spec.memory.bytes().unwrap() > 0);
+        assert!(available_disk("/does/not/exist").is_err());
+
+        assert!(is_nvidia_device("nvidia0"));
+        assert!(is_nvidia_device("nvidia12"));
+        assert!(!is_nvidia_device("nvidiactl"));
+        assert!(!is_nvidia_device("nvidia-uvm"));
+        assert!(!is_nvidia_device("nvidia"));
+    }
+}
@@ -0,0 +1,220 @@
+//! Health of the node the client is connected to.
+//!
+//! [`BaseClient::node_info`](crate::base_client::BaseClient::node_info) and
+//! [`BaseClient::chain_status`](crate::base_client::BaseClient::chain_status) describe the node
+//! and the latest block it knows of. [`GevulotClient::healthcheck`](crate::GevulotClient::healthcheck)
+//! combines them into a [`Health`] report, which tells whether the node answers, belongs to the
+//! chain the client signs for and follows the chain.
+
+use std::time::{Duration, SystemTime};
+
+/// Age of the latest block above which a node is considered stale unless set otherwise with
+/// [`GevulotClient::healthcheck_with`](crate::GevulotClient::healthcheck_with).
+pub const DEFAULT_MAX_BLOCK_AGE: Duration = Duration::from_secs(60);
+
+/// Identity and software of a node.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct NodeInfo {
+    /// ID of the node in the peer-to-peer network.
+    pub node_id: String,
+    pub moniker: String,
+    /// Chain ID of the network the node belongs to.
+    pub network: String,
+    /// Version of CometBFT.
+    pub version: String,
+    /// Name of the application binary, e.g. `gevulotd`.
+    pub app_name: String,
+    /// Version of the application.
+    pub app_version: String,
+    pub cosmos_sdk_version: String,
+}
+
+/// Latest block known to a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainStatus {
+    pub chain_id: String,
+    pub latest_height: i64,
+    pub latest_block_time: SystemTime,
+    /// Whether the node is still syncing blocks from its peers.
+    pub catching_up: bool,
+}
+
+/// Reason why a node is unhealthy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HealthProblem {
+    /// A request to the node failed.
+    Unreachable(String),
+    /// The node belongs to another chain than the client signs for.
+    ChainIdMismatch { expected: String, actual: String },
+    /// The node is still syncing blocks from its peers.
+    CatchingUp,
+    /// The latest block of the node is older than allowed.
+    StaleBlock { age: Duration, max_age: Duration },
+}
+
+impl std::fmt::Display for HealthProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unreachable(error) => write!(f, "node unreachable: {}", error),
+            Self::ChainIdMismatch { expected, actual } => {
+                write!(f, "node is on chain {} instead of {}", actual, expected)
+            }
+            Self::CatchingUp => write!(f, "node is catching up"),
+            Self::StaleBlock { age, max_age } => write!(
+                f,
+                "latest block is {} old, more than {}",
+                humantime::format_duration(*age),
+                humantime::format_duration(*max_age)
+            ),
+        }
+    }
+}
+
+/// Result of a health check of a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Health {
+    /// The node, None if it couldn't be queried.
+    pub node_info: Option<NodeInfo>,
+    /// The latest block of the node, None if it couldn't be queried.
+    pub chain_status: Option<ChainStatus>,
+    /// Everything found wrong with the node.
+    pub problems: Vec<HealthProblem>,
+}
+
+impl Health {
+    /// Checks the node info and chain status of a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `expected_chain_id` - The chain ID the client signs for.
+    /// * `node_info` - The node info, or the error querying it.
+    /// * `chain_status` - The chain status, or the error querying it.
+    /// * `now` - The current time, to tell the age of the latest block.
+    /// * `max_block_age` - The age of the latest block above which the node is stale.
+    pub(crate) fn new(
+        expected_chain_id: &str,
+        node_info: crate::error::Result<NodeInfo>,
+        chain_status: crate::error::Result<ChainStatus>,
+        now: SystemTime,
+        max_block_age: Duration,
+    ) -> Self {
+        let mut problems = Vec::new();
+        let node_info = node_info
+            .map_err(|e| problems.push(HealthProblem::Unreachable(e.to_string())))
+            .ok();
+        let chain_status = chain_status
+            .map_err(|e| problems.push(HealthProblem::Unreachable(e.to_string())))
+            .ok();
+
+        let actual = chain_status
+            .as_ref()
+            .map(|status| &status.chain_id)
+            .or(node_info.as_ref().map(|info| &info.network));
+        if let Some(actual) = actual {
+            if actual != expected_chain_id {
+                problems.push(HealthProblem::ChainIdMismatch {
+                    expected: expected_chain_id.to_string(),
+                    actual: actual.clone(),
+                });
+            }
+        }
+        if let Some(status) = &chain_status {
+            if status.catching_up {
+                problems.push(HealthProblem::CatchingUp);
+            }
+            // Blocks from the future due to clock skew count as fresh
+            let age = now
+                .duration_since(status.latest_block_time)
+                .unwrap_or_default();
+            if age > max_block_age {
+                problems.push(HealthProblem::StaleBlock {
+                    age,
+                    max_age: max_block_age,
+                });
+            }
+        }
+
+        Self {
+            node_info,
+            chain_status,
+            problems,
+        }
+    }
+
+    /// Returns true if no problem was found.
+    pub fn is_healthy(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn test_health() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let status = |chain_id: &str, age, catching_up| {
+            Ok(ChainStatus {
+                chain_id: chain_id.to_string(),
+                latest_height: 42,
+                latest_block_time: now - Duration::from_secs(age),
+                catching_up,
+            })
+        };
+        let info = || {
+            Ok(NodeInfo {
+                network: "gevulot".to_string(),
+                ..Default::default()
+            })
+        };
+
+        let health = Health::new(
+            "gevulot",
+            info(),
+            status("gevulot", 5, false),
+            now,
+            DEFAULT_MAX_BLOCK_AGE,
+        );
+        assert!(health.is_healthy());
+        assert_eq!(health.chain_status.unwrap().latest_height, 42);
+
+        let health = Health::new(
+            "gevulot",
+            info(),
+            status("devnet", 120, true),
+            now,
+            DEFAULT_MAX_BLOCK_AGE,
+        );
+        assert_eq!(
+            health.problems,
+            vec![
+                HealthProblem::ChainIdMismatch {
+                    expected: "gevulot".to_string(),
+                    actual: "devnet".to_string(),
+                },
+                HealthProblem::CatchingUp,
+                HealthProblem::StaleBlock {
+                    age: Duration::from_secs(120),
+                    max_age: DEFAULT_MAX_BLOCK_AGE,
+                },
+            ]
+        );
+        assert_eq!(
+            health.problems[2].to_string(),
+            "latest block is 2m old, more than 1m"
+        );
+
+        let health = Health::new(
+            "gevulot",
+            Err(Error::RpcConnectionError("connection refused".to_string())),
+            Err(Error::RpcConnectionError("connection refused".to_string())),
+            now,
+            DEFAULT_MAX_BLOCK_AGE,
+        );
+        assert!(!health.is_healthy());
+        assert_eq!(health.node_info, None);
+        assert_eq!(health.problems.len(), 2);
+    }
+}
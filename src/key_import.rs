@@ -0,0 +1,426 @@
+//! Import of private keys exported by other Cosmos wallets.
+//!
+//! [`GevulotSigner::import`] detects the [`KeyFormat`] of the exported data and loads the
+//! secp256k1 key it contains:
+//!
+//! - hex keys as shown by Keplr's "View private key" or `gaiad keys export --unarmored-hex`;
+//! - ASCII armored exports of `gaiad keys export` (and other Cosmos SDK daemons);
+//! - SEC1 `EC PRIVATE KEY` and PKCS #8 `PRIVATE KEY` keys, as DER or PEM, e.g. from OpenSSL;
+//! - passphrase protected PKCS #8 `ENCRYPTED PRIVATE KEY` keys, see
+//!   [`GevulotSigner::import_encrypted`].
+//!
+//! Armored exports are encrypted with a bcrypt derived key, which this crate can't decrypt.
+//! They are recognized to give a helpful error, export such keys as unarmored hex instead.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sec1::der::{asn1::ObjectIdentifier, Decode};
+use zeroize::Zeroizing;
+
+use crate::error::{Error, Result};
+use crate::signer::GevulotSigner;
+
+/// Object identifier of the secp256k1 curve.
+const SECP256K1_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.10");
+
+/// Object identifier of elliptic curve public keys in PKCS #8.
+const EC_PUBLIC_KEY_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+
+/// Label of the ASCII armor of keys exported by Cosmos SDK daemons.
+const ARMOR_LABEL: &str = "TENDERMINT PRIVATE KEY";
+
+/// Format of an exported private key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyFormat {
+    /// 32 byte key as hex, optionally prefixed with `0x`.
+    Hex,
+    /// ASCII armored export of `gaiad keys export`.
+    Armored,
+    /// SEC1 `ECPrivateKey` as DER or PEM.
+    Sec1,
+    /// PKCS #8 `PrivateKeyInfo` as DER or PEM.
+    Pkcs8,
+    /// PKCS #8 `EncryptedPrivateKeyInfo` as DER or PEM, encrypted with a passphrase.
+    EncryptedPkcs8,
+}
+
+impl std::fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hex => write!(f, "hex key"),
+            Self::Armored => write!(f, "armored key export"),
+            Self::Sec1 => write!(f, "SEC1 key"),
+            Self::Pkcs8 => write!(f, "PKCS #8 key"),
+            Self::EncryptedPkcs8 => write!(f, "encrypted PKCS #8 key"),
+        }
+    }
+}
+
+impl KeyFormat {
+    /// Detects the format of an exported key, None if it isn't any of the known formats.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if let Ok(text) = std::str::from_utf8(data).map(str::trim) {
+            if let Some(label) = pem_label(text) {
+                return match label {
+                    ARMOR_LABEL => Some(Self::Armored),
+                    "EC PRIVATE KEY" => Some(Self::Sec1),
+                    "PRIVATE KEY" => Some(Self::Pkcs8),
+                    "ENCRYPTED PRIVATE KEY" => Some(Self::EncryptedPkcs8),
+                    _ => None,
+                };
+            }
+            let hex = text.strip_prefix("0x").unwrap_or(text);
+            if hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Some(Self::Hex);
+            }
+        }
+        // DER encoded SEQUENCE
+        (data.first() == Some(&0x30)).then(|| {
+            if pkcs8::PrivateKeyInfo::try_from(data).is_ok() {
+                Self::Pkcs8
+            } else if pkcs8::EncryptedPrivateKeyInfo::try_from(data).is_ok() {
+                Self::EncryptedPkcs8
+            } else {
+                Self::Sec1
+            }
+        })
+    }
+}
+
+/// Contents of a PEM file or an ASCII armor.
+struct Armor<'a> {
+    headers: Vec<(&'a str, &'a str)>,
+    data: Vec<u8>,
+}
+
+fn pem_label(text: &str) -> Option<&str> {
+    text.lines()
+        .next()?
+        .trim()
+        .strip_prefix("-----BEGIN ")?
+        .strip_suffix("-----")
+}
+
+fn dearmor(text: &str) -> std::result::Result<Armor<'_>, String> {
+    let text = text.trim();
+    let label = pem_label(text).ok_or("missing BEGIN line")?;
+    let end = format!("-----END {}-----", label);
+    let mut headers = Vec::new();
+    let mut body = String::new();
+    let mut checksum = None;
+    let mut ended = false;
+    for line in text.lines().skip(1).map(str::trim) {
+        if line == end {
+            ended = true;
+            break;
+        }
+        if let Some((key, value)) = line.split_once(": ") {
+            headers.push((key, value));
+        } else if line.len() == 5 && line.starts_with('=') {
+            checksum = Some(&line[1..]);
+        } else {
+            body.push_str(line);
+        }
+    }
+    if !ended {
+        return Err(format!("missing `{}` line", end));
+    }
+    let data = STANDARD.decode(body).map_err(|e| e.to_string())?;
+    if let Some(checksum) = checksum {
+        let checksum = STANDARD.decode(checksum).map_err(|e| e.to_string())?;
+        if checksum != crc24(&data).to_be_bytes()[1..] {
+            return Err("armor checksum mismatch".to_string());
+        }
+    }
+    Ok(Armor { headers, data })
+}
+
+// CRC-24 of the OpenPGP ASCII armor (RFC 4880)
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc = 0xB704CE_u32;
+    for byte in data {
+        crc ^= u32::from(*byte) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864CFB;
+            }
+        }
+    }
+    crc & 0xFFFFFF
+}
+
+fn sec1_key(der: &[u8]) -> std::result::Result<Zeroizing<Vec<u8>>, String> {
+    let key = sec1::EcPrivateKey::from_der(der).map_err(|e| e.to_string())?;
+    match key.parameters.and_then(|p| p.named_curve()) {
+        Some(curve) if curve != SECP256K1_OID => Err(format!("unsupported curve {}", curve)),
+        _ => Ok(Zeroizing::new(key.private_key.to_vec())),
+    }
+}
+
+fn pkcs8_key(der: &[u8]) -> std::result::Result<Zeroizing<Vec<u8>>, String> {
+    let info = pkcs8::PrivateKeyInfo::try_from(der).map_err(|e| e.to_string())?;
+    if info.algorithm.oid != EC_PUBLIC_KEY_OID {
+        return Err(format!("unsupported algorithm {}", info.algorithm.oid));
+    }
+    match info.algorithm.parameters_oid() {
+        Ok(curve) if curve == SECP256K1_OID => sec1_key(info.private_key),
+        Ok(curve) => Err(format!("unsupported curve {}", curve)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn encrypted_pkcs8_key(
+    der: &[u8],
+    passphrase: Option<&str>,
+) -> std::result::Result<Zeroizing<Vec<u8>>, String> {
+    let passphrase = passphrase.ok_or("the key is encrypted, a passphrase is required")?;
+    let info = pkcs8::EncryptedPrivateKeyInfo::try_from(der).map_err(|e| e.to_string())?;
+    // Decryption only fails with a wrong passphrase or an unsupported encryption scheme
+    let document = info
+        .decrypt(passphrase)
+        .map_err(|e| format!("can't decrypt the key: {}", e))?;
+    pkcs8_key(document.as_bytes())
+}
+
+// Returns the raw key, with the reason as error
+fn key_bytes(
+    data: &[u8],
+    format: KeyFormat,
+    passphrase: Option<&str>,
+) -> std::result::Result<Zeroizing<Vec<u8>>, String> {
+    let text = || std::str::from_utf8(data).map(str::trim);
+    match format {
+        KeyFormat::Hex => {
+            let text = text().map_err(|e| e.to_string())?;
+            hex::decode(text.strip_prefix("0x").unwrap_or(text))
+                .map(Zeroizing::new)
+                .map_err(|e| e.to_string())
+        }
+        KeyFormat::Armored => {
+            let armor = dearmor(text().map_err(|e| e.to_string())?)?;
+            let header = |name| {
+                armor
+                    .headers
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| *value)
+            };
+            if let Some(key_type) = header("type").filter(|t| *t != "secp256k1") {
+                return Err(format!("unsupported key type {}", key_type));
+            }
+            match header("kdf") {
+                Some(kdf) => Err(format!(
+                    "the key is encrypted with {} ({} bytes), which isn't supported; export it \
+                     with `keys export <name> --unarmored-hex --unsafe` instead",
+                    kdf,
+                    armor.data.len()
+                )),
+                None => Err("missing kdf header".to_string()),
+            }
+        }
+        KeyFormat::Sec1 | KeyFormat::Pkcs8 | KeyFormat::EncryptedPkcs8 => {
+            let der = Zeroizing::new(match text() {
+                Ok(pem) if pem.starts_with("-----BEGIN ") => dearmor(pem)?.data,
+                _ => data.to_vec(),
+            });
+            match format {
+                KeyFormat::Sec1 => sec1_key(&der),
+                KeyFormat::Pkcs8 => pkcs8_key(&der),
+                _ => encrypted_pkcs8_key(&der, passphrase),
+            }
+        }
+    }
+}
+
+impl GevulotSigner {
+    /// Imports a private key exported by another wallet, detecting its format.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The exported key, as text or DER.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new instance of GevulotSigner or an error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` naming the detected format if:
+    /// - the format isn't known
+    /// - the key can't be decoded or isn't a secp256k1 key
+    /// - the key is an encrypted armored export
+    pub fn import(data: &[u8]) -> Result<Self> {
+        Self::import_encrypted(data, None)
+    }
+
+    /// Imports a private key exported by another wallet, decrypting it with a passphrase.
+    ///
+    /// The passphrase is only used for encrypted PKCS #8 keys, other formats are imported as
+    /// by [`GevulotSigner::import`].
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The exported key, as text or DER.
+    /// * `passphrase` - The passphrase the key is encrypted with, if any.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new instance of GevulotSigner or an error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` naming the detected format if:
+    /// - the format isn't known
+    /// - the key can't be decoded or isn't a secp256k1 key
+    /// - the key is encrypted and the passphrase is missing or wrong
+    /// - the key is an encrypted armored export
+    pub fn import_encrypted(data: &[u8], passphrase: Option<&str>) -> Result<Self> {
+        let format = KeyFormat::detect(data).ok_or_else(|| {
+            Error::Parse(
+                "unknown key format, expected a hex key, an armored key export or a SEC1 or \
+                 PKCS #8 key"
+                    .to_string(),
+            )
+        })?;
+        Self::import_with(data, format, passphrase)
+    }
+
+    /// Imports a private key exported by another wallet in the given format.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The exported key, as text or DER.
+    /// * `format` - The format of the exported key.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new instance of GevulotSigner or an error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` naming the format if the key can't be decoded, isn't a secp256k1 key
+    /// or is an encrypted armored export.
+    pub fn import_as(data: &[u8], format: KeyFormat) -> Result<Self> {
+        Self::import_with(data, format, None)
+    }
+
+    fn import_with(data: &[u8], format: KeyFormat, passphrase: Option<&str>) -> Result<Self> {
+        let key = key_bytes(data, format, passphrase)
+            .map_err(|e| Error::Parse(format!("{}: {}", format, e)))?;
+        Self::from_private_key(&Zeroizing::new(hex::encode(key)))
+            .map_err(|e| Error::Parse(format!("{}: {}", format, e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd";
+
+    // `openssl ec -outform der` and `openssl pkey -outform der` of KEY
+    fn sec1_der() -> Vec<u8> {
+        hex::decode(format!("302e0201010420{}a00706052b8104000a", KEY)).unwrap()
+    }
+
+    fn pkcs8_der() -> Vec<u8> {
+        hex::decode(format!(
+            "303e020100301006072a8648ce3d020106052b8104000a042730250201010420{}",
+            KEY
+        ))
+        .unwrap()
+    }
+
+    fn pem(label: &str, headers: &str, data: &[u8], checksum: bool) -> String {
+        let checksum = if checksum {
+            format!("={}\n", STANDARD.encode(&crc24(data).to_be_bytes()[1..]))
+        } else {
+            String::new()
+        };
+        format!(
+            "-----BEGIN {label}-----\n{headers}{}\n{checksum}-----END {label}-----\n",
+            STANDARD.encode(data)
+        )
+    }
+
+    #[test]
+    fn test_import() {
+        let expected = GevulotSigner::from_private_key(KEY).unwrap();
+        let keys = [
+            (KEY.as_bytes().to_vec(), KeyFormat::Hex),
+            (format!("0x{}\n", KEY).into_bytes(), KeyFormat::Hex),
+            (sec1_der(), KeyFormat::Sec1),
+            (
+                pem("EC PRIVATE KEY", "", &sec1_der(), false).into_bytes(),
+                KeyFormat::Sec1,
+            ),
+            (pkcs8_der(), KeyFormat::Pkcs8),
+            (
+                pem("PRIVATE KEY", "", &pkcs8_der(), false).into_bytes(),
+                KeyFormat::Pkcs8,
+            ),
+        ];
+        for (data, format) in keys {
+            assert_eq!(KeyFormat::detect(&data), Some(format));
+            let signer = GevulotSigner::import(&data).unwrap();
+            assert_eq!(signer.address(), expected.address());
+        }
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let armored = pem(
+            ARMOR_LABEL,
+            "kdf: bcrypt\nsalt: 5C9E3F1A\ntype: secp256k1\n\n",
+            &[1, 2, 3, 4],
+            true,
+        );
+        assert_eq!(
+            KeyFormat::detect(armored.as_bytes()),
+            Some(KeyFormat::Armored)
+        );
+        let err = GevulotSigner::import(armored.as_bytes()).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("parse error: armored key export: the key is encrypted with bcrypt"));
+
+        let corrupted = armored.replace("AQIDBA==", "AQIDBQ==");
+        let err = GevulotSigner::import(corrupted.as_bytes()).unwrap_err();
+        assert!(err.to_string().ends_with("armor checksum mismatch"));
+
+        assert_eq!(KeyFormat::detect(b"not a key"), None);
+        let err = GevulotSigner::import_as(b"0xzz", KeyFormat::Hex).unwrap_err();
+        assert!(err.to_string().starts_with("parse error: hex key: "));
+    }
+
+    #[test]
+    fn test_import_encrypted() {
+        let expected = GevulotSigner::from_private_key(KEY).unwrap();
+        let pkcs8 = pkcs8_der();
+        let params =
+            pkcs8::pkcs5::pbes2::Parameters::pbkdf2_sha256_aes256cbc(1000, &[7; 16], &[9; 16])
+                .unwrap();
+        let encrypted = pkcs8::PrivateKeyInfo::try_from(&pkcs8[..])
+            .unwrap()
+            .encrypt_with_params(params, "secret")
+            .unwrap();
+        let der = encrypted.as_bytes();
+        let pem = pem("ENCRYPTED PRIVATE KEY", "", der, false);
+
+        for data in [der, pem.as_bytes()] {
+            assert_eq!(KeyFormat::detect(data), Some(KeyFormat::EncryptedPkcs8));
+            let signer = GevulotSigner::import_encrypted(data, Some("secret")).unwrap();
+            assert_eq!(signer.address(), expected.address());
+        }
+
+        let err = GevulotSigner::import(der).unwrap_err();
+        assert!(err.to_string().ends_with("a passphrase is required"));
+        let err = GevulotSigner::import_encrypted(der, Some("wrong")).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("parse error: encrypted PKCS #8 key: can't decrypt the key"));
+        // The passphrase is ignored for unencrypted keys
+        let signer = GevulotSigner::import_encrypted(KEY.as_bytes(), Some("secret")).unwrap();
+        assert_eq!(signer.address(), expected.address());
+    }
+}
@@ -0,0 +1,112 @@
+//! Storage of signer keys in the keychain of the operating system.
+//!
+//! Keys are kept in the macOS Keychain, the Linux Secret Service or the Windows Credential
+//! Manager instead of config files. Each key is stored as a hex encoded secp256k1 private key
+//! under a service name and an account name, e.g. `gevulot` and the name of a wallet.
+
+use cosmrs::crypto::secp256k1::SigningKey;
+use zeroize::Zeroizing;
+
+use crate::{
+    error::{Error, Result},
+    signer::GevulotSigner,
+};
+
+/// Service name used for keys when no other one is given.
+pub const DEFAULT_SERVICE: &str = "gevulot";
+
+/// Signer keys stored in the keychain of the operating system under a service name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keychain {
+    service: String,
+}
+
+impl Default for Keychain {
+    fn default() -> Self {
+        Self::new(DEFAULT_SERVICE)
+    }
+}
+
+impl Keychain {
+    /// Creates a keychain storing keys under the given service name.
+    pub fn new(service: &str) -> Self {
+        Self {
+            service: service.to_string(),
+        }
+    }
+
+    /// Returns the service name the keys are stored under.
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    /// Stores a private key, replacing any key already stored for the account.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name the key is stored under.
+    /// * `private_key` - The private key as a hex string.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the private key is invalid or the keychain
+    /// cannot be accessed.
+    pub fn store_private_key(&self, account: &str, private_key: &str) -> Result<()> {
+        // Refuse to store anything which can't be loaded as a signer later on
+        SigningKey::from_slice(&Zeroizing::new(hex::decode(private_key)?))?;
+        self.entry(account)?.set_password(private_key)?;
+        Ok(())
+    }
+
+    /// Loads a private key.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name the key is stored under.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the private key as a hex string, wiped from memory when dropped, or an
+    /// error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::NotFound` if no key is stored for the account, or an
+    /// error if the keychain cannot be accessed.
+    pub fn load_private_key(&self, account: &str) -> Result<Zeroizing<String>> {
+        Ok(Zeroizing::new(self.entry(account)?.get_password()?))
+    }
+
+    /// Deletes a private key.
+    ///
+    /// # Arguments
+    ///
+    /// * `account` - The name the key is stored under.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::NotFound` if no key is stored for the account, or an
+    /// error if the keychain cannot be accessed.
+    pub fn delete_private_key(&self, account: &str) -> Result<()> {
+        self.entry(account)?.delete_credential()?;
+        Ok(())
+    }
+
+    /// Loads the signer for an account.
+    pub(crate) fn load_signer(&self, account: &str) -> Result<GevulotSigner> {
+        GevulotSigner::from_private_key(&self.load_private_key(account)?)
+    }
+
+    fn entry(&self, account: &str) -> Result<keyring::Entry> {
+        Ok(keyring::Entry::new(&self.service, account)?)
+    }
+}
+
+impl From<keyring::Error> for Error {
+    fn from(error: keyring::Error) -> Self {
+        match error {
+            keyring::Error::NoEntry => Error::NotFound(None),
+            error => Error::Keychain(error.to_string()),
+        }
+    }
+}
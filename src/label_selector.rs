@@ -0,0 +1,275 @@
+//! Selection of entities by their labels and tags.
+//!
+//! Selectors are written like Kubernetes label selectors: a comma separated list of
+//! requirements which all have to match.
+//!
+//! | Requirement            | Matches entities                                   |
+//! |------------------------|----------------------------------------------------|
+//! | `key=value`            | with label `key` set to `value` (`==` works too)   |
+//! | `key!=value`           | without label `key` set to `value`                 |
+//! | `key in (v1, v2)`      | with label `key` set to one of the values          |
+//! | `key notin (v1, v2)`   | without label `key` set to one of the values       |
+//! | `key`                  | with label `key`                                   |
+//! | `!key`                 | without label `key`                                |
+//!
+//! The key `tags` refers to the tags instead of a label: `tags=gpu` matches entities tagged
+//! `gpu`, `tags in (gpu, fpga)` entities with either tag and `tags` entities with any tag.
+//!
+//! Selectors are evaluated on the client. Requirements which can't be expressed in the syntax
+//! can be added with [`Requirement::Custom`].
+//!
+//! ```
+//! use gevulot_rs::label_selector::LabelSelector;
+//!
+//! let selector: LabelSelector = "gpu_type=nvidia_a100, region in (us-east, eu-west)"
+//!     .parse()
+//!     .unwrap();
+//! ```
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use crate::{
+    error::{Error, Result},
+    models::Metadata,
+};
+
+/// Key referring to the tags instead of a label.
+pub const TAGS_KEY: &str = "tags";
+
+/// Predicate of a [`Requirement::Custom`].
+pub type MetadataPredicate = Arc<dyn Fn(&Metadata) -> bool + Send + Sync>;
+
+/// A single requirement of a [`LabelSelector`].
+#[derive(Clone)]
+pub enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    In(String, Vec<String>),
+    NotIn(String, Vec<String>),
+    Exists(String),
+    NotExists(String),
+    /// Requirement evaluated by a custom predicate.
+    Custom(MetadataPredicate),
+}
+
+impl fmt::Debug for Requirement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Requirement::Equals(key, value) => write!(f, "{key}={value}"),
+            Requirement::NotEquals(key, value) => write!(f, "{key}!={value}"),
+            Requirement::In(key, values) => write!(f, "{key} in ({})", values.join(", ")),
+            Requirement::NotIn(key, values) => write!(f, "{key} notin ({})", values.join(", ")),
+            Requirement::Exists(key) => write!(f, "{key}"),
+            Requirement::NotExists(key) => write!(f, "!{key}"),
+            Requirement::Custom(_) => write!(f, "<custom>"),
+        }
+    }
+}
+
+impl Requirement {
+    /// Parses a single requirement.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::Parse` if the requirement is malformed.
+    pub fn parse(requirement: &str) -> Result<Self> {
+        let requirement = requirement.trim();
+        if let Some((key, values)) = split_set(requirement, "notin")? {
+            return Ok(Requirement::NotIn(key, values));
+        }
+        if let Some((key, values)) = split_set(requirement, "in")? {
+            return Ok(Requirement::In(key, values));
+        }
+        if let Some((key, value)) = requirement.split_once("!=") {
+            return Ok(Requirement::NotEquals(parse_key(key)?, parse_value(value)?));
+        }
+        if let Some((key, value)) = requirement
+            .split_once("==")
+            .or_else(|| requirement.split_once('='))
+        {
+            return Ok(Requirement::Equals(parse_key(key)?, parse_value(value)?));
+        }
+        match requirement.strip_prefix('!') {
+            Some(key) => Ok(Requirement::NotExists(parse_key(key)?)),
+            None => Ok(Requirement::Exists(parse_key(requirement)?)),
+        }
+    }
+
+    /// Returns true if the metadata fulfills the requirement.
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        match self {
+            Requirement::Equals(key, value) => values(metadata, key).any(|v| v == value),
+            Requirement::NotEquals(key, value) => !values(metadata, key).any(|v| v == value),
+            Requirement::In(key, set) => values(metadata, key).any(|v| set.iter().any(|s| s == v)),
+            Requirement::NotIn(key, set) => {
+                !values(metadata, key).any(|v| set.iter().any(|s| s == v))
+            }
+            Requirement::Exists(key) => values(metadata, key).next().is_some(),
+            Requirement::NotExists(key) => values(metadata, key).next().is_none(),
+            Requirement::Custom(predicate) => predicate(metadata),
+        }
+    }
+}
+
+/// Values of a label, or the tags for [`TAGS_KEY`].
+fn values<'a>(metadata: &'a Metadata, key: &'a str) -> Box<dyn Iterator<Item = &'a str> + 'a> {
+    if key == TAGS_KEY {
+        Box::new(metadata.tags.iter().map(String::as_str))
+    } else {
+        Box::new(
+            metadata
+                .labels
+                .iter()
+                .filter(move |label| label.key == key)
+                .map(|label| label.value.as_str()),
+        )
+    }
+}
+
+/// Splits `key <operator> (v1, v2)`, None if the requirement uses another operator.
+fn split_set(requirement: &str, operator: &str) -> Result<Option<(String, Vec<String>)>> {
+    let mut words = requirement.splitn(2, char::is_whitespace);
+    let key = words.next().unwrap_or_default();
+    let Some(rest) = words.next().map(str::trim_start) else {
+        return Ok(None);
+    };
+    let Some(set) = rest
+        .strip_prefix(operator)
+        .filter(|set| set.starts_with([' ', '(']))
+    else {
+        return Ok(None);
+    };
+    let set = set
+        .trim()
+        .strip_prefix('(')
+        .and_then(|set| set.strip_suffix(')'))
+        .ok_or_else(|| {
+            Error::Parse(format!(
+                "values of '{requirement}' must be enclosed in parentheses"
+            ))
+        })?;
+    let values = set
+        .split(',')
+        .map(parse_value)
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some((parse_key(key)?, values)))
+}
+
+fn parse_key(key: &str) -> Result<String> {
+    let key = key.trim();
+    if key.is_empty() || key.contains(|c: char| c.is_whitespace() || "!=(),".contains(c)) {
+        return Err(Error::Parse(format!("invalid label key: '{key}'")));
+    }
+    Ok(key.to_string())
+}
+
+fn parse_value(value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.contains(|c: char| c.is_whitespace() || "!=(),".contains(c)) {
+        return Err(Error::Parse(format!("invalid label value: '{value}'")));
+    }
+    Ok(value.to_string())
+}
+
+/// Requirements on labels and tags which all have to match, see the [module docs](self).
+#[derive(Clone, Debug, Default)]
+pub struct LabelSelector {
+    pub requirements: Vec<Requirement>,
+}
+
+impl LabelSelector {
+    /// Creates a selector matching everything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a requirement.
+    pub fn with(mut self, requirement: Requirement) -> Self {
+        self.requirements.push(requirement);
+        self
+    }
+
+    /// Returns true if the metadata fulfills all requirements.
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        self.requirements.iter().all(|r| r.matches(metadata))
+    }
+}
+
+impl FromStr for LabelSelector {
+    type Err = Error;
+
+    fn from_str(selector: &str) -> Result<Self> {
+        let mut requirements = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (i, c) in selector.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth = depth.checked_sub(1).ok_or_else(|| {
+                        Error::Parse(format!("unbalanced parentheses in '{selector}'"))
+                    })?
+                }
+                ',' if depth == 0 => {
+                    requirements.push(Requirement::parse(&selector[start..i])?);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if depth != 0 {
+            return Err(Error::Parse(format!(
+                "unbalanced parentheses in '{selector}'"
+            )));
+        }
+        if !selector[start..].trim().is_empty() || !requirements.is_empty() {
+            requirements.push(Requirement::parse(&selector[start..])?);
+        }
+        Ok(Self { requirements })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_label_selector() {
+        let metadata = serde_json::from_value::<Metadata>(json!({
+            "id": "w1", "name": "w1", "description": "", "tags": ["gpu"],
+            "labels": [
+                {"key": "gpu_type", "value": "nvidia_a100"},
+                {"key": "region", "value": "eu-west"}
+            ]
+        }))
+        .unwrap();
+        let matches = |selector: &str| {
+            selector
+                .parse::<LabelSelector>()
+                .unwrap()
+                .matches(&metadata)
+        };
+
+        assert!(matches(""));
+        assert!(matches(
+            "gpu_type=nvidia_a100, region in (us-east, eu-west)"
+        ));
+        assert!(matches("gpu_type==nvidia_a100,!zone,tags"));
+        assert!(matches("region notin (us-east),tags in (gpu, fpga)"));
+        assert!(!matches("gpu_type!=nvidia_a100"));
+        assert!(!matches("region in (us-east)"));
+        assert!(!matches("tags=fpga"));
+        assert!(!matches("zone"));
+
+        assert!("region in us-east".parse::<LabelSelector>().is_err());
+        assert!("region in (us-east".parse::<LabelSelector>().is_err());
+        assert!("a=b,".parse::<LabelSelector>().is_err());
+        assert!("=b".parse::<LabelSelector>().is_err());
+
+        let selector = LabelSelector::new().with(Requirement::Custom(Arc::new(|metadata| {
+            metadata.labels.len() == 2
+        })));
+        assert!(selector.matches(&metadata));
+    }
+}
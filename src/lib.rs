@@ -1,31 +1,146 @@
+/// This module contains alerting rules evaluated on snapshots of the chain state.
+#[cfg(feature = "query-only")]
+pub mod alerts;
+/// This module contains the supervision of background tasks.
+pub mod background;
 /// This module contains the base client implementation.
+///
+/// It is an implementation detail of the clients and not covered by the stability guarantees of
+/// the [`prelude`].
+#[doc(hidden)]
+#[cfg(feature = "query-only")]
 pub mod base_client;
 /// This module contains various builders for constructing messages.
+#[cfg(feature = "query-only")]
 pub mod builders;
+/// This module contains the estimation of the chain time from recent block headers.
+#[cfg(feature = "query-only")]
+pub mod chain_clock;
+/// This module contains gRPC compression settings and metrics.
+#[cfg(feature = "query-only")]
+pub mod compression;
+/// This module contains limiting of concurrent gRPC requests.
+#[cfg(feature = "query-only")]
+pub mod concurrency;
+/// This module contains failover between several gRPC endpoints.
+#[cfg(feature = "query-only")]
+pub mod failover;
+/// This module contains pre-flight checks of the fallback URLs of pins.
+pub mod fallback_check;
+/// This module contains the estimation of gas limits from recent simulations.
+#[cfg(feature = "query-only")]
+pub mod gas_cache;
+/// This module contains reading the state of the Gevulot module from genesis files.
+#[cfg(feature = "genesis")]
+pub mod genesis;
 /// This module contains the client implementation for Gevulot.
+#[cfg(feature = "query-only")]
 pub mod gevulot_client;
+/// This module contains the detection of the local hardware resources.
+#[cfg(all(feature = "hardware-detect", unix))]
+pub mod hardware;
+/// This module contains the health checks of the node the client is connected to.
+#[cfg(feature = "query-only")]
+pub mod health;
+/// This module contains the import of private keys exported by other Cosmos wallets.
+#[cfg(feature = "full")]
+pub mod key_import;
+/// This module contains the OS keychain storage for signer keys.
+#[cfg(feature = "keychain")]
+pub mod keychain;
+/// This module contains selection of entities by their labels and tags.
+pub mod label_selector;
+/// This module contains metrics of event handlers.
+pub mod metrics;
+/// This module contains the migration of legacy manifests to the current model.
+pub mod migrate;
+/// This module contains labels recording the origin of created entities.
+pub mod origin;
+/// This module contains propagation of the OpenTelemetry trace context.
+#[cfg(feature = "otel")]
+mod otel;
+/// This module contains the persistent queue of messages to be sent.
+#[cfg(feature = "outbox")]
+pub mod outbox;
+/// This module contains pagination of list queries.
+pub mod pagination;
 /// This module contains the client implementation for managing pins.
+#[cfg(feature = "query-only")]
 pub mod pin_client;
+/// This module contains prefetch planning for task inputs.
+pub mod prefetch;
+/// This module contains the stable, supported public API.
+pub mod prelude;
+/// This module contains the client implementation for managing proofs.
+#[cfg(feature = "query-only")]
+pub mod proof_client;
+/// This module contains stable paths to the commonly used protobuf types.
+pub mod proto_api;
+/// This module contains fluent queries combining several entities.
+#[cfg(feature = "query-only")]
+pub mod query;
+/// This module contains retries of read-only gRPC calls failing with transient errors.
+#[cfg(feature = "query-only")]
+pub mod query_retry;
+/// This module contains the limits and current usage of accounts.
+#[cfg(feature = "query-only")]
+pub mod quotas;
+/// This module contains decoders for messages and events of chain extensions.
+pub mod registry;
 /// This module contains the client implementation for sudo functionality.
+#[cfg(feature = "query-only")]
 pub mod sudo_client;
 /// This module contains the client implementation for managing tasks.
+#[cfg(feature = "query-only")]
 pub mod task_client;
+/// This module contains utilities for testing code built on the clients.
+pub mod testing;
+/// This module contains the clock used by the clients when waiting.
+pub mod timer;
+/// This module contains the dispatcher pipelining transactions of one account.
+#[cfg(feature = "query-only")]
+pub mod tx_dispatcher;
+/// This module contains the export and import of transactions as JSON for offline signing.
+#[cfg(feature = "tx-json")]
+pub mod tx_json;
+/// This module contains receipts of transactions and the fees charged for them.
+pub mod tx_receipt;
 /// This module contains the client implementation for managing workers.
+#[cfg(feature = "query-only")]
 pub mod worker_client;
+/// This module contains client-side chaining of workflows.
+#[cfg(feature = "query-only")]
+pub mod workflow_chain;
 /// This module contains the client implementation for managing workflows.
+#[cfg(feature = "query-only")]
 pub mod workflow_client;
+/// This module contains construction of workflows from task dependencies.
+pub mod workflow_dag;
+/// This module contains client-side retries of failed workflow tasks.
+#[cfg(feature = "query-only")]
+pub mod workflow_runner;
 
 pub mod models;
 pub mod runtime_config;
+/// This module contains organization-wide defaults for task and workflow specs.
+#[cfg(feature = "query-only")]
+pub mod spec_defaults;
 
 pub mod error;
+/// This module contains in-process distribution of chain events to several subscribers.
+pub mod event_bus;
 pub mod event_fetcher;
+pub mod event_filter;
 pub mod events;
+#[cfg(feature = "query-only")]
 pub mod gov_client;
 /// This module contains the signer implementation.
+#[cfg(feature = "query-only")]
 mod signer;
 
 /// This module contains the protocol buffer definitions.
+///
+/// Its layout follows the protobuf packaging, prefer the stable paths of [`proto_api`].
 pub mod proto {
     pub mod cosmos {
         pub mod base {
@@ -55,6 +170,8 @@ pub mod proto {
         #![allow(clippy::module_inception)]
         pub mod gevulot {
             tonic::include_proto!("gevulot.gevulot");
+            #[cfg(feature = "tx-json")]
+            include!(concat!(env!("OUT_DIR"), "/gevulot.gevulot.serde.rs"));
             pub mod module {
                 tonic::include_proto!("gevulot.gevulot.module");
             }
@@ -64,12 +181,18 @@ pub mod proto {
 
 pub use cosmrs::tendermint::abci::Event;
 pub use cosmrs::tendermint::block::Height;
-pub use error::{Error, Result};
-pub use event_fetcher::{EventFetcher, EventHandler};
+pub use error::{Error, ErrorCode, GevulotError, Result};
+pub use event_fetcher::{
+    BlockBatched, BlockEventHandler, BlockInfo, ChainNotification, DeadLetterCallback,
+    EventContext, EventFetcher, EventHandler, HandlerErrorPolicy, RawAndParsed,
+    RawAndParsedEventHandler,
+};
+pub use event_filter::EventFilter;
 pub use events::GevulotEvent;
+#[cfg(feature = "query-only")]
 pub use gevulot_client::{GevulotClient, GevulotClientBuilder};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "query-only"))]
 mod tests {
     use cosmrs::tendermint::block::Height;
 
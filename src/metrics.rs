@@ -0,0 +1,220 @@
+//! Metrics of event handlers.
+//!
+//! Wrap an [`EventHandler`] in [`Metered`] to count the events it receives by kind, measure how
+//! long it takes to handle them and record the Gevulot events which couldn't be parsed:
+//!
+//! ```no_run
+//! # use gevulot_rs::{EventHandler, metrics::Metered};
+//! # fn wrap<H: EventHandler>(handler: H) {
+//! let handler = Metered::new(handler);
+//! let metrics = handler.metrics();
+//! // Register the handler with an EventFetcher, then read the metrics from anywhere
+//! println!("{:?}", metrics.snapshot());
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    error::{Error, Result},
+    event_fetcher::{BlockInfo, ChainNotification, EventContext, EventHandler},
+    events::GevulotEvent,
+};
+
+/// Snapshot of the metrics of a [`Metered`] handler.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventStats {
+    /// Number of events received per event kind, e.g. `create-task`.
+    pub events_by_kind: BTreeMap<String, u64>,
+    /// Number of Gevulot events which couldn't be parsed.
+    pub parse_failures: u64,
+    /// Number of events the handler returned an error for.
+    pub handler_errors: u64,
+    /// Total time the handler spent handling events.
+    pub total_handler_time: Duration,
+    /// Longest time the handler spent handling a single event.
+    pub max_handler_time: Duration,
+}
+
+impl EventStats {
+    /// Number of events received of all kinds.
+    pub fn events(&self) -> u64 {
+        self.events_by_kind.values().sum()
+    }
+
+    /// Average time the handler spent handling an event, zero if none was handled.
+    pub fn mean_handler_time(&self) -> Duration {
+        match self.events() {
+            0 => Duration::ZERO,
+            events => {
+                Duration::from_secs_f64(self.total_handler_time.as_secs_f64() / events as f64)
+            }
+        }
+    }
+}
+
+/// Shared metrics of a [`Metered`] handler.
+#[derive(Clone, Debug, Default)]
+pub struct EventMetrics {
+    stats: Arc<Mutex<EventStats>>,
+}
+
+impl EventMetrics {
+    /// Returns the current values of the metrics.
+    pub fn snapshot(&self) -> EventStats {
+        self.lock().clone()
+    }
+
+    /// Resets all metrics to zero.
+    pub fn reset(&self) {
+        *self.lock() = EventStats::default();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, EventStats> {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn record_event(&self, event: &crate::Event, block_height: crate::Height) {
+        let parse_failed = matches!(
+            GevulotEvent::from_cosmos(event, block_height),
+            Err(e) if !matches!(e, Error::UnknownEventKind(_))
+        );
+        let mut stats = self.lock();
+        *stats.events_by_kind.entry(event.kind.clone()).or_default() += 1;
+        if parse_failed {
+            stats.parse_failures += 1;
+        }
+    }
+
+    fn record_handling(&self, elapsed: Duration, result: &Result<()>) {
+        let mut stats = self.lock();
+        stats.total_handler_time += elapsed;
+        stats.max_handler_time = stats.max_handler_time.max(elapsed);
+        // Malformed events reported by parsing adapters are already counted as parse failures
+        if matches!(result, Err(e) if !matches!(e, Error::MalformedEvent(_))) {
+            stats.handler_errors += 1;
+        }
+    }
+}
+
+/// Event handler decorator recording [`EventMetrics`] of the handler it wraps.
+///
+/// Notifications and block ends are passed through without being measured.
+pub struct Metered<H: EventHandler> {
+    pub handler: H,
+    metrics: EventMetrics,
+}
+
+impl<H> Metered<H>
+where
+    H: EventHandler,
+{
+    /// Wraps a handler, recording to new metrics.
+    pub fn new(handler: H) -> Self {
+        Self::with_metrics(handler, EventMetrics::default())
+    }
+
+    /// Wraps a handler, recording to existing metrics, e.g. to combine several handlers.
+    pub fn with_metrics(handler: H, metrics: EventMetrics) -> Self {
+        Self { handler, metrics }
+    }
+
+    /// Returns the metrics of the handler.
+    pub fn metrics(&self) -> EventMetrics {
+        self.metrics.clone()
+    }
+}
+
+impl<H> EventHandler for Metered<H>
+where
+    H: EventHandler,
+{
+    async fn handle_event(
+        &mut self,
+        event: &crate::Event,
+        block_height: crate::Height,
+    ) -> Result<()> {
+        self.metrics.record_event(event, block_height);
+        let start = Instant::now();
+        let result = self.handler.handle_event(event, block_height).await;
+        self.metrics.record_handling(start.elapsed(), &result);
+        result
+    }
+
+    async fn handle_event_with_context(
+        &mut self,
+        event: &crate::Event,
+        context: &EventContext,
+    ) -> Result<()> {
+        self.metrics.record_event(event, context.block_height);
+        let start = Instant::now();
+        let result = self.handler.handle_event_with_context(event, context).await;
+        self.metrics.record_handling(start.elapsed(), &result);
+        result
+    }
+
+    async fn handle_notification(&mut self, notification: &ChainNotification) -> Result<()> {
+        self.handler.handle_notification(notification).await
+    }
+
+    async fn handle_block_end(&mut self, block: &BlockInfo) -> Result<()> {
+        self.handler.handle_block_end(block).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmrs::{rpc::dialect::v0_34::EventAttribute, tendermint::abci::Event};
+
+    struct Failing;
+
+    impl EventHandler for Failing {
+        async fn handle_event(
+            &mut self,
+            event: &Event,
+            _block_height: crate::Height,
+        ) -> Result<()> {
+            if event.kind == "coin_spent" {
+                return Err(Error::Unknown("failed".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_metered() {
+        let worker = Event::new(
+            "create-worker",
+            vec![EventAttribute {
+                index: true,
+                key: b"worker-id".to_vec(),
+                value: b"worker1".to_vec(),
+            }],
+        );
+        let malformed = Event::new("create-worker", Vec::<EventAttribute>::new());
+        let other = Event::new("coin_spent", Vec::<EventAttribute>::new());
+
+        let mut handler = Metered::new(Failing);
+        let metrics = handler.metrics();
+        let height = crate::Height::from(10u32);
+        handler.handle_event(&worker, height).await.unwrap();
+        handler.handle_event(&malformed, height).await.unwrap();
+        assert!(handler.handle_event(&other, height).await.is_err());
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.events(), 3);
+        assert_eq!(stats.events_by_kind["create-worker"], 2);
+        assert_eq!(stats.events_by_kind["coin_spent"], 1);
+        assert_eq!(stats.parse_failures, 1);
+        assert_eq!(stats.handler_errors, 1);
+        assert!(stats.max_handler_time <= stats.total_handler_time);
+
+        metrics.reset();
+        assert_eq!(metrics.snapshot(), EventStats::default());
+    }
+}
@@ -0,0 +1,366 @@
+//! Migration of legacy manifests to the current model.
+//!
+//! Manifests written for early versions of the tooling are still declared as `version: v0`,
+//! but use field shapes the [`models`](crate::models) no longer accept:
+//!
+//! - `metadata.desc` instead of `metadata.description`;
+//! - `metadata.labels` as a map instead of a list of `key`/`value` pairs;
+//! - metadata without `name`, `description`, `tags` or `labels`;
+//! - task `env` as a map instead of a list of `name`/`value` pairs;
+//! - task `inputContexts` as a map from source to target;
+//! - task `outputContexts` as a list of paths without retention period;
+//! - pin `fallbackUrl` with a single URL instead of `fallbackUrls`.
+//!
+//! [`migrate`] rewrites such documents into the current schema and reports every change, so
+//! tools can upgrade the files of their users and show what was changed. Tasks nested in the
+//! stages of workflows are migrated as well.
+//!
+//! ```
+//! use gevulot_rs::migrate::migrate;
+//! use serde_json::json;
+//!
+//! let migration = migrate(json!({
+//!     "kind": "Task",
+//!     "version": "v0",
+//!     "metadata": {"name": "prove", "desc": "Proves a block"},
+//!     "spec": {
+//!         "image": "prover:latest",
+//!         "env": {"LOG": "debug"},
+//!         "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}
+//!     }
+//! }))
+//! .unwrap();
+//! assert!(migration.is_changed());
+//! let task: gevulot_rs::models::Task = serde_json::from_value(migration.document).unwrap();
+//! assert_eq!(task.metadata.description, "Proves a block");
+//! ```
+
+use serde_json::{json, Map, Value};
+
+use crate::error::{Error, Result};
+
+/// The version of the manifests the migration produces.
+pub const CURRENT_VERSION: &str = "v0";
+
+/// Retention period in seconds given to legacy output contexts, which had none.
+pub const DEFAULT_RETENTION_PERIOD: i64 = 24 * 60 * 60;
+
+/// A single rewrite done by [`migrate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    /// Path of the changed field, e.g. `spec.stages[0].tasks[1].env`.
+    pub path: String,
+    /// What was changed.
+    pub description: String,
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.description)
+    }
+}
+
+/// A migrated manifest and the changes made to it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Migration {
+    /// The manifest in the current schema.
+    pub document: Value,
+    /// The changes made, empty if the manifest already was in the current schema.
+    pub changes: Vec<Change>,
+}
+
+impl Migration {
+    /// Returns true if the manifest had to be changed.
+    pub fn is_changed(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// Migrates a manifest of any kind to the current schema.
+///
+/// # Arguments
+///
+/// * `document` - The manifest.
+///
+/// # Returns
+///
+/// A Result containing the migrated manifest and the changes made, or an error.
+///
+/// # Errors
+///
+/// This function will return `Error::Parse` if the manifest is no object or has an unknown
+/// kind or version.
+pub fn migrate(mut document: Value) -> Result<Migration> {
+    let mut changes = Vec::new();
+    let Some(object) = document.as_object_mut() else {
+        return Err(Error::Parse("manifest must be an object".to_string()));
+    };
+    let kind = object
+        .get("kind")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::Parse("manifest has no kind".to_string()))?
+        .to_string();
+
+    match object.get("version") {
+        None => {
+            object.insert("version".to_string(), json!(CURRENT_VERSION));
+            changes.push(change("version", "added missing version"));
+        }
+        Some(Value::String(version)) if version == CURRENT_VERSION => {}
+        Some(version) => {
+            return Err(Error::Parse(format!(
+                "unsupported manifest version {}, expected {}",
+                version, CURRENT_VERSION
+            )))
+        }
+    }
+
+    if let Some(metadata) = object.get_mut("metadata") {
+        migrate_metadata(metadata, "metadata", &mut changes);
+    }
+    let spec = object.get_mut("spec");
+    match (kind.as_str(), spec) {
+        ("Task", Some(spec)) => migrate_task_spec(spec, "spec", &mut changes),
+        ("Workflow", Some(spec)) => migrate_workflow_spec(spec, "spec", &mut changes),
+        ("Pin", Some(spec)) => migrate_pin_spec(spec, "spec", &mut changes),
+        ("Task" | "Workflow" | "Pin" | "Worker", _) => {}
+        (kind, _) => return Err(Error::Parse(format!("unknown manifest kind {}", kind))),
+    }
+
+    Ok(Migration { document, changes })
+}
+
+/// Migrates a manifest in YAML to the current schema.
+///
+/// # Returns
+///
+/// A Result containing the migrated manifest in YAML and the changes made, or an error.
+///
+/// # Errors
+///
+/// This function will return `Error::Parse` if the YAML is invalid or the manifest can't be
+/// migrated, see [`migrate`].
+pub fn migrate_yaml(yaml: &str) -> Result<(String, Vec<Change>)> {
+    let document: Value = serde_yaml::from_str(yaml).map_err(|e| Error::Parse(e.to_string()))?;
+    let migration = migrate(document)?;
+    let yaml =
+        serde_yaml::to_string(&migration.document).map_err(|e| Error::Parse(e.to_string()))?;
+    Ok((yaml, migration.changes))
+}
+
+fn change(path: &str, description: &str) -> Change {
+    Change {
+        path: path.to_string(),
+        description: description.to_string(),
+    }
+}
+
+// Turns a map into a list of objects with the map keys and values under the given names
+fn map_to_list(map: &Map<String, Value>, key: &str, value: &str) -> Value {
+    map.iter()
+        .map(|(k, v)| {
+            let v = match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            let mut entry = Map::new();
+            entry.insert(key.to_string(), Value::String(k.clone()));
+            entry.insert(value.to_string(), Value::String(v));
+            Value::Object(entry)
+        })
+        .collect()
+}
+
+fn migrate_metadata(metadata: &mut Value, path: &str, changes: &mut Vec<Change>) {
+    let Some(metadata) = metadata.as_object_mut() else {
+        return;
+    };
+    if let Some(desc) = metadata.remove("desc") {
+        if !metadata.contains_key("description") {
+            metadata.insert("description".to_string(), desc);
+        }
+        changes.push(change(&format!("{}.desc", path), "renamed to description"));
+    }
+    if let Some(Value::Object(labels)) = metadata.get("labels") {
+        let labels = map_to_list(labels, "key", "value");
+        metadata.insert("labels".to_string(), labels);
+        changes.push(change(
+            &format!("{}.labels", path),
+            "converted map to list of key/value pairs",
+        ));
+    }
+    for (field, default) in [
+        ("name", json!("")),
+        ("description", json!("")),
+        ("tags", json!([])),
+        ("labels", json!([])),
+    ] {
+        if !metadata.contains_key(field) {
+            metadata.insert(field.to_string(), default);
+            changes.push(change(
+                &format!("{}.{}", path, field),
+                "added missing field",
+            ));
+        }
+    }
+}
+
+fn migrate_task_spec(spec: &mut Value, path: &str, changes: &mut Vec<Change>) {
+    let Some(spec) = spec.as_object_mut() else {
+        return;
+    };
+    if let Some(Value::Object(env)) = spec.get("env") {
+        let env = map_to_list(env, "name", "value");
+        spec.insert("env".to_string(), env);
+        changes.push(change(
+            &format!("{}.env", path),
+            "converted map to list of name/value pairs",
+        ));
+    }
+    if let Some(Value::Object(inputs)) = spec.get("inputContexts") {
+        let inputs = map_to_list(inputs, "source", "target");
+        spec.insert("inputContexts".to_string(), inputs);
+        changes.push(change(
+            &format!("{}.inputContexts", path),
+            "converted map to list of source/target pairs",
+        ));
+    }
+    if let Some(Value::Array(outputs)) = spec.get_mut("outputContexts") {
+        let mut converted = false;
+        for output in outputs.iter_mut() {
+            if let Value::String(source) = output {
+                *output = json!({
+                    "source": source,
+                    "retentionPeriod": DEFAULT_RETENTION_PERIOD,
+                });
+                converted = true;
+            }
+        }
+        if converted {
+            changes.push(change(
+                &format!("{}.outputContexts", path),
+                &format!(
+                    "converted paths to outputs retained for {} seconds",
+                    DEFAULT_RETENTION_PERIOD
+                ),
+            ));
+        }
+    }
+}
+
+fn migrate_workflow_spec(spec: &mut Value, path: &str, changes: &mut Vec<Change>) {
+    let Some(Value::Array(stages)) = spec.get_mut("stages") else {
+        return;
+    };
+    for (i, stage) in stages.iter_mut().enumerate() {
+        if let Some(Value::Array(tasks)) = stage.get_mut("tasks") {
+            for (j, task) in tasks.iter_mut().enumerate() {
+                let path = format!("{}.stages[{}].tasks[{}]", path, i, j);
+                migrate_task_spec(task, &path, changes);
+            }
+        }
+    }
+}
+
+fn migrate_pin_spec(spec: &mut Value, path: &str, changes: &mut Vec<Change>) {
+    let Some(spec) = spec.as_object_mut() else {
+        return;
+    };
+    if let Some(url) = spec.remove("fallbackUrl") {
+        let urls = spec
+            .entry("fallbackUrls")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        if let (Value::Array(urls), Value::String(_)) = (urls, &url) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        changes.push(change(
+            &format!("{}.fallbackUrl", path),
+            "moved into fallbackUrls",
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Pin, Task, Workflow};
+
+    #[test]
+    fn test_migrate_task() {
+        let migration = migrate(json!({
+            "kind": "Task",
+            "metadata": {"name": "prove", "desc": "Proves a block", "labels": {"team": "zk"}},
+            "spec": {
+                "image": "prover:latest",
+                "env": {"LOG": "debug"},
+                "inputContexts": {"cid1": "/input"},
+                "outputContexts": ["/output"],
+                "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}
+            }
+        }))
+        .unwrap();
+        let paths: Vec<_> = migration.changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "version",
+                "metadata.desc",
+                "metadata.labels",
+                "metadata.tags",
+                "spec.env",
+                "spec.inputContexts",
+                "spec.outputContexts",
+            ]
+        );
+
+        let task: Task = serde_json::from_value(migration.document).unwrap();
+        assert_eq!(task.version, CURRENT_VERSION);
+        assert_eq!(task.metadata.description, "Proves a block");
+        assert_eq!(task.metadata.labels[0].key, "team");
+        assert_eq!(task.spec.env[0].name, "LOG");
+        assert_eq!(task.spec.input_contexts[0].target, "/input");
+        assert_eq!(
+            task.spec.output_contexts[0].retention_period,
+            DEFAULT_RETENTION_PERIOD
+        );
+
+        // Migrating again changes nothing
+        let document = serde_json::to_value(&task).unwrap();
+        assert!(!migrate(document).unwrap().is_changed());
+    }
+
+    #[test]
+    fn test_migrate_workflow_and_pin() {
+        let migration = migrate(json!({
+            "kind": "Workflow",
+            "version": "v0",
+            "spec": {"stages": [{"tasks": [{
+                "image": "alpine",
+                "env": {"A": "1"},
+                "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}
+            }]}]}
+        }))
+        .unwrap();
+        assert_eq!(
+            migration.changes[0].to_string(),
+            "spec.stages[0].tasks[0].env: converted map to list of name/value pairs"
+        );
+        serde_json::from_value::<Workflow>(migration.document).unwrap();
+
+        let (yaml, changes) = migrate_yaml(
+            "kind: Pin\nversion: v0\nspec:\n  bytes: 1024\n  time: 3600\n  fallbackUrl: https://example.com/a\n",
+        )
+        .unwrap();
+        assert_eq!(changes.len(), 1);
+        let pin: Pin = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(
+            pin.spec.fallback_urls,
+            Some(vec!["https://example.com/a".to_string()])
+        );
+
+        assert!(migrate(json!({"kind": "Task", "version": "v2"})).is_err());
+        assert!(migrate(json!({"kind": "Unknown", "version": "v0"})).is_err());
+    }
+}
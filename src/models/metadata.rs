@@ -25,6 +25,7 @@ use serde::{Deserialize, Serialize};
 /// };
 /// ```
 #[derive(Serialize, Deserialize, Debug, Default)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Metadata {
     /// Unique identifier for the resource
     pub id: Option<String>,
@@ -39,10 +40,49 @@ pub struct Metadata {
     /// List of key-value labels
     pub labels: Vec<Label>,
     /// Reference to a parent workflow (only used in TaskMetadata)
-    #[serde(rename = "workflowRef")]
+    #[serde(rename = "workflowRef", alias = "workflow_ref")]
     pub workflow_ref: Option<String>,
 }
 
+impl Metadata {
+    /// Converts protobuf metadata, moving the fields out of it instead of copying them.
+    pub(crate) fn from_proto(
+        proto: Option<gevulot::Metadata>,
+        workflow_ref: Option<String>,
+    ) -> Self {
+        match proto {
+            Some(proto) => Metadata {
+                id: Some(proto.id),
+                name: proto.name,
+                creator: Some(proto.creator),
+                description: proto.desc,
+                tags: proto.tags,
+                labels: proto.labels.into_iter().map(Label::from).collect(),
+                workflow_ref,
+            },
+            None => Metadata {
+                workflow_ref,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<Metadata> for gevulot::Metadata {
+    /// Converts metadata into protobuf metadata. The workflow reference is part of the task
+    /// spec in protobuf messages and is dropped here.
+    fn from(metadata: Metadata) -> Self {
+        gevulot::Metadata {
+            id: metadata.id.unwrap_or_default(),
+            creator: metadata.creator.unwrap_or_default(),
+            name: metadata.name,
+            desc: metadata.description,
+            tags: metadata.tags,
+            labels: metadata.labels.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 /// Label represents a key-value pair used for resource classification and filtering.
 ///
 /// # Examples
@@ -55,7 +95,8 @@ pub struct Metadata {
 ///     value: "production".to_string()
 /// };
 /// ```
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Label {
     /// The label key
     pub key: String,
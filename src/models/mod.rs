@@ -1,7 +1,14 @@
+//! Declarative models of tasks, workers, pins and workflows, as written in manifests.
+//!
+//! Field names are camelCase, e.g. `inputContexts` and `storeStdout`, and models are always
+//! serialized that way. When parsing, the snake_case spelling of every field, e.g.
+//! `input_contexts`, is accepted as well.
+
 use serde::{Deserialize, Serialize};
 
 mod serialization_helpers;
 use serialization_helpers::*;
+pub use serialization_helpers::{ByteUnit, CoreUnit, TimeUnit};
 
 mod metadata;
 pub use metadata::{Label, Metadata};
@@ -9,16 +16,42 @@ pub use metadata::{Label, Metadata};
 mod task;
 pub use task::{InputContext, OutputContext, Task, TaskEnv, TaskResources, TaskSpec, TaskStatus};
 
+mod resources;
+pub use resources::ResourceVector;
+
+mod validation;
+pub use validation::{TaskValidator, ValidationError, DEFAULT_MIN_MEMORY};
+
 mod worker;
-pub use worker::{Worker, WorkerSpec, WorkerStatus};
+pub use worker::{
+    MaintenanceWindow, Worker, WorkerSpec, WorkerStatus, MAINTENANCE_END_LABEL,
+    MAINTENANCE_START_LABEL,
+};
 
 mod pin;
 pub use pin::{Pin, PinAck, PinSpec, PinStatus};
 
 mod workflow;
-pub use workflow::{Workflow, WorkflowSpec, WorkflowStage, WorkflowStageStatus, WorkflowStatus};
+pub use workflow::{
+    ResourcePrices, RetryPolicy, StagePreview, StageProgress, SubWorkflow, TaskPreview, Workflow,
+    WorkflowPreview, WorkflowProgress, WorkflowSpec, WorkflowStage, WorkflowStageStatus,
+    WorkflowStatus,
+};
+
+/// Checks the kind of a model before converting it into a protobuf message.
+fn check_kind(expected: &str, kind: &str) -> crate::error::Result<()> {
+    if kind == expected {
+        Ok(())
+    } else {
+        Err(crate::error::Error::Parse(format!(
+            "expected kind {}, got {}",
+            expected, kind
+        )))
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Generic {
     pub kind: String,
     pub version: String,
@@ -26,3 +59,181 @@ pub struct Generic {
     pub spec: serde_json::Value,
     pub status: Option<serde_json::Value>,
 }
+
+#[cfg(test)]
+mod tests {
+    use serde::de::DeserializeOwned;
+    use serde_json::json;
+
+    use super::*;
+
+    // Serializes a parsed manifest and parses it again, the second serialization has to match
+    // the first one exactly, in JSON as well as in YAML
+    fn assert_round_trip<T: Serialize + DeserializeOwned>(manifest: serde_json::Value) {
+        let model = serde_json::from_value::<T>(manifest).unwrap();
+        let first = serde_json::to_value(&model).unwrap();
+        let second = serde_json::to_value(serde_json::from_value::<T>(first.clone()).unwrap());
+        assert_eq!(first, second.unwrap());
+
+        let yaml = serde_yaml::to_string(&model).unwrap();
+        let second = serde_json::to_value(serde_yaml::from_str::<T>(&yaml).unwrap());
+        assert_eq!(first, second.unwrap());
+    }
+
+    fn metadata() -> serde_json::Value {
+        json!({
+            "id": "id1",
+            "name": "name",
+            "creator": "creator",
+            "description": "description",
+            "tags": ["tag"],
+            "labels": [{"key": "key", "value": "value"}],
+            "workflowRef": null
+        })
+    }
+
+    fn task_spec() -> serde_json::Value {
+        json!({
+            "image": "prover:latest",
+            "command": ["prove"],
+            "args": ["--fast"],
+            "env": [{"name": "LOG", "value": "debug"}],
+            "inputContexts": [{"source": "cid1", "target": "/input"}],
+            "outputContexts": [{"source": "/output", "retentionPeriod": 3600}],
+            "resources": {"cpus": "500mcpu", "gpus": 1, "memory": "4gb", "time": "2h"},
+            "storeStdout": true,
+            "storeStderr": false
+        })
+    }
+
+    #[test]
+    fn test_task_round_trip() {
+        assert_round_trip::<Task>(json!({
+            "kind": "Task",
+            "version": "v0",
+            "metadata": metadata(),
+            "spec": task_spec(),
+            "status": {
+                "state": "Done",
+                "createdAt": 1,
+                "startedAt": 2,
+                "completedAt": 3,
+                "assignedWorkers": ["w1"],
+                "activeWorker": "w1",
+                "exitCode": 0,
+                "outputContexts": ["cid2"],
+                "stdout": "ok",
+                "stderr": null,
+                "error": null
+            }
+        }));
+        assert_round_trip::<Task>(json!({
+            "kind": "Task",
+            "version": "v0",
+            "spec": {
+                "image": "alpine",
+                "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}
+            }
+        }));
+    }
+
+    #[test]
+    fn test_worker_round_trip() {
+        assert_round_trip::<Worker>(json!({
+            "kind": "Worker",
+            "version": "v0",
+            "metadata": metadata(),
+            "spec": {"cpus": "8 cores", "gpus": 0, "memory": "16GiB", "disk": 100000},
+            "status": {
+                "cpusUsed": "1500mcores",
+                "gpusUsed": 0,
+                "memoryUsed": "1GiB",
+                "diskUsed": 0,
+                "exitAnnouncedAt": 0
+            }
+        }));
+    }
+
+    #[test]
+    fn test_pin_round_trip() {
+        assert_round_trip::<Pin>(json!({
+            "kind": "Pin",
+            "version": "v0",
+            "metadata": metadata(),
+            "spec": {
+                "cid": "cid1",
+                "bytes": "1GB",
+                "time": "24h",
+                "redundancy": 3,
+                "fallbackUrls": ["https://example.com/data.bin"]
+            },
+            "status": {
+                "assignedWorkers": ["w1", "w2"],
+                "workerAcks": [
+                    {"worker": "w1", "blockHeight": 10, "success": true, "error": null},
+                    {"worker": "w2", "blockHeight": 11, "success": false, "error": "fetch failed"}
+                ],
+                "cid": "cid1"
+            }
+        }));
+        assert_round_trip::<Pin>(json!({
+            "kind": "Pin",
+            "version": "v0",
+            "spec": {"bytes": 1024, "time": 3600, "fallbackUrls": ["https://example.com/a"]}
+        }));
+    }
+
+    #[test]
+    fn test_workflow_round_trip() {
+        assert_round_trip::<Workflow>(json!({
+            "kind": "Workflow",
+            "version": "v0",
+            "metadata": metadata(),
+            "spec": {
+                "stages": [
+                    {"tasks": [task_spec()]},
+                    {"tasks": [], "workflows": [{"id": "wf1"}]}
+                ]
+            },
+            "status": {
+                "state": "Running",
+                "currentStage": 1,
+                "stages": [{"taskIds": ["t1"], "finishedTasks": 1}]
+            }
+        }));
+    }
+
+    #[test]
+    fn test_snake_case_aliases() {
+        let spec = serde_json::from_value::<TaskSpec>(json!({
+            "image": "prover:latest",
+            "input_contexts": [{"source": "cid1", "target": "/input"}],
+            "output_contexts": [{"source": "/output", "retention_period": 3600}],
+            "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60},
+            "store_stdout": true
+        }))
+        .unwrap();
+        assert_eq!(spec.input_contexts[0].source, "cid1");
+        assert_eq!(spec.output_contexts[0].retention_period, 3600);
+        assert!(spec.store_stdout);
+
+        let value = serde_json::to_value(&spec).unwrap();
+        assert_eq!(value["inputContexts"][0]["target"], "/input");
+        assert_eq!(value["outputContexts"][0]["retentionPeriod"], 3600);
+        assert_eq!(value["storeStdout"], true);
+        assert!(value.get("store_stdout").is_none());
+    }
+
+    #[test]
+    fn test_unknown_fields() {
+        let mut spec = task_spec();
+        spec["storeStdOut"] = json!(true);
+        let result = serde_json::from_value::<TaskSpec>(spec);
+        if cfg!(feature = "strict-models") {
+            let err = result.unwrap_err().to_string();
+            assert!(err.contains("unknown field `storeStdOut`"), "{}", err);
+        } else {
+            assert!(result.is_ok());
+        }
+    }
+}
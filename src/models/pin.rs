@@ -5,7 +5,9 @@
 //! data either by CID or fallback URLs.
 
 use super::{
-    metadata::{Label, Metadata},
+    check_kind,
+    metadata::Metadata,
+    serialization_helpers::unsigned,
     serialization_helpers::{ByteUnit, DefaultFactorOne, TimeUnit},
 };
 use crate::proto::gevulot::gevulot;
@@ -65,6 +67,7 @@ use serde::{Deserialize, Serialize};
 /// };
 /// ```
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Pin {
     pub kind: String,
     pub version: String,
@@ -85,37 +88,7 @@ impl From<gevulot::Pin> for Pin {
         Pin {
             kind: "Pin".to_string(),
             version: "v0".to_string(),
-            metadata: Metadata {
-                id: proto.metadata.as_ref().map(|m| m.id.clone()),
-                name: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.name.clone())
-                    .unwrap_or_default(),
-                creator: proto.metadata.as_ref().map(|m| m.creator.clone()),
-                description: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.desc.clone())
-                    .unwrap_or_default(),
-                tags: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.tags.clone())
-                    .unwrap_or_default(),
-                labels: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.labels.clone())
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|l| Label {
-                        key: l.key,
-                        value: l.value,
-                    })
-                    .collect(),
-                workflow_ref: None,
-            },
+            metadata: Metadata::from_proto(proto.metadata, None),
             status: proto.status.map(|s| s.into()),
             spec,
         }
@@ -147,7 +120,7 @@ pub struct PinSpec {
     pub bytes: ByteUnit<DefaultFactorOne>,
     pub time: TimeUnit,
     pub redundancy: i64,
-    #[serde(rename = "fallbackUrls", default)]
+    #[serde(rename = "fallbackUrls", alias = "fallback_urls", default)]
     pub fallback_urls: Option<Vec<String>>,
 }
 
@@ -158,13 +131,14 @@ impl<'de> Deserialize<'de> for PinSpec {
     {
         // Create an intermediate struct for initial deserialization
         #[derive(Deserialize)]
+        #[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
         struct PinSpecHelper {
             #[serde(default)]
             cid: Option<String>,
             bytes: ByteUnit,
             time: TimeUnit,
             redundancy: Option<i64>,
-            #[serde(rename = "fallbackUrls", default)]
+            #[serde(rename = "fallbackUrls", alias = "fallback_urls", default)]
             fallback_urls: Option<Vec<String>>,
         }
 
@@ -205,8 +179,8 @@ impl From<gevulot::PinSpec> for PinSpec {
     fn from(proto: gevulot::PinSpec) -> Self {
         PinSpec {
             cid: None,
-            bytes: (proto.bytes as i64).into(),
-            time: (proto.time as i64).into(),
+            bytes: ByteUnit::from_bytes(proto.bytes),
+            time: TimeUnit::from_seconds(proto.time),
             redundancy: proto.redundancy as i64,
             fallback_urls: Some(proto.fallback_urls),
         }
@@ -236,10 +210,11 @@ impl From<gevulot::PinSpec> for PinSpec {
 /// };
 /// ```
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct PinStatus {
-    #[serde(rename = "assignedWorkers", default)]
+    #[serde(rename = "assignedWorkers", alias = "assigned_workers", default)]
     pub assigned_workers: Vec<String>,
-    #[serde(rename = "workerAcks", default)]
+    #[serde(rename = "workerAcks", alias = "worker_acks", default)]
     pub worker_acks: Vec<PinAck>,
     pub cid: Option<String>,
 }
@@ -248,20 +223,7 @@ impl From<gevulot::PinStatus> for PinStatus {
     fn from(proto: gevulot::PinStatus) -> Self {
         PinStatus {
             assigned_workers: proto.assigned_workers,
-            worker_acks: proto
-                .worker_acks
-                .into_iter()
-                .map(|a| PinAck {
-                    worker: a.worker,
-                    block_height: a.block_height as i64,
-                    success: a.success,
-                    error: if a.error.is_empty() {
-                        None
-                    } else {
-                        Some(a.error)
-                    },
-                })
-                .collect(),
+            worker_acks: proto.worker_acks.into_iter().map(PinAck::from).collect(),
             cid: Some(proto.cid),
         }
     }
@@ -283,15 +245,92 @@ impl From<gevulot::PinStatus> for PinStatus {
 ///     error: None,
 /// };
 /// ```
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct PinAck {
     pub worker: String,
-    #[serde(rename = "blockHeight")]
+    #[serde(rename = "blockHeight", alias = "block_height")]
     pub block_height: i64,
     pub success: bool,
     pub error: Option<String>,
 }
 
+impl From<gevulot::PinAck> for PinAck {
+    fn from(proto: gevulot::PinAck) -> Self {
+        PinAck {
+            worker: proto.worker,
+            block_height: proto.block_height as i64,
+            success: proto.success,
+            error: if proto.error.is_empty() {
+                None
+            } else {
+                Some(proto.error)
+            },
+        }
+    }
+}
+
+impl TryFrom<Pin> for gevulot::Pin {
+    type Error = crate::error::Error;
+
+    fn try_from(pin: Pin) -> Result<Self, Self::Error> {
+        check_kind("Pin", &pin.kind)?;
+        let mut metadata: gevulot::Metadata = pin.metadata.into();
+        // The CID doubles as the ID of a pin
+        if metadata.id.is_empty() {
+            metadata.id = pin.spec.cid.clone().unwrap_or_default();
+        }
+        let cid = pin.spec.cid.clone();
+        Ok(gevulot::Pin {
+            metadata: Some(metadata),
+            spec: Some(pin.spec.try_into()?),
+            status: pin
+                .status
+                .map(|mut status| {
+                    status.cid = status.cid.or(cid);
+                    status.try_into()
+                })
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<PinSpec> for gevulot::PinSpec {
+    type Error = crate::error::Error;
+
+    fn try_from(spec: PinSpec) -> Result<Self, Self::Error> {
+        Ok(gevulot::PinSpec {
+            bytes: unsigned("bytes", spec.bytes.bytes())?,
+            time: unsigned("time", spec.time.seconds())?,
+            redundancy: unsigned("redundancy", Ok(spec.redundancy))?,
+            fallback_urls: spec.fallback_urls.unwrap_or_default(),
+        })
+    }
+}
+
+impl TryFrom<PinStatus> for gevulot::PinStatus {
+    type Error = crate::error::Error;
+
+    fn try_from(status: PinStatus) -> Result<Self, Self::Error> {
+        Ok(gevulot::PinStatus {
+            assigned_workers: status.assigned_workers,
+            worker_acks: status
+                .worker_acks
+                .into_iter()
+                .map(|ack| {
+                    Ok(gevulot::PinAck {
+                        worker: ack.worker,
+                        block_height: unsigned("blockHeight", Ok(ack.block_height))?,
+                        success: ack.success,
+                        error: ack.error.unwrap_or_default(),
+                    })
+                })
+                .collect::<Result<_, Self::Error>>()?,
+            cid: status.cid.unwrap_or_default(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -400,6 +439,42 @@ mod tests {
         assert_eq!(pin.spec.fallback_urls, None);
     }
 
+    #[test]
+    fn test_pin_into_proto() {
+        let pin = serde_json::from_value::<Pin>(json!({
+            "kind": "Pin",
+            "version": "v0",
+            "spec": {
+                "cid": "test-cid",
+                "bytes": "1234KiB",
+                "time": "24h",
+                "redundancy": 3,
+            },
+            "status": {
+                "workerAcks": [{"worker": "worker1", "blockHeight": 10, "success": true}]
+            }
+        }))
+        .unwrap();
+
+        let proto = gevulot::Pin::try_from(pin).unwrap();
+        assert_eq!(proto.metadata.unwrap().id, "test-cid");
+        let spec = proto.spec.unwrap();
+        assert_eq!(spec.bytes, 1234 * 1024);
+        assert_eq!(spec.time, 24 * 60 * 60);
+        assert_eq!(spec.redundancy, 3);
+        let status = proto.status.unwrap();
+        assert_eq!(status.cid, "test-cid");
+        assert_eq!(status.worker_acks[0].block_height, 10);
+
+        let pin = serde_json::from_value::<Pin>(json!({
+            "kind": "Task",
+            "version": "v0",
+            "spec": {"cid": "test-cid", "bytes": 1, "time": 1}
+        }))
+        .unwrap();
+        assert!(gevulot::Pin::try_from(pin).is_err());
+    }
+
     #[test]
     fn test_pin_requires_cid_or_fallback_urls() {
         // Should fail without either cid or fallback_urls
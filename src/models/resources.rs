@@ -0,0 +1,189 @@
+//! Resource vectors for capacity planning.
+//!
+//! A [`ResourceVector`] holds the resources of a task or worker with all units resolved, so
+//! schedulers and worker implementations can add up requests and compare them against the
+//! capacity of a worker.
+
+use serde::{Deserialize, Serialize};
+
+use super::{unsigned, TaskResources, TaskSpec, Worker, WorkerSpec, WorkerStatus};
+
+/// Resources with resolved units: cpus and gpus in millicores, memory and disk in bytes and
+/// time in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct ResourceVector {
+    pub cpus: u64,
+    pub gpus: u64,
+    pub memory: u64,
+    pub disk: u64,
+    pub time: u64,
+}
+
+impl ResourceVector {
+    /// Returns the resources requested by a task, tasks request no disk.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a resource unit of the task is invalid.
+    pub fn from_task(spec: &TaskSpec) -> crate::error::Result<Self> {
+        Self::from_resources(&spec.resources)
+    }
+
+    /// Returns the resources of a task resource request, tasks request no disk.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a resource unit is invalid.
+    pub fn from_resources(resources: &TaskResources) -> crate::error::Result<Self> {
+        Ok(Self {
+            cpus: unsigned("cpus", resources.cpus.millicores())?,
+            gpus: unsigned("gpus", resources.gpus.millicores())?,
+            memory: unsigned("memory", resources.memory.bytes())?,
+            disk: 0,
+            time: unsigned("time", resources.time.seconds())?,
+        })
+    }
+
+    /// Returns the capacity of a worker, workers have no time limit.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a resource unit of the worker is invalid.
+    pub fn from_worker(spec: &WorkerSpec) -> crate::error::Result<Self> {
+        Ok(Self {
+            cpus: unsigned("cpus", spec.cpus.millicores())?,
+            gpus: unsigned("gpus", spec.gpus.millicores())?,
+            memory: unsigned("memory", spec.memory.bytes())?,
+            disk: unsigned("disk", spec.disk.bytes())?,
+            time: 0,
+        })
+    }
+
+    /// Returns the resources a worker currently uses.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a resource unit of the status is invalid.
+    pub fn from_worker_usage(status: &WorkerStatus) -> crate::error::Result<Self> {
+        Ok(Self {
+            cpus: unsigned("cpusUsed", status.cpus_used.millicores())?,
+            gpus: unsigned("gpusUsed", status.gpus_used.millicores())?,
+            memory: unsigned("memoryUsed", status.memory_used.bytes())?,
+            disk: unsigned("diskUsed", status.disk_used.bytes())?,
+            time: 0,
+        })
+    }
+
+    /// Returns the resources of a worker not in use, workers without a status are unused.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a resource unit of the worker is invalid.
+    pub fn from_worker_free(worker: &Worker) -> crate::error::Result<Self> {
+        let capacity = Self::from_worker(&worker.spec)?;
+        match &worker.status {
+            Some(status) => Ok(capacity.saturating_sub(&Self::from_worker_usage(status)?)),
+            None => Ok(capacity),
+        }
+    }
+
+    /// Adds up resource vectors, saturating at the largest value.
+    pub fn sum<'a>(vectors: impl IntoIterator<Item = &'a ResourceVector>) -> Self {
+        vectors
+            .into_iter()
+            .fold(Self::default(), |sum, vector| sum.saturating_add(vector))
+    }
+
+    /// Adds two resource vectors, saturating at the largest value.
+    pub fn saturating_add(&self, other: &Self) -> Self {
+        Self {
+            cpus: self.cpus.saturating_add(other.cpus),
+            gpus: self.gpus.saturating_add(other.gpus),
+            memory: self.memory.saturating_add(other.memory),
+            disk: self.disk.saturating_add(other.disk),
+            time: self.time.saturating_add(other.time),
+        }
+    }
+
+    /// Subtracts a resource vector, saturating at zero.
+    pub fn saturating_sub(&self, other: &Self) -> Self {
+        Self {
+            cpus: self.cpus.saturating_sub(other.cpus),
+            gpus: self.gpus.saturating_sub(other.gpus),
+            memory: self.memory.saturating_sub(other.memory),
+            disk: self.disk.saturating_sub(other.disk),
+            time: self.time.saturating_sub(other.time),
+        }
+    }
+
+    /// Subtracts a resource vector, None if any resource would drop below zero.
+    pub fn subtract(&self, other: &Self) -> Option<Self> {
+        Some(Self {
+            cpus: self.cpus.checked_sub(other.cpus)?,
+            gpus: self.gpus.checked_sub(other.gpus)?,
+            memory: self.memory.checked_sub(other.memory)?,
+            disk: self.disk.checked_sub(other.disk)?,
+            time: self.time.checked_sub(other.time)?,
+        })
+    }
+
+    /// Returns true if no resource exceeds the one of the other vector, time is ignored.
+    pub fn fits_within_vector(&self, capacity: &Self) -> bool {
+        self.cpus <= capacity.cpus
+            && self.gpus <= capacity.gpus
+            && self.memory <= capacity.memory
+            && self.disk <= capacity.disk
+    }
+
+    /// Returns true if the resources fit within the capacity of a worker.
+    ///
+    /// Returns false if a resource unit of the worker is invalid.
+    pub fn fits_within(&self, worker: &WorkerSpec) -> bool {
+        Self::from_worker(worker).is_ok_and(|capacity| self.fits_within_vector(&capacity))
+    }
+}
+
+impl std::iter::Sum for ResourceVector {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |sum, vector| sum.saturating_add(&vector))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_capacity_planning() {
+        let task = serde_json::from_value::<TaskSpec>(json!({
+            "image": "prover:latest",
+            "resources": {"cpus": "1500mcpu", "gpus": 0, "memory": "4GiB", "time": "1h"}
+        }))
+        .unwrap();
+        let worker = serde_json::from_value::<WorkerSpec>(json!({
+            "cpus": 4, "gpus": 1, "memory": "16GiB", "disk": "100GiB"
+        }))
+        .unwrap();
+
+        let task = ResourceVector::from_task(&task).unwrap();
+        assert_eq!(task.cpus, 1500);
+        assert_eq!(task.time, 3600);
+
+        let two = ResourceVector::sum([&task, &task]);
+        assert_eq!(two.cpus, 3000);
+        assert_eq!(two.memory, 8 * 1024 * 1024 * 1024);
+        assert!(two.fits_within(&worker));
+        let three: ResourceVector = [task; 3].into_iter().sum();
+        assert!(!three.fits_within(&worker));
+
+        let capacity = ResourceVector::from_worker(&worker).unwrap();
+        let free = capacity
+            .subtract(&ResourceVector { time: 0, ..two })
+            .unwrap();
+        assert_eq!(free.cpus, 1000);
+        assert_eq!(free.gpus, 1000);
+        assert!(free.subtract(&two).is_none());
+    }
+}
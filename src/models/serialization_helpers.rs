@@ -50,6 +50,10 @@ impl DefaultFactor for DefaultFactorOneGigabyte {
 /// - String representations (e.g. "500MB", "1.5GB")
 /// - Default factor based on type parameter
 ///
+/// Numbers are multiplied by the default factor, so they serialize with the unit of the factor
+/// (e.g. `1` with a factor of 1MiB as `"1MiB"`) to read back as the same size with any factor.
+/// With the default factor of 1 they stay plain numbers.
+///
 /// # Examples
 ///
 /// ```rust
@@ -63,7 +67,7 @@ impl DefaultFactor for DefaultFactorOneGigabyte {
 /// let bytes = ByteUnit::<DefaultFactorOneMegabyte>::from(1);
 /// assert_eq!(bytes.bytes().unwrap(), 1 * 1024 * 1024);
 /// ```
-#[derive(Debug, Serialize, Deserialize, Eq)]
+#[derive(Debug, Deserialize, Eq)]
 #[serde(untagged)]
 pub enum ByteUnit<D: DefaultFactor = DefaultFactorOne> {
     Number(i64),
@@ -73,6 +77,19 @@ pub enum ByteUnit<D: DefaultFactor = DefaultFactorOne> {
 }
 
 impl<D: DefaultFactor> ByteUnit<D> {
+    /// Creates a byte size from a number of bytes, e.g. the bytes of a protobuf message.
+    ///
+    /// Numbers are multiplied by the default factor, so sizes which aren't a multiple of it are
+    /// kept with an explicit byte unit.
+    pub fn from_bytes(bytes: u64) -> Self {
+        let bytes = saturating_i64(bytes);
+        if bytes % D::FACTOR == 0 {
+            ByteUnit::Number(bytes / D::FACTOR)
+        } else {
+            ByteUnit::String(format!("{}B", bytes))
+        }
+    }
+
     /// Convert to number of bytes
     pub fn bytes(&self) -> Result<i64, String> {
         match self {
@@ -88,6 +105,33 @@ impl<D: DefaultFactor> ByteUnit<D> {
     }
 }
 
+impl<D: DefaultFactor> Serialize for ByteUnit<D> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let number = match self {
+            ByteUnit::Number(n) => *n,
+            // Plain digits are multiplied by the factor like numbers
+            ByteUnit::String(s) => match s.parse::<i64>() {
+                Ok(n) if s.chars().all(|c| c.is_ascii_digit()) => n,
+                _ => return serializer.serialize_str(s),
+            },
+            ByteUnit::Factor(_) => 1,
+        };
+        match D::FACTOR {
+            DefaultFactorOne::FACTOR => serializer.serialize_i64(number),
+            DefaultFactorOneKilobyte::FACTOR => {
+                serializer.collect_str(&format_args!("{}KiB", number))
+            }
+            DefaultFactorOneMegabyte::FACTOR => {
+                serializer.collect_str(&format_args!("{}MiB", number))
+            }
+            DefaultFactorOneGigabyte::FACTOR => {
+                serializer.collect_str(&format_args!("{}GiB", number))
+            }
+            factor => serializer.collect_str(&format_args!("{}B", number.saturating_mul(factor))),
+        }
+    }
+}
+
 impl<D: DefaultFactor> PartialEq for ByteUnit<D> {
     fn eq(&self, other: &Self) -> bool {
         self.bytes() == other.bytes()
@@ -138,6 +182,16 @@ pub enum CoreUnit {
 }
 
 impl CoreUnit {
+    /// Creates a core count from millicores, e.g. the cpus or gpus of a protobuf message.
+    pub fn from_millicores(millicores: u64) -> Self {
+        let millicores = saturating_i64(millicores);
+        if millicores % 1000 == 0 {
+            CoreUnit::Number(millicores / 1000)
+        } else {
+            CoreUnit::String(format!("{}mcores", millicores))
+        }
+    }
+
     /// Convert to millicores (1 core = 1000 millicores)
     pub fn millicores(&self) -> Result<i64, String> {
         match self {
@@ -218,6 +272,11 @@ pub enum TimeUnit {
 }
 
 impl TimeUnit {
+    /// Creates a duration from seconds, e.g. the time of a protobuf message.
+    pub fn from_seconds(seconds: u64) -> Self {
+        TimeUnit::Number(saturating_i64(seconds))
+    }
+
     /// Convert to seconds
     pub fn seconds(&self) -> Result<i64, String> {
         match self {
@@ -254,6 +313,132 @@ impl From<i64> for TimeUnit {
     }
 }
 
+impl<D: DefaultFactor> std::fmt::Display for ByteUnit<D> {
+    /// Formats the size with binary units, e.g. `1.5 GiB`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.bytes(), self) {
+            (Ok(bytes), _) if bytes >= 0 => f.write_str(&ByteSize(bytes as u64).to_string_as(true)),
+            (Ok(bytes), _) => write!(f, "{} B", bytes),
+            (Err(_), ByteUnit::String(s)) => f.write_str(s),
+            (Err(e), _) => f.write_str(&e),
+        }
+    }
+}
+
+impl std::fmt::Display for CoreUnit {
+    /// Formats the core count in cores, e.g. `2 cores` or `1.5 cores`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.millicores(), self) {
+            (Ok(1000), _) => f.write_str("1 core"),
+            (Ok(millicores), _) if millicores % 1000 == 0 => {
+                write!(f, "{} cores", millicores / 1000)
+            }
+            (Ok(millicores), _) => write!(f, "{} cores", millicores as f64 / 1000.0),
+            (Err(_), CoreUnit::String(s)) => f.write_str(s),
+            (Err(e), _) => f.write_str(&e),
+        }
+    }
+}
+
+impl std::fmt::Display for TimeUnit {
+    /// Formats the duration in human readable form, e.g. `1h 30m`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.seconds(), self) {
+            (Ok(seconds), _) if seconds >= 0 => write!(
+                f,
+                "{}",
+                humantime::format_duration(std::time::Duration::from_secs(seconds as u64))
+            ),
+            (Ok(seconds), _) => write!(f, "{}s", seconds),
+            (Err(_), TimeUnit::String(s)) => f.write_str(s),
+            (Err(e), _) => f.write_str(&e),
+        }
+    }
+}
+
+/// Implements ordering and arithmetic on the normalized value of a unit type.
+///
+/// Checked operations return None on overflow, on results below zero and for values which
+/// can't be resolved. Saturating operations and the `Add` and `Sub` operators treat such values
+/// as zero and clamp the result to the valid range.
+macro_rules! unit_arithmetic {
+    ($ty:ty, [$($generics:tt)*], $value:ident, $from:ident) => {
+        impl<$($generics)*> $ty {
+            /// Adds two values, None on overflow or if a value is invalid.
+            pub fn checked_add(&self, other: &Self) -> Option<Self> {
+                let sum = self.$value().ok()?.checked_add(other.$value().ok()?)?;
+                Some(Self::$from(u64::try_from(sum).ok()?))
+            }
+
+            /// Subtracts a value, None if the result is negative or a value is invalid.
+            pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+                let difference = self.$value().ok()?.checked_sub(other.$value().ok()?)?;
+                Some(Self::$from(u64::try_from(difference).ok()?))
+            }
+
+            /// Adds two values, saturating at the largest value.
+            pub fn saturating_add(&self, other: &Self) -> Self {
+                Self::$from(self.normalized().saturating_add(other.normalized()))
+            }
+
+            /// Subtracts a value, saturating at zero.
+            pub fn saturating_sub(&self, other: &Self) -> Self {
+                Self::$from(self.normalized().saturating_sub(other.normalized()))
+            }
+
+            // The value as u64, invalid and negative values as 0
+            fn normalized(&self) -> u64 {
+                self.$value()
+                    .ok()
+                    .and_then(|value| u64::try_from(value).ok())
+                    .unwrap_or_default()
+            }
+        }
+
+        impl<$($generics)*> PartialOrd for $ty {
+            fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+                Some(self.$value().cmp(&other.$value()))
+            }
+        }
+
+        impl<$($generics)*> std::ops::Add for $ty {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                self.saturating_add(&other)
+            }
+        }
+
+        impl<$($generics)*> std::ops::Sub for $ty {
+            type Output = Self;
+
+            fn sub(self, other: Self) -> Self {
+                self.saturating_sub(&other)
+            }
+        }
+    };
+}
+
+unit_arithmetic!(ByteUnit<D>, [D: DefaultFactor], bytes, from_bytes);
+unit_arithmetic!(CoreUnit, [], millicores, from_millicores);
+unit_arithmetic!(TimeUnit, [], seconds, from_seconds);
+
+// Protobuf messages use u64, the units i64
+fn saturating_i64(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+/// Converts a resolved unit value into the unsigned value used by the chain messages.
+///
+/// `name` is used in the error message if the value couldn't be resolved or is negative.
+pub(crate) fn unsigned(name: &str, value: Result<i64, String>) -> crate::error::Result<u64> {
+    let value =
+        value.map_err(|e| crate::error::Error::Parse(format!("invalid {}: {}", name, e)))?;
+    u64::try_from(value).map_err(|_| {
+        crate::error::Error::Parse(format!("{} must not be negative: {}", name, value))
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -364,6 +549,75 @@ mod tests {
         assert_eq!(cores.millicores().unwrap(), 2000);
     }
 
+    #[test]
+    fn test_from_proto_values() {
+        assert_eq!(CoreUnit::from_millicores(2000), CoreUnit::Number(2));
+        assert_eq!(CoreUnit::from_millicores(500).millicores().unwrap(), 500);
+
+        let memory = ByteUnit::<DefaultFactorOneMegabyte>::from_bytes(512 * 1024 * 1024);
+        assert_eq!(memory, ByteUnit::Number(512));
+        let memory = ByteUnit::<DefaultFactorOneMegabyte>::from_bytes(1500);
+        assert_eq!(memory.bytes().unwrap(), 1500);
+        assert_eq!(
+            ByteUnit::<DefaultFactorOne>::from_bytes(1500)
+                .bytes()
+                .unwrap(),
+            1500
+        );
+
+        assert_eq!(TimeUnit::from_seconds(3600).seconds().unwrap(), 3600);
+        assert_eq!(
+            TimeUnit::from_seconds(u64::MAX).seconds().unwrap(),
+            i64::MAX
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!("1.5GiB".parse::<ByteUnit>().unwrap().to_string(), "1.5 GiB");
+        assert_eq!(
+            ByteUnit::<DefaultFactorOneMegabyte>::Number(2).to_string(),
+            "2.0 MiB"
+        );
+        assert_eq!(
+            ByteUnit::<DefaultFactorOne>::Number(100).to_string(),
+            "100 B"
+        );
+        assert_eq!(CoreUnit::Number(2).to_string(), "2 cores");
+        assert_eq!(CoreUnit::Number(1).to_string(), "1 core");
+        assert_eq!(CoreUnit::from_millicores(1500).to_string(), "1.5 cores");
+        assert_eq!(TimeUnit::Number(5400).to_string(), "1h 30m");
+        assert_eq!(CoreUnit::String("lots".to_string()).to_string(), "lots");
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let small: ByteUnit<DefaultFactorOneMegabyte> = "512MiB".parse().unwrap();
+        let large = ByteUnit::<DefaultFactorOneMegabyte>::Number(1024);
+        assert!(small < large);
+        assert_eq!(
+            small.checked_add(&large).unwrap().bytes(),
+            Ok(1536 * 1024 * 1024)
+        );
+        assert!(small.checked_sub(&large).is_none());
+        assert_eq!(small.saturating_sub(&large).bytes(), Ok(0));
+        assert_eq!((large - small).bytes(), Ok(512 * 1024 * 1024));
+
+        let cores = CoreUnit::Number(2) + "500mcpu".parse().unwrap();
+        assert_eq!(cores.millicores(), Ok(2500));
+        assert!(CoreUnit::Number(3) > cores);
+        assert!(CoreUnit::String("lots".to_string())
+            .checked_add(&cores)
+            .is_none());
+
+        let time = TimeUnit::Number(i64::MAX).saturating_add(&TimeUnit::Number(1));
+        assert_eq!(time.seconds(), Ok(i64::MAX));
+        assert_eq!(
+            TimeUnit::Number(60).checked_sub(&TimeUnit::Number(30)),
+            Some(TimeUnit::Number(30))
+        );
+    }
+
     #[test]
     fn test_invalid_formats() {
         assert!("invalid".parse::<ByteUnit>().is_err());
@@ -405,6 +659,26 @@ mod tests {
         assert_eq!(json, "2");
     }
 
+    #[test]
+    fn test_factor_serialization() {
+        let memory = ByteUnit::<DefaultFactorOneMegabyte>::Number(1);
+        let json = serde_json::to_string(&memory).unwrap();
+        assert_eq!(json, "\"1MiB\"");
+        let bytes: ByteUnit = serde_json::from_str(&json).unwrap();
+        assert_eq!(bytes.bytes().unwrap(), 1024 * 1024);
+        let memory: ByteUnit<DefaultFactorOneMegabyte> = serde_json::from_str(&json).unwrap();
+        assert_eq!(memory.bytes().unwrap(), 1024 * 1024);
+
+        let disk = ByteUnit::<DefaultFactorOneGigabyte>::String("2".to_string());
+        assert_eq!(serde_json::to_string(&disk).unwrap(), "\"2GiB\"");
+        let disk = ByteUnit::<DefaultFactorOneKilobyte>::String("1.5GB".to_string());
+        assert_eq!(serde_json::to_string(&disk).unwrap(), "\"1.5GB\"");
+
+        let yaml = serde_yaml::to_string(&ByteUnit::<DefaultFactorOneKilobyte>::Number(4)).unwrap();
+        let bytes: ByteUnit = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(bytes.bytes().unwrap(), 4096);
+    }
+
     #[test]
     fn test_json_deserialization() {
         // Test ByteUnit deserialization
@@ -8,9 +8,13 @@
 //! - Metadata like tags and labels
 
 use crate::proto::gevulot::gevulot;
+use crate::runtime_config::{self, RuntimeConfig};
 use serde::{Deserialize, Serialize};
 
-use super::serialization_helpers::DefaultFactorOneMegabyte;
+use super::check_kind;
+use super::serialization_helpers::{
+    unsigned, ByteUnit, CoreUnit, DefaultFactorOneMegabyte, TimeUnit,
+};
 
 /// Represents a complete task definition with metadata, specification and status
 ///
@@ -61,6 +65,7 @@ use super::serialization_helpers::DefaultFactorOneMegabyte;
 /// }"#).unwrap();
 /// ```
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Task {
     // The kind is always "Task" - used for type identification in serialized form
     pub kind: String,
@@ -87,43 +92,30 @@ impl From<gevulot::Task> for Task {
         Task {
             kind: "Task".to_string(),
             version: "v0".to_string(),
-            metadata: crate::models::Metadata {
-                id: proto.metadata.as_ref().map(|m| m.id.clone()),
-                name: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.name.clone())
-                    .unwrap_or_default(),
-                creator: proto.metadata.as_ref().map(|m| m.creator.clone()),
-                description: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.desc.clone())
-                    .unwrap_or_default(),
-                tags: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.tags.clone())
-                    .unwrap_or_default(),
-                labels: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.labels.clone())
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|l| crate::models::Label {
-                        key: l.key,
-                        value: l.value,
-                    })
-                    .collect(),
-                workflow_ref,
-            },
+            metadata: crate::models::Metadata::from_proto(proto.metadata, workflow_ref),
             spec: proto.spec.unwrap().into(),
             status: proto.status.map(|s| s.into()),
         }
     }
 }
 
+// Conversion into protobuf Task message, validating kind, units and state
+impl TryFrom<Task> for gevulot::Task {
+    type Error = crate::error::Error;
+
+    fn try_from(task: Task) -> Result<Self, Self::Error> {
+        check_kind("Task", &task.kind)?;
+        let workflow_ref = task.metadata.workflow_ref.clone().unwrap_or_default();
+        let mut spec = gevulot::TaskSpec::try_from(task.spec)?;
+        spec.workflow_ref = workflow_ref;
+        Ok(gevulot::Task {
+            metadata: Some(task.metadata.into()),
+            spec: Some(spec),
+            status: task.status.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
 /// Task specification containing all execution parameters
 ///
 /// # Examples
@@ -143,6 +135,7 @@ impl From<gevulot::Task> for Task {
 /// }"#).unwrap();
 /// ```
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct TaskSpec {
     // Container image to run
     pub image: String,
@@ -156,24 +149,37 @@ pub struct TaskSpec {
     #[serde(default)]
     pub env: Vec<TaskEnv>,
     // Input data contexts to mount
-    #[serde(rename = "inputContexts", default)]
+    #[serde(rename = "inputContexts", alias = "input_contexts", default)]
     pub input_contexts: Vec<InputContext>,
     // Output data contexts to capture
-    #[serde(rename = "outputContexts", default)]
+    #[serde(rename = "outputContexts", alias = "output_contexts", default)]
     pub output_contexts: Vec<OutputContext>,
     // Resource requirements
     pub resources: TaskResources,
     // Whether to store stdout stream
-    #[serde(rename = "storeStdout", default)]
+    #[serde(rename = "storeStdout", alias = "store_stdout", default)]
     pub store_stdout: bool,
     // Whether to store stderr stream
-    #[serde(rename = "storeStderr", default)]
+    #[serde(rename = "storeStderr", alias = "store_stderr", default)]
     pub store_stderr: bool,
+    // Runtime configuration of the VM, sent in the task environment
+    #[serde(
+        rename = "runtimeConfig",
+        alias = "runtime_config",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub runtime_config: Option<RuntimeConfig>,
 }
 
 // Conversion from protobuf TaskSpec message
 impl From<gevulot::TaskSpec> for TaskSpec {
-    fn from(proto: gevulot::TaskSpec) -> Self {
+    fn from(mut proto: gevulot::TaskSpec) -> Self {
+        // Move a valid runtime configuration out of the environment, invalid ones stay there
+        let runtime_config = RuntimeConfig::from_task_env(&proto.env).ok().flatten();
+        if runtime_config.is_some() {
+            proto.env.retain(|e| e.name != runtime_config::TASK_ENV);
+        }
         TaskSpec {
             image: proto.image,
             command: proto.command,
@@ -203,19 +209,107 @@ impl From<gevulot::TaskSpec> for TaskSpec {
                 })
                 .collect(),
             resources: TaskResources {
-                cpus: (proto.cpus as i64).into(),
-                gpus: (proto.gpus as i64).into(),
-                memory: (proto.memory as i64).into(),
-                time: (proto.time as i64).into(),
+                cpus: CoreUnit::from_millicores(proto.cpus),
+                gpus: CoreUnit::from_millicores(proto.gpus),
+                memory: ByteUnit::from_bytes(proto.memory),
+                time: TimeUnit::from_seconds(proto.time),
             },
             store_stdout: proto.store_stdout,
             store_stderr: proto.store_stderr,
+            runtime_config,
         }
     }
 }
 
+/// Conversion of a declarative task spec into the message creating the task
+///
+/// Resolves the resource units: cpus and gpus to millicores, memory to bytes and time to
+/// seconds.
+impl TryFrom<(&str, TaskSpec)> for gevulot::MsgCreateTask {
+    type Error = crate::error::Error;
+
+    fn try_from((creator, spec): (&str, TaskSpec)) -> Result<Self, Self::Error> {
+        let spec = gevulot::TaskSpec::try_from(spec)?;
+        Ok(gevulot::MsgCreateTask {
+            creator: creator.to_string(),
+            image: spec.image,
+            command: spec.command,
+            args: spec.args,
+            env: spec.env,
+            input_contexts: spec.input_contexts,
+            output_contexts: spec.output_contexts,
+            cpus: spec.cpus,
+            gpus: spec.gpus,
+            memory: spec.memory,
+            time: spec.time,
+            store_stdout: spec.store_stdout,
+            store_stderr: spec.store_stderr,
+            tags: Vec::new(),
+            labels: Vec::new(),
+        })
+    }
+}
+
+// Conversion into protobuf TaskSpec message, resolving the resource units
+impl TryFrom<TaskSpec> for gevulot::TaskSpec {
+    type Error = crate::error::Error;
+
+    fn try_from(spec: TaskSpec) -> Result<Self, Self::Error> {
+        let runtime_config = spec
+            .runtime_config
+            .as_ref()
+            .map(|config| {
+                Ok::<_, Self::Error>(gevulot::TaskEnv {
+                    name: runtime_config::TASK_ENV.to_string(),
+                    value: config.to_env_value()?,
+                })
+            })
+            .transpose()?;
+        Ok(gevulot::TaskSpec {
+            image: spec.image,
+            command: spec.command,
+            args: spec.args,
+            env: spec
+                .env
+                .into_iter()
+                .map(|e| gevulot::TaskEnv {
+                    name: e.name,
+                    value: e.value,
+                })
+                .chain(runtime_config)
+                .collect(),
+            input_contexts: spec
+                .input_contexts
+                .into_iter()
+                .map(|ic| gevulot::InputContext {
+                    source: ic.source,
+                    target: ic.target,
+                })
+                .collect(),
+            output_contexts: spec
+                .output_contexts
+                .into_iter()
+                .map(|oc| {
+                    Ok(gevulot::OutputContext {
+                        source: oc.source,
+                        retention_period: unsigned("retentionPeriod", Ok(oc.retention_period))?,
+                    })
+                })
+                .collect::<Result<_, Self::Error>>()?,
+            cpus: unsigned("cpus", spec.resources.cpus.millicores())?,
+            gpus: unsigned("gpus", spec.resources.gpus.millicores())?,
+            memory: unsigned("memory", spec.resources.memory.bytes())?,
+            time: unsigned("time", spec.resources.time.seconds())?,
+            store_stdout: spec.store_stdout,
+            store_stderr: spec.store_stderr,
+            workflow_ref: String::new(),
+        })
+    }
+}
+
 /// Environment variable definition for task container
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct TaskEnv {
     pub name: String,
     pub value: String,
@@ -223,6 +317,7 @@ pub struct TaskEnv {
 
 /// Input context for mounting data into task container
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct InputContext {
     // Source data identifier
     pub source: String,
@@ -232,16 +327,18 @@ pub struct InputContext {
 
 /// Output context for capturing data from task container
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct OutputContext {
     // Source path in container to capture
     pub source: String,
     // How long to retain the output data
-    #[serde(rename = "retentionPeriod")]
+    #[serde(rename = "retentionPeriod", alias = "retention_period")]
     pub retention_period: i64,
 }
 
 /// Resource requirements for task execution
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct TaskResources {
     // CPU cores required (supports units like "2cpu", "500mcpu")
     pub cpus: crate::models::CoreUnit,
@@ -255,26 +352,27 @@ pub struct TaskResources {
 
 /// Runtime status of a task
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct TaskStatus {
     // Current state (Pending, Running, Done, Failed etc)
     pub state: String,
     // Timestamps for task lifecycle
-    #[serde(rename = "createdAt")]
+    #[serde(rename = "createdAt", alias = "created_at")]
     pub created_at: i64,
-    #[serde(rename = "startedAt")]
+    #[serde(rename = "startedAt", alias = "started_at")]
     pub started_at: i64,
-    #[serde(rename = "completedAt")]
+    #[serde(rename = "completedAt", alias = "completed_at")]
     pub completed_at: i64,
     // Workers assigned/active for this task
-    #[serde(rename = "assignedWorkers")]
+    #[serde(rename = "assignedWorkers", alias = "assigned_workers")]
     pub assigned_workers: Vec<String>,
-    #[serde(rename = "activeWorker")]
+    #[serde(rename = "activeWorker", alias = "active_worker")]
     pub active_worker: String,
     // Exit code if task completed
-    #[serde(rename = "exitCode")]
+    #[serde(rename = "exitCode", alias = "exit_code")]
     pub exit_code: Option<i64>,
     // Output context identifiers
-    #[serde(rename = "outputContexts")]
+    #[serde(rename = "outputContexts", alias = "output_contexts")]
     pub output_contexts: Vec<String>,
     // Captured output streams if enabled
     pub stdout: Option<String>,
@@ -336,11 +434,133 @@ impl From<gevulot::TaskStatus> for TaskStatus {
     }
 }
 
+// Conversion into protobuf TaskStatus message
+impl TryFrom<TaskStatus> for gevulot::TaskStatus {
+    type Error = crate::error::Error;
+
+    fn try_from(status: TaskStatus) -> Result<Self, Self::Error> {
+        use gevulot::task_status::State;
+
+        let state = match status.state.as_str() {
+            "Pending" => State::Pending,
+            "Running" => State::Running,
+            "Declined" => State::Declined,
+            "Done" => State::Done,
+            "Failed" => State::Failed,
+            state => {
+                return Err(crate::error::Error::Parse(format!(
+                    "invalid task state: {}",
+                    state
+                )))
+            }
+        };
+        Ok(gevulot::TaskStatus {
+            state: state.into(),
+            created_at: unsigned("createdAt", Ok(status.created_at))?,
+            started_at: unsigned("startedAt", Ok(status.started_at))?,
+            completed_at: unsigned("completedAt", Ok(status.completed_at))?,
+            assigned_workers: status.assigned_workers,
+            active_worker: status.active_worker,
+            exit_code: status.exit_code.unwrap_or_default(),
+            stdout: status.stdout.unwrap_or_default(),
+            stderr: status.stderr.unwrap_or_default(),
+            output_contexts: status.output_contexts,
+            error: status.error.unwrap_or_default(),
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use serde_json::json;
 
+    #[test]
+    fn test_task_into_proto() {
+        let task = serde_json::from_value::<Task>(json!({
+            "kind": "Task",
+            "version": "v0",
+            "metadata": {
+                "id": "task1",
+                "name": "prove",
+                "creator": "gvlt1creator",
+                "description": "",
+                "tags": ["zk"],
+                "labels": [{"key": "team", "value": "proving"}],
+                "workflowRef": "wf1"
+            },
+            "spec": {
+                "image": "prover:latest",
+                "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": "1h"}
+            },
+            "status": {
+                "state": "Done",
+                "createdAt": 1,
+                "startedAt": 2,
+                "completedAt": 3,
+                "assignedWorkers": ["worker1"],
+                "activeWorker": "worker1",
+                "exitCode": 0,
+                "outputContexts": ["cid1"],
+                "stdout": null,
+                "stderr": null,
+                "error": null
+            }
+        }))
+        .unwrap();
+
+        let proto = gevulot::Task::try_from(task).unwrap();
+        let metadata = proto.metadata.unwrap();
+        assert_eq!(metadata.id, "task1");
+        assert_eq!(metadata.labels[0].value, "proving");
+        let spec = proto.spec.unwrap();
+        assert_eq!(spec.workflow_ref, "wf1");
+        assert_eq!(spec.cpus, 1000);
+        assert_eq!(spec.memory, 512 * 1024 * 1024);
+        assert_eq!(spec.time, 3600);
+        let status = proto.status.unwrap();
+        assert_eq!(status.state(), gevulot::task_status::State::Done);
+        assert_eq!(status.output_contexts, vec!["cid1"]);
+    }
+
+    #[test]
+    fn test_task_spec_into_msg_create_task() {
+        let spec = serde_json::from_value::<TaskSpec>(json!({
+            "image": "prover:latest",
+            "env": [{"name": "RUST_LOG", "value": "info"}],
+            "inputContexts": [{"source": "cid1", "target": "/input"}],
+            "outputContexts": [{"source": "/output", "retentionPeriod": 3600}],
+            "resources": {
+                "cpus": "500mcpu",
+                "gpus": 1,
+                "memory": "2GiB",
+                "time": "1h"
+            },
+            "storeStdout": true
+        }))
+        .unwrap();
+
+        let msg = gevulot::MsgCreateTask::try_from(("gvlt1creator", spec)).unwrap();
+        assert_eq!(msg.creator, "gvlt1creator");
+        assert_eq!(msg.image, "prover:latest");
+        assert_eq!(msg.cpus, 500);
+        assert_eq!(msg.gpus, 1000);
+        assert_eq!(msg.memory, 2 * 1024 * 1024 * 1024);
+        assert_eq!(msg.time, 3600);
+        assert_eq!(msg.env[0].name, "RUST_LOG");
+        assert_eq!(msg.input_contexts[0].target, "/input");
+        assert_eq!(msg.output_contexts[0].retention_period, 3600);
+        assert!(msg.store_stdout);
+        assert!(!msg.store_stderr);
+
+        let spec = serde_json::from_value::<TaskSpec>(json!({
+            "image": "prover:latest",
+            "resources": {"cpus": 1, "gpus": 0, "memory": 1, "time": -1}
+        }))
+        .unwrap();
+        assert!(gevulot::MsgCreateTask::try_from(("gvlt1creator", spec)).is_err());
+    }
+
     #[test]
     fn test_parse_task_with_units() {
         let task = serde_json::from_value::<Task>(json!({
@@ -0,0 +1,223 @@
+//! Client-side validation of task specs.
+//!
+//! Catches specs the chain or the workers would reject anyway before any gas is spent on them.
+
+use crate::error::{Error, Result};
+use crate::proto::gevulot::gevulot;
+
+use super::TaskSpec;
+
+/// Smallest amount of memory in bytes a task may request by default.
+pub const DEFAULT_MIN_MEMORY: u64 = 1024 * 1024;
+
+/// Reason a task spec is invalid.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    #[error("image must not be empty")]
+    EmptyImage,
+    #[error("invalid {name}: {reason}")]
+    InvalidResource { name: &'static str, reason: String },
+    #[error("time must be greater than 0")]
+    ZeroTime,
+    #[error("memory of {memory} bytes is below the minimum of {minimum} bytes")]
+    MemoryBelowMinimum { memory: u64, minimum: u64 },
+    #[error("duplicate environment variable: {0}")]
+    DuplicateEnv(String),
+    #[error("input context target is not an absolute path: {0}")]
+    RelativeInputTarget(String),
+}
+
+/// Validates task specs and task creation messages.
+///
+/// # Examples
+///
+/// ```
+/// use gevulot_rs::models::TaskValidator;
+///
+/// let validator = TaskValidator {
+///     min_memory: 64 * 1024 * 1024,
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskValidator {
+    /// Smallest amount of memory in bytes a task may request.
+    pub min_memory: u64,
+}
+
+impl Default for TaskValidator {
+    fn default() -> Self {
+        Self {
+            min_memory: DEFAULT_MIN_MEMORY,
+        }
+    }
+}
+
+impl TaskValidator {
+    /// Validates a declarative task spec.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::Validation` with every problem found in the spec.
+    pub fn validate_spec(&self, spec: &TaskSpec) -> Result<()> {
+        let mut errors = Vec::new();
+        let mut resolve = |name: &'static str, value: std::result::Result<i64, String>| match value
+            .map(u64::try_from)
+        {
+            Ok(Ok(value)) => Some(value),
+            Ok(Err(_)) => {
+                errors.push(ValidationError::InvalidResource {
+                    name,
+                    reason: "must not be negative".to_string(),
+                });
+                None
+            }
+            Err(reason) => {
+                errors.push(ValidationError::InvalidResource { name, reason });
+                None
+            }
+        };
+        let time = resolve("time", spec.resources.time.seconds());
+        let memory = resolve("memory", spec.resources.memory.bytes());
+
+        errors.extend(self.check(
+            &spec.image,
+            time,
+            memory,
+            spec.env.iter().map(|e| e.name.as_str()),
+            spec.input_contexts.iter().map(|ic| ic.target.as_str()),
+        ));
+        into_result(errors)
+    }
+
+    /// Validates a task creation message.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::Validation` with every problem found in the message.
+    pub fn validate_msg(&self, msg: &gevulot::MsgCreateTask) -> Result<()> {
+        into_result(self.check(
+            &msg.image,
+            Some(msg.time),
+            Some(msg.memory),
+            msg.env.iter().map(|e| e.name.as_str()),
+            msg.input_contexts.iter().map(|ic| ic.target.as_str()),
+        ))
+    }
+
+    // Resources which couldn't be resolved are None and have been reported already
+    fn check<'a>(
+        &self,
+        image: &str,
+        time: Option<u64>,
+        memory: Option<u64>,
+        env_names: impl Iterator<Item = &'a str>,
+        input_targets: impl Iterator<Item = &'a str>,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if image.trim().is_empty() {
+            errors.push(ValidationError::EmptyImage);
+        }
+        if time == Some(0) {
+            errors.push(ValidationError::ZeroTime);
+        }
+        if let Some(memory) = memory.filter(|memory| *memory < self.min_memory) {
+            errors.push(ValidationError::MemoryBelowMinimum {
+                memory,
+                minimum: self.min_memory,
+            });
+        }
+        let mut seen = std::collections::HashSet::new();
+        for name in env_names {
+            if !seen.insert(name) {
+                errors.push(ValidationError::DuplicateEnv(name.to_string()));
+            }
+        }
+        for target in input_targets {
+            if !target.starts_with('/') {
+                errors.push(ValidationError::RelativeInputTarget(target.to_string()));
+            }
+        }
+        errors
+    }
+}
+
+fn into_result(errors: Vec<ValidationError>) -> Result<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Validation(errors))
+    }
+}
+
+impl TaskSpec {
+    /// Validates the spec with the default [`TaskValidator`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::Validation` with every problem found in the spec.
+    pub fn validate(&self) -> Result<()> {
+        TaskValidator::default().validate_spec(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_spec() {
+        let spec = serde_json::from_value::<TaskSpec>(json!({
+            "image": "prover:latest",
+            "env": [{"name": "A", "value": "1"}],
+            "inputContexts": [{"source": "cid1", "target": "/input"}],
+            "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": "1h"}
+        }))
+        .unwrap();
+        assert!(spec.validate().is_ok());
+
+        let spec = serde_json::from_value::<TaskSpec>(json!({
+            "image": " ",
+            "env": [{"name": "A", "value": "1"}, {"name": "A", "value": "2"}],
+            "inputContexts": [{"source": "cid1", "target": "input"}],
+            "resources": {"cpus": 1, "gpus": 0, "memory": "1kib", "time": -5}
+        }))
+        .unwrap();
+        let Err(Error::Validation(errors)) = spec.validate() else {
+            panic!("spec should be invalid");
+        };
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::InvalidResource {
+                    name: "time",
+                    reason: "must not be negative".to_string(),
+                },
+                ValidationError::EmptyImage,
+                ValidationError::MemoryBelowMinimum {
+                    memory: 1024,
+                    minimum: DEFAULT_MIN_MEMORY,
+                },
+                ValidationError::DuplicateEnv("A".to_string()),
+                ValidationError::RelativeInputTarget("input".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_msg() {
+        let msg = gevulot::MsgCreateTask {
+            image: "prover:latest".to_string(),
+            memory: 1024,
+            time: 0,
+            ..Default::default()
+        };
+        let Err(Error::Validation(errors)) = TaskValidator::default().validate_msg(&msg) else {
+            panic!("message should be invalid");
+        };
+        assert_eq!(errors.len(), 2);
+        assert!(TaskValidator { min_memory: 1024 }
+            .validate_msg(&gevulot::MsgCreateTask { time: 60, ..msg })
+            .is_ok());
+    }
+}
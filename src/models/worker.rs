@@ -6,13 +6,20 @@
 //! - Metadata like tags and labels
 //! - Protobuf serialization/deserialization
 
+use std::time::SystemTime;
+
 use super::{
-    metadata::{Label, Metadata},
-    ByteUnit, CoreUnit, DefaultFactorOneMegabyte,
+    check_kind, metadata::Metadata, unsigned, ByteUnit, CoreUnit, DefaultFactorOneMegabyte,
 };
 use crate::proto::gevulot::gevulot;
 use serde::{Deserialize, Serialize};
 
+/// Label holding the start of a worker's maintenance window as RFC 3339 timestamp.
+pub const MAINTENANCE_START_LABEL: &str = "maintenance.start";
+
+/// Label holding the end of a worker's maintenance window as RFC 3339 timestamp.
+pub const MAINTENANCE_END_LABEL: &str = "maintenance.end";
+
 /// Represents a complete worker definition with metadata, specification and status
 ///
 /// # Examples
@@ -59,6 +66,7 @@ use serde::{Deserialize, Serialize};
 /// let worker = Worker::from(proto_worker);
 /// ```
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Worker {
     pub kind: String,
     pub version: String,
@@ -67,43 +75,124 @@ pub struct Worker {
     pub status: Option<WorkerStatus>,
 }
 
+impl Worker {
+    /// Parses a worker manifest in YAML, e.g. a `worker.yaml` kept by a node operator.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the YAML is invalid or not of kind `Worker`.
+    pub fn from_yaml(yaml: &str) -> crate::error::Result<Self> {
+        let worker: Self =
+            serde_yaml::from_str(yaml).map_err(|e| crate::error::Error::Parse(e.to_string()))?;
+        check_kind("Worker", &worker.kind)?;
+        Ok(worker)
+    }
+
+    /// Parses a worker manifest in JSON.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the JSON is invalid or not of kind `Worker`.
+    pub fn from_json(json: &str) -> crate::error::Result<Self> {
+        let worker: Self =
+            serde_json::from_str(json).map_err(|e| crate::error::Error::Parse(e.to_string()))?;
+        check_kind("Worker", &worker.kind)?;
+        Ok(worker)
+    }
+
+    /// Returns the maintenance window declared in the labels of the worker.
+    ///
+    /// Windows with missing or invalid timestamps are ignored.
+    pub fn maintenance_window(&self) -> Option<MaintenanceWindow> {
+        MaintenanceWindow::from_labels(
+            self.metadata
+                .labels
+                .iter()
+                .map(|label| (label.key.as_str(), label.value.as_str())),
+        )
+    }
+
+    /// Returns true if the worker is in its maintenance window at the given time.
+    pub fn in_maintenance(&self, now: SystemTime) -> bool {
+        self.maintenance_window()
+            .is_some_and(|window| window.contains(now))
+    }
+}
+
+/// Time span in which a worker is under maintenance and shouldn't be assigned tasks.
+///
+/// Declared by the [`MAINTENANCE_START_LABEL`] and [`MAINTENANCE_END_LABEL`] labels of the
+/// worker, which schedulers honoring the convention check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaintenanceWindow {
+    pub start: SystemTime,
+    pub end: SystemTime,
+}
+
+impl MaintenanceWindow {
+    /// Creates a window from its start and end.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the window doesn't end after it starts.
+    pub fn new(start: SystemTime, end: SystemTime) -> crate::error::Result<Self> {
+        if end <= start {
+            return Err(crate::error::Error::Parse(
+                "maintenance window must end after it starts".to_string(),
+            ));
+        }
+        Ok(Self { start, end })
+    }
+
+    /// Reads the window from the keys and values of labels, None if they don't declare a
+    /// valid one.
+    pub fn from_labels<'a>(labels: impl IntoIterator<Item = (&'a str, &'a str)>) -> Option<Self> {
+        let (mut start, mut end) = (None, None);
+        for (key, value) in labels {
+            let time = humantime::parse_rfc3339_weak(value).ok();
+            match key {
+                MAINTENANCE_START_LABEL => start = time,
+                MAINTENANCE_END_LABEL => end = time,
+                _ => {}
+            }
+        }
+        Self::new(start?, end?).ok()
+    }
+
+    /// Returns true if the time is within the window, including its start.
+    pub fn contains(&self, time: SystemTime) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    /// Declares the window in labels, replacing a window declared before.
+    pub fn apply(&self, labels: &mut Vec<gevulot::Label>) {
+        Self::clear(labels);
+        for (key, time) in [
+            (MAINTENANCE_START_LABEL, self.start),
+            (MAINTENANCE_END_LABEL, self.end),
+        ] {
+            labels.push(gevulot::Label {
+                key: key.to_string(),
+                value: humantime::format_rfc3339_seconds(time).to_string(),
+            });
+        }
+    }
+
+    /// Removes a declared window from labels.
+    pub fn clear(labels: &mut Vec<gevulot::Label>) {
+        labels.retain(|label| {
+            label.key != MAINTENANCE_START_LABEL && label.key != MAINTENANCE_END_LABEL
+        });
+    }
+}
+
 impl From<gevulot::Worker> for Worker {
     fn from(proto: gevulot::Worker) -> Self {
         // Convert protobuf worker to our internal worker model
         Worker {
             kind: "Worker".to_string(),
             version: "v0".to_string(),
-            metadata: Metadata {
-                id: proto.metadata.as_ref().map(|m| m.id.clone()),
-                name: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.name.clone())
-                    .unwrap_or_default(),
-                creator: proto.metadata.as_ref().map(|m| m.creator.clone()),
-                description: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.desc.clone())
-                    .unwrap_or_default(),
-                tags: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.tags.clone())
-                    .unwrap_or_default(),
-                labels: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.labels.clone())
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|l| Label {
-                        key: l.key,
-                        value: l.value,
-                    })
-                    .collect(),
-                workflow_ref: None,
-            },
+            metadata: Metadata::from_proto(proto.metadata, None),
             spec: proto.spec.unwrap().into(),
             status: proto.status.map(|s| s.into()),
         }
@@ -117,7 +206,11 @@ impl From<gevulot::Worker> for Worker {
 /// - GPU devices  
 /// - Memory in bytes
 /// - Disk space in bytes
+///
+/// Workers don't advertise prices of their own. Resources are priced chain-wide by the module
+/// parameters, see [`ResourcePrices`](crate::models::ResourcePrices).
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct WorkerSpec {
     pub cpus: CoreUnit,
     pub gpus: CoreUnit,
@@ -129,10 +222,10 @@ impl From<gevulot::WorkerSpec> for WorkerSpec {
     fn from(proto: gevulot::WorkerSpec) -> Self {
         // Convert protobuf spec to internal spec
         WorkerSpec {
-            cpus: (proto.cpus as i64).into(),
-            gpus: (proto.gpus as i64).into(),
-            memory: (proto.memory as i64).into(),
-            disk: (proto.disk as i64).into(),
+            cpus: CoreUnit::from_millicores(proto.cpus),
+            gpus: CoreUnit::from_millicores(proto.gpus),
+            memory: ByteUnit::from_bytes(proto.memory),
+            disk: ByteUnit::from_bytes(proto.disk),
         }
     }
 }
@@ -143,16 +236,17 @@ impl From<gevulot::WorkerSpec> for WorkerSpec {
 /// - Currently used resources (CPU, GPU, memory, disk)
 /// - When the worker announced it will exit
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct WorkerStatus {
-    #[serde(rename = "cpusUsed")]
+    #[serde(rename = "cpusUsed", alias = "cpus_used")]
     pub cpus_used: CoreUnit,
-    #[serde(rename = "gpusUsed")]
+    #[serde(rename = "gpusUsed", alias = "gpus_used")]
     pub gpus_used: CoreUnit,
-    #[serde(rename = "memoryUsed")]
+    #[serde(rename = "memoryUsed", alias = "memory_used")]
     pub memory_used: ByteUnit<DefaultFactorOneMegabyte>,
-    #[serde(rename = "diskUsed")]
+    #[serde(rename = "diskUsed", alias = "disk_used")]
     pub disk_used: ByteUnit<DefaultFactorOneMegabyte>,
-    #[serde(rename = "exitAnnouncedAt")]
+    #[serde(rename = "exitAnnouncedAt", alias = "exit_announced_at")]
     pub exit_announced_at: i64,
 }
 
@@ -160,11 +254,143 @@ impl From<gevulot::WorkerStatus> for WorkerStatus {
     fn from(proto: gevulot::WorkerStatus) -> Self {
         // Convert protobuf status to internal status
         WorkerStatus {
-            cpus_used: (proto.cpus_used as i64).into(),
-            gpus_used: (proto.gpus_used as i64).into(),
-            memory_used: (proto.memory_used as i64).into(),
-            disk_used: (proto.disk_used as i64).into(),
+            cpus_used: CoreUnit::from_millicores(proto.cpus_used),
+            gpus_used: CoreUnit::from_millicores(proto.gpus_used),
+            memory_used: ByteUnit::from_bytes(proto.memory_used),
+            disk_used: ByteUnit::from_bytes(proto.disk_used),
             exit_announced_at: proto.exit_announced_at as i64,
         }
     }
 }
+
+impl TryFrom<Worker> for gevulot::Worker {
+    type Error = crate::error::Error;
+
+    fn try_from(worker: Worker) -> Result<Self, Self::Error> {
+        // Convert internal worker model to protobuf worker, validating kind and units
+        check_kind("Worker", &worker.kind)?;
+        Ok(gevulot::Worker {
+            metadata: Some(worker.metadata.into()),
+            spec: Some(worker.spec.try_into()?),
+            status: worker.status.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+impl TryFrom<WorkerSpec> for gevulot::WorkerSpec {
+    type Error = crate::error::Error;
+
+    fn try_from(spec: WorkerSpec) -> Result<Self, Self::Error> {
+        // Resolve units to millicores and bytes
+        Ok(gevulot::WorkerSpec {
+            cpus: unsigned("cpus", spec.cpus.millicores())?,
+            gpus: unsigned("gpus", spec.gpus.millicores())?,
+            memory: unsigned("memory", spec.memory.bytes())?,
+            disk: unsigned("disk", spec.disk.bytes())?,
+        })
+    }
+}
+
+impl TryFrom<WorkerStatus> for gevulot::WorkerStatus {
+    type Error = crate::error::Error;
+
+    fn try_from(status: WorkerStatus) -> Result<Self, Self::Error> {
+        // Resolve units to millicores and bytes
+        Ok(gevulot::WorkerStatus {
+            cpus_used: unsigned("cpusUsed", status.cpus_used.millicores())?,
+            gpus_used: unsigned("gpusUsed", status.gpus_used.millicores())?,
+            memory_used: unsigned("memoryUsed", status.memory_used.bytes())?,
+            disk_used: unsigned("diskUsed", status.disk_used.bytes())?,
+            exit_announced_at: unsigned("exitAnnouncedAt", Ok(status.exit_announced_at))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "query-only")]
+    use crate::builders::{MsgCreateWorkerBuilder, MsgUpdateWorkerBuilder};
+
+    #[test]
+    #[cfg(feature = "query-only")]
+    fn test_worker_from_yaml() {
+        let worker = Worker::from_yaml(
+            r#"
+            kind: Worker
+            version: v0
+            metadata:
+              name: prover-01
+              description: A100 prover
+              tags: [gpu]
+              labels:
+                - key: region
+                  value: eu-west
+            spec:
+              cpus: 8
+              gpus: 1
+              memory: 16GiB
+              disk: 100GiB
+            "#,
+        )
+        .unwrap();
+
+        let msg = MsgCreateWorkerBuilder::from_spec("alice", &worker.spec, &worker.metadata)
+            .unwrap()
+            .into_message()
+            .unwrap();
+        assert_eq!(msg.name, "prover-01");
+        assert_eq!(msg.cpus, 8000);
+        assert_eq!(msg.memory, 16 * 1024 * 1024 * 1024);
+        assert_eq!(msg.labels[0].value, "eu-west");
+        assert_eq!(msg.tags, vec!["gpu"]);
+
+        let msg = MsgUpdateWorkerBuilder::from_spec("alice", "w1", &worker.spec, &worker.metadata)
+            .unwrap()
+            .into_message()
+            .unwrap();
+        assert_eq!(msg.id, "w1");
+        assert_eq!(msg.disk, 100 * 1024 * 1024 * 1024);
+
+        let json = r#"{"kind": "Task", "version": "v0", "metadata": {"name": "t"},
+            "spec": {"cpus": 1, "gpus": 0, "memory": 1, "disk": 1}}"#;
+        assert!(Worker::from_json(json).is_err());
+    }
+
+    #[test]
+    fn test_maintenance_window() {
+        let mut worker = Worker::from_json(
+            r#"{"kind": "Worker", "version": "v0", "metadata": {"name": "w",
+                "labels": [{"key": "maintenance.start", "value": "2030-01-01T02:00:00Z"},
+                           {"key": "maintenance.end", "value": "2030-01-01T04:00:00Z"}]},
+                "spec": {"cpus": 1, "gpus": 0, "memory": 1, "disk": 1}}"#,
+        )
+        .unwrap();
+        let at = |time: &str| humantime::parse_rfc3339(time).unwrap();
+        let window = worker.maintenance_window().unwrap();
+        assert_eq!(window.start, at("2030-01-01T02:00:00Z"));
+        assert!(!worker.in_maintenance(at("2030-01-01T01:59:59Z")));
+        assert!(worker.in_maintenance(at("2030-01-01T02:00:00Z")));
+        assert!(!worker.in_maintenance(at("2030-01-01T04:00:00Z")));
+
+        let mut labels = vec![gevulot::Label {
+            key: "region".to_string(),
+            value: "eu-west".to_string(),
+        }];
+        let window =
+            MaintenanceWindow::new(at("2030-02-01T00:00:00Z"), at("2030-02-02T00:00:00Z")).unwrap();
+        window.apply(&mut labels);
+        window.apply(&mut labels);
+        assert_eq!(labels.len(), 3);
+        assert_eq!(labels[2].value, "2030-02-02T00:00:00Z");
+        worker.metadata.labels = labels.iter().cloned().map(Into::into).collect();
+        assert_eq!(worker.maintenance_window(), Some(window));
+
+        MaintenanceWindow::clear(&mut labels);
+        assert_eq!(labels.len(), 1);
+        // Windows ending before they start are ignored
+        assert!(MaintenanceWindow::new(window.end, window.start).is_err());
+        worker.metadata.labels[2].value = "2029-01-01T00:00:00Z".to_string();
+        assert_eq!(worker.maintenance_window(), None);
+    }
+}
@@ -6,7 +6,7 @@
 //! - Metadata like tags and labels
 //! - Protobuf serialization/deserialization
 
-use super::{Label, Metadata, TaskSpec};
+use super::{check_kind, Metadata, TaskSpec, TimeUnit};
 use crate::proto::gevulot::gevulot;
 use serde::{Deserialize, Serialize};
 
@@ -42,6 +42,7 @@ use serde::{Deserialize, Serialize};
 /// }"#).unwrap();
 /// ```
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct Workflow {
     pub kind: String,
     pub version: String,
@@ -58,37 +59,7 @@ impl From<gevulot::Workflow> for Workflow {
         Workflow {
             kind: "Workflow".to_string(),
             version: "v0".to_string(),
-            metadata: Metadata {
-                id: proto.metadata.as_ref().map(|m| m.id.clone()),
-                name: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.name.clone())
-                    .unwrap_or_default(),
-                creator: proto.metadata.as_ref().map(|m| m.creator.clone()),
-                description: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.desc.clone())
-                    .unwrap_or_default(),
-                tags: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.tags.clone())
-                    .unwrap_or_default(),
-                labels: proto
-                    .metadata
-                    .as_ref()
-                    .map(|m| m.labels.clone())
-                    .unwrap_or_default()
-                    .into_iter()
-                    .map(|l| Label {
-                        key: l.key,
-                        value: l.value,
-                    })
-                    .collect(),
-                workflow_ref: None,
-            },
+            metadata: Metadata::from_proto(proto.metadata, None),
             spec: proto.spec.map(|s| s.into()).unwrap(),
             status: proto.status.map(|s| s.into()),
         }
@@ -99,9 +70,84 @@ impl From<gevulot::Workflow> for Workflow {
 ///
 /// Tasks within a stage can be executed in parallel. The workflow will only
 /// proceed to the next stage once all tasks in the current stage are complete.
+///
+/// A stage may also run other workflows, see [`SubWorkflow`].
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct WorkflowStage {
+    #[serde(default)]
     pub tasks: Vec<TaskSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub workflows: Vec<SubWorkflow>,
+    /// Retry policy of the tasks of this stage, overrides the policy of the spec.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+}
+
+/// How often failed tasks of a workflow are rescheduled
+///
+/// The chain doesn't retry tasks of workflows, the policy is applied on the client by
+/// [`WorkflowRunner`](crate::workflow_runner::WorkflowRunner) and not part of the protobuf
+/// message.
+///
+/// ```
+/// use crate::models::RetryPolicy;
+///
+/// let policy = serde_json::from_str::<RetryPolicy>(r#"{"maxRetries": 3, "backoff": "30s"}"#).unwrap();
+/// assert_eq!(policy.delay(2).unwrap().as_secs(), 120);
+/// ```
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
+pub struct RetryPolicy {
+    /// Number of times a failed task is rescheduled at most
+    #[serde(rename = "maxRetries", alias = "max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every following retry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff: Option<TimeUnit>,
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the retry with the given index, starting at 0.
+    pub fn delay(&self, retry: u32) -> crate::error::Result<std::time::Duration> {
+        let seconds = match &self.backoff {
+            Some(backoff) => backoff.seconds().map_err(crate::error::Error::Parse)?,
+            None => 0,
+        };
+        let factor = 1u64.checked_shl(retry).unwrap_or(u64::MAX);
+        Ok(std::time::Duration::from_secs(
+            (seconds.max(0) as u64).saturating_mul(factor),
+        ))
+    }
+}
+
+/// A workflow run as part of a stage of another workflow
+///
+/// The chain only knows flat workflows, so sub-workflows are expanded on the client before a
+/// workflow is created: the first stage of a sub-workflow runs alongside the tasks of the stage
+/// referencing it, its following stages are merged into the following stages of the parent.
+/// The parent gets additional stages if a sub-workflow has more stages than are left.
+///
+/// Inline specs are expanded when converting a spec into its protobuf message, references by
+/// ID have to be resolved with [`WorkflowClient::expand`](crate::workflow_client::WorkflowClient::expand).
+///
+/// ```
+/// use crate::models::WorkflowStage;
+///
+/// let stage = serde_json::from_str::<WorkflowStage>(r#"{
+///     "workflows": [
+///         {"id": "proving-pipeline"},
+///         {"spec": {"stages": [{"tasks": [{"image": "alpine", "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}}]}]}}
+///     ]
+/// }"#).unwrap();
+/// ```
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum SubWorkflow {
+    // ID of a workflow on chain whose spec is reused
+    Id(String),
+    // Spec of the workflow
+    Spec(WorkflowSpec),
 }
 
 /// Specification for a workflow defining its stages and tasks
@@ -109,8 +155,12 @@ pub struct WorkflowStage {
 /// The stages are executed sequentially, with tasks in each stage potentially
 /// running in parallel depending on available resources.
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct WorkflowSpec {
     pub stages: Vec<WorkflowStage>,
+    /// Retry policy of the tasks of all stages without their own policy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
 }
 
 // Converts a protobuf workflow spec into our internal WorkflowSpec model
@@ -123,8 +173,11 @@ impl From<gevulot::WorkflowSpec> for WorkflowSpec {
                 .into_iter()
                 .map(|stage| WorkflowStage {
                     tasks: stage.tasks.into_iter().map(|t| t.into()).collect(),
+                    workflows: Vec::new(),
+                    retry: None,
                 })
                 .collect(),
+            retry: None,
         }
     }
 }
@@ -133,10 +186,11 @@ impl From<gevulot::WorkflowSpec> for WorkflowSpec {
 ///
 /// Tracks which tasks have been created and how many have completed.
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct WorkflowStageStatus {
-    #[serde(rename = "taskIds")]
+    #[serde(rename = "taskIds", alias = "task_ids")]
     pub task_ids: Vec<String>,
-    #[serde(rename = "finishedTasks")]
+    #[serde(rename = "finishedTasks", alias = "finished_tasks")]
     pub finished_tasks: u64,
 }
 
@@ -147,9 +201,10 @@ pub struct WorkflowStageStatus {
 /// - Which stage is currently executing
 /// - Status of each stage including task completion
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "strict-models", serde(deny_unknown_fields))]
 pub struct WorkflowStatus {
     pub state: String,
-    #[serde(rename = "currentStage")]
+    #[serde(rename = "currentStage", alias = "current_stage")]
     pub current_stage: u64,
     pub stages: Vec<WorkflowStageStatus>,
 }
@@ -179,6 +234,372 @@ impl From<gevulot::WorkflowStatus> for WorkflowStatus {
     }
 }
 
+// Converts our internal Workflow model into a protobuf workflow message
+impl TryFrom<Workflow> for gevulot::Workflow {
+    type Error = crate::error::Error;
+
+    fn try_from(workflow: Workflow) -> Result<Self, Self::Error> {
+        check_kind("Workflow", &workflow.kind)?;
+        Ok(gevulot::Workflow {
+            metadata: Some(workflow.metadata.into()),
+            spec: Some(workflow.spec.try_into()?),
+            status: workflow.status.map(TryInto::try_into).transpose()?,
+        })
+    }
+}
+
+// Converts our internal WorkflowSpec model into a protobuf workflow spec, resolving task units
+// and expanding inline sub-workflows
+impl TryFrom<WorkflowSpec> for gevulot::WorkflowSpec {
+    type Error = crate::error::Error;
+
+    fn try_from(spec: WorkflowSpec) -> Result<Self, Self::Error> {
+        spec.expand(&mut |id: &str| {
+            Err(crate::error::Error::Parse(format!(
+                "unresolved sub-workflow {}, expand the spec with WorkflowClient::expand",
+                id
+            )))
+        })
+    }
+}
+
+impl WorkflowSpec {
+    /// Returns the retry policy of the tasks of a stage, None if failed tasks aren't retried.
+    pub fn retry_policy(&self, stage: usize) -> Option<&RetryPolicy> {
+        self.stages
+            .get(stage)
+            .and_then(|stage| stage.retry.as_ref())
+            .or(self.retry.as_ref())
+    }
+
+    /// Returns the IDs of all sub-workflows referenced by the spec, including nested ones.
+    pub fn sub_workflow_ids(&self) -> Vec<&str> {
+        self.stages
+            .iter()
+            .flat_map(|stage| stage.workflows.iter())
+            .flat_map(|workflow| match workflow {
+                SubWorkflow::Id(id) => vec![id.as_str()],
+                SubWorkflow::Spec(spec) => spec.sub_workflow_ids(),
+            })
+            .collect()
+    }
+
+    /// Expands the spec into a flat protobuf workflow spec, see [`SubWorkflow`].
+    ///
+    /// `resolve` returns the spec of a sub-workflow referenced by ID.
+    pub fn expand(
+        self,
+        resolve: &mut dyn FnMut(&str) -> crate::error::Result<gevulot::WorkflowSpec>,
+    ) -> crate::error::Result<gevulot::WorkflowSpec> {
+        let mut stages: Vec<gevulot::workflow_spec::Stage> = Vec::new();
+        for (index, stage) in self.stages.into_iter().enumerate() {
+            let tasks = stage
+                .tasks
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<crate::error::Result<Vec<_>>>()?;
+            merge_stages(
+                &mut stages,
+                index,
+                vec![gevulot::workflow_spec::Stage { tasks }],
+            );
+            for workflow in stage.workflows {
+                let spec = match workflow {
+                    SubWorkflow::Id(id) => resolve(&id)?,
+                    SubWorkflow::Spec(spec) => spec.expand(resolve)?,
+                };
+                merge_stages(&mut stages, index, spec.stages);
+            }
+        }
+        Ok(gevulot::WorkflowSpec { stages })
+    }
+}
+
+// Adds the tasks of the given stages to the stages starting at the index
+fn merge_stages(
+    stages: &mut Vec<gevulot::workflow_spec::Stage>,
+    index: usize,
+    merged: Vec<gevulot::workflow_spec::Stage>,
+) {
+    for (offset, stage) in merged.into_iter().enumerate() {
+        if stages.len() <= index + offset {
+            stages.resize_with(index + offset + 1, Default::default);
+        }
+        stages[index + offset].tasks.extend(stage.tasks);
+    }
+}
+
+// Converts our internal WorkflowStatus model into a protobuf workflow status
+impl TryFrom<WorkflowStatus> for gevulot::WorkflowStatus {
+    type Error = crate::error::Error;
+
+    fn try_from(status: WorkflowStatus) -> Result<Self, Self::Error> {
+        use gevulot::workflow_status::State;
+
+        let state = match status.state.as_str() {
+            "Pending" => State::Pending,
+            "Running" => State::Running,
+            "Done" => State::Done,
+            "Failed" => State::Failed,
+            state => {
+                return Err(crate::error::Error::Parse(format!(
+                    "invalid workflow state: {}",
+                    state
+                )))
+            }
+        };
+        Ok(gevulot::WorkflowStatus {
+            state: state.into(),
+            current_stage: status.current_stage,
+            stages: status
+                .stages
+                .into_iter()
+                .map(|s| gevulot::workflow_status::StageState {
+                    task_ids: s.task_ids,
+                    finished_tasks: s.finished_tasks,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Resource prices as configured in the chain parameters, in ucredit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResourcePrices {
+    /// Price per CPU-second
+    pub cpu: u128,
+    /// Price per GPU-second
+    pub gpu: u128,
+    /// Price per byte-second of memory
+    pub memory: u128,
+}
+
+impl TryFrom<&gevulot::Params> for ResourcePrices {
+    type Error = crate::error::Error;
+
+    fn try_from(params: &gevulot::Params) -> Result<Self, Self::Error> {
+        let parse = |name: &str, value: &str| {
+            if value.is_empty() {
+                return Ok(0);
+            }
+            value
+                .parse::<u128>()
+                .map_err(|e| crate::error::Error::Parse(format!("invalid {}: {}", name, e)))
+        };
+        Ok(ResourcePrices {
+            cpu: parse("cpuPrice", &params.cpu_price)?,
+            gpu: parse("gpuPrice", &params.gpu_price)?,
+            memory: parse("memoryPrice", &params.memory_price)?,
+        })
+    }
+}
+
+/// A task as it would be created by a workflow, with all units resolved
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaskPreview {
+    pub image: String,
+    /// CPUs in millicores
+    pub cpus: i64,
+    /// GPUs in millicores
+    pub gpus: i64,
+    /// Memory in bytes
+    pub memory: i64,
+    /// Time limit in seconds
+    pub time: i64,
+    /// Input contexts as (source, target) pairs
+    pub input_contexts: Vec<(String, String)>,
+    /// Output contexts as (source, retention period) pairs
+    pub output_contexts: Vec<(String, i64)>,
+    /// Estimated cost of running the task for its full time limit, if prices are known
+    pub estimated_cost: Option<u128>,
+}
+
+/// The tasks a single workflow stage would create
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StagePreview {
+    pub tasks: Vec<TaskPreview>,
+}
+
+/// Expansion of a workflow into the tasks it would create, stage by stage
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorkflowPreview {
+    pub stages: Vec<StagePreview>,
+}
+
+impl WorkflowPreview {
+    /// Expands a workflow spec, estimating task costs if prices are given
+    ///
+    /// Tasks of sub-workflows are not included.
+    pub fn new(spec: &WorkflowSpec, prices: Option<&ResourcePrices>) -> crate::error::Result<Self> {
+        let stages = spec
+            .stages
+            .iter()
+            .map(|stage| {
+                let tasks = stage
+                    .tasks
+                    .iter()
+                    .map(|task| TaskPreview::new(task, prices))
+                    .collect::<crate::error::Result<Vec<_>>>()?;
+                Ok(StagePreview { tasks })
+            })
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        Ok(WorkflowPreview { stages })
+    }
+
+    /// Total number of tasks the workflow would create
+    pub fn task_count(&self) -> usize {
+        self.stages.iter().map(|stage| stage.tasks.len()).sum()
+    }
+
+    /// Estimated cost of the whole workflow, if prices are known
+    pub fn estimated_cost(&self) -> Option<u128> {
+        self.stages
+            .iter()
+            .flat_map(|stage| stage.tasks.iter())
+            .map(|task| task.estimated_cost)
+            .sum()
+    }
+}
+
+impl TaskPreview {
+    fn new(task: &TaskSpec, prices: Option<&ResourcePrices>) -> crate::error::Result<Self> {
+        let parse_error = |e: String| crate::error::Error::Parse(e);
+        let cpus = task.resources.cpus.millicores().map_err(parse_error)?;
+        let gpus = task.resources.gpus.millicores().map_err(parse_error)?;
+        let memory = task.resources.memory.bytes().map_err(parse_error)?;
+        let time = task.resources.time.seconds().map_err(parse_error)?;
+        let estimated_cost = prices.map(|prices| {
+            let time = time.max(0) as u128;
+            (cpus.max(0) as u128 * prices.cpu / 1000
+                + gpus.max(0) as u128 * prices.gpu / 1000
+                + memory.max(0) as u128 * prices.memory)
+                * time
+        });
+        Ok(TaskPreview {
+            image: task.image.clone(),
+            cpus,
+            gpus,
+            memory,
+            time,
+            input_contexts: task
+                .input_contexts
+                .iter()
+                .map(|ic| (ic.source.clone(), ic.target.clone()))
+                .collect(),
+            output_contexts: task
+                .output_contexts
+                .iter()
+                .map(|oc| (oc.source.clone(), oc.retention_period))
+                .collect(),
+            estimated_cost,
+        })
+    }
+}
+
+/// Completion of a single workflow stage
+#[derive(Clone, Debug, PartialEq)]
+pub struct StageProgress {
+    /// Number of tasks in the stage
+    pub total_tasks: usize,
+    /// Number of tasks which finished, successfully or not
+    pub finished_tasks: u64,
+    /// Share of finished tasks from 0 to 100
+    pub percent: f64,
+    /// IDs of the tasks of the stage which failed
+    pub failed_task_ids: Vec<String>,
+}
+
+/// Summary of the execution of a workflow, stage by stage
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkflowProgress {
+    /// State of the workflow (Pending, Running, Done, Failed)
+    pub state: String,
+    /// Index of the stage currently executing
+    pub current_stage: u64,
+    pub stages: Vec<StageProgress>,
+    /// Share of finished tasks of the whole workflow from 0 to 100
+    pub percent: f64,
+    /// Stages left to execute including the current one, 0 once the workflow is done or failed
+    pub remaining_stages: usize,
+}
+
+impl WorkflowProgress {
+    /// Summarizes the status of a workflow
+    ///
+    /// `failed` tells whether the task with the given ID failed, the status only counts the
+    /// finished tasks of a stage.
+    pub fn new(workflow: &Workflow, failed: &dyn Fn(&str) -> bool) -> Self {
+        let (state, current_stage, statuses) = match &workflow.status {
+            Some(status) => (
+                status.state.clone(),
+                status.current_stage,
+                status.stages.as_slice(),
+            ),
+            None => ("Pending".to_string(), 0, [].as_slice()),
+        };
+        let finished = state == "Done" || state == "Failed";
+
+        let stage_count = workflow.spec.stages.len().max(statuses.len());
+        let stages: Vec<_> = (0..stage_count)
+            .map(|index| {
+                let status = statuses.get(index);
+                let task_ids = status.map(|s| s.task_ids.as_slice()).unwrap_or_default();
+                let total_tasks = workflow
+                    .spec
+                    .stages
+                    .get(index)
+                    .map_or(0, |stage| stage.tasks.len())
+                    .max(task_ids.len());
+                let finished_tasks = status.map_or(0, |s| s.finished_tasks);
+                let percent = if total_tasks > 0 {
+                    (finished_tasks as f64 / total_tasks as f64 * 100.0).min(100.0)
+                } else if state == "Done" || (index as u64) < current_stage {
+                    100.0
+                } else {
+                    0.0
+                };
+                StageProgress {
+                    total_tasks,
+                    finished_tasks,
+                    percent,
+                    failed_task_ids: task_ids.iter().filter(|id| failed(id)).cloned().collect(),
+                }
+            })
+            .collect();
+
+        let total_tasks: usize = stages.iter().map(|stage| stage.total_tasks).sum();
+        let finished_tasks: u64 = stages.iter().map(|stage| stage.finished_tasks).sum();
+        let percent = if total_tasks > 0 {
+            (finished_tasks as f64 / total_tasks as f64 * 100.0).min(100.0)
+        } else if state == "Done" {
+            100.0
+        } else {
+            0.0
+        };
+        let remaining_stages = if finished {
+            0
+        } else {
+            stage_count.saturating_sub(current_stage as usize)
+        };
+
+        WorkflowProgress {
+            state,
+            current_stage,
+            stages,
+            percent,
+            remaining_stages,
+        }
+    }
+
+    /// IDs of all failed tasks of the workflow
+    pub fn failed_task_ids(&self) -> Vec<&str> {
+        self.stages
+            .iter()
+            .flat_map(|stage| stage.failed_task_ids.iter().map(String::as_str))
+            .collect()
+    }
+}
+
 // Unit tests to verify workflow serialization/deserialization and field mapping
 #[cfg(test)]
 mod tests {
@@ -283,4 +704,236 @@ mod tests {
         assert_eq!(workflow.spec.stages.len(), 0);
         assert!(workflow.status.is_none());
     }
+
+    #[test]
+    fn test_workflow_into_proto() {
+        let workflow = serde_json::from_value::<Workflow>(json!({
+            "kind": "Workflow",
+            "version": "v0",
+            "spec": {
+                "stages": [{
+                    "tasks": [{
+                        "image": "prover:latest",
+                        "resources": {"cpus": "2cpus", "gpus": 0, "memory": "1GiB", "time": "10m"}
+                    }]
+                }]
+            },
+            "status": {"state": "Running", "currentStage": 0, "stages": []}
+        }))
+        .unwrap();
+
+        let proto = gevulot::Workflow::try_from(workflow).unwrap();
+        let task = &proto.spec.unwrap().stages[0].tasks[0];
+        assert_eq!(task.cpus, 2000);
+        assert_eq!(task.memory, 1024 * 1024 * 1024);
+        assert_eq!(task.time, 600);
+        assert_eq!(
+            proto.status.unwrap().state(),
+            gevulot::workflow_status::State::Running
+        );
+
+        let workflow = serde_json::from_value::<Workflow>(json!({
+            "kind": "Workflow",
+            "version": "v0",
+            "spec": {"stages": []},
+            "status": {"state": "Sleeping", "currentStage": 0, "stages": []}
+        }))
+        .unwrap();
+        assert!(gevulot::Workflow::try_from(workflow).is_err());
+    }
+
+    #[test]
+    fn test_expand_sub_workflows() {
+        let task = |image: &str| json!({"image": image, "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}});
+        let spec = serde_json::from_value::<WorkflowSpec>(json!({
+            "stages": [
+                {
+                    "tasks": [task("prepare")],
+                    "workflows": [
+                        {"id": "prove"},
+                        {"spec": {"stages": [{"tasks": [task("index")]}]}}
+                    ]
+                },
+                {"tasks": [task("publish")]}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(spec.sub_workflow_ids(), vec!["prove"]);
+
+        let expanded = spec
+            .expand(&mut |id: &str| {
+                assert_eq!(id, "prove");
+                let image = |image: &str| gevulot::TaskSpec {
+                    image: image.to_string(),
+                    ..Default::default()
+                };
+                Ok(gevulot::WorkflowSpec {
+                    stages: vec![
+                        gevulot::workflow_spec::Stage {
+                            tasks: vec![image("witness")],
+                        },
+                        gevulot::workflow_spec::Stage {
+                            tasks: vec![image("prove")],
+                        },
+                        gevulot::workflow_spec::Stage {
+                            tasks: vec![image("verify")],
+                        },
+                    ],
+                })
+            })
+            .unwrap();
+        let images: Vec<Vec<&str>> = expanded
+            .stages
+            .iter()
+            .map(|stage| stage.tasks.iter().map(|t| t.image.as_str()).collect())
+            .collect();
+        assert_eq!(
+            images,
+            vec![
+                vec!["prepare", "witness", "index"],
+                vec!["prove", "publish"],
+                vec!["verify"],
+            ]
+        );
+
+        let spec = serde_json::from_value::<WorkflowSpec>(json!({
+            "stages": [{"workflows": [{"id": "prove"}]}]
+        }))
+        .unwrap();
+        assert!(gevulot::WorkflowSpec::try_from(spec).is_err());
+    }
+
+    #[test]
+    fn test_workflow_preview() {
+        let spec = serde_json::from_value::<WorkflowSpec>(json!({
+            "stages": [
+                {
+                    "tasks": [
+                        {
+                            "image": "alpine",
+                            "inputContexts": [{"source": "cid1", "target": "/input"}],
+                            "resources": {
+                                "cpus": "2cpus",
+                                "gpus": 0,
+                                "memory": "1kb",
+                                "time": "10s"
+                            }
+                        }
+                    ]
+                },
+                {
+                    "tasks": [
+                        {
+                            "image": "busybox",
+                            "resources": {"cpus": 1, "gpus": 0, "memory": 1, "time": 5}
+                        },
+                        {
+                            "image": "busybox",
+                            "resources": {"cpus": 1, "gpus": 0, "memory": 1, "time": 5}
+                        }
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
+
+        let prices = ResourcePrices {
+            cpu: 10,
+            gpu: 100,
+            memory: 0,
+        };
+        let preview = WorkflowPreview::new(&spec, Some(&prices)).unwrap();
+        assert_eq!(preview.task_count(), 3);
+        let first = &preview.stages[0].tasks[0];
+        assert_eq!(first.cpus, 2000);
+        assert_eq!(first.memory, 1000);
+        assert_eq!(first.time, 10);
+        assert_eq!(
+            first.input_contexts,
+            vec![("cid1".to_string(), "/input".to_string())]
+        );
+        assert_eq!(first.estimated_cost, Some(200));
+        assert_eq!(preview.estimated_cost(), Some(200 + 50 + 50));
+
+        let preview = WorkflowPreview::new(&spec, None).unwrap();
+        assert_eq!(preview.estimated_cost(), None);
+    }
+
+    #[test]
+    fn test_workflow_progress() {
+        let task = json!({
+            "image": "prover:latest",
+            "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}
+        });
+        let mut workflow = serde_json::from_value::<Workflow>(json!({
+            "kind": "Workflow",
+            "version": "v0",
+            "spec": {
+                "stages": [
+                    {"tasks": [task.clone(), task.clone()]},
+                    {"tasks": [task.clone(), task.clone(), task.clone(), task]},
+                    {"tasks": []}
+                ]
+            },
+            "status": {
+                "state": "Running",
+                "currentStage": 1,
+                "stages": [
+                    {"taskIds": ["t1", "t2"], "finishedTasks": 2},
+                    {"taskIds": ["t3", "t4", "t5", "t6"], "finishedTasks": 1}
+                ]
+            }
+        }))
+        .unwrap();
+
+        let progress = WorkflowProgress::new(&workflow, &|id| id == "t2" || id == "t4");
+        assert_eq!(progress.stages.len(), 3);
+        assert_eq!(progress.stages[0].percent, 100.0);
+        assert_eq!(progress.stages[0].failed_task_ids, vec!["t2"]);
+        assert_eq!(progress.stages[1].total_tasks, 4);
+        assert_eq!(progress.stages[1].percent, 25.0);
+        assert_eq!(progress.stages[2].percent, 0.0);
+        assert_eq!(progress.percent, 50.0);
+        assert_eq!(progress.remaining_stages, 2);
+        assert_eq!(progress.failed_task_ids(), vec!["t2", "t4"]);
+
+        workflow.status.as_mut().unwrap().state = "Failed".to_string();
+        let progress = WorkflowProgress::new(&workflow, &|_| false);
+        assert_eq!(progress.remaining_stages, 0);
+        assert!(progress.failed_task_ids().is_empty());
+
+        workflow.status = None;
+        let progress = WorkflowProgress::new(&workflow, &|_| false);
+        assert_eq!(progress.state, "Pending");
+        assert_eq!(progress.percent, 0.0);
+        assert_eq!(progress.remaining_stages, 3);
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let task = json!({
+            "image": "prover:latest",
+            "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}
+        });
+        let spec = serde_json::from_value::<WorkflowSpec>(json!({
+            "retry": {"maxRetries": 1},
+            "stages": [
+                {"tasks": [task.clone()], "retry": {"max_retries": 3, "backoff": "10s"}},
+                {"tasks": [task]}
+            ]
+        }))
+        .unwrap();
+
+        let policy = spec.retry_policy(0).unwrap();
+        assert_eq!(policy.max_retries, 3);
+        assert_eq!(policy.delay(0).unwrap().as_secs(), 10);
+        assert_eq!(policy.delay(2).unwrap().as_secs(), 40);
+        assert_eq!(spec.retry_policy(1).unwrap().max_retries, 1);
+        assert_eq!(spec.retry_policy(1).unwrap().delay(5).unwrap().as_secs(), 0);
+
+        // Retry policies are applied on the client and not sent to the chain
+        let proto: gevulot::WorkflowSpec = spec.try_into().unwrap();
+        let spec = WorkflowSpec::from(proto);
+        assert!(spec.retry_policy(0).is_none());
+    }
 }
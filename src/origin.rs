@@ -0,0 +1,131 @@
+//! Labels recording where an entity was created.
+//!
+//! Once enabled with [`GevulotClientBuilder::origin`](crate::GevulotClientBuilder::origin),
+//! every task, pin, worker and proof created through the client is labeled with the SDK
+//! version, a hash of the submitting host's name and the name of the application. Fleet
+//! operators can use them to trace problematic submissions back to the system producing them
+//! without the labels revealing host names.
+//!
+//! Labels set explicitly in a message are never overwritten. Workflows carry no labels and
+//! aren't stamped.
+
+use sha2::{Digest, Sha256};
+
+use crate::{models::Label, proto::gevulot::gevulot};
+
+/// Label key of the SDK version.
+pub const SDK_VERSION_LABEL: &str = "gevulot.sdk/version";
+/// Label key of the hashed host name.
+pub const HOST_LABEL: &str = "gevulot.sdk/host";
+/// Label key of the application name.
+pub const APP_LABEL: &str = "gevulot.sdk/app";
+
+/// Origin of the entities created by a client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Origin {
+    /// Name of the application creating the entities.
+    pub app_name: String,
+    /// Version of this SDK.
+    pub sdk_version: String,
+    /// Hex encoded SHA-256 hash of the host name, shortened to 16 characters.
+    pub host_hash: String,
+}
+
+impl Origin {
+    /// Creates the origin of an application running on this host.
+    ///
+    /// The host name is taken from the `HOSTNAME` or `COMPUTERNAME` environment variables, or
+    /// `/etc/hostname`, and is `unknown` if none of them are set.
+    pub fn new(app_name: &str) -> Self {
+        Self {
+            app_name: app_name.to_string(),
+            sdk_version: env!("CARGO_PKG_VERSION").to_string(),
+            host_hash: hash_host_name(&host_name()),
+        }
+    }
+
+    /// Returns the origin labels.
+    pub fn labels(&self) -> Vec<Label> {
+        vec![
+            Label {
+                key: SDK_VERSION_LABEL.to_string(),
+                value: self.sdk_version.clone(),
+            },
+            Label {
+                key: HOST_LABEL.to_string(),
+                value: self.host_hash.clone(),
+            },
+            Label {
+                key: APP_LABEL.to_string(),
+                value: self.app_name.clone(),
+            },
+        ]
+    }
+
+    /// Adds the origin labels to the labels of a message, keeping labels already set.
+    pub(crate) fn apply(&self, labels: &mut Vec<gevulot::Label>) {
+        for label in self.labels() {
+            if !labels.iter().any(|l| l.key == label.key) {
+                labels.push(label.into());
+            }
+        }
+    }
+
+    /// Adds the origin labels to labels kept in a map, keeping labels already set.
+    pub(crate) fn apply_to_map(&self, labels: &mut std::collections::HashMap<String, String>) {
+        for label in self.labels() {
+            labels.entry(label.key).or_insert(label.value);
+        }
+    }
+}
+
+/// Hashes a host name so it can be compared without being revealed.
+pub fn hash_host_name(host_name: &str) -> String {
+    let hash = Sha256::digest(host_name.trim().as_bytes());
+    hex::encode(&hash[..8])
+}
+
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let origin = Origin {
+            app_name: "prover".to_string(),
+            sdk_version: "0.1.3".to_string(),
+            host_hash: hash_host_name("builder-01"),
+        };
+        assert_eq!(origin.host_hash.len(), 16);
+        assert_eq!(origin.host_hash, hash_host_name("builder-01\n"));
+        assert_ne!(origin.host_hash, hash_host_name("builder-02"));
+
+        let mut labels = vec![gevulot::Label {
+            key: APP_LABEL.to_string(),
+            value: "custom".to_string(),
+        }];
+        origin.apply(&mut labels);
+        let labels: Vec<_> = labels
+            .iter()
+            .map(|l| (l.key.as_str(), l.value.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                (APP_LABEL, "custom"),
+                (SDK_VERSION_LABEL, "0.1.3"),
+                (HOST_LABEL, origin.host_hash.as_str()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,66 @@
+//! Propagation of the OpenTelemetry trace context to the node.
+//!
+//! Every gRPC request carries the span context of the current OpenTelemetry context as W3C
+//! `traceparent` and `tracestate` metadata, so node-side logs can be correlated with client
+//! traces. Requests made outside of a sampled or valid span are sent unchanged.
+
+use opentelemetry::trace::{SpanContext, TraceContextExt};
+
+/// Formats a span context as W3C `traceparent` header value.
+fn traceparent(span_context: &SpanContext) -> String {
+    format!(
+        "00-{}-{}-{:02x}",
+        span_context.trace_id(),
+        span_context.span_id(),
+        span_context.trace_flags().to_u8()
+    )
+}
+
+/// Adds the trace context of the current OpenTelemetry context to the request headers.
+pub(crate) fn inject_trace_context(headers: &mut http::HeaderMap) {
+    let context = opentelemetry::Context::current();
+    let span = context.span();
+    let span_context = span.span_context();
+    if !span_context.is_valid() {
+        return;
+    }
+    if let Ok(value) = http::HeaderValue::from_str(&traceparent(span_context)) {
+        headers.insert("traceparent", value);
+    }
+    let trace_state = span_context.trace_state().header();
+    if !trace_state.is_empty() {
+        if let Ok(value) = http::HeaderValue::from_str(&trace_state) {
+            headers.insert("tracestate", value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::{SpanId, TraceFlags, TraceId, TraceState};
+
+    #[test]
+    fn test_inject_trace_context() {
+        let mut headers = http::HeaderMap::new();
+        inject_trace_context(&mut headers);
+        assert!(headers.is_empty());
+
+        let span_context = SpanContext::new(
+            TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap(),
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::from_key_value([("vendor", "value")]).unwrap(),
+        );
+        let context = opentelemetry::Context::current().with_remote_span_context(span_context);
+        let _guard = context.attach();
+
+        inject_trace_context(&mut headers);
+        assert_eq!(
+            headers["traceparent"],
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+        );
+        assert_eq!(headers["tracestate"], "vendor=value");
+    }
+}
@@ -0,0 +1,1013 @@
+//! Persistent queue of messages which must not be lost.
+//!
+//! Messages are first written to an [`OutboxStore`] and then submitted by a dispatcher, so a
+//! crash between deciding to submit a message and its broadcast doesn't lose it. Every
+//! transaction is persisted after signing and before broadcasting. A signed transaction is
+//! only signed again once the chain shows its account sequence unused or used by another
+//! transaction, so each message is included in at most one transaction. While the node can't
+//! tell which transaction used the sequence, e.g. because its transaction indexer is disabled or
+//! lagging behind, the entry is kept signed and checked again later.
+//!
+//! The guarantee only holds while a single outbox dispatches the messages of a store and the
+//! signing account isn't used for anything else at the same time.
+//!
+//! [`FileStore`] keeps one JSON file per message in a directory and `SledStore` (feature
+//! `outbox-sled`) keeps them in a sled database. Other storage like sqlite can be plugged in by
+//! implementing [`OutboxStore`].
+//!
+//! ```no_run
+//! # async fn example(client: gevulot_rs::GevulotClient, msg: gevulot_rs::proto::gevulot::gevulot::MsgCreateTask) -> gevulot_rs::error::Result<()> {
+//! use gevulot_rs::outbox::{FileStore, Outbox};
+//! use std::{sync::Arc, time::Duration};
+//!
+//! let store = Arc::new(FileStore::open("/var/lib/submitter/outbox")?);
+//! let outbox = Outbox::new(client.base_client.clone(), store)?;
+//! outbox.spawn(Duration::from_secs(5));
+//! let id = outbox.enqueue(&msg, "")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use cosmos_sdk_proto::prost::{Message, Name};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::{Notify, RwLock},
+    task::JoinHandle,
+    time::Duration,
+};
+
+use crate::{
+    base_client::{BaseClient, SignedTx},
+    error::{Error, Result, SDK_CODESPACE},
+};
+
+/// Number of attempts after which a message which couldn't be signed is given up by default.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 10;
+
+// File of the FileStore and key of the SledStore holding the ID following the highest removed
+// entry
+const NEXT_ID_FILE: &str = "next-id";
+
+// Code of the sdk error for transactions which are already in the mempool
+const CODE_TX_IN_MEMPOOL_CACHE: u32 = 19;
+
+/// State of a message in the outbox.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum EntryState {
+    /// Waiting to be signed.
+    Pending,
+    /// Signed and possibly broadcast, the transaction may still be included.
+    Signed {
+        hash: String,
+        sequence: u64,
+        #[serde(with = "base64_bytes")]
+        tx_bytes: Vec<u8>,
+    },
+    /// Broadcast and accepted by the node.
+    Sent { hash: String },
+    /// Given up, the message was never included in a transaction.
+    Failed { error: String },
+}
+
+/// Message in the outbox.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub type_url: String,
+    #[serde(with = "base64_bytes")]
+    pub value: Vec<u8>,
+    pub memo: String,
+    #[serde(flatten)]
+    pub state: EntryState,
+    /// Number of failed attempts to submit the message.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+/// Durable storage of outbox entries.
+pub trait OutboxStore: std::fmt::Debug + Send + Sync {
+    /// Stores a new or changed entry, the entry has to be durable once this returns.
+    fn save(&self, entry: &OutboxEntry) -> Result<()>;
+
+    /// Removes an entry.
+    fn remove(&self, id: u64) -> Result<()>;
+
+    /// Returns all entries ordered by their ID.
+    fn load(&self) -> Result<Vec<OutboxEntry>>;
+
+    /// Returns an ID greater than the ID of every entry saved so far, also of removed ones, so
+    /// IDs are never reused.
+    fn next_id(&self) -> Result<u64>;
+}
+
+/// Store keeping entries in memory, for tests.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    entries: Mutex<BTreeMap<u64, OutboxEntry>>,
+    next_id: AtomicU64,
+}
+
+impl OutboxStore for MemoryStore {
+    fn save(&self, entry: &OutboxEntry) -> Result<()> {
+        self.lock().insert(entry.id, entry.clone());
+        self.next_id.fetch_max(entry.id + 1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn remove(&self, id: u64) -> Result<()> {
+        self.lock().remove(&id);
+        Ok(())
+    }
+
+    fn load(&self) -> Result<Vec<OutboxEntry>> {
+        Ok(self.lock().values().cloned().collect())
+    }
+
+    fn next_id(&self) -> Result<u64> {
+        Ok(self.next_id.load(Ordering::SeqCst))
+    }
+}
+
+impl MemoryStore {
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<u64, OutboxEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Store keeping every entry in a JSON file in a directory.
+///
+/// Files are replaced atomically, so an entry is either in its old or its new state after a
+/// crash. The ID following the highest removed entry is kept in a `next-id` file.
+#[derive(Debug)]
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// Opens the store in a directory, creating the directory if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the directory can't be created.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(io_error)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, id: u64) -> PathBuf {
+        self.dir.join(format!("{id:020}.json"))
+    }
+
+    fn next_id_path(&self) -> PathBuf {
+        self.dir.join(NEXT_ID_FILE)
+    }
+
+    // Replaces a file atomically and durably
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp).map_err(io_error)?;
+        std::io::Write::write_all(&mut file, data).map_err(io_error)?;
+        file.sync_all().map_err(io_error)?;
+        std::fs::rename(&tmp, path).map_err(io_error)?;
+        self.sync_dir()
+    }
+
+    // Makes renames and removals in the directory durable
+    fn sync_dir(&self) -> Result<()> {
+        #[cfg(unix)]
+        std::fs::File::open(&self.dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(io_error)?;
+        Ok(())
+    }
+
+    fn stored_next_id(&self) -> Result<u64> {
+        match std::fs::read_to_string(self.next_id_path()) {
+            Ok(id) => id
+                .trim()
+                .parse()
+                .map_err(|e| Error::DecodeError(format!("invalid {NEXT_ID_FILE}: {e}"))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+}
+
+impl OutboxStore for FileStore {
+    fn save(&self, entry: &OutboxEntry) -> Result<()> {
+        let json = serde_json::to_vec(entry).map_err(|e| Error::EncodeError(e.to_string()))?;
+        self.write(&self.path(entry.id), &json)
+    }
+
+    fn remove(&self, id: u64) -> Result<()> {
+        // Remember the ID first, it may be the highest one
+        if self.stored_next_id()? <= id {
+            self.write(&self.next_id_path(), (id + 1).to_string().as_bytes())?;
+        }
+        match std::fs::remove_file(self.path(id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(e)),
+            _ => self.sync_dir(),
+        }
+    }
+
+    fn load(&self) -> Result<Vec<OutboxEntry>> {
+        let mut entries = Vec::new();
+        for file in std::fs::read_dir(&self.dir).map_err(io_error)? {
+            let path = file.map_err(io_error)?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let json = std::fs::read(&path).map_err(io_error)?;
+                entries.push(
+                    serde_json::from_slice::<OutboxEntry>(&json)
+                        .map_err(|e| Error::DecodeError(e.to_string()))?,
+                );
+            }
+        }
+        entries.sort_by_key(|entry| entry.id);
+        Ok(entries)
+    }
+
+    fn next_id(&self) -> Result<u64> {
+        let next_entry_id = self.load()?.last().map_or(0, |entry| entry.id + 1);
+        Ok(next_entry_id.max(self.stored_next_id()?))
+    }
+}
+
+/// Store keeping entries in a [sled](https://docs.rs/sled) database.
+///
+/// Entries are stored as JSON in the `outbox` tree and every change is flushed to disk before
+/// the store returns.
+#[cfg(feature = "outbox-sled")]
+#[derive(Debug)]
+pub struct SledStore {
+    entries: sled::Tree,
+    meta: sled::Tree,
+}
+
+#[cfg(feature = "outbox-sled")]
+impl SledStore {
+    /// Opens the store in a database directory, creating the database if it doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the database can't be opened.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::with_db(&sled::open(path).map_err(sled_error)?)
+    }
+
+    /// Opens the store in an open database, e.g. one shared with the application.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the trees of the store can't be opened.
+    pub fn with_db(db: &sled::Db) -> Result<Self> {
+        Ok(Self {
+            entries: db.open_tree("outbox").map_err(sled_error)?,
+            meta: db.open_tree("outbox-meta").map_err(sled_error)?,
+        })
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.entries.flush().map_err(sled_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "outbox-sled")]
+impl OutboxStore for SledStore {
+    fn save(&self, entry: &OutboxEntry) -> Result<()> {
+        let json = serde_json::to_vec(entry).map_err(|e| Error::EncodeError(e.to_string()))?;
+        // Big endian keys keep the entries ordered by their ID
+        self.entries
+            .insert(entry.id.to_be_bytes(), json)
+            .map_err(sled_error)?;
+        self.flush()
+    }
+
+    fn remove(&self, id: u64) -> Result<()> {
+        self.meta
+            .fetch_and_update(NEXT_ID_FILE, |next_id| {
+                let next_id = next_id.map_or(0, id_from_bytes).max(id + 1);
+                Some(next_id.to_be_bytes().to_vec())
+            })
+            .map_err(sled_error)?;
+        self.entries.remove(id.to_be_bytes()).map_err(sled_error)?;
+        self.flush()
+    }
+
+    fn load(&self) -> Result<Vec<OutboxEntry>> {
+        self.entries
+            .iter()
+            .values()
+            .map(|json| {
+                serde_json::from_slice(&json.map_err(sled_error)?)
+                    .map_err(|e| Error::DecodeError(e.to_string()))
+            })
+            .collect()
+    }
+
+    fn next_id(&self) -> Result<u64> {
+        let next_entry_id = self
+            .entries
+            .last()
+            .map_err(sled_error)?
+            .map_or(0, |(key, _)| id_from_bytes(&key) + 1);
+        let stored_next_id = self
+            .meta
+            .get(NEXT_ID_FILE)
+            .map_err(sled_error)?
+            .map_or(0, |next_id| id_from_bytes(&next_id));
+        Ok(next_entry_id.max(stored_next_id))
+    }
+}
+
+#[cfg(feature = "outbox-sled")]
+fn id_from_bytes(bytes: &[u8]) -> u64 {
+    u64::from_be_bytes(bytes.try_into().unwrap_or_default())
+}
+
+#[cfg(feature = "outbox-sled")]
+fn sled_error(error: sled::Error) -> Error {
+    Error::Unknown(format!("outbox store: {error}"))
+}
+
+fn prune(store: &dyn OutboxStore) -> Result<usize> {
+    let mut pruned = 0;
+    for entry in store.load()? {
+        if matches!(
+            entry.state,
+            EntryState::Sent { .. } | EntryState::Failed { .. }
+        ) {
+            store.remove(entry.id)?;
+            pruned += 1;
+        }
+    }
+    Ok(pruned)
+}
+
+fn io_error(error: std::io::Error) -> Error {
+    Error::Unknown(format!("outbox store: {error}"))
+}
+
+/// Queue persisting messages before a dispatcher submits them.
+#[derive(Clone, Debug)]
+pub struct Outbox {
+    base_client: Arc<RwLock<BaseClient>>,
+    store: Arc<dyn OutboxStore>,
+    next_id: Arc<AtomicU64>,
+    wake: Arc<Notify>,
+    // Held while dispatching so entries are never submitted twice concurrently
+    dispatching: Arc<tokio::sync::Mutex<()>>,
+    max_attempts: u32,
+}
+
+/// Chain operations of the dispatcher.
+trait Chain {
+    /// Signs a transaction with the message at the next account sequence.
+    async fn sign(&mut self, msg: cosmrs::Any, memo: &str) -> Result<SignedTx>;
+
+    /// Broadcasts a signed transaction, returning its hash.
+    async fn broadcast(&mut self, tx: &SignedTx) -> Result<String>;
+
+    /// Returns true if the transaction with the hash was included in a block.
+    async fn is_included(&mut self, hash: &str) -> Result<bool>;
+
+    /// Returns the sequence of the signing account on the chain, the one of its next
+    /// transaction.
+    async fn account_sequence(&mut self) -> Result<u64>;
+
+    /// Returns the hash of the included transaction of the signing account with the sequence,
+    /// None if the node doesn't know it.
+    async fn tx_with_sequence(&mut self, sequence: u64) -> Result<Option<String>>;
+
+    /// Forgets the cached account sequence so the next transaction is signed with the one of
+    /// the chain.
+    fn reset_sequence(&mut self);
+}
+
+impl Chain for BaseClient {
+    async fn sign(&mut self, msg: cosmrs::Any, memo: &str) -> Result<SignedTx> {
+        self.sign_anys(vec![msg], memo).await
+    }
+
+    async fn broadcast(&mut self, tx: &SignedTx) -> Result<String> {
+        self.broadcast_signed(tx).await
+    }
+
+    async fn is_included(&mut self, hash: &str) -> Result<bool> {
+        match self.get_tx(hash).await {
+            Ok(_) => Ok(true),
+            Err(Error::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn account_sequence(&mut self) -> Result<u64> {
+        let address = signer_address(self)?;
+        Ok(self.get_account(&address).await?.sequence)
+    }
+
+    async fn tx_with_sequence(&mut self, sequence: u64) -> Result<Option<String>> {
+        let address = signer_address(self)?;
+        // The ante handler emits the signer's address and sequence for every transaction
+        let request = cosmos_sdk_proto::cosmos::tx::v1beta1::GetTxsEventRequest {
+            query: format!("tx.acc_seq='{}/{}'", address, sequence),
+            limit: 1,
+            ..Default::default()
+        };
+        let response = self.tx_client.get_txs_event(request).await?.into_inner();
+        Ok(response
+            .tx_responses
+            .into_iter()
+            .next()
+            .map(|tx_response| tx_response.txhash))
+    }
+
+    fn reset_sequence(&mut self) {
+        self.account_sequence = None;
+    }
+}
+
+fn signer_address(base_client: &BaseClient) -> Result<String> {
+    base_client
+        .address
+        .clone()
+        .ok_or_else(|| Error::Unknown("no signer set".to_string()))
+}
+
+/// Use of the account sequence of a signed transaction.
+#[derive(Debug, PartialEq, Eq)]
+enum Slot {
+    /// Not used yet, the transaction wasn't included.
+    Free,
+    /// Used by the transaction itself.
+    Included,
+    /// Used by another transaction, the transaction won't be included anymore.
+    Taken,
+    /// Used, but the node doesn't know by which transaction.
+    Unknown,
+}
+
+async fn slot(chain: &mut impl Chain, tx: &SignedTx) -> Result<Slot> {
+    if chain.is_included(&tx.hash).await? {
+        return Ok(Slot::Included);
+    }
+    if chain.account_sequence().await? <= tx.sequence {
+        return Ok(Slot::Free);
+    }
+    // The transaction might just not be indexed yet
+    Ok(match chain.tx_with_sequence(tx.sequence).await? {
+        Some(hash) if hash.eq_ignore_ascii_case(&tx.hash) => Slot::Included,
+        Some(_) => Slot::Taken,
+        None => Slot::Unknown,
+    })
+}
+
+fn unknown_slot(tx: &SignedTx) -> Error {
+    Error::Unknown(format!(
+        "sequence {} was used, but the node doesn't know whether by transaction {}",
+        tx.sequence, tx.hash
+    ))
+}
+
+impl Outbox {
+    /// Creates an outbox submitting the messages of a store.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_client` - The client signing and broadcasting the messages.
+    /// * `store` - The store of the messages.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the entries can't be loaded from the store.
+    pub fn new(base_client: Arc<RwLock<BaseClient>>, store: Arc<dyn OutboxStore>) -> Result<Self> {
+        Ok(Self {
+            base_client,
+            next_id: Arc::new(AtomicU64::new(store.next_id()?)),
+            store,
+            wake: Arc::new(Notify::new()),
+            dispatching: Arc::default(),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        })
+    }
+
+    /// Sets the number of attempts after which a message which couldn't be signed is given up.
+    ///
+    /// Signed transactions are never given up as they may still be included.
+    pub fn set_max_attempts(&mut self, max_attempts: u32) {
+        self.max_attempts = max_attempts;
+    }
+
+    /// Persists a message and wakes up the dispatcher.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message to be sent.
+    /// * `memo` - The memo of the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the ID of the outbox entry or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the message can't be persisted.
+    pub fn enqueue<M: Message + Name>(&self, msg: &M, memo: &str) -> Result<u64> {
+        let entry = OutboxEntry {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            type_url: M::type_url(),
+            value: msg.encode_to_vec(),
+            memo: memo.to_string(),
+            state: EntryState::Pending,
+            attempts: 0,
+            last_error: None,
+        };
+        self.store.save(&entry)?;
+        self.wake.notify_one();
+        Ok(entry.id)
+    }
+
+    /// Returns all entries of the outbox.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the entries can't be loaded from the store.
+    pub fn entries(&self) -> Result<Vec<OutboxEntry>> {
+        self.store.load()
+    }
+
+    /// Removes the entries which were sent or given up.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the number of removed entries or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the store fails.
+    pub fn prune(&self) -> Result<usize> {
+        prune(self.store.as_ref())
+    }
+
+    /// Tries to submit every pending or signed entry once.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the number of entries sent or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the store fails. Failed submissions are recorded
+    /// in the entries instead.
+    pub async fn dispatch(&self) -> Result<usize> {
+        let _dispatching = self.dispatching.lock().await;
+        let mut sent = 0;
+        for mut entry in self.store.load()? {
+            if matches!(entry.state, EntryState::Pending | EntryState::Signed { .. }) {
+                let mut base_client = self.base_client.write().await;
+                dispatch_entry(
+                    self.store.as_ref(),
+                    self.max_attempts,
+                    &mut *base_client,
+                    &mut entry,
+                )
+                .await?;
+                sent += usize::from(matches!(entry.state, EntryState::Sent { .. }));
+            }
+        }
+        Ok(sent)
+    }
+
+    /// Spawns a dispatcher submitting new entries right away and retrying the others in an
+    /// interval.
+    pub fn spawn(&self, retry_interval: Duration) -> JoinHandle<()> {
+        let outbox = self.clone();
+        crate::background::spawn_named("gevulot-outbox", async move {
+            loop {
+                if let Err(e) = outbox.dispatch().await {
+                    log::error!("Failed to dispatch outbox: {}", e);
+                }
+                let timer = outbox.base_client.read().await.timer();
+                tokio::select! {
+                    _ = outbox.wake.notified() => {}
+                    _ = timer.sleep(retry_interval) => {}
+                }
+            }
+        })
+    }
+}
+
+async fn dispatch_entry(
+    store: &dyn OutboxStore,
+    max_attempts: u32,
+    chain: &mut impl Chain,
+    entry: &mut OutboxEntry,
+) -> Result<()> {
+    let tx = match &entry.state {
+        EntryState::Signed {
+            hash,
+            sequence,
+            tx_bytes,
+        } => {
+            let tx = SignedTx {
+                tx_bytes: tx_bytes.clone(),
+                hash: hash.clone(),
+                sequence: *sequence,
+            };
+            // The transaction may have been broadcast before a crash
+            match slot(chain, &tx).await {
+                Ok(Slot::Included) => {
+                    return transition(store, entry, EntryState::Sent { hash: tx.hash })
+                }
+                // Broadcast again, a taken sequence is detected when the node rejects it
+                Ok(Slot::Free | Slot::Taken) => tx,
+                Ok(Slot::Unknown) => {
+                    return record_error(store, max_attempts, entry, unknown_slot(&tx), false)
+                }
+                Err(e) => return record_error(store, max_attempts, entry, e, false),
+            }
+        }
+        _ => {
+            let msg = cosmrs::Any {
+                type_url: entry.type_url.clone(),
+                value: entry.value.clone(),
+            };
+            match chain.sign(msg, &entry.memo).await {
+                Ok(tx) => {
+                    let state = EntryState::Signed {
+                        hash: tx.hash.clone(),
+                        sequence: tx.sequence,
+                        tx_bytes: tx.tx_bytes.clone(),
+                    };
+                    transition(store, entry, state)?;
+                    tx
+                }
+                Err(e) => return record_error(store, max_attempts, entry, e, true),
+            }
+        }
+    };
+
+    match chain.broadcast(&tx).await {
+        Ok(hash) => transition(store, entry, EntryState::Sent { hash }),
+        Err(e) if e.is_sequence_mismatch() => {
+            chain.reset_sequence();
+            // The sequence might have been used by this very transaction, it is only signed
+            // again once it is known to be unused or used by another transaction
+            match slot(chain, &tx).await {
+                Ok(Slot::Included) => transition(store, entry, EntryState::Sent { hash: tx.hash }),
+                Ok(Slot::Free | Slot::Taken) => {
+                    entry.state = EntryState::Pending;
+                    record_error(store, max_attempts, entry, e, true)
+                }
+                Ok(Slot::Unknown) => {
+                    record_error(store, max_attempts, entry, unknown_slot(&tx), false)
+                }
+                Err(error) => record_error(store, max_attempts, entry, error, false),
+            }
+        }
+        Err(Error::Tx(e)) if e.codespace == SDK_CODESPACE && e.code == CODE_TX_IN_MEMPOOL_CACHE => {
+            transition(store, entry, EntryState::Sent { hash: tx.hash })
+        }
+        // Rejected by the node, the transaction won't be included
+        Err(Error::Tx(e)) => give_up(store, entry, Error::Tx(e)),
+        Err(e) => record_error(store, max_attempts, entry, e, false),
+    }
+}
+
+fn transition(store: &dyn OutboxStore, entry: &mut OutboxEntry, state: EntryState) -> Result<()> {
+    entry.state = state;
+    store.save(entry)
+}
+
+fn give_up(store: &dyn OutboxStore, entry: &mut OutboxEntry, error: Error) -> Result<()> {
+    log::warn!("Giving up outbox entry {}: {}", entry.id, error);
+    entry.last_error = Some(error.to_string());
+    transition(
+        store,
+        entry,
+        EntryState::Failed {
+            error: error.to_string(),
+        },
+    )
+}
+
+// Entries which were never signed may be given up, signed ones are retried forever
+fn record_error(
+    store: &dyn OutboxStore,
+    max_attempts: u32,
+    entry: &mut OutboxEntry,
+    error: Error,
+    unsigned: bool,
+) -> Result<()> {
+    entry.attempts += 1;
+    // Messages signed with a used sequence are signed again
+    let retryable = error.is_retryable() || error.is_sequence_mismatch();
+    if unsigned && (!retryable || entry.attempts >= max_attempts) {
+        return give_up(store, entry, error);
+    }
+    log::debug!("Failed to submit outbox entry {}: {}", entry.id, error);
+    entry.last_error = Some(error.to_string());
+    store.save(entry)
+}
+
+mod base64_bytes {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, state: EntryState) -> OutboxEntry {
+        OutboxEntry {
+            id,
+            type_url: "/gevulot.gevulot.MsgDeleteTask".to_string(),
+            value: vec![10, 5],
+            memo: String::new(),
+            state,
+            attempts: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_file_store() {
+        let dir = std::env::temp_dir().join(format!("gevulot-outbox-{}", std::process::id()));
+        let store = FileStore::open(&dir).unwrap();
+        let signed = entry(
+            2,
+            EntryState::Signed {
+                hash: "AB12".to_string(),
+                sequence: 7,
+                tx_bytes: vec![1, 2, 3],
+            },
+        );
+        store.save(&signed).unwrap();
+        store.save(&entry(1, EntryState::Pending)).unwrap();
+        store
+            .save(&entry(
+                3,
+                EntryState::Sent {
+                    hash: "CD34".to_string(),
+                },
+            ))
+            .unwrap();
+        store.remove(3).unwrap();
+        store.remove(3).unwrap();
+
+        // A reopened store sees the same entries and doesn't reuse the removed ID
+        let store = FileStore::open(&dir).unwrap();
+        let entries = store.load().unwrap();
+        let next_id = store.next_id().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(entries, vec![entry(1, EntryState::Pending), signed]);
+        assert_eq!(next_id, 4);
+    }
+
+    #[cfg(feature = "outbox-sled")]
+    #[test]
+    fn test_sled_store() {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        let store = SledStore::with_db(&db).unwrap();
+        store.save(&entry(2, EntryState::Pending)).unwrap();
+        store.save(&entry(1, EntryState::Pending)).unwrap();
+        store.save(&entry(3, EntryState::Pending)).unwrap();
+        store.remove(3).unwrap();
+
+        let store = SledStore::with_db(&db).unwrap();
+        assert_eq!(
+            store.load().unwrap(),
+            vec![entry(1, EntryState::Pending), entry(2, EntryState::Pending)]
+        );
+        assert_eq!(store.next_id().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_prune() {
+        let store = Arc::new(MemoryStore::default());
+        store.save(&entry(4, EntryState::Pending)).unwrap();
+        store
+            .save(&entry(
+                5,
+                EntryState::Failed {
+                    error: "insufficient funds".to_string(),
+                },
+            ))
+            .unwrap();
+        let json = serde_json::to_value(store.load().unwrap()[1].clone()).unwrap();
+        assert_eq!(json["state"], "failed");
+        assert_eq!(json["value"], "CgU=");
+
+        // IDs continue after the stored entries, e.g. after a restart
+        assert_eq!(store.next_id().unwrap(), 6);
+        assert_eq!(prune(store.as_ref()).unwrap(), 1);
+        assert_eq!(store.load().unwrap(), vec![entry(4, EntryState::Pending)]);
+    }
+
+    #[derive(Default)]
+    struct FakeChain {
+        // Sequence of the account on the chain
+        sequence: u64,
+        // Hashes of the transactions the node finds by hash
+        indexed: Vec<String>,
+        // Hashes of the transactions using a sequence, as found by the node's event index
+        slots: BTreeMap<u64, String>,
+        // Errors returned by the next broadcasts
+        broadcast_errors: Vec<Error>,
+        signed: Vec<SignedTx>,
+        broadcast: Vec<String>,
+    }
+
+    impl Chain for FakeChain {
+        async fn sign(&mut self, _msg: cosmrs::Any, _memo: &str) -> Result<SignedTx> {
+            let tx = SignedTx {
+                tx_bytes: vec![self.signed.len() as u8],
+                hash: format!("TX{}", self.signed.len()),
+                sequence: self.sequence,
+            };
+            self.signed.push(tx.clone());
+            Ok(tx)
+        }
+
+        async fn broadcast(&mut self, tx: &SignedTx) -> Result<String> {
+            self.broadcast.push(tx.hash.clone());
+            match self.broadcast_errors.pop() {
+                Some(error) => Err(error),
+                None => Ok(tx.hash.clone()),
+            }
+        }
+
+        async fn is_included(&mut self, hash: &str) -> Result<bool> {
+            Ok(self.indexed.iter().any(|indexed| indexed == hash))
+        }
+
+        async fn account_sequence(&mut self) -> Result<u64> {
+            Ok(self.sequence)
+        }
+
+        async fn tx_with_sequence(&mut self, sequence: u64) -> Result<Option<String>> {
+            Ok(self.slots.get(&sequence).cloned())
+        }
+
+        fn reset_sequence(&mut self) {}
+    }
+
+    fn wrong_sequence() -> Error {
+        Error::Tx(Box::new(crate::error::TxError {
+            tx_hash: String::new(),
+            code: crate::error::WRONG_SEQUENCE_CODE,
+            codespace: SDK_CODESPACE.to_string(),
+            raw_log: "account sequence mismatch, expected 4, got 3: incorrect account sequence"
+                .to_string(),
+            log_entries: Vec::new(),
+            events: Vec::new(),
+        }))
+    }
+
+    fn signed(hash: &str, sequence: u64) -> EntryState {
+        EntryState::Signed {
+            hash: hash.to_string(),
+            sequence,
+            tx_bytes: vec![1],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_pending_entry() {
+        let store = MemoryStore::default();
+        let mut chain = FakeChain {
+            sequence: 3,
+            ..Default::default()
+        };
+        let mut pending = entry(1, EntryState::Pending);
+        dispatch_entry(&store, DEFAULT_MAX_ATTEMPTS, &mut chain, &mut pending)
+            .await
+            .unwrap();
+        assert_eq!(
+            store.load().unwrap(),
+            vec![entry(
+                1,
+                EntryState::Sent {
+                    hash: "TX0".to_string()
+                }
+            )]
+        );
+        assert_eq!(chain.broadcast, vec!["TX0".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_signed_entry_not_indexed_yet() {
+        // The sequence was used by the transaction, but the node can't find it by its hash
+        let store = MemoryStore::default();
+        let mut chain = FakeChain {
+            sequence: 4,
+            slots: BTreeMap::from([(3, "AB12".to_string())]),
+            ..Default::default()
+        };
+        let mut entry = entry(1, signed("AB12", 3));
+        dispatch_entry(&store, DEFAULT_MAX_ATTEMPTS, &mut chain, &mut entry)
+            .await
+            .unwrap();
+        assert_eq!(
+            entry.state,
+            EntryState::Sent {
+                hash: "AB12".to_string()
+            }
+        );
+        assert!(chain.signed.is_empty());
+        assert!(chain.broadcast.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_signed_entry_with_unknown_slot() {
+        // Without knowing which transaction used the sequence the entry isn't signed again
+        let store = MemoryStore::default();
+        let mut chain = FakeChain {
+            sequence: 4,
+            ..Default::default()
+        };
+        let mut entry = entry(1, signed("AB12", 3));
+        for _ in 0..2 {
+            dispatch_entry(&store, DEFAULT_MAX_ATTEMPTS, &mut chain, &mut entry)
+                .await
+                .unwrap();
+        }
+        assert_eq!(entry.state, signed("AB12", 3));
+        assert_eq!(entry.attempts, 2);
+        assert!(chain.signed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_signed_entry_with_taken_slot() {
+        // Another transaction used the sequence, so the message is signed again
+        let store = MemoryStore::default();
+        let mut chain = FakeChain {
+            sequence: 4,
+            slots: BTreeMap::from([(3, "CD34".to_string())]),
+            broadcast_errors: vec![wrong_sequence()],
+            ..Default::default()
+        };
+        let mut entry = entry(1, signed("AB12", 3));
+        dispatch_entry(&store, DEFAULT_MAX_ATTEMPTS, &mut chain, &mut entry)
+            .await
+            .unwrap();
+        assert_eq!(entry.state, EntryState::Pending);
+        assert_eq!(entry.attempts, 1);
+
+        dispatch_entry(&store, DEFAULT_MAX_ATTEMPTS, &mut chain, &mut entry)
+            .await
+            .unwrap();
+        assert_eq!(
+            entry.state,
+            EntryState::Sent {
+                hash: "TX0".to_string()
+            }
+        );
+        assert_eq!(chain.signed[0].sequence, 4);
+        assert_eq!(chain.broadcast, vec!["AB12".to_string(), "TX0".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_gives_up_rejected_entry() {
+        let store = MemoryStore::default();
+        let mut chain = FakeChain {
+            broadcast_errors: vec![Error::Tx(Box::new(crate::error::TxError {
+                tx_hash: String::new(),
+                code: 5,
+                codespace: SDK_CODESPACE.to_string(),
+                raw_log: "insufficient funds".to_string(),
+                log_entries: Vec::new(),
+                events: Vec::new(),
+            }))],
+            ..Default::default()
+        };
+        let mut entry = entry(1, EntryState::Pending);
+        dispatch_entry(&store, DEFAULT_MAX_ATTEMPTS, &mut chain, &mut entry)
+            .await
+            .unwrap();
+        assert!(matches!(entry.state, EntryState::Failed { .. }));
+        assert_eq!(store.load().unwrap(), vec![entry]);
+    }
+}
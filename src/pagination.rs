@@ -0,0 +1,166 @@
+//! Pagination of list queries.
+//!
+//! List queries return their results in pages. A page ends with a [`PageToken`] pointing at the
+//! next page, which is passed back in [`PageOptions`] to continue listing.
+//!
+//! Tokens serialize as URL-safe base64 strings, so services exposing Gevulot lists can hand them
+//! to their own clients as cursors and accept them back unchanged.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    error::{Error, Result},
+    proto::cosmos::base::query::v1beta1::{PageRequest, PageResponse},
+};
+
+/// Number of items requested per page when listing everything.
+pub(crate) const DEFAULT_PAGE_LIMIT: u64 = 100;
+
+/// Opaque token pointing at the next page of a list query.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PageToken(Vec<u8>);
+
+/// Portable cursor of a list query, an alias of [`PageToken`].
+pub type Cursor = PageToken;
+
+impl PageToken {
+    /// Returns the raw key of the token as used by the chain.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the token for the page following a response, None if it was the last page.
+    pub(crate) fn next(response: Option<&PageResponse>) -> Option<Self> {
+        response
+            .filter(|page| !page.next_key.is_empty())
+            .map(|page| PageToken(page.next_key.clone()))
+    }
+}
+
+impl From<Vec<u8>> for PageToken {
+    fn from(key: Vec<u8>) -> Self {
+        PageToken(key)
+    }
+}
+
+impl std::fmt::Display for PageToken {
+    /// Formats the token as URL-safe base64 without padding.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&URL_SAFE_NO_PAD.encode(&self.0))
+    }
+}
+
+impl std::str::FromStr for PageToken {
+    type Err = Error;
+
+    /// Parses a token formatted with [`PageToken`]'s `Display` implementation.
+    fn from_str(s: &str) -> Result<Self> {
+        URL_SAFE_NO_PAD
+            .decode(s)
+            .map(PageToken)
+            .map_err(|e| Error::Parse(format!("invalid page token: {}", e)))
+    }
+}
+
+impl Serialize for PageToken {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for PageToken {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Options for fetching a single page of a list query.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PageOptions {
+    /// Maximum number of items in the page, the chain's default if None.
+    pub limit: Option<u64>,
+    /// Token of the page to fetch, the first page if None.
+    pub token: Option<PageToken>,
+    /// Whether to list the items in descending order.
+    pub reverse: bool,
+}
+
+impl PageOptions {
+    /// Creates options for the first page.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of items in the page.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Sets the token of the page to fetch.
+    pub fn token(mut self, token: PageToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// Lists the items in descending order.
+    pub fn reverse(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    /// Converts the options into the request sent to the chain.
+    pub(crate) fn to_request(&self) -> PageRequest {
+        PageRequest {
+            key: self
+                .token
+                .as_ref()
+                .map(|token| token.0.clone())
+                .unwrap_or_default(),
+            offset: 0,
+            limit: self.limit.unwrap_or_default(),
+            count_total: false,
+            reverse: self.reverse,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_token() {
+        assert_eq!(PageToken::next(None), None);
+        let last = PageResponse {
+            next_key: vec![],
+            total: 0,
+        };
+        assert_eq!(PageToken::next(Some(&last)), None);
+
+        let response = PageResponse {
+            next_key: vec![1, 2, 3],
+            total: 0,
+        };
+        let token = PageToken::next(Some(&response)).unwrap();
+        let request = PageOptions::new().limit(10).token(token).to_request();
+        assert_eq!(request.key, vec![1, 2, 3]);
+        assert_eq!(request.limit, 10);
+        assert!(!request.reverse);
+    }
+
+    #[test]
+    fn test_cursor_serde() {
+        let cursor = Cursor::from(vec![0xfb, 0xff, 0x01]);
+        assert_eq!(cursor.to_string(), "-_8B");
+        assert_eq!("-_8B".parse::<Cursor>().unwrap(), cursor);
+
+        let json = serde_json::to_string(&cursor).unwrap();
+        assert_eq!(json, "\"-_8B\"");
+        assert_eq!(serde_json::from_str::<Cursor>(&json).unwrap(), cursor);
+        assert!(serde_json::from_str::<Cursor>("\"not base64!\"").is_err());
+    }
+}
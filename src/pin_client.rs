@@ -1,19 +1,258 @@
+use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::{
+    sync::{broadcast, RwLock},
+    time::{Duration, Instant},
+};
 
 use crate::{
     base_client::BaseClient,
+    builders::{MsgBatch, MsgExtendPinBuilder},
     error::{Error, Result},
+    event_fetcher::EventHandler,
+    events::{GevulotEvent, PinEvent},
+    models::{PinAck, PinStatus},
+    origin::Origin,
+    pagination::{PageOptions, PageToken, DEFAULT_PAGE_LIMIT},
     proto::gevulot::gevulot::{
         MsgAckPin, MsgAckPinResponse, MsgCreatePin, MsgCreatePinResponse, MsgDeletePin,
         MsgDeletePinResponse,
     },
 };
 
+/// First interval in which [`PinClient::wait_for_acks`] polls the pin, doubled after every poll.
+const ACK_POLL_INITIAL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest interval in which [`PinClient::wait_for_acks`] polls the pin.
+const ACK_POLL_MAX_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Event handler waking up [`PinClient::wait_for_acks`] when a worker acknowledges a pin.
+///
+/// Register a clone with an [`crate::EventFetcher`] and another one with the pin client through
+/// [`PinClient::set_notifier`]. Pins are then re-checked as soon as they are acknowledged
+/// instead of on the next poll.
+#[derive(Clone, Debug)]
+pub struct PinNotifier {
+    sender: broadcast::Sender<String>,
+}
+
+impl Default for PinNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PinNotifier {
+    /// Creates a new notifier.
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Self { sender }
+    }
+
+    /// Returns a receiver of the CIDs of acknowledged pins.
+    fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+}
+
+impl EventHandler for PinNotifier {
+    async fn handle_event(
+        &mut self,
+        event: &crate::Event,
+        block_height: crate::Height,
+    ) -> Result<()> {
+        if let Ok(GevulotEvent::Pin(PinEvent::Ack(event))) =
+            GevulotEvent::from_cosmos(event, block_height)
+        {
+            // Nobody waiting for any pin is fine
+            let _ = self.sender.send(event.cid);
+        }
+        Ok(())
+    }
+}
+
+/// A pin which didn't get the required acks, see [`PinClient::wait_for_acks`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PinReplicationError {
+    pub cid: String,
+    /// The number of acks which were required.
+    pub required: usize,
+    /// Workers which pinned the data.
+    pub acked_workers: Vec<String>,
+    /// Workers which failed to pin the data, with the error they reported.
+    pub failed_workers: Vec<(String, String)>,
+    /// Assigned workers which didn't acknowledge the pin yet.
+    pub pending_workers: Vec<String>,
+    /// True if the timeout passed, false if too many workers failed for the acks to arrive.
+    pub timed_out: bool,
+}
+
+impl std::fmt::Display for PinReplicationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "pin {} {} with {} of {} required acks",
+            self.cid,
+            if self.timed_out {
+                "timed out"
+            } else {
+                "failed"
+            },
+            self.acked_workers.len(),
+            self.required
+        )?;
+        if !self.failed_workers.is_empty() {
+            let failed = self
+                .failed_workers
+                .iter()
+                .map(|(worker, error)| format!("{} ({})", worker, error))
+                .collect::<Vec<_>>();
+            write!(f, ", failed: {}", failed.join(", "))?;
+        }
+        if !self.pending_workers.is_empty() {
+            write!(f, ", pending: {}", self.pending_workers.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Successful and failed acks of a pin, the latest ack of each worker counts.
+fn split_acks(status: &PinStatus) -> (Vec<PinAck>, Vec<PinAck>) {
+    let mut latest: Vec<&PinAck> = Vec::new();
+    for ack in &status.worker_acks {
+        match latest.iter_mut().find(|a| a.worker == ack.worker) {
+            Some(a) if a.block_height <= ack.block_height => *a = ack,
+            Some(_) => {}
+            None => latest.push(ack),
+        }
+    }
+    latest.into_iter().cloned().partition(|ack| ack.success)
+}
+
+/// Workers which pinned the data, failed to with their error, and didn't acknowledge the pin
+/// yet.
+fn replication(status: &PinStatus) -> (Vec<String>, Vec<(String, String)>, Vec<String>) {
+    let (acks, failures) = split_acks(status);
+    let pending = status
+        .assigned_workers
+        .iter()
+        .filter(|worker| {
+            !acks
+                .iter()
+                .chain(&failures)
+                .any(|ack| ack.worker == **worker)
+        })
+        .cloned()
+        .collect();
+    (
+        acks.into_iter().map(|ack| ack.worker).collect(),
+        failures
+            .into_iter()
+            .map(|ack| (ack.worker, ack.error.unwrap_or_default()))
+            .collect(),
+        pending,
+    )
+}
+
+/// Whether a pin can't get the required acks anymore. A pin without assigned workers, e.g.
+/// right after its creation, is still waiting for its assignment.
+fn replication_failed(status: &PinStatus, acked: usize, pending: usize, required: usize) -> bool {
+    !status.assigned_workers.is_empty() && acked + pending < required
+}
+
+/// Replication summary of a pin, see [`PinClient::health`].
+///
+/// The latest ack of each worker counts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PinHealth {
+    pub cid: String,
+    /// Number of workers which have to pin the data.
+    pub redundancy: u64,
+    /// Workers which pinned the data.
+    pub acked_workers: Vec<String>,
+    /// Workers which failed to pin the data, with the error they reported.
+    pub failed_workers: Vec<(String, String)>,
+    /// Assigned workers which didn't acknowledge the pin yet.
+    pub pending_workers: Vec<String>,
+}
+
+impl PinHealth {
+    fn new(cid: &str, redundancy: u64, status: &PinStatus) -> Self {
+        let (acked_workers, failed_workers, pending_workers) = replication(status);
+        Self {
+            cid: cid.to_string(),
+            redundancy,
+            acked_workers,
+            failed_workers,
+            pending_workers,
+        }
+    }
+
+    /// Returns the number of workers which pinned the data.
+    pub fn acked(&self) -> usize {
+        self.acked_workers.len()
+    }
+
+    /// Returns true if as many workers pinned the data as the redundancy requires.
+    pub fn is_fully_replicated(&self) -> bool {
+        self.acked() as u64 >= self.redundancy
+    }
+}
+
+/// A pin found by [`PinClient::list_expiring_within`].
+#[derive(Debug)]
+pub struct ExpiringPin {
+    pub pin: crate::models::Pin,
+    /// Height of the block the first worker acknowledged the pin in.
+    pub pinned_at: u64,
+    /// Time left until the pin lapses, zero if it already did.
+    pub expires_in: Duration,
+}
+
+/// Height of the first successful ack of a pin, None if no worker pinned it yet.
+fn pinned_at(pin: &crate::proto::gevulot::gevulot::Pin) -> Option<u64> {
+    pin.status
+        .as_ref()?
+        .worker_acks
+        .iter()
+        .filter(|ack| ack.success)
+        .map(|ack| ack.block_height)
+        .min()
+}
+
+/// Time left of a pin pinned at `pinned_time` for `time` seconds, both times in Unix seconds.
+fn expires_in(pinned_time: i64, time: u64, now: i64) -> Duration {
+    let expiry = pinned_time.saturating_add(i64::try_from(time).unwrap_or(i64::MAX));
+    Duration::from_secs(u64::try_from(expiry.saturating_sub(now)).unwrap_or_default())
+}
+
+/// Returns the time of the block at a height in Unix seconds.
+async fn block_time(base_client: &mut BaseClient, height: u64) -> Result<i64> {
+    let block = base_client
+        .get_block_by_height(i64::try_from(height).unwrap_or(i64::MAX))
+        .await?;
+    Ok(block
+        .header
+        .and_then(|header| header.time)
+        .ok_or("Block time not found")?
+        .seconds)
+}
+
+/// Returns the current chain time in Unix seconds, see [`crate::chain_clock`].
+async fn chain_time(base_client: &mut BaseClient) -> Result<i64> {
+    let time = base_client.chain_time().await?;
+    let seconds = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    Ok(i64::try_from(seconds).unwrap_or(i64::MAX))
+}
+
 /// Client for managing pins in the Gevulot system.
 #[derive(Debug, Clone)]
 pub struct PinClient {
     base_client: Arc<RwLock<BaseClient>>,
+    origin: Option<Arc<Origin>>,
+    notifier: Option<PinNotifier>,
 }
 
 impl PinClient {
@@ -27,11 +266,35 @@ impl PinClient {
     ///
     /// A new instance of PinClient.
     pub fn new(base_client: Arc<RwLock<BaseClient>>) -> Self {
-        Self { base_client }
+        Self {
+            base_client,
+            origin: None,
+            notifier: None,
+        }
+    }
+
+    /// Sets the notifier waking up [`PinClient::wait_for_acks`] on ack events.
+    ///
+    /// # Arguments
+    ///
+    /// * `notifier` - A notifier registered with an event fetcher.
+    pub fn set_notifier(&mut self, notifier: PinNotifier) {
+        self.notifier = Some(notifier);
+    }
+
+    /// Sets the origin labels added to every pin created by this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The origin, None stops adding origin labels.
+    pub fn set_origin(&mut self, origin: Option<Arc<Origin>>) {
+        self.origin = origin;
     }
 
     /// Lists all pins.
     ///
+    /// Fetches the pins page by page until all pins were received.
+    ///
     /// # Returns
     ///
     /// A Result containing a vector of pins or an error.
@@ -40,15 +303,168 @@ impl PinClient {
     ///
     /// This function will return an error if the request to the Gevulot client fails.
     pub async fn list(&mut self) -> Result<Vec<crate::proto::gevulot::gevulot::Pin>> {
-        let request = crate::proto::gevulot::gevulot::QueryAllPinRequest { pagination: None };
+        self.list_filtered(|_| true).await
+    }
+
+    /// Lists the pins created by an account.
+    ///
+    /// The chain cannot filter pins, so this fetches all pins page by page and keeps the
+    /// matching ones only.
+    ///
+    /// # Arguments
+    ///
+    /// * `creator` - The address of the creator.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of pins or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_by_creator(
+        &mut self,
+        creator: &str,
+    ) -> Result<Vec<crate::proto::gevulot::gevulot::Pin>> {
+        self.list_filtered(|pin| {
+            pin.metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.creator == creator)
+        })
+        .await
+    }
+
+    /// Lists the pins lapsing within a duration, the soonest first.
+    ///
+    /// The chain doesn't record when a pin was created, so the pinned time of a pin starts with
+    /// the block of its first successful ack. Pins no worker acknowledged yet are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `within` - How far to look ahead from the current chain time.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the expiring pins or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any request to the Gevulot client fails.
+    pub async fn list_expiring_within(&mut self, within: Duration) -> Result<Vec<ExpiringPin>> {
+        let pins = self.list().await?;
+        let mut base_client = self.base_client.write().await;
+        let now = chain_time(&mut base_client).await?;
+        // Pins acknowledged in the same block share its time
+        let mut block_times: HashMap<u64, i64> = HashMap::new();
+        let mut expiring = Vec::new();
+        for pin in pins {
+            let (Some(height), Some(spec)) = (pinned_at(&pin), pin.spec.as_ref()) else {
+                continue;
+            };
+            let pinned_time = match block_times.get(&height) {
+                Some(time) => *time,
+                None => {
+                    let time = block_time(&mut base_client, height).await?;
+                    block_times.insert(height, time);
+                    time
+                }
+            };
+            let expires_in = expires_in(pinned_time, spec.time, now);
+            if expires_in <= within {
+                expiring.push(ExpiringPin {
+                    pin: pin.into(),
+                    pinned_at: height,
+                    expires_in,
+                });
+            }
+        }
+        expiring.sort_by_key(|pin| pin.expires_in);
+        Ok(expiring)
+    }
+
+    // Lists all pins page by page, dropping the ones not matching the predicate right away
+    async fn list_filtered(
+        &mut self,
+        predicate: impl Fn(&crate::proto::gevulot::gevulot::Pin) -> bool,
+    ) -> Result<Vec<crate::proto::gevulot::gevulot::Pin>> {
+        let mut pins = Vec::new();
+        let mut options = PageOptions::new().limit(DEFAULT_PAGE_LIMIT);
+        loop {
+            let (page, next) = self.list_paged(options.clone()).await?;
+            pins.extend(page.into_iter().filter(|pin| predicate(pin)));
+            match next {
+                Some(token) => options.token = Some(token),
+                None => break,
+            }
+        }
+        Ok(pins)
+    }
+
+    /// Lists a single page of pins.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The size and position of the page.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the pins of the page and the token of the next page, None if this
+    /// was the last page, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_paged(
+        &mut self,
+        options: PageOptions,
+    ) -> Result<(Vec<crate::proto::gevulot::gevulot::Pin>, Option<PageToken>)> {
+        let request = crate::proto::gevulot::gevulot::QueryAllPinRequest {
+            pagination: Some(options.to_request()),
+        };
         let response = self
             .base_client
             .write()
             .await
             .gevulot_client
             .pin_all(request)
-            .await?;
-        Ok(response.into_inner().pin)
+            .await?
+            .into_inner();
+        Ok((response.pin, PageToken::next(response.pagination.as_ref())))
+    }
+
+    /// Lists all pins as models.
+    ///
+    /// Like `list`, but converts the pins into [`crate::models::Pin`]s.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of pins or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_models(&mut self) -> Result<Vec<crate::models::Pin>> {
+        Ok(self.list().await?.into_iter().map(Into::into).collect())
+    }
+
+    /// Gets a pin by its CID as a model.
+    ///
+    /// Like `get`, but converts the pin into a [`crate::models::Pin`] with parsed units and
+    /// metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The CID of the pin to retrieve.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the pin or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pin is not found or if the request to the Gevulot client fails.
+    pub async fn get_model(&mut self, cid: &str) -> Result<crate::models::Pin> {
+        Ok(self.get(cid).await?.into())
     }
 
     /// Gets a pin by its CID.
@@ -75,7 +491,7 @@ impl PinClient {
             .gevulot_client
             .pin(request)
             .await?;
-        response.into_inner().pin.ok_or(Error::NotFound)
+        response.into_inner().pin.ok_or(Error::NotFound(None))
     }
 
     /// Creates a new pin.
@@ -91,7 +507,10 @@ impl PinClient {
     /// # Errors
     ///
     /// This function will return an error if the request to the Gevulot client fails.
-    pub async fn create(&mut self, msg: MsgCreatePin) -> Result<MsgCreatePinResponse> {
+    pub async fn create(&mut self, mut msg: MsgCreatePin) -> Result<MsgCreatePinResponse> {
+        if let Some(origin) = &self.origin {
+            origin.apply(&mut msg.labels);
+        }
         let resp: MsgCreatePinResponse = self
             .base_client
             .write()
@@ -146,4 +565,332 @@ impl PinClient {
             .await?;
         Ok(resp)
     }
+
+    /// Summarizes how well a pin is replicated.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The CID of the pin.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the replication summary or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pin is not found or if the request to the Gevulot client fails.
+    pub async fn health(&mut self, cid: &str) -> Result<PinHealth> {
+        let pin = self.get(cid).await?;
+        let redundancy = pin.spec.map(|spec| spec.redundancy).unwrap_or_default();
+        let status: PinStatus = pin.status.unwrap_or_default().into();
+        Ok(PinHealth::new(cid, redundancy, &status))
+    }
+
+    /// Extends the retention of a pin.
+    ///
+    /// The chain can't change pins, so the pin is deleted and created again in a single
+    /// transaction, see [`MsgExtendPinBuilder`]. Its time is set to the time it had left, as
+    /// for [`PinClient::list_expiring_within`], plus the extension. The re-created pin has to be
+    /// acknowledged by the workers again.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The CID of the pin.
+    /// * `extra_time` - How much longer to keep the data.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the response of the re-created pin or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the pin is not found, no signer is set or if any
+    /// request to the Gevulot client fails.
+    pub async fn extend(
+        &mut self,
+        cid: &str,
+        extra_time: Duration,
+    ) -> Result<MsgCreatePinResponse> {
+        let pin = self.get(cid).await?;
+        let mut base_client = self.base_client.write().await;
+        let creator = base_client
+            .address
+            .clone()
+            .ok_or_else(|| Error::Unknown("no signer set".to_string()))?;
+        let time = pin.spec.as_ref().map(|spec| spec.time).unwrap_or_default();
+        let left = match pinned_at(&pin) {
+            Some(height) => {
+                let pinned_time = block_time(&mut base_client, height).await?;
+                let now = chain_time(&mut base_client).await?;
+                expires_in(pinned_time, time, now).as_secs()
+            }
+            None => time,
+        };
+        let (delete, create) = MsgExtendPinBuilder::default()
+            .creator(creator)
+            .pin(pin)
+            .time(left.saturating_add(extra_time.as_secs()))
+            .into_messages()?;
+        let batch = MsgBatch::new().msg(&delete)?.msg(&create)?;
+        let responses = base_client.send_batch_sync(batch, "").await?;
+        responses.decode(1)
+    }
+
+    /// Waits until enough workers acknowledged a pin.
+    ///
+    /// The pin is re-checked whenever the notifier set with [`PinClient::set_notifier`] reports
+    /// an ack for it, and polled in exponentially growing intervals otherwise. Fails early once
+    /// so many assigned workers failed that the required acks can't arrive anymore, a pin
+    /// without assigned workers yet is waited for until the timeout.
+    ///
+    /// # Arguments
+    ///
+    /// * `cid` - The CID of the pin.
+    /// * `required` - The number of workers which have to pin the data.
+    /// * `timeout` - How long to wait at most.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the IDs of the workers which pinned the data or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::PinReplication` with the acked, failed and pending
+    /// workers if the timeout passed or the required acks can't arrive anymore, or an error if
+    /// any request to the Gevulot client fails.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use gevulot_rs::prelude::*;
+    ///
+    /// # async fn example(mut client: GevulotClient) -> gevulot_rs::Result<()> {
+    /// let notifier = PinNotifier::new();
+    /// client.pins.set_notifier(notifier.clone());
+    /// let mut fetcher =
+    ///     EventFetcher::new("http://127.0.0.1:26657", None, Duration::from_secs(5), notifier);
+    /// tokio::spawn(async move { fetcher.start_fetching().await });
+    ///
+    /// match client.pins.wait_for_acks("bafy...", 3, Duration::from_secs(300)).await {
+    ///     Ok(workers) => println!("pinned by {}", workers.join(", ")),
+    ///     Err(Error::PinReplication(err)) => println!("missing acks: {err}"),
+    ///     Err(err) => return Err(err),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_acks(
+        &mut self,
+        cid: &str,
+        required: usize,
+        timeout: Duration,
+    ) -> Result<Vec<String>> {
+        // Subscribe before the first check so no event in between is lost
+        let mut events = self.notifier.as_ref().map(PinNotifier::subscribe);
+        let timer = self.base_client.read().await.timer();
+        let deadline: Instant = timer.now() + timeout;
+        let mut interval = ACK_POLL_INITIAL_INTERVAL;
+
+        loop {
+            let status: PinStatus = self.get(cid).await?.status.unwrap_or_default().into();
+            let (acked_workers, failed_workers, pending_workers) = replication(&status);
+            if acked_workers.len() >= required {
+                return Ok(acked_workers);
+            }
+            let failed = replication_failed(
+                &status,
+                acked_workers.len(),
+                pending_workers.len(),
+                required,
+            );
+            let now = timer.now();
+            if failed || now >= deadline {
+                return Err(Error::PinReplication(Box::new(PinReplicationError {
+                    cid: cid.to_string(),
+                    required,
+                    acked_workers,
+                    failed_workers,
+                    pending_workers,
+                    timed_out: !failed,
+                })));
+            }
+
+            let sleep = timer.sleep(interval.min(deadline - now));
+            interval = (interval * 2).min(ACK_POLL_MAX_INTERVAL);
+            let Some(receiver) = events.as_mut() else {
+                sleep.await;
+                continue;
+            };
+            let ack_event = async {
+                loop {
+                    match receiver.recv().await {
+                        Ok(acked) if acked == cid => return true,
+                        Ok(_) => {}
+                        // Missed events might have been for this pin
+                        Err(broadcast::error::RecvError::Lagged(_)) => return true,
+                        Err(broadcast::error::RecvError::Closed) => return false,
+                    }
+                }
+            };
+            let open = tokio::select! {
+                open = ack_event => open,
+                _ = sleep => true,
+            };
+            if !open {
+                events = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ack(worker: &str, block_height: i64, success: bool) -> PinAck {
+        PinAck {
+            worker: worker.to_string(),
+            block_height,
+            success,
+            error: (!success).then(|| "fetch failed".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_expiry() {
+        let pin = crate::proto::gevulot::gevulot::Pin {
+            status: Some(crate::proto::gevulot::gevulot::PinStatus {
+                worker_acks: [(12, true), (9, false), (10, true)]
+                    .into_iter()
+                    .map(
+                        |(block_height, success)| crate::proto::gevulot::gevulot::PinAck {
+                            block_height,
+                            success,
+                            ..Default::default()
+                        },
+                    )
+                    .collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(pinned_at(&pin), Some(10));
+        assert_eq!(
+            pinned_at(&crate::proto::gevulot::gevulot::Pin::default()),
+            None
+        );
+
+        assert_eq!(expires_in(1000, 3600, 1600), Duration::from_secs(3000));
+        assert_eq!(expires_in(1000, 3600, 5000), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_extend_messages() {
+        use crate::proto::gevulot::gevulot;
+        let pin = gevulot::Pin {
+            metadata: Some(gevulot::Metadata {
+                id: "pin1".to_string(),
+                name: "data".to_string(),
+                tags: vec!["a".to_string()],
+                ..Default::default()
+            }),
+            spec: Some(gevulot::PinSpec {
+                bytes: 1024,
+                time: 3600,
+                redundancy: 2,
+                fallback_urls: vec!["https://example.com/data".to_string()],
+            }),
+            status: Some(gevulot::PinStatus {
+                cid: "cid1".to_string(),
+                ..Default::default()
+            }),
+        };
+        let (delete, create) = MsgExtendPinBuilder::default()
+            .creator("alice".to_string())
+            .pin(pin)
+            .time(7200)
+            .into_messages()
+            .unwrap();
+        assert_eq!((delete.cid.as_str(), delete.id.as_str()), ("cid1", "pin1"));
+        assert_eq!(create.cid, "cid1");
+        assert_eq!(create.time, 7200);
+        assert_eq!((create.bytes, create.redundancy), (1024, 2));
+        assert_eq!(create.name, "data");
+        assert_eq!(create.tags, vec!["a"]);
+        assert_eq!(create.fallback_urls, vec!["https://example.com/data"]);
+    }
+
+    #[test]
+    fn test_health() {
+        let status = PinStatus {
+            assigned_workers: vec!["w1".to_string(), "w2".to_string(), "w3".to_string()],
+            worker_acks: vec![ack("w1", 10, true), ack("w2", 11, false)],
+            cid: Some("cid1".to_string()),
+        };
+        let health = PinHealth::new("cid1", 2, &status);
+        assert_eq!(health.acked_workers, vec!["w1"]);
+        assert_eq!(
+            health.failed_workers,
+            vec![("w2".to_string(), "fetch failed".to_string())]
+        );
+        assert_eq!(health.pending_workers, vec!["w3"]);
+        assert_eq!(health.acked(), 1);
+        assert!(!health.is_fully_replicated());
+        assert!(PinHealth::new("cid1", 1, &status).is_fully_replicated());
+    }
+
+    #[test]
+    fn test_replication() {
+        let mut status = PinStatus {
+            assigned_workers: vec!["w1".to_string(), "w2".to_string(), "w3".to_string()],
+            worker_acks: vec![ack("w1", 10, true)],
+            cid: Some("cid1".to_string()),
+        };
+        let (acked, failed, pending) = replication(&status);
+        assert_eq!(acked, vec!["w1".to_string()]);
+        assert!(failed.is_empty());
+        assert_eq!(pending, vec!["w2".to_string(), "w3".to_string()]);
+
+        // A later ack of the same worker replaces its earlier one
+        status.worker_acks.push(ack("w2", 11, false));
+        status.worker_acks.push(ack("w2", 12, true));
+        let (acked, failed, pending) = replication(&status);
+        assert_eq!(acked, vec!["w1".to_string(), "w2".to_string()]);
+        assert!(failed.is_empty());
+        assert_eq!(pending, vec!["w3".to_string()]);
+
+        status.worker_acks = vec![ack("w1", 10, true), ack("w2", 11, false)];
+        let (acked, failed, pending) = replication(&status);
+        let err = Error::PinReplication(Box::new(PinReplicationError {
+            cid: "cid1".to_string(),
+            required: 2,
+            acked_workers: acked,
+            failed_workers: failed,
+            pending_workers: pending,
+            timed_out: true,
+        }));
+        assert_eq!(
+            err.to_string(),
+            "pin cid1 timed out with 1 of 2 required acks, failed: w2 (fetch failed), pending: w3"
+        );
+    }
+
+    #[test]
+    fn test_replication_failed() {
+        // Not assigned yet, the assignment may still show up
+        let mut status = PinStatus {
+            assigned_workers: Vec::new(),
+            worker_acks: Vec::new(),
+            cid: Some("cid1".to_string()),
+        };
+        assert!(!replication_failed(&status, 0, 0, 2));
+
+        status.assigned_workers = vec!["w1".to_string(), "w2".to_string()];
+        status.worker_acks = vec![ack("w2", 11, false)];
+        let (acked, _, pending) = replication(&status);
+        assert!(!replication_failed(&status, acked.len(), pending.len(), 1));
+        assert!(replication_failed(&status, acked.len(), pending.len(), 2));
+    }
 }
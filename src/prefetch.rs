@@ -0,0 +1,196 @@
+//! Prefetch planning for task inputs.
+//!
+//! Before accepting a task a worker can resolve the task's input contexts to the pins holding
+//! the data and check where the data can be downloaded from and how long that will take.
+//! Tasks whose inputs cannot be fetched can then be declined right away instead of failing
+//! after they were accepted.
+
+use std::time::Duration;
+
+use crate::proto::gevulot::gevulot;
+
+/// A place an input can be downloaded from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PrefetchSource {
+    /// A worker which acknowledged storing the pinned data.
+    Worker(String),
+    /// A fallback URL registered with the pin.
+    Url(String),
+}
+
+/// Download plan for a single task input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefetchInput {
+    /// CID of the input data.
+    pub cid: String,
+    /// Path the input is mounted at inside the task.
+    pub target: String,
+    /// Size of the input in bytes as declared by its pin, 0 if the input isn't pinned.
+    pub bytes: u64,
+    /// Sources the input can be downloaded from, workers first.
+    pub sources: Vec<PrefetchSource>,
+    /// Estimated time needed to download the input.
+    pub estimated_download_time: Duration,
+}
+
+impl PrefetchInput {
+    /// Returns true if there is at least one source for the input.
+    pub fn is_available(&self) -> bool {
+        !self.sources.is_empty()
+    }
+}
+
+/// Download plan for all inputs of a task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrefetchPlan {
+    /// ID of the task the plan was made for.
+    pub task_id: String,
+    /// Plans for the individual inputs.
+    pub inputs: Vec<PrefetchInput>,
+    /// Total size of all inputs in bytes.
+    pub total_bytes: u64,
+    /// Estimated time needed to download all inputs one after another.
+    pub estimated_download_time: Duration,
+}
+
+impl PrefetchPlan {
+    /// Builds a plan from the task's input contexts and the pins found for them.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_id` - The ID of the task.
+    /// * `inputs` - The input contexts of the task, each with its pin if one exists.
+    /// * `bandwidth` - Expected download bandwidth in bytes per second.
+    ///
+    /// # Returns
+    ///
+    /// The prefetch plan for the task.
+    pub fn new(
+        task_id: &str,
+        inputs: &[(gevulot::InputContext, Option<gevulot::Pin>)],
+        bandwidth: u64,
+    ) -> Self {
+        let inputs: Vec<PrefetchInput> = inputs
+            .iter()
+            .map(|(input, pin)| {
+                let bytes = pin
+                    .as_ref()
+                    .and_then(|pin| pin.spec.as_ref())
+                    .map(|spec| spec.bytes)
+                    .unwrap_or_default();
+                let mut sources: Vec<PrefetchSource> = pin
+                    .as_ref()
+                    .and_then(|pin| pin.status.as_ref())
+                    .map(|status| {
+                        status
+                            .worker_acks
+                            .iter()
+                            .filter(|ack| ack.success)
+                            .map(|ack| PrefetchSource::Worker(ack.worker.clone()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if let Some(spec) = pin.as_ref().and_then(|pin| pin.spec.as_ref()) {
+                    sources.extend(spec.fallback_urls.iter().cloned().map(PrefetchSource::Url));
+                }
+                PrefetchInput {
+                    cid: input.source.clone(),
+                    target: input.target.clone(),
+                    bytes,
+                    sources,
+                    estimated_download_time: download_time(bytes, bandwidth),
+                }
+            })
+            .collect();
+        let total_bytes = inputs.iter().map(|input| input.bytes).sum();
+        Self {
+            task_id: task_id.to_string(),
+            inputs,
+            total_bytes,
+            estimated_download_time: download_time(total_bytes, bandwidth),
+        }
+    }
+
+    /// Returns true if every input has at least one source.
+    pub fn is_satisfiable(&self) -> bool {
+        self.inputs.iter().all(PrefetchInput::is_available)
+    }
+
+    /// Returns the inputs which cannot be downloaded from anywhere.
+    pub fn unavailable_inputs(&self) -> impl Iterator<Item = &PrefetchInput> {
+        self.inputs.iter().filter(|input| !input.is_available())
+    }
+}
+
+fn download_time(bytes: u64, bandwidth: u64) -> Duration {
+    if bandwidth == 0 {
+        return Duration::MAX;
+    }
+    Duration::from_secs_f64(bytes as f64 / bandwidth as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(cid: &str) -> gevulot::InputContext {
+        gevulot::InputContext {
+            source: cid.to_string(),
+            target: format!("/input/{}", cid),
+        }
+    }
+
+    #[test]
+    fn test_prefetch_plan() {
+        let pin = gevulot::Pin {
+            metadata: None,
+            spec: Some(gevulot::PinSpec {
+                bytes: 2_000_000,
+                time: 3600,
+                redundancy: 2,
+                fallback_urls: vec!["https://example.com/data".to_string()],
+            }),
+            status: Some(gevulot::PinStatus {
+                assigned_workers: vec!["worker1".to_string(), "worker2".to_string()],
+                worker_acks: vec![
+                    gevulot::PinAck {
+                        worker: "worker1".to_string(),
+                        block_height: 10,
+                        success: true,
+                        error: String::new(),
+                    },
+                    gevulot::PinAck {
+                        worker: "worker2".to_string(),
+                        block_height: 11,
+                        success: false,
+                        error: "out of disk".to_string(),
+                    },
+                ],
+                cid: "cid1".to_string(),
+            }),
+        };
+
+        let plan = PrefetchPlan::new(
+            "task1",
+            &[(input("cid1"), Some(pin)), (input("cid2"), None)],
+            1_000_000,
+        );
+
+        assert_eq!(plan.total_bytes, 2_000_000);
+        assert_eq!(plan.estimated_download_time, Duration::from_secs(2));
+        assert_eq!(
+            plan.inputs[0].sources,
+            vec![
+                PrefetchSource::Worker("worker1".to_string()),
+                PrefetchSource::Url("https://example.com/data".to_string()),
+            ]
+        );
+        assert!(!plan.is_satisfiable());
+        assert_eq!(
+            plan.unavailable_inputs()
+                .map(|input| input.cid.as_str())
+                .collect::<Vec<_>>(),
+            vec!["cid2"]
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! The supported public API of the crate.
+//!
+//! Everything exported here follows semantic versioning: it is only changed in a breaking way
+//! together with a major version bump. Downstream code should prefer importing from here over
+//! reaching into individual modules, whose layout may change between minor versions.
+//!
+//! ```no_run
+//! use gevulot_rs::prelude::*;
+//! ```
+//!
+//! Byte sizes passed to the message builders use [`builders::ByteUnit`], which is not exported
+//! here to avoid a clash with the [`ByteUnit`] of the models.
+
+#[cfg(feature = "query-only")]
+pub use crate::alerts::{Alert, AlertEngine, Rule, Snapshot};
+pub use crate::background::{
+    BackgroundTasks, RestartPolicy, SupervisedState, SupervisedTask, TaskFailure,
+};
+#[cfg(feature = "query-only")]
+pub use crate::builders::{
+    self, ByteSize, MsgAcceptTaskBuilder, MsgAckPinBuilder, MsgAnnounceWorkerExitBuilder, MsgBatch,
+    MsgBatchResponses, MsgCreatePinBuilder, MsgCreateTaskBuilder, MsgCreateWorkerBuilder,
+    MsgDeclineTaskBuilder, MsgDeletePinBuilder, MsgDeleteTaskBuilder, MsgDeleteWorkerBuilder,
+    MsgExtendPinBuilder, MsgFinishTaskBuilder, MsgRescheduleTaskBuilder, MsgSudoDeletePinBuilder,
+    MsgSudoDeleteTaskBuilder, MsgSudoDeleteWorkerBuilder, MsgSudoFreezeAccountBuilder,
+    MsgUpdateWorkerBuilder,
+};
+#[cfg(feature = "query-only")]
+pub use crate::chain_clock::ChainClock;
+#[cfg(feature = "query-only")]
+pub use crate::compression::{Compression, CompressionConfig, CompressionStats};
+#[cfg(feature = "query-only")]
+pub use crate::concurrency::ConcurrencyStats;
+pub use crate::error::{AbciLogEntry, Error, ErrorCode, GevulotError, Result, TxError};
+pub use crate::event_bus::{BusMessage, EventBus, EventSubscription};
+pub use crate::event_fetcher::{
+    BlockBatched, BlockEventHandler, BlockInfo, ChainNotification, EventContext, EventFetcher,
+    EventHandler, HandlerErrorPolicy, RawAndParsed, RawAndParsedEventHandler,
+};
+pub use crate::event_filter::EventFilter;
+pub use crate::events::{GevulotEvent, PinEvent, TaskEvent, WorkerEvent, WorkflowEvent};
+#[cfg(feature = "query-only")]
+pub use crate::failover::EndpointHealth;
+pub use crate::fallback_check::FallbackUrlError;
+#[cfg(feature = "query-only")]
+pub use crate::gas_cache::{GasCache, GasOverride};
+#[cfg(feature = "query-only")]
+pub use crate::gevulot_client::{GevulotClient, GevulotClientBuilder};
+#[cfg(feature = "query-only")]
+pub use crate::gov_client::GovClient;
+#[cfg(feature = "query-only")]
+pub use crate::health::{ChainStatus, Health, HealthProblem, NodeInfo};
+#[cfg(feature = "full")]
+pub use crate::key_import::KeyFormat;
+#[cfg(feature = "keychain")]
+pub use crate::keychain::Keychain;
+pub use crate::label_selector::LabelSelector;
+pub use crate::metrics::{EventMetrics, EventStats, Metered};
+pub use crate::models::{
+    ByteUnit, CoreUnit, InputContext, Label, MaintenanceWindow, Metadata, OutputContext, Pin,
+    PinAck, PinSpec, PinStatus, RetryPolicy, StageProgress, Task, TaskEnv, TaskResources, TaskSpec,
+    TaskStatus, TimeUnit, Worker, WorkerSpec, WorkerStatus, Workflow, WorkflowProgress,
+    WorkflowSpec, WorkflowStage, WorkflowStageStatus, WorkflowStatus,
+};
+pub use crate::origin::Origin;
+pub use crate::pagination::{Cursor, PageOptions, PageToken};
+#[cfg(feature = "query-only")]
+pub use crate::pin_client::{ExpiringPin, PinClient, PinHealth, PinNotifier, PinReplicationError};
+#[cfg(feature = "query-only")]
+pub use crate::proof_client::ProofClient;
+#[cfg(feature = "query-only")]
+pub use crate::query::{Query, TaskQuery, TaskResult, WorkerQuery, WorkerResult};
+#[cfg(feature = "query-only")]
+pub use crate::query_retry::QueryRetryPolicy;
+#[cfg(feature = "query-only")]
+pub use crate::quotas::Quotas;
+pub use crate::runtime_config::RuntimeConfig;
+#[cfg(feature = "query-only")]
+pub use crate::signer::{GevulotSigner, HdPath};
+#[cfg(feature = "query-only")]
+pub use crate::spec_defaults::SpecDefaults;
+#[cfg(feature = "query-only")]
+pub use crate::sudo_client::SudoClient;
+#[cfg(feature = "query-only")]
+pub use crate::task_client::{CancelAction, OutputLoading, TaskClient, TaskNotifier};
+#[cfg(feature = "query-only")]
+pub use crate::tx_dispatcher::{PendingTx, TxDispatcher};
+#[cfg(feature = "tx-json")]
+pub use crate::tx_json::TxJson;
+pub use crate::tx_receipt::{FeeBreakdown, TxReceipt};
+#[cfg(feature = "query-only")]
+pub use crate::worker_client::{ExitStatus, WorkerClient, WorkerPatch, WorkerTransfer};
+#[cfg(feature = "query-only")]
+pub use crate::workflow_client::{WorkflowClient, WorkflowUpdate};
+#[cfg(feature = "query-only")]
+pub use crate::workflow_runner::{WorkflowRun, WorkflowRunner};
+pub use crate::{Event, Height};
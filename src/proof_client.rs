@@ -0,0 +1,186 @@
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::{
+    base_client::BaseClient,
+    error::{Error, Result},
+    origin::Origin,
+    pagination::{PageOptions, PageToken, DEFAULT_PAGE_LIMIT},
+    proto::gevulot::gevulot::{
+        MsgCreateProof, MsgCreateProofResponse, MsgDeleteProof, MsgDeleteProofResponse,
+    },
+};
+
+/// Client for managing proofs in the Gevulot system.
+///
+/// The chain only supports creating and deleting proofs, there are no messages to update or
+/// finish a proof.
+#[derive(Debug, Clone)]
+pub struct ProofClient {
+    base_client: Arc<RwLock<BaseClient>>,
+    origin: Option<Arc<Origin>>,
+}
+
+impl ProofClient {
+    /// Creates a new instance of ProofClient.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_client` - An Arc-wrapped RwLock of the BaseClient.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of ProofClient.
+    pub fn new(base_client: Arc<RwLock<BaseClient>>) -> Self {
+        Self {
+            base_client,
+            origin: None,
+        }
+    }
+
+    /// Sets the origin labels added to every proof created by this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The origin, None stops adding origin labels.
+    pub fn set_origin(&mut self, origin: Option<Arc<Origin>>) {
+        self.origin = origin;
+    }
+
+    /// Lists all proofs.
+    ///
+    /// Fetches the proofs page by page until all proofs were received.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of proofs or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list(&mut self) -> Result<Vec<crate::proto::gevulot::gevulot::Proof>> {
+        let mut proofs = Vec::new();
+        let mut options = PageOptions::new().limit(DEFAULT_PAGE_LIMIT);
+        loop {
+            let (page, next) = self.list_paged(options.clone()).await?;
+            proofs.extend(page);
+            match next {
+                Some(token) => options.token = Some(token),
+                None => break,
+            }
+        }
+        Ok(proofs)
+    }
+
+    /// Lists a single page of proofs.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The size and position of the page.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the proofs of the page and the token of the next page, None if this
+    /// was the last page, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_paged(
+        &mut self,
+        options: PageOptions,
+    ) -> Result<(
+        Vec<crate::proto::gevulot::gevulot::Proof>,
+        Option<PageToken>,
+    )> {
+        let request = crate::proto::gevulot::gevulot::QueryAllProofRequest {
+            pagination: Some(options.to_request()),
+        };
+        let response = self
+            .base_client
+            .write()
+            .await
+            .gevulot_client
+            .proof_all(request)
+            .await?
+            .into_inner();
+        Ok((
+            response.proof,
+            PageToken::next(response.pagination.as_ref()),
+        ))
+    }
+
+    /// Gets a proof by its ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the proof to retrieve.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the proof or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the proof is not found or if the request to the Gevulot client fails.
+    pub async fn get(&mut self, id: &str) -> Result<crate::proto::gevulot::gevulot::Proof> {
+        let request = crate::proto::gevulot::gevulot::QueryGetProofRequest { id: id.to_owned() };
+        let response = self
+            .base_client
+            .write()
+            .await
+            .gevulot_client
+            .proof(request)
+            .await?;
+        response.into_inner().proof.ok_or(Error::NotFound(None))
+    }
+
+    /// Creates a new proof.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message containing the details of the proof to create.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the response of the create proof operation or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn create(&mut self, mut msg: MsgCreateProof) -> Result<MsgCreateProofResponse> {
+        if let Some(origin) = &self.origin {
+            origin.apply_to_map(&mut msg.labels);
+        }
+        let resp: MsgCreateProofResponse = self
+            .base_client
+            .write()
+            .await
+            .send_msg_sync(msg, "")
+            .await?;
+        Ok(resp)
+    }
+
+    /// Deletes a proof.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message containing the ID of the proof to delete.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the response of the delete proof operation or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn delete(&mut self, msg: MsgDeleteProof) -> Result<MsgDeleteProofResponse> {
+        let resp: MsgDeleteProofResponse = self
+            .base_client
+            .write()
+            .await
+            .send_msg_sync(msg, "")
+            .await?;
+        Ok(resp)
+    }
+}
@@ -0,0 +1,56 @@
+//! Stable paths to the commonly used protobuf types of the Gevulot module.
+//!
+//! The types under [`crate::proto`] are generated and their module paths follow the packaging
+//! of the protobuf files, which may change with the chain. The paths here are covered by
+//! semantic versioning like the [`prelude`](crate::prelude): when the protobuf packaging
+//! changes, the re-exports are updated and downstream imports keep compiling. Types of
+//! nested protobuf messages and enums are exported under flat names.
+//!
+//! Fields of the types follow the protobuf definitions and change together with the chain.
+//!
+//! ```no_run
+//! use gevulot_rs::proto_api::{MsgCreateTask, TaskState};
+//! ```
+
+use crate::proto::gevulot::gevulot;
+
+// Entities
+pub use gevulot::{
+    InputContext, Label, Metadata, OutputContext, Params, Pin, PinAck, PinSpec, PinStatus, Proof,
+    ProofSpec, ProofStatus, Task, TaskEnv, TaskSpec, TaskStatus, Worker, WorkerSpec, WorkerStatus,
+    Workflow, WorkflowSpec, WorkflowStatus,
+};
+
+// Messages and their responses
+pub use gevulot::{
+    MsgAcceptTask, MsgAcceptTaskResponse, MsgAckPin, MsgAckPinResponse, MsgAnnounceWorkerExit,
+    MsgAnnounceWorkerExitResponse, MsgCreatePin, MsgCreatePinResponse, MsgCreateProof,
+    MsgCreateProofResponse, MsgCreateTask, MsgCreateTaskResponse, MsgCreateWorker,
+    MsgCreateWorkerResponse, MsgCreateWorkflow, MsgCreateWorkflowResponse, MsgDeclineTask,
+    MsgDeclineTaskResponse, MsgDeletePin, MsgDeletePinResponse, MsgDeleteProof,
+    MsgDeleteProofResponse, MsgDeleteTask, MsgDeleteTaskResponse, MsgDeleteWorker,
+    MsgDeleteWorkerResponse, MsgDeleteWorkflow, MsgDeleteWorkflowResponse, MsgFinishTask,
+    MsgFinishTaskResponse, MsgRescheduleTask, MsgRescheduleTaskResponse, MsgSudoDeletePin,
+    MsgSudoDeletePinResponse, MsgSudoDeleteTask, MsgSudoDeleteTaskResponse, MsgSudoDeleteWorker,
+    MsgSudoDeleteWorkerResponse, MsgSudoFreezeAccount, MsgSudoFreezeAccountResponse,
+    MsgUpdateParams, MsgUpdateParamsResponse, MsgUpdateWorker, MsgUpdateWorkerResponse,
+};
+
+// Queries and their responses
+pub use gevulot::{
+    QueryAllPinRequest, QueryAllPinResponse, QueryAllProofRequest, QueryAllProofResponse,
+    QueryAllTaskRequest, QueryAllTaskResponse, QueryAllWorkerRequest, QueryAllWorkerResponse,
+    QueryAllWorkflowRequest, QueryAllWorkflowResponse, QueryGetPinRequest, QueryGetPinResponse,
+    QueryGetProofRequest, QueryGetProofResponse, QueryGetTaskRequest, QueryGetTaskResponse,
+    QueryGetWorkerRequest, QueryGetWorkerResponse, QueryGetWorkflowRequest,
+    QueryGetWorkflowResponse, QueryParamsRequest, QueryParamsResponse,
+};
+
+/// State of a task, see [`TaskStatus`].
+pub type TaskState = gevulot::task_status::State;
+/// Stage of a workflow, see [`WorkflowSpec`].
+pub type WorkflowStage = gevulot::workflow_spec::Stage;
+/// State of a workflow stage, see [`WorkflowStatus`].
+pub type WorkflowStageState = gevulot::workflow_status::StageState;
+/// State of a workflow, see [`WorkflowStatus`].
+pub type WorkflowState = gevulot::workflow_status::State;
@@ -0,0 +1,334 @@
+//! Fluent queries combining several entities.
+//!
+//! Dashboards often list tasks and then fetch the worker of every task one by one. A query
+//! built from [`GevulotClient::query`](crate::GevulotClient::query) fetches every kind of entity
+//! it needs once, filters on the client and joins the results:
+//!
+//! ```no_run
+//! # use gevulot_rs::{GevulotClient, proto::gevulot::gevulot::task_status::State};
+//! # async fn dashboard(client: &GevulotClient) -> gevulot_rs::Result<()> {
+//! let running = client
+//!     .query()
+//!     .tasks()
+//!     .by_creator("gvlt1...")
+//!     .state(State::Running)
+//!     .with_worker_details()
+//!     .execute()
+//!     .await?;
+//! for result in running {
+//!     let workers: Vec<_> = result.workers.iter().map(|w| &w.metadata.name).collect();
+//!     println!("{} runs on {:?}", result.task.metadata.name, workers);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use crate::{
+    error::Result,
+    label_selector::LabelSelector,
+    models::{Task, Worker},
+    pagination::{PageOptions, DEFAULT_PAGE_LIMIT},
+    proto::gevulot::gevulot::{self, task_status::State},
+    task_client::TaskClient,
+    worker_client::WorkerClient,
+};
+
+/// Entry point of queries, see [`GevulotClient::query`](crate::GevulotClient::query).
+#[derive(Debug, Clone)]
+pub struct Query {
+    tasks: TaskClient,
+    workers: WorkerClient,
+}
+
+impl Query {
+    pub(crate) fn new(tasks: TaskClient, workers: WorkerClient) -> Self {
+        Self { tasks, workers }
+    }
+
+    /// Starts a query of tasks.
+    pub fn tasks(&self) -> TaskQuery {
+        TaskQuery {
+            query: self.clone(),
+            creator: None,
+            worker: None,
+            states: Vec::new(),
+            selector: None,
+            limit: None,
+            worker_details: false,
+        }
+    }
+
+    /// Starts a query of workers.
+    pub fn workers(&self) -> WorkerQuery {
+        WorkerQuery {
+            query: self.clone(),
+            creator: None,
+            selector: None,
+            task_details: false,
+        }
+    }
+}
+
+/// A task with the workers it is assigned to.
+#[derive(Debug)]
+pub struct TaskResult {
+    pub task: Task,
+    /// The assigned and active workers of the task, empty unless requested with
+    /// [`TaskQuery::with_worker_details`]. Workers which were deleted are missing.
+    pub workers: Vec<Worker>,
+}
+
+/// A worker with the tasks assigned to it.
+#[derive(Debug)]
+pub struct WorkerResult {
+    pub worker: Worker,
+    /// The tasks assigned to the worker or running on it, empty unless requested with
+    /// [`WorkerQuery::with_task_details`].
+    pub tasks: Vec<Task>,
+}
+
+/// Returns the IDs of the assigned and active workers of a task.
+fn task_workers(task: &gevulot::Task) -> Vec<&str> {
+    let Some(status) = task.status.as_ref() else {
+        return Vec::new();
+    };
+    let mut workers: Vec<&str> = status.assigned_workers.iter().map(String::as_str).collect();
+    if !status.active_worker.is_empty() && !workers.contains(&status.active_worker.as_str()) {
+        workers.push(&status.active_worker);
+    }
+    workers
+}
+
+/// Query of tasks, the filters combine with AND.
+#[derive(Debug)]
+pub struct TaskQuery {
+    query: Query,
+    creator: Option<String>,
+    worker: Option<String>,
+    states: Vec<State>,
+    selector: Option<LabelSelector>,
+    limit: Option<usize>,
+    worker_details: bool,
+}
+
+impl TaskQuery {
+    /// Keeps the tasks created by an account.
+    pub fn by_creator(mut self, creator: &str) -> Self {
+        self.creator = Some(creator.to_string());
+        self
+    }
+
+    /// Keeps the tasks assigned to a worker or running on it.
+    pub fn by_worker(mut self, worker_id: &str) -> Self {
+        self.worker = Some(worker_id.to_string());
+        self
+    }
+
+    /// Keeps the tasks in a state, call it several times to keep tasks in any of the states.
+    pub fn state(mut self, state: State) -> Self {
+        self.states.push(state);
+        self
+    }
+
+    /// Keeps the tasks whose labels and tags match a selector.
+    pub fn labels(mut self, selector: LabelSelector) -> Self {
+        self.selector = Some(selector);
+        self
+    }
+
+    /// Returns at most `limit` tasks, stopping to list tasks once enough were found.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Joins the assigned and active workers onto the tasks.
+    pub fn with_worker_details(mut self) -> Self {
+        self.worker_details = true;
+        self
+    }
+
+    fn matches(&self, task: &gevulot::Task) -> bool {
+        let creator = task.metadata.as_ref().map(|m| m.creator.as_str());
+        let state = task.status.as_ref().map(|status| status.state());
+        self.creator
+            .as_deref()
+            .map_or(true, |expected| creator == Some(expected))
+            && self
+                .worker
+                .as_deref()
+                .map_or(true, |worker| task_workers(task).contains(&worker))
+            && (self.states.is_empty() || state.is_some_and(|s| self.states.contains(&s)))
+    }
+
+    /// Runs the query.
+    ///
+    /// Tasks are listed page by page, workers are listed once if their details are requested.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the matching tasks or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any request to the Gevulot client fails.
+    pub async fn execute(mut self) -> Result<Vec<TaskResult>> {
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let mut tasks = Vec::new();
+        let mut options = PageOptions::new().limit(DEFAULT_PAGE_LIMIT);
+        while tasks.len() < limit {
+            let (page, next) = self.query.tasks.list_paged(options.clone()).await?;
+            for task in page.into_iter().filter(|task| self.matches(task)) {
+                let model = Task::from(task.clone());
+                if self
+                    .selector
+                    .as_ref()
+                    .map_or(true, |selector| selector.matches(&model.metadata))
+                {
+                    tasks.push((task, model));
+                }
+            }
+            match next {
+                Some(token) => options.token = Some(token),
+                None => break,
+            }
+        }
+        tasks.truncate(limit);
+
+        let workers: HashMap<String, gevulot::Worker> = if self.worker_details && !tasks.is_empty()
+        {
+            self.query
+                .workers
+                .list()
+                .await?
+                .into_iter()
+                .filter(|worker| worker.spec.is_some())
+                .filter_map(|worker| Some((worker.metadata.as_ref()?.id.clone(), worker)))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        Ok(tasks
+            .into_iter()
+            .map(|(proto, task)| TaskResult {
+                workers: task_workers(&proto)
+                    .into_iter()
+                    .filter_map(|id| workers.get(id).cloned().map(Worker::from))
+                    .collect(),
+                task,
+            })
+            .collect())
+    }
+}
+
+/// Query of workers, the filters combine with AND.
+#[derive(Debug)]
+pub struct WorkerQuery {
+    query: Query,
+    creator: Option<String>,
+    selector: Option<LabelSelector>,
+    task_details: bool,
+}
+
+impl WorkerQuery {
+    /// Keeps the workers created by an account.
+    pub fn by_creator(mut self, creator: &str) -> Self {
+        self.creator = Some(creator.to_string());
+        self
+    }
+
+    /// Keeps the workers whose labels and tags match a selector.
+    pub fn labels(mut self, selector: LabelSelector) -> Self {
+        self.selector = Some(selector);
+        self
+    }
+
+    /// Joins the tasks assigned to the workers or running on them onto the workers.
+    pub fn with_task_details(mut self) -> Self {
+        self.task_details = true;
+        self
+    }
+
+    /// Runs the query.
+    ///
+    /// Workers are listed once, tasks are listed page by page if their details are requested.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the matching workers or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any request to the Gevulot client fails.
+    pub async fn execute(mut self) -> Result<Vec<WorkerResult>> {
+        let workers: Vec<Worker> = self
+            .query
+            .workers
+            .list()
+            .await?
+            .into_iter()
+            .filter(|worker| worker.spec.is_some())
+            .filter(|worker| {
+                self.creator.as_deref().map_or(true, |creator| {
+                    worker.metadata.as_ref().map(|m| m.creator.as_str()) == Some(creator)
+                })
+            })
+            .map(Worker::from)
+            .filter(|worker| {
+                self.selector
+                    .as_ref()
+                    .map_or(true, |selector| selector.matches(&worker.metadata))
+            })
+            .collect();
+
+        let mut tasks: HashMap<String, Vec<gevulot::Task>> = HashMap::new();
+        if self.task_details && !workers.is_empty() {
+            for task in self.query.tasks.list().await? {
+                for worker in task_workers(&task) {
+                    tasks
+                        .entry(worker.to_string())
+                        .or_default()
+                        .push(task.clone());
+                }
+            }
+        }
+        Ok(workers
+            .into_iter()
+            .map(|worker| WorkerResult {
+                tasks: worker
+                    .metadata
+                    .id
+                    .as_ref()
+                    .and_then(|id| tasks.remove(id))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(Task::from)
+                    .collect(),
+                worker,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_workers() {
+        let mut task = gevulot::Task {
+            status: Some(gevulot::TaskStatus {
+                assigned_workers: vec!["w1".to_string(), "w2".to_string()],
+                active_worker: "w2".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(task_workers(&task), vec!["w1", "w2"]);
+        task.status.as_mut().unwrap().active_worker = "w3".to_string();
+        assert_eq!(task_workers(&task), vec!["w1", "w2", "w3"]);
+        assert!(task_workers(&gevulot::Task::default()).is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+//! Retries of read-only gRPC calls failing with transient errors.
+//!
+//! Nodes behind load balancers regularly reset connections or answer with `Unavailable` while
+//! restarting. A [`QueryRetryPolicy`] set on a connection retries such calls with exponential
+//! backoff, within a budget of retries per call and a total deadline.
+//!
+//! Only read-only calls are retried by default: the queries of all modules, the Tendermint
+//! service and the tx service except `BroadcastTx`. Retrying a broadcast whose response was lost
+//! may submit the transaction twice, [`QueryRetryPolicy::retry_broadcasts`] opts in to it.
+//!
+//! Connection errors and responses without a body are retried, errors the server reports after
+//! it started sending a response aren't.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use http_body::{Body, Frame, SizeHint};
+use tonic::body::BoxBody;
+
+const TX_SERVICE: &str = "cosmos.tx.v1beta1.Service";
+const TENDERMINT_SERVICE: &str = "cosmos.base.tendermint.v1beta1.Service";
+
+// gRPC status code of Unavailable
+const UNAVAILABLE: &[u8] = b"14";
+
+/// Retry budget of gRPC calls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueryRetryPolicy {
+    /// Most retries of a single call.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every further retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between two attempts.
+    pub max_backoff: Duration,
+    /// Time since the first attempt after which no further retry is started.
+    pub deadline: Duration,
+    /// Whether `BroadcastTx` calls are retried as well.
+    pub retry_broadcasts: bool,
+}
+
+impl Default for QueryRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            deadline: Duration::from_secs(10),
+            retry_broadcasts: false,
+        }
+    }
+}
+
+impl QueryRetryPolicy {
+    /// Returns the delay before the given retry, counting from 0.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        2u32.checked_pow(retry)
+            .and_then(|factor| self.initial_backoff.checked_mul(factor))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    /// Returns true if calls of the gRPC method with the given path, e.g.
+    /// `/cosmos.bank.v1beta1.Query/Balance`, are retried.
+    pub fn applies_to(&self, path: &str) -> bool {
+        let Some((service, method)) = path.trim_start_matches('/').split_once('/') else {
+            return false;
+        };
+        match service {
+            TX_SERVICE => method != "BroadcastTx" || self.retry_broadcasts,
+            TENDERMINT_SERVICE => true,
+            _ => service.ends_with(".Query"),
+        }
+    }
+}
+
+// Returns the reason if the call failed in a way worth retrying
+fn transient_error<B, E>(result: &Result<http::Response<B>, E>) -> Option<String>
+where
+    E: std::fmt::Display,
+{
+    let response = match result {
+        Err(e) => return Some(e.to_string()),
+        Ok(response) => response,
+    };
+    match response.headers().get("grpc-status") {
+        Some(status) if status.as_bytes() == UNAVAILABLE => Some(format!(
+            "unavailable: {}",
+            response
+                .headers()
+                .get("grpc-message")
+                .and_then(|message| message.to_str().ok())
+                .unwrap_or_default()
+        )),
+        Some(_) => None,
+        // Proxies answer without a gRPC status, tonic maps these to Unavailable
+        None => matches!(response.status().as_u16(), 502..=504)
+            .then(|| format!("http status {}", response.status())),
+    }
+}
+
+/// Sends a call until it succeeds, fails permanently or the retry budget is used up.
+pub(crate) async fn call_with_retry<B, E, F, Fut>(
+    policy: &QueryRetryPolicy,
+    path: &str,
+    mut send: F,
+) -> Result<http::Response<B>, E>
+where
+    E: std::fmt::Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<http::Response<B>, E>>,
+{
+    let start = tokio::time::Instant::now();
+    let mut retry = 0;
+    loop {
+        let result = send().await;
+        if retry >= policy.max_retries {
+            return result;
+        }
+        let Some(reason) = transient_error(&result) else {
+            return result;
+        };
+        let backoff = policy.backoff(retry);
+        if start.elapsed() + backoff > policy.deadline {
+            return result;
+        }
+        log::debug!("Retrying {} in {:?}: {}", path, backoff, reason);
+        tokio::time::sleep(backoff).await;
+        retry += 1;
+    }
+}
+
+/// Request body held in memory, so that the request can be sent again.
+pub(crate) struct BufferedBody(Option<Result<Bytes, tonic::Status>>);
+
+impl BufferedBody {
+    /// Creates a body of the given data.
+    pub(crate) fn new(data: Bytes) -> BoxBody {
+        tonic::body::boxed(Self(Some(Ok(data))))
+    }
+
+    /// Creates a body failing with the error reading the original body failed with.
+    pub(crate) fn failed(status: tonic::Status) -> BoxBody {
+        tonic::body::boxed(Self(Some(Err(status))))
+    }
+
+    /// Reads a body into memory.
+    pub(crate) async fn read(mut body: BoxBody) -> Result<Bytes, tonic::Status> {
+        let mut data = BytesMut::new();
+        while let Some(frame) = std::future::poll_fn(|cx| Pin::new(&mut body).poll_frame(cx)).await
+        {
+            if let Ok(chunk) = frame?.into_data() {
+                data.extend_from_slice(&chunk);
+            }
+        }
+        Ok(data.freeze())
+    }
+}
+
+impl Body for BufferedBody {
+    type Data = Bytes;
+    type Error = tonic::Status;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        Poll::Ready(self.0.take().map(|data| data.map(Frame::data)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.0.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        match &self.0 {
+            Some(Ok(data)) => SizeHint::with_exact(data.len() as u64),
+            _ => SizeHint::with_exact(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn response(grpc_status: Option<&str>, http_status: u16) -> http::Response<()> {
+        let mut response = http::Response::builder().status(http_status);
+        if let Some(status) = grpc_status {
+            response = response.header("grpc-status", status);
+        }
+        response.body(()).unwrap()
+    }
+
+    #[test]
+    fn test_applies_to() {
+        let policy = QueryRetryPolicy::default();
+        assert!(policy.applies_to("/gevulot.gevulot.Query/Task"));
+        assert!(policy.applies_to("/cosmos.bank.v1beta1.Query/Balance"));
+        assert!(policy.applies_to("/cosmos.tx.v1beta1.Service/Simulate"));
+        assert!(policy.applies_to("/cosmos.base.tendermint.v1beta1.Service/GetLatestBlock"));
+        assert!(!policy.applies_to("/cosmos.tx.v1beta1.Service/BroadcastTx"));
+        assert!(!policy.applies_to("/gevulot.gevulot.Msg/CreateTask"));
+        assert!(!policy.applies_to("invalid"));
+
+        let policy = QueryRetryPolicy {
+            retry_broadcasts: true,
+            ..Default::default()
+        };
+        assert!(policy.applies_to("/cosmos.tx.v1beta1.Service/BroadcastTx"));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let policy = QueryRetryPolicy::default();
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_secs(2));
+        assert_eq!(policy.backoff(64), Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_call_with_retry() {
+        let policy = QueryRetryPolicy::default();
+        let path = "/gevulot.gevulot.Query/Task";
+
+        // Retried until the call succeeds
+        let attempts = Cell::new(0);
+        let result = call_with_retry(&policy, path, || {
+            attempts.set(attempts.get() + 1);
+            let result = match attempts.get() {
+                1 => Err("connection reset"),
+                2 => Ok(response(Some("14"), 200)),
+                _ => Ok(response(None, 200)),
+            };
+            async move { result }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts.get(), 3);
+
+        // Permanent errors aren't retried
+        attempts.set(0);
+        let result = call_with_retry(&policy, path, || {
+            attempts.set(attempts.get() + 1);
+            async { Ok::<_, &str>(response(Some("5"), 200)) }
+        })
+        .await;
+        assert_eq!(result.unwrap().headers()["grpc-status"], "5");
+        assert_eq!(attempts.get(), 1);
+
+        // The retry budget is limited
+        attempts.set(0);
+        let result = call_with_retry(&policy, path, || {
+            attempts.set(attempts.get() + 1);
+            async { Ok::<_, &str>(response(None, 503)) }
+        })
+        .await;
+        assert_eq!(result.unwrap().status(), 503);
+        assert_eq!(attempts.get(), 4);
+
+        // So is the total time
+        attempts.set(0);
+        let policy = QueryRetryPolicy {
+            max_retries: 10,
+            deadline: Duration::from_millis(500),
+            ..Default::default()
+        };
+        let result = call_with_retry(&policy, path, || {
+            attempts.set(attempts.get() + 1);
+            async { Err::<http::Response<()>, _>("connection refused") }
+        })
+        .await;
+        assert!(result.is_err());
+        // 100ms + 200ms, the next retry after 400ms more would exceed the deadline
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_buffered_body() {
+        let body = BufferedBody::new(Bytes::from_static(b"message"));
+        let data = BufferedBody::read(body).await.unwrap();
+        assert_eq!(data, Bytes::from_static(b"message"));
+
+        let body = BufferedBody::failed(tonic::Status::internal("encoding failed"));
+        let err = BufferedBody::read(body).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Internal);
+    }
+}
@@ -0,0 +1,178 @@
+//! Limits and current usage of the signing account.
+//!
+//! The chain has no per-account quotas, the effective limits of an account are the largest
+//! task a node can run according to the module params and the funds of the account. Together
+//! with the tasks, workflows and pins the account currently has, submission tooling can queue
+//! work on the client side instead of sending transactions which get rejected.
+
+use crate::proto::gevulot::gevulot::{self, task_status, workflow_status, MsgCreateTask};
+
+/// Limits and current usage of an account.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Quotas {
+    /// Address of the account.
+    pub account: String,
+    /// Balance of the account in ucredit.
+    pub balance: u128,
+    /// Most CPUs in millicores a single task can request, the maximum of CPU and GPU nodes.
+    pub max_task_cpus: u64,
+    /// Most GPUs in millicores a single task can request.
+    pub max_task_gpus: u64,
+    /// Most memory in bytes a single task can request, the maximum of CPU and GPU nodes.
+    pub max_task_memory: u64,
+    /// Tasks of the account which are pending or running.
+    pub active_tasks: usize,
+    /// Workflows of the account which are pending or running.
+    pub active_workflows: usize,
+    /// Pins created by the account.
+    pub pins: usize,
+    /// Bytes pinned by the account.
+    pub pinned_bytes: u64,
+}
+
+impl Quotas {
+    /// Derives the quotas from the module params and the entities on chain.
+    pub(crate) fn new(
+        account: &str,
+        balance: u128,
+        params: &gevulot::Params,
+        tasks: &[gevulot::Task],
+        workflows: &[gevulot::Workflow],
+        pins: &[gevulot::Pin],
+    ) -> Self {
+        let created_by_account = |metadata: &Option<gevulot::Metadata>| {
+            metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.creator == account)
+        };
+        let pins: Vec<_> = pins
+            .iter()
+            .filter(|pin| created_by_account(&pin.metadata))
+            .collect();
+
+        Self {
+            account: account.to_string(),
+            balance,
+            // The params count whole CPUs and GPUs, tasks request millicores
+            max_task_cpus: params
+                .cpu_node_max_cp_us
+                .max(params.gpu_node_max_cp_us)
+                .saturating_mul(1000),
+            max_task_gpus: params.gpu_node_max_gp_us.saturating_mul(1000),
+            max_task_memory: params.cpu_node_max_memory.max(params.gpu_node_max_memory),
+            active_tasks: tasks
+                .iter()
+                .filter(|task| created_by_account(&task.metadata))
+                .filter(|task| {
+                    matches!(
+                        task.status.as_ref().map(|status| status.state()),
+                        None | Some(task_status::State::Pending | task_status::State::Running)
+                    )
+                })
+                .count(),
+            active_workflows: workflows
+                .iter()
+                .filter(|workflow| created_by_account(&workflow.metadata))
+                .filter(|workflow| {
+                    matches!(
+                        workflow.status.as_ref().map(|status| status.state()),
+                        None | Some(
+                            workflow_status::State::Pending | workflow_status::State::Running
+                        )
+                    )
+                })
+                .count(),
+            pins: pins.len(),
+            pinned_bytes: pins
+                .iter()
+                .filter_map(|pin| pin.spec.as_ref())
+                .map(|spec| spec.bytes)
+                .sum(),
+        }
+    }
+
+    /// Returns true if a node is large enough to run the task.
+    pub fn fits(&self, msg: &MsgCreateTask) -> bool {
+        msg.cpus <= self.max_task_cpus
+            && msg.gpus <= self.max_task_gpus
+            && msg.memory <= self.max_task_memory
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(creator: &str) -> Option<gevulot::Metadata> {
+        Some(gevulot::Metadata {
+            creator: creator.to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn task(creator: &str, state: task_status::State) -> gevulot::Task {
+        gevulot::Task {
+            metadata: metadata(creator),
+            spec: None,
+            status: Some(gevulot::TaskStatus {
+                state: state.into(),
+                ..Default::default()
+            }),
+        }
+    }
+
+    #[test]
+    fn test_quotas() {
+        let params = gevulot::Params {
+            cpu_node_max_cp_us: 16,
+            cpu_node_max_memory: 64,
+            gpu_node_max_cp_us: 8,
+            gpu_node_max_memory: 128,
+            gpu_node_max_gp_us: 2,
+            ..Default::default()
+        };
+        let tasks = [
+            task("alice", task_status::State::Pending),
+            task("alice", task_status::State::Running),
+            task("alice", task_status::State::Done),
+            task("bob", task_status::State::Running),
+        ];
+        let pins = [
+            gevulot::Pin {
+                metadata: metadata("alice"),
+                spec: Some(gevulot::PinSpec {
+                    bytes: 100,
+                    ..Default::default()
+                }),
+                status: None,
+            },
+            gevulot::Pin {
+                metadata: metadata("bob"),
+                spec: Some(gevulot::PinSpec {
+                    bytes: 1000,
+                    ..Default::default()
+                }),
+                status: None,
+            },
+        ];
+
+        let quotas = Quotas::new("alice", 500, &params, &tasks, &[], &pins);
+        assert_eq!(quotas.max_task_cpus, 16000);
+        assert_eq!(quotas.max_task_gpus, 2000);
+        assert_eq!(quotas.max_task_memory, 128);
+        assert_eq!(quotas.active_tasks, 2);
+        assert_eq!(quotas.active_workflows, 0);
+        assert_eq!(quotas.pins, 1);
+        assert_eq!(quotas.pinned_bytes, 100);
+
+        assert!(quotas.fits(&MsgCreateTask {
+            cpus: 16000,
+            memory: 64,
+            ..Default::default()
+        }));
+        assert!(!quotas.fits(&MsgCreateTask {
+            gpus: 4000,
+            ..Default::default()
+        }));
+    }
+}
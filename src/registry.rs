@@ -0,0 +1,201 @@
+//! Decoders for messages and events of chain extensions.
+//!
+//! Chains built on Gevulot may add modules this crate knows nothing about. Instead of forking
+//! gevulot-rs, downstream crates register decoders for their messages (by type URL) and events
+//! (by event kind). They are used by
+//! [`BaseClient::get_tx_decoded`](crate::base_client::BaseClient::get_tx_decoded) and when
+//! parsing events with [`GevulotEvent::from_cosmos`](crate::events::GevulotEvent::from_cosmos),
+//! which returns [`GevulotEvent::Extension`](crate::events::GevulotEvent::Extension) for
+//! registered event kinds.
+//!
+//! The messages of the Gevulot module are registered by default.
+//!
+//! # Examples
+//!
+//! ```ignore
+//! gevulot_rs::registry::register_msg::<my_chain::MsgCreateBounty>();
+//! gevulot_rs::registry::register_event("create-bounty", |event, _height| {
+//!     Ok(serde_json::json!({ "attributes": event.attributes.len() }))
+//! });
+//! ```
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock, RwLock},
+};
+
+use cosmos_sdk_proto::prost::{Message, Name};
+use cosmrs::tendermint::{abci, block::Height};
+
+use crate::{error::Result, proto::gevulot::gevulot};
+
+/// A decoded protobuf message of any type.
+pub trait AnyMessage: std::fmt::Debug + Send + Sync + 'static {
+    /// Returns the message as [`std::any::Any`] to downcast it to its type.
+    fn as_any(&self) -> &dyn std::any::Any;
+}
+
+impl<M: Message + 'static> AnyMessage for M {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// Decodes the value of a protobuf `Any` with a registered type URL.
+pub type MsgDecoder = Arc<dyn Fn(&[u8]) -> Result<Box<dyn AnyMessage>> + Send + Sync>;
+
+/// Decodes an event of a registered kind into JSON.
+pub type EventDecoder =
+    Arc<dyn Fn(&abci::Event, Height) -> Result<serde_json::Value> + Send + Sync>;
+
+/// Message of a transaction, decoded if its type URL is registered.
+#[derive(Debug)]
+pub struct DecodedMsg {
+    pub type_url: String,
+    /// The decoded message, None if no decoder is registered for the type URL.
+    pub message: Option<Box<dyn AnyMessage>>,
+    /// The encoded message.
+    pub value: Vec<u8>,
+}
+
+impl DecodedMsg {
+    /// Returns the message if it was decoded and is of type `M`.
+    pub fn downcast_ref<M: 'static>(&self) -> Option<&M> {
+        self.message.as_ref()?.as_any().downcast_ref()
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    messages: HashMap<String, MsgDecoder>,
+    events: HashMap<String, EventDecoder>,
+}
+
+fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut registry = Registry::default();
+        macro_rules! builtin {
+            ($($msg:ty),* $(,)?) => {
+                $(registry.messages.insert(<$msg>::type_url(), decoder::<$msg>());)*
+            };
+        }
+        builtin!(
+            gevulot::MsgCreateWorker,
+            gevulot::MsgUpdateWorker,
+            gevulot::MsgDeleteWorker,
+            gevulot::MsgAnnounceWorkerExit,
+            gevulot::MsgCreateTask,
+            gevulot::MsgDeleteTask,
+            gevulot::MsgRescheduleTask,
+            gevulot::MsgAcceptTask,
+            gevulot::MsgDeclineTask,
+            gevulot::MsgFinishTask,
+            gevulot::MsgCreateWorkflow,
+            gevulot::MsgDeleteWorkflow,
+            gevulot::MsgCreateProof,
+            gevulot::MsgDeleteProof,
+            gevulot::MsgCreatePin,
+            gevulot::MsgDeletePin,
+            gevulot::MsgAckPin,
+            gevulot::MsgUpdateParams,
+            gevulot::MsgSudoFreezeAccount,
+            gevulot::MsgSudoDeleteWorker,
+            gevulot::MsgSudoDeletePin,
+            gevulot::MsgSudoDeleteTask,
+        );
+        RwLock::new(registry)
+    })
+}
+
+fn decoder<M: Message + Default + 'static>() -> MsgDecoder {
+    Arc::new(|bytes| Ok(Box::new(M::decode(bytes)?)))
+}
+
+/// Registers the decoder of a message type under its type URL, replacing any previous one.
+pub fn register_msg<M: Message + Name + Default + 'static>() {
+    register_msg_decoder(&M::type_url(), decoder::<M>());
+}
+
+/// Registers a custom decoder for a type URL, replacing any previous one.
+pub fn register_msg_decoder(type_url: &str, decoder: MsgDecoder) {
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .messages
+        .insert(type_url.to_string(), decoder);
+}
+
+/// Registers the decoder of an event kind, replacing any previous one.
+pub fn register_event<F>(kind: &str, decoder: F)
+where
+    F: Fn(&abci::Event, Height) -> Result<serde_json::Value> + Send + Sync + 'static,
+{
+    registry()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .events
+        .insert(kind.to_string(), Arc::new(decoder));
+}
+
+/// Decodes a message with the decoder registered for its type URL.
+///
+/// # Errors
+///
+/// This function will return an error if the registered decoder fails. Messages without a
+/// decoder are returned undecoded.
+pub fn decode_msg(type_url: &str, value: &[u8]) -> Result<DecodedMsg> {
+    let decoder = registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .messages
+        .get(type_url)
+        .cloned();
+    Ok(DecodedMsg {
+        type_url: type_url.to_string(),
+        message: decoder.map(|decode| decode(value)).transpose()?,
+        value: value.to_vec(),
+    })
+}
+
+/// Decodes an event with the decoder registered for its kind, None if there is none.
+pub(crate) fn decode_event(
+    event: &abci::Event,
+    block_height: Height,
+) -> Option<Result<serde_json::Value>> {
+    let decoder = registry()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .events
+        .get(&event.kind)
+        .cloned()?;
+    Some(decoder(event, block_height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_msg() {
+        let msg = gevulot::MsgDeleteTask {
+            creator: "alice".to_string(),
+            id: "t1".to_string(),
+        };
+        let decoded =
+            decode_msg(&gevulot::MsgDeleteTask::type_url(), &msg.encode_to_vec()).unwrap();
+        assert_eq!(decoded.downcast_ref::<gevulot::MsgDeleteTask>(), Some(&msg));
+        assert!(decoded.downcast_ref::<gevulot::MsgCreateTask>().is_none());
+
+        let decoded = decode_msg("/ext.v1.MsgUnknown", &[1, 2, 3]).unwrap();
+        assert!(decoded.message.is_none());
+        assert_eq!(decoded.value, vec![1, 2, 3]);
+
+        register_msg_decoder(
+            "/ext.v1.MsgUnknown",
+            Arc::new(|bytes| Ok(Box::new(bytes.len() as u64))),
+        );
+        let decoded = decode_msg("/ext.v1.MsgUnknown", &[1, 2, 3]).unwrap();
+        assert_eq!(decoded.downcast_ref::<u64>(), Some(&3));
+    }
+}
@@ -36,10 +36,36 @@
 //!
 //! Runtime configurations are expected to be serialized into and deserialized from YAML files.
 //! Every Gevulot runtime configuration YAML file MUST start with `version` field.
+//!
+//! ## Schemas
+//!
+//! The `version` field selects the [`SchemaVersion`] of the file:
+//!
+//! - `v1` or a SemVer version of major 1 up to [`VERSION`] for the [`RuntimeConfig`] schema;
+//! - `v1alpha` for the pre-release [`RuntimeConfigV1Alpha`] schema, which is migrated to
+//!   [`RuntimeConfig`] when parsed.
+//!
+//! [`RuntimeConfig::parse`] detects the schema, migrates older schemas and validates the result,
+//! reporting every problem found as [`ConfigError`]s.
+//!
+//! ## Distribution with tasks
+//!
+//! A runtime configuration is attached to a task through the
+//! [`runtime_config`](crate::models::TaskSpec::runtime_config) of its spec or
+//! [`MsgCreateTaskBuilder::runtime_config`](crate::builders::MsgCreateTaskBuilder::runtime_config).
+//! On chain it travels in the task environment variable [`TASK_ENV`] as base64 encoded YAML.
+//! Workers retrieve it with [`RuntimeConfig::from_task_env`], pass the remaining variables to
+//! the program and mount the file written by [`RuntimeConfig::write_to_dir`] to
+//! [`MOUNT_PATH`].
+
+use std::collections::{BTreeMap, HashSet};
 
+use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::de::Error;
 use serde::{Deserialize, Serialize};
 
+use crate::proto::gevulot::gevulot::TaskEnv;
+
 const MAJOR: u64 = 1;
 const MINOR: u64 = 1;
 const PATCH: u64 = 0;
@@ -49,6 +75,81 @@ const SEM_VERSION: semver::Version = semver::Version::new(MAJOR, MINOR, PATCH);
 /// Version of runtime configuration.
 pub const VERSION: &str = const_format::concatcp!(MAJOR, ".", MINOR, ".", PATCH);
 
+/// Name of the task environment variable carrying the runtime configuration of a task.
+pub const TASK_ENV: &str = "GEVULOT_RUNTIME_CONFIG";
+
+/// Path of the runtime configuration file inside the VM.
+pub const MOUNT_PATH: &str = "/mnt/gevulot-rt-config/config.yaml";
+
+/// Schema of a runtime configuration file, selected by its `version` field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SchemaVersion {
+    /// Pre-release schema, see [`RuntimeConfigV1Alpha`].
+    V1Alpha,
+    /// Current schema, see [`RuntimeConfig`].
+    V1,
+}
+
+impl SchemaVersion {
+    /// The schema written by this crate.
+    pub const LATEST: Self = Self::V1;
+}
+
+impl std::fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::V1Alpha => write!(f, "v1alpha"),
+            Self::V1 => write!(f, "v1"),
+        }
+    }
+}
+
+impl std::str::FromStr for SchemaVersion {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "v1alpha" => return Ok(Self::V1Alpha),
+            "v1" => return Ok(Self::V1),
+            _ => {}
+        }
+        let mut version = s.to_string();
+        for _ in s.split('.').count()..3 {
+            version.push_str(".0");
+        }
+        match semver::Version::parse(&version) {
+            Ok(version) if version.major == MAJOR && version <= SEM_VERSION => Ok(Self::V1),
+            _ => Err(ConfigError::UnsupportedVersion(s.to_string())),
+        }
+    }
+}
+
+/// Problem found while parsing a runtime configuration.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ConfigError {
+    #[error("missing `version` field")]
+    MissingVersion,
+    #[error("`version` must be the first field")]
+    VersionNotFirst,
+    #[error("unsupported version {0}, expected v1alpha, v1 or 1.x up to {VERSION}")]
+    UnsupportedVersion(String),
+    /// The file doesn't match the schema of its version, e.g. because of an unknown field.
+    #[error("{0}")]
+    Malformed(String),
+    /// A field has a value workers can't process.
+    #[error("{field}: {reason}")]
+    Invalid { field: String, reason: String },
+}
+
+impl ConfigError {
+    fn invalid(field: impl Into<String>, reason: &str) -> Self {
+        Self::Invalid {
+            field: field.into(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
 /// Environment variable definition.
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub struct EnvVar {
@@ -120,6 +221,9 @@ where
     D: serde::Deserializer<'de>,
 {
     let mut version = String::deserialize(deserializer)?;
+    if version == "v1" {
+        return Ok(VERSION.to_string());
+    }
     // After deserialization, complete the version up to SemVer format: "X.Y.Z"
     let split = version.split('.').collect::<Vec<_>>();
     match split.len() {
@@ -206,17 +310,286 @@ pub struct RuntimeConfig {
     pub follow_config: Option<String>,
 }
 
-// TODO: Implement strict version check to get proper error messages.
-//       Deserializer needs to ensure that version field goes first (as it is described in docs
-//       above) and decline going further if version is not correct. Otherwise such file:
-//         abracadabra: xxxyyyzzz
-//         version: 123
-//       will report error "unknown field `abracadabra`" instead of version error
-//       (like "no `version` at the beginning").
+/// Gevulot VM runtime configuration of the pre-release `v1alpha` schema.
+///
+/// Unlike [`RuntimeConfig`] its fields are snake_case, environment variables are a map and
+/// `isa_debug_exit` enables the [default x86 debug exit](DebugExit::default_x86). Default
+/// filesystems are always mounted.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuntimeConfigV1Alpha {
+    /// Version of the config, always `v1alpha`.
+    pub version: String,
+
+    /// Program to execute.
+    pub command: Option<String>,
+
+    /// Arguments to the command.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Environment variables.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// Working directory.
+    pub working_dir: Option<String>,
+
+    /// Mounts.
+    #[serde(default)]
+    pub mounts: Vec<Mount>,
+
+    /// Kernel modules.
+    #[serde(default)]
+    pub kernel_modules: Vec<String>,
+
+    /// Whether to exit through the x86 QEMU `isa-debug-exit` device.
+    #[serde(default)]
+    pub isa_debug_exit: bool,
+
+    /// Boot commands.
+    #[serde(default)]
+    pub bootcmd: Vec<Vec<String>>,
+
+    /// Path to another runtime configuration file to process after current one.
+    pub follow_config: Option<String>,
+}
+
+impl From<RuntimeConfigV1Alpha> for RuntimeConfig {
+    fn from(config: RuntimeConfigV1Alpha) -> Self {
+        Self {
+            version: VERSION.to_string(),
+            command: config.command,
+            args: config.args,
+            env: config
+                .env
+                .into_iter()
+                .map(|(key, value)| EnvVar { key, value })
+                .collect(),
+            working_dir: config.working_dir,
+            mounts: config.mounts,
+            default_mounts: true,
+            kernel_modules: config.kernel_modules,
+            debug_exit: config.isa_debug_exit.then(DebugExit::default_x86),
+            bootcmd: config.bootcmd,
+            follow_config: config.follow_config,
+        }
+    }
+}
+
+// Checks that `version` comes first and returns the schema it selects
+fn detect_schema(yaml: &str) -> Result<SchemaVersion, ConfigError> {
+    let value = serde_yaml::from_str::<serde_yaml::Value>(yaml)
+        .map_err(|e| ConfigError::Malformed(e.to_string()))?;
+    let mapping = value
+        .as_mapping()
+        .ok_or_else(|| ConfigError::Malformed("expected a mapping of fields".to_string()))?;
+    match mapping.iter().next() {
+        Some((key, value)) if key.as_str() == Some("version") => match value {
+            serde_yaml::Value::String(version) => version.parse(),
+            serde_yaml::Value::Number(version) => version.to_string().parse(),
+            _ => Err(ConfigError::UnsupportedVersion(format!("{:?}", value))),
+        },
+        _ if mapping.contains_key("version") => Err(ConfigError::VersionNotFirst),
+        _ => Err(ConfigError::MissingVersion),
+    }
+}
+
+fn check_path(errors: &mut Vec<ConfigError>, field: impl Into<String>, path: &str) {
+    if !path.starts_with('/') {
+        errors.push(ConfigError::invalid(field, "must be an absolute path"));
+    }
+}
+
+impl RuntimeConfig {
+    /// Parses and validates a runtime configuration of any supported [`SchemaVersion`].
+    ///
+    /// Configurations of older schemas are migrated to the current one.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::RuntimeConfig` if the YAML doesn't start with a
+    /// supported version, doesn't match the schema of its version or fails
+    /// [validation](Self::validate).
+    pub fn parse(yaml: &str) -> crate::error::Result<Self> {
+        let malformed = |e: serde_yaml::Error| {
+            crate::error::Error::RuntimeConfig(vec![ConfigError::Malformed(e.to_string())])
+        };
+        let config =
+            match detect_schema(yaml).map_err(|e| crate::error::Error::RuntimeConfig(vec![e]))? {
+                SchemaVersion::V1Alpha => serde_yaml::from_str::<RuntimeConfigV1Alpha>(yaml)
+                    .map_err(malformed)?
+                    .into(),
+                SchemaVersion::V1 => serde_yaml::from_str::<Self>(yaml).map_err(malformed)?,
+            };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks that workers can process the configuration.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::RuntimeConfig` with every problem found.
+    pub fn validate(&self) -> crate::error::Result<()> {
+        let mut errors = Vec::new();
+        match &self.command {
+            Some(command) if command.trim().is_empty() => {
+                errors.push(ConfigError::invalid("command", "must not be empty"));
+            }
+            None if !self.args.is_empty() => {
+                errors.push(ConfigError::invalid("args", "set without a command"));
+            }
+            _ => {}
+        }
+        let mut keys = HashSet::new();
+        for (i, var) in self.env.iter().enumerate() {
+            let field = format!("env[{}].key", i);
+            if var.key.is_empty() || var.key.contains('=') {
+                errors.push(ConfigError::invalid(field, "must be non-empty without `=`"));
+            } else if !keys.insert(var.key.as_str()) {
+                errors.push(ConfigError::invalid(field, "duplicate variable"));
+            }
+        }
+        if let Some(dir) = &self.working_dir {
+            check_path(&mut errors, "working-dir", dir);
+        }
+        for (i, mount) in self.mounts.iter().enumerate() {
+            if mount.source.is_empty() {
+                errors.push(ConfigError::invalid(
+                    format!("mounts[{}].source", i),
+                    "must not be empty",
+                ));
+            }
+            check_path(&mut errors, format!("mounts[{}].target", i), &mount.target);
+        }
+        for (i, module) in self.kernel_modules.iter().enumerate() {
+            if module.trim().is_empty() {
+                errors.push(ConfigError::invalid(
+                    format!("kernel-modules[{}]", i),
+                    "must not be empty",
+                ));
+            }
+        }
+        if let Some(DebugExit::X86 {
+            iosize,
+            success_code,
+            ..
+        }) = &self.debug_exit
+        {
+            if ![1, 2, 4].contains(iosize) {
+                errors.push(ConfigError::invalid(
+                    "debug-exit.iosize",
+                    "must be 1, 2 or 4",
+                ));
+            }
+            if success_code % 2 == 0 || *success_code == 1 {
+                errors.push(ConfigError::invalid(
+                    "debug-exit.success-code",
+                    "must be an odd number greater than 1",
+                ));
+            }
+        }
+        for (i, cmd) in self.bootcmd.iter().enumerate() {
+            if cmd.first().map_or(true, |program| program.is_empty()) {
+                errors.push(ConfigError::invalid(
+                    format!("bootcmd[{}]", i),
+                    "must start with a program",
+                ));
+            }
+        }
+        if self.follow_config.as_deref() == Some("") {
+            errors.push(ConfigError::invalid("follow-config", "must not be empty"));
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::error::Error::RuntimeConfig(errors))
+        }
+    }
+
+    /// Parses a runtime configuration from YAML, see [`parse`](Self::parse).
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the YAML is invalid, of an unsupported version or
+    /// fails validation.
+    pub fn from_yaml(yaml: &str) -> crate::error::Result<Self> {
+        Self::parse(yaml)
+    }
+
+    /// Serializes the runtime configuration to YAML.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the configuration can't be serialized.
+    pub fn to_yaml(&self) -> crate::error::Result<String> {
+        serde_yaml::to_string(self).map_err(|e| crate::error::Error::EncodeError(e.to_string()))
+    }
+
+    /// Encodes the runtime configuration as the value of the [`TASK_ENV`] variable.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the configuration can't be serialized.
+    pub fn to_env_value(&self) -> crate::error::Result<String> {
+        Ok(STANDARD.encode(self.to_yaml()?))
+    }
+
+    /// Decodes a runtime configuration from the value of the [`TASK_ENV`] variable.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the value isn't a valid runtime configuration.
+    pub fn from_env_value(value: &str) -> crate::error::Result<Self> {
+        let yaml = STANDARD
+            .decode(value)
+            .map_err(|e| crate::error::Error::DecodeError(e.to_string()))?;
+        let yaml =
+            String::from_utf8(yaml).map_err(|e| crate::error::Error::DecodeError(e.to_string()))?;
+        Self::from_yaml(&yaml)
+    }
+
+    /// Returns the runtime configuration attached to a task, None if it has none.
+    ///
+    /// # Arguments
+    ///
+    /// * `env` - The environment variables of the task.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the attached configuration is invalid.
+    pub fn from_task_env(env: &[TaskEnv]) -> crate::error::Result<Option<Self>> {
+        env.iter()
+            .find(|e| e.name == TASK_ENV)
+            .map(|e| Self::from_env_value(&e.value))
+            .transpose()
+    }
+
+    /// Writes the runtime configuration to `config.yaml` in a directory, which the worker
+    /// mounts to the parent directory of [`MOUNT_PATH`].
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the path of the written file or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the file can't be written.
+    pub fn write_to_dir(
+        &self,
+        dir: impl AsRef<std::path::Path>,
+    ) -> crate::error::Result<std::path::PathBuf> {
+        let path = dir.as_ref().join("config.yaml");
+        std::fs::write(&path, self.to_yaml()?)
+            .map_err(|e| crate::error::Error::Unknown(format!("{}: {}", path.display(), e)))?;
+        Ok(path)
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use super::{DebugExit, EnvVar, RuntimeConfig};
+    use super::{ConfigError, DebugExit, EnvVar, RuntimeConfig, SchemaVersion};
 
     #[test]
     fn test_deserialize_version_ok() {
@@ -251,7 +624,11 @@ mod tests {
         ";
         let result = serde_yaml::from_str::<RuntimeConfig>(source);
         assert!(result.is_err());
-        // TODO: check error message. Can be done only after completing TODO above.
+        let err = RuntimeConfig::parse(source).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::error::Error::RuntimeConfig(ref errors) if errors == &[ConfigError::VersionNotFirst]
+        ));
     }
 
     const EXAMPLE_CONFIG: &str = "
@@ -316,4 +693,134 @@ mod tests {
             "/my/local/config.yaml"
         );
     }
+
+    #[test]
+    fn test_schema_versions() {
+        assert_eq!("v1alpha".parse(), Ok(SchemaVersion::V1Alpha));
+        assert_eq!("v1".parse(), Ok(SchemaVersion::V1));
+        assert_eq!("1.1".parse(), Ok(SchemaVersion::V1));
+        assert!("1.2".parse::<SchemaVersion>().is_err());
+        assert!("v2".parse::<SchemaVersion>().is_err());
+
+        let config = RuntimeConfig::parse(EXAMPLE_CONFIG).unwrap();
+        assert_eq!(config.command.as_deref(), Some("prover"));
+        let config = RuntimeConfig::parse("version: v1\ncommand: prover").unwrap();
+        assert_eq!(config.version, super::VERSION);
+    }
+
+    #[test]
+    fn test_migrate_v1alpha() {
+        let config = RuntimeConfig::parse(
+            "
+            version: v1alpha
+            command: prover
+            env:
+              TMPDIR: /tmp
+            working_dir: /
+            isa_debug_exit: true
+            ",
+        )
+        .unwrap();
+        assert_eq!(config.version, super::VERSION);
+        assert_eq!(
+            config.env,
+            vec![EnvVar {
+                key: "TMPDIR".to_string(),
+                value: "/tmp".to_string()
+            }]
+        );
+        assert!(config.default_mounts);
+        assert_eq!(config.debug_exit, Some(DebugExit::default_x86()));
+
+        // Fields of the current schema are unknown to v1alpha
+        assert!(RuntimeConfig::parse("version: v1alpha\nworking-dir: /").is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let err = RuntimeConfig::parse(
+            "
+            version: 1
+            args: [--fast]
+            env:
+              - {key: A, value: '1'}
+              - {key: A, value: '2'}
+            working-dir: tmp
+            mounts:
+              - {source: '', target: /input}
+            debug-exit: {arch: x86, iobase: 0xf4, iosize: 4, success-code: 2}
+            bootcmd: [[]]
+            ",
+        )
+        .unwrap_err();
+        let crate::error::Error::RuntimeConfig(errors) = err else {
+            panic!("unexpected error: {}", err);
+        };
+        let fields = errors
+            .iter()
+            .map(|e| match e {
+                ConfigError::Invalid { field, .. } => field.as_str(),
+                e => panic!("unexpected error: {}", e),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            fields,
+            vec![
+                "args",
+                "env[1].key",
+                "working-dir",
+                "mounts[0].source",
+                "debug-exit.success-code",
+                "bootcmd[0]"
+            ]
+        );
+        assert!(matches!(
+            RuntimeConfig::parse("command: echo").unwrap_err(),
+            crate::error::Error::RuntimeConfig(errors) if errors == [ConfigError::MissingVersion]
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "query-only")]
+    fn test_task_env() {
+        use crate::builders::MsgCreateTaskBuilder;
+        use crate::models::TaskSpec;
+
+        let spec = serde_yaml::from_str::<TaskSpec>(
+            "
+            image: prover:latest
+            env:
+              - name: LOG
+                value: debug
+            resources: {cpus: 1, gpus: 0, memory: 1GiB, time: 1h}
+            runtimeConfig:
+              version: 1
+              command: /bin/prover
+              args: [--fast]
+            ",
+        )
+        .unwrap();
+        let msg = MsgCreateTaskBuilder::from_spec("alice", &spec)
+            .unwrap()
+            .into_message()
+            .unwrap();
+        assert_eq!(msg.env.len(), 2);
+        let config = RuntimeConfig::from_task_env(&msg.env).unwrap().unwrap();
+        assert_eq!(config.command.as_deref(), Some("/bin/prover"));
+        assert_eq!(config.args, vec!["--fast"]);
+
+        // The configuration is moved out of the environment of the model again
+        let proto = crate::proto::gevulot::gevulot::TaskSpec::try_from(spec).unwrap();
+        let spec = TaskSpec::from(proto);
+        assert_eq!(spec.env.len(), 1);
+        assert_eq!(spec.runtime_config.unwrap().args, vec!["--fast"]);
+
+        let dir = std::env::temp_dir().join(format!("gevulot-rt-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = config.write_to_dir(&dir).unwrap();
+        let written = std::fs::read_to_string(path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(written.starts_with("version: 1.0.0"));
+        assert!(RuntimeConfig::from_env_value("not base64!").is_err());
+    }
 }
@@ -4,12 +4,97 @@ use cosmrs::crypto::PublicKey;
 use cosmrs::AccountId;
 use hex::decode;
 use rand_core::OsRng;
+use zeroize::Zeroizing;
 
 use crate::error::Result;
 
+/// Coin type of the derivation path used by the Cosmos Hub and most Cosmos wallets.
+pub const COSMOS_COIN_TYPE: u32 = 118;
+
+/// Bech32 prefix of Gevulot addresses unless set otherwise.
+pub const DEFAULT_ADDRESS_PREFIX: &str = "gvlt";
+
+/// BIP-44 derivation path `m/44'/{coin_type}'/{account}'/0/{address_index}` of a key.
+///
+/// Defaults to the first address of the first account with the [`COSMOS_COIN_TYPE`]. Wallets
+/// of other chains use their own coin type, e.g. 60 for Ethereum compatible ones.
+///
+/// # Examples
+///
+/// ```
+/// use gevulot_rs::prelude::HdPath;
+///
+/// let path = HdPath {
+///     address_index: 2,
+///     ..Default::default()
+/// };
+/// assert_eq!(path.to_string(), "m/44'/118'/0'/0/2");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct HdPath {
+    pub coin_type: u32,
+    pub account: u32,
+    pub address_index: u32,
+}
+
+impl Default for HdPath {
+    fn default() -> Self {
+        Self {
+            coin_type: COSMOS_COIN_TYPE,
+            account: 0,
+            address_index: 0,
+        }
+    }
+}
+
+impl std::fmt::Display for HdPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "m/44'/{}'/{}'/0/{}",
+            self.coin_type, self.account, self.address_index
+        )
+    }
+}
+
+impl HdPath {
+    /// Returns the Gevulot addresses of consecutive address indexes starting at this path.
+    ///
+    /// Useful to find the index of an address created with another wallet.
+    ///
+    /// # Arguments
+    ///
+    /// * `mnemonic` - The mnemonic phrase.
+    /// * `password` - The optional password of the mnemonic.
+    /// * `count` - The number of addresses to derive.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the mnemonic is invalid.
+    pub fn derive_addresses(
+        &self,
+        mnemonic: &str,
+        password: Option<&str>,
+        count: u32,
+    ) -> Result<Vec<String>> {
+        (self.address_index..self.address_index.saturating_add(count))
+            .map(|address_index| {
+                let path = Self {
+                    address_index,
+                    ..*self
+                };
+                let signer = GevulotSigner::from_mnemonic_with_path(mnemonic, password, &path)?;
+                Ok(signer.address().to_string())
+            })
+            .collect()
+    }
+}
+
 /// Struct representing a signer with mnemonic, public address, private key, and public key.
+///
+/// The mnemonic is wiped from memory when the signer is dropped, as is the private key.
 pub struct Signer {
-    pub mnemonic: Option<String>,
+    pub mnemonic: Option<Zeroizing<String>>,
     pub public_address: AccountId,
     pub private_key: SigningKey,
     pub public_key: PublicKey,
@@ -17,7 +102,9 @@ pub struct Signer {
 
 impl std::fmt::Debug for Signer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("Signer").finish()
+        f.debug_struct("Signer")
+            .field("public_address", &self.public_address)
+            .finish_non_exhaustive()
     }
 }
 
@@ -86,7 +173,7 @@ impl Signer {
             Signer::load_from_mnemonic(mnemonic.phrase(), prefix, derivation, password)?;
 
         Ok(Signer {
-            mnemonic: Some(mnemonic.phrase().to_string()),
+            mnemonic: Some(Zeroizing::new(mnemonic.phrase().to_string())),
             public_address,
             private_key,
             public_key,
@@ -110,7 +197,7 @@ impl Signer {
     /// - the private key is invalid
     /// - the prefix is invalid
     pub fn from_pkey(private_key: &str, prefix: &str) -> Result<Self> {
-        let private_key = SigningKey::from_slice(decode(private_key)?.as_slice())?;
+        let private_key = SigningKey::from_slice(&Zeroizing::new(decode(private_key)?))?;
         let public_key = private_key.public_key();
         let public_address = public_key.account_id(prefix)?;
 
@@ -150,7 +237,7 @@ impl Signer {
             Signer::load_from_mnemonic(phrase, prefix, derivation, password)?;
 
         Ok(Signer {
-            mnemonic: Some(phrase.to_string()),
+            mnemonic: Some(Zeroizing::new(phrase.to_string())),
             public_address,
             private_key,
             public_key,
@@ -180,7 +267,53 @@ impl GevulotSigner {
     /// - the derivation path is invalid
     /// - the prefix is invalid
     pub fn from_mnemonic(mnemonic: &str, password: Option<&str>) -> Result<Self> {
-        let signer = Signer::from_mnemonic(mnemonic, "gvlt", None, password)?;
+        let signer = Signer::from_mnemonic(mnemonic, DEFAULT_ADDRESS_PREFIX, None, password)?;
+        Ok(GevulotSigner(signer))
+    }
+
+    /// Creates a GevulotSigner from a mnemonic phrase and a derivation path.
+    ///
+    /// # Arguments
+    ///
+    /// * `mnemonic` - The mnemonic phrase.
+    /// * `path` - The derivation path of the key.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new instance of GevulotSigner or an error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the mnemonic is invalid.
+    pub fn from_mnemonic_with_path(
+        mnemonic: &str,
+        password: Option<&str>,
+        path: &HdPath,
+    ) -> Result<Self> {
+        let signer = Signer::from_mnemonic(
+            mnemonic,
+            DEFAULT_ADDRESS_PREFIX,
+            Some(&path.to_string()),
+            password,
+        )?;
+        Ok(GevulotSigner(signer))
+    }
+
+    /// Creates a GevulotSigner from a private key.
+    ///
+    /// # Arguments
+    ///
+    /// * `private_key` - The private key as a hex string.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new instance of GevulotSigner or an error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the private key is invalid.
+    pub fn from_private_key(private_key: &str) -> Result<Self> {
+        let signer = Signer::from_pkey(private_key, DEFAULT_ADDRESS_PREFIX)?;
         Ok(GevulotSigner(signer))
     }
 
@@ -203,7 +336,8 @@ impl GevulotSigner {
     /// - the prefix is invalid
     pub fn from_entropy(entropy: &[u8; 32], password: Option<&str>) -> Result<Self> {
         let mnemonic = bip32::Mnemonic::from_entropy(*entropy, bip32::Language::English);
-        let signer = Signer::from_mnemonic(mnemonic.phrase(), "gvlt", None, password)?;
+        let signer =
+            Signer::from_mnemonic(mnemonic.phrase(), DEFAULT_ADDRESS_PREFIX, None, password)?;
         Ok(GevulotSigner(signer))
     }
 
@@ -226,6 +360,24 @@ impl GevulotSigner {
         Ok(signer)
     }
 
+    /// Derives the address of the signer with another bech32 prefix.
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix` - The bech32 prefix of the network's addresses.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the GevulotSigner with the new address or an error.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the prefix is invalid.
+    pub fn with_address_prefix(mut self, prefix: &str) -> Result<Self> {
+        self.0.public_address = self.0.public_key.account_id(prefix)?;
+        Ok(self)
+    }
+
     /// Returns the public address of the signer.
     ///
     /// # Returns
@@ -235,3 +387,62 @@ impl GevulotSigner {
         &self.0.public_address
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon \
+        abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+        abandon abandon abandon abandon art";
+
+    #[test]
+    fn test_debug_redacts_secrets() {
+        let signer = GevulotSigner::from_mnemonic(MNEMONIC, None).unwrap();
+        let debug = format!("{:?}", signer);
+        assert!(debug.contains(&signer.address().to_string()));
+        assert!(!debug.contains("abandon"));
+
+        let key = "1e99423a4ed27608a15a2616a2b0e9e52ced330ac530edcc32c8ffc6a526aedd";
+        let debug = format!("{:?}", GevulotSigner::from_private_key(key).unwrap());
+        assert!(!debug.contains(key));
+    }
+
+    #[test]
+    fn test_hd_path() {
+        let default = GevulotSigner::from_mnemonic(MNEMONIC, None).unwrap();
+        let path = HdPath::default();
+        assert_eq!(path.to_string(), "m/44'/118'/0'/0/0");
+
+        let addresses = path.derive_addresses(MNEMONIC, None, 3).unwrap();
+        assert_eq!(addresses.len(), 3);
+        assert_eq!(addresses[0], default.address().to_string());
+        assert_ne!(addresses[1], addresses[0]);
+
+        let second = HdPath {
+            address_index: 1,
+            ..path
+        };
+        let signer = GevulotSigner::from_mnemonic_with_path(MNEMONIC, None, &second).unwrap();
+        assert_eq!(signer.address().to_string(), addresses[1]);
+
+        let ethereum = HdPath {
+            coin_type: 60,
+            ..path
+        };
+        let signer = GevulotSigner::from_mnemonic_with_path(MNEMONIC, None, &ethereum).unwrap();
+        assert!(!addresses.contains(&signer.address().to_string()));
+    }
+
+    #[test]
+    fn test_address_prefix() {
+        let signer = GevulotSigner::from_mnemonic(MNEMONIC, None).unwrap();
+        assert_eq!(signer.address().prefix(), DEFAULT_ADDRESS_PREFIX);
+        let public_key = signer.0.public_key;
+
+        let signer = signer.with_address_prefix("cosmos").unwrap();
+        assert_eq!(signer.address().prefix(), "cosmos");
+        assert_eq!(signer.0.public_key, public_key);
+        assert!(signer.with_address_prefix("Not Valid").is_err());
+    }
+}
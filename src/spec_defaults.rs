@@ -0,0 +1,222 @@
+//! Organization-wide defaults for task and workflow specs.
+//!
+//! [`SpecDefaults`] are merged into every task and workflow created through
+//! [`TaskClient`](crate::task_client::TaskClient) and
+//! [`WorkflowClient`](crate::workflow_client::WorkflowClient) once set with
+//! [`GevulotClientBuilder::spec_defaults`](crate::GevulotClientBuilder::spec_defaults).
+//!
+//! Values set explicitly in a message always win, defaults only fill in what was left empty:
+//!
+//! - labels and environment variables are added unless the message has one with the same key
+//!   or name,
+//! - tags are added unless the message has them already,
+//! - resources and output retention periods are used where the message leaves them at 0.
+//!
+//! The only exception is `min_time`, which raises time limits below it.
+//!
+//! Workflow task specs carry no labels or tags, so only environment variables, resources and
+//! retention periods apply to workflows.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    models::{Label, TaskEnv},
+    proto::gevulot::gevulot::{self, MsgCreateTask, MsgCreateWorkflow},
+};
+
+/// Defaults merged into created tasks and workflows.
+///
+/// Resources use the units of the chain messages: cpus and gpus as in
+/// [`MsgCreateTask`], memory in bytes and time in seconds.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SpecDefaults {
+    /// Labels added to every task, e.g. `team` or `cost-center`.
+    pub labels: Vec<Label>,
+    /// Tags added to every task.
+    pub tags: Vec<String>,
+    /// Environment variables added to every task.
+    pub env: Vec<TaskEnv>,
+    /// CPUs for tasks not requesting any.
+    pub cpus: Option<u64>,
+    /// GPUs for tasks not requesting any.
+    pub gpus: Option<u64>,
+    /// Memory in bytes for tasks not requesting any.
+    pub memory: Option<u64>,
+    /// Time limit in seconds for tasks without one.
+    pub time: Option<u64>,
+    /// Minimum time limit in seconds, shorter limits are raised to it.
+    pub min_time: Option<u64>,
+    /// Retention period in seconds for output contexts without one.
+    pub retention_period: Option<u64>,
+}
+
+impl SpecDefaults {
+    /// Parses defaults from JSON.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the JSON is invalid.
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| Error::Parse(e.to_string()))
+    }
+
+    /// Merges the defaults into a task creation message.
+    pub fn apply_to_task(&self, msg: &mut MsgCreateTask) {
+        for label in &self.labels {
+            if !msg.labels.iter().any(|l| l.key == label.key) {
+                msg.labels.push(label.clone().into());
+            }
+        }
+        for tag in &self.tags {
+            if !msg.tags.contains(tag) {
+                msg.tags.push(tag.clone());
+            }
+        }
+        self.apply_env(&mut msg.env);
+        self.apply_resources(&mut msg.cpus, &mut msg.gpus, &mut msg.memory, &mut msg.time);
+        self.apply_retention(&mut msg.output_contexts);
+    }
+
+    /// Merges the defaults into every task of a workflow creation message.
+    pub fn apply_to_workflow(&self, msg: &mut MsgCreateWorkflow) {
+        let Some(spec) = msg.spec.as_mut() else {
+            return;
+        };
+        for task in spec
+            .stages
+            .iter_mut()
+            .flat_map(|stage| stage.tasks.iter_mut())
+        {
+            self.apply_env(&mut task.env);
+            self.apply_resources(
+                &mut task.cpus,
+                &mut task.gpus,
+                &mut task.memory,
+                &mut task.time,
+            );
+            self.apply_retention(&mut task.output_contexts);
+        }
+    }
+
+    fn apply_env(&self, env: &mut Vec<gevulot::TaskEnv>) {
+        for var in &self.env {
+            if !env.iter().any(|e| e.name == var.name) {
+                env.push(gevulot::TaskEnv {
+                    name: var.name.clone(),
+                    value: var.value.clone(),
+                });
+            }
+        }
+    }
+
+    fn apply_resources(&self, cpus: &mut u64, gpus: &mut u64, memory: &mut u64, time: &mut u64) {
+        for (value, default) in [
+            (cpus, self.cpus),
+            (gpus, self.gpus),
+            (memory, self.memory),
+            (&mut *time, self.time),
+        ] {
+            if *value == 0 {
+                *value = default.unwrap_or_default();
+            }
+        }
+        if let Some(min_time) = self.min_time {
+            *time = (*time).max(min_time);
+        }
+    }
+
+    fn apply_retention(&self, output_contexts: &mut [gevulot::OutputContext]) {
+        let Some(retention_period) = self.retention_period else {
+            return;
+        };
+        for output in output_contexts.iter_mut() {
+            if output.retention_period == 0 {
+                output.retention_period = retention_period;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_to_task() {
+        let defaults = SpecDefaults::from_json(
+            r#"{
+                "labels": [{"key": "team", "value": "proving"}, {"key": "cost-center", "value": "42"}],
+                "tags": ["batch"],
+                "env": [{"name": "RUST_LOG", "value": "info"}],
+                "memory": 1073741824,
+                "time": 3600,
+                "minTime": 600,
+                "retentionPeriod": 86400
+            }"#,
+        )
+        .unwrap();
+
+        let mut msg = MsgCreateTask {
+            labels: vec![gevulot::Label {
+                key: "team".to_string(),
+                value: "research".to_string(),
+            }],
+            env: vec![gevulot::TaskEnv {
+                name: "RUST_LOG".to_string(),
+                value: "debug".to_string(),
+            }],
+            output_contexts: vec![
+                gevulot::OutputContext {
+                    source: "/out/a".to_string(),
+                    retention_period: 0,
+                },
+                gevulot::OutputContext {
+                    source: "/out/b".to_string(),
+                    retention_period: 60,
+                },
+            ],
+            cpus: 2,
+            time: 60,
+            ..Default::default()
+        };
+        defaults.apply_to_task(&mut msg);
+
+        let labels: Vec<_> = msg
+            .labels
+            .iter()
+            .map(|l| (l.key.as_str(), l.value.as_str()))
+            .collect();
+        assert_eq!(labels, vec![("team", "research"), ("cost-center", "42")]);
+        assert_eq!(msg.tags, vec!["batch"]);
+        assert_eq!(msg.env.len(), 1);
+        assert_eq!(msg.env[0].value, "debug");
+        assert_eq!(msg.cpus, 2);
+        assert_eq!(msg.memory, 1073741824);
+        assert_eq!(msg.time, 600);
+        assert_eq!(msg.output_contexts[0].retention_period, 86400);
+        assert_eq!(msg.output_contexts[1].retention_period, 60);
+    }
+
+    #[test]
+    fn test_apply_to_workflow() {
+        let defaults = SpecDefaults {
+            time: Some(3600),
+            ..Default::default()
+        };
+        let mut msg = MsgCreateWorkflow {
+            creator: "alice".to_string(),
+            spec: Some(gevulot::WorkflowSpec {
+                stages: vec![gevulot::workflow_spec::Stage {
+                    tasks: vec![gevulot::TaskSpec::default(), gevulot::TaskSpec::default()],
+                }],
+            }),
+        };
+        defaults.apply_to_workflow(&mut msg);
+        assert!(msg.spec.unwrap().stages[0]
+            .tasks
+            .iter()
+            .all(|task| task.time == 3600));
+    }
+}
@@ -1,20 +1,242 @@
+use futures::{Stream, StreamExt};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::{
+    sync::{broadcast, RwLock},
+    time::{Duration, Instant},
+};
 
 use crate::{
     base_client::BaseClient,
     error::{Error, Result},
+    event_fetcher::EventHandler,
+    events::GevulotEvent,
+    origin::Origin,
+    pagination::{PageOptions, PageToken, DEFAULT_PAGE_LIMIT},
+    prefetch::PrefetchPlan,
     proto::gevulot::gevulot::{
-        MsgAcceptTask, MsgAcceptTaskResponse, MsgCreateTask, MsgCreateTaskResponse, MsgDeclineTask,
-        MsgDeclineTaskResponse, MsgDeleteTask, MsgDeleteTaskResponse, MsgFinishTask,
-        MsgFinishTaskResponse, MsgRescheduleTask, MsgRescheduleTaskResponse,
+        task_status::State, MsgAcceptTask, MsgAcceptTaskResponse, MsgCreateTask,
+        MsgCreateTaskResponse, MsgDeclineTask, MsgDeclineTaskResponse, MsgDeleteTask,
+        MsgDeleteTaskResponse, MsgFinishTask, MsgFinishTaskResponse, MsgRescheduleTask,
+        MsgRescheduleTaskResponse, MsgSudoDeleteTask, MsgSudoDeleteTaskResponse,
     },
+    spec_defaults::SpecDefaults,
 };
 
+/// Interval in which [`TaskClient::create_with_deadline`] polls the task state when no event
+/// arrives.
+const DEADLINE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Interval in which [`TaskClient::watch`] polls the task state when no event arrives.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Result of submitting a task with [`TaskClient::create_with_deadline`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DeadlineOutcome {
+    /// A worker accepted the task before the deadline.
+    Accepted {
+        task_id: String,
+        worker_id: String,
+        reschedules: usize,
+    },
+    /// No worker accepted the task before the deadline, so the task was deleted.
+    Cancelled { task_id: String, reschedules: usize },
+}
+
+/// How [`TaskClient::cancel`] stopped a task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CancelAction {
+    /// The pending or declined task was deleted by its creator.
+    Deleted,
+    /// The running task was finished as failed by its worker.
+    Finished,
+    /// The task was deleted by the sudo authority.
+    SudoDeleted,
+}
+
+/// Picks how a task can be stopped by the given signer, returns the state of the task if it
+/// can't be stopped.
+fn cancel_action(
+    task: &crate::proto::gevulot::gevulot::Task,
+    signer: &str,
+    sudo: bool,
+) -> std::result::Result<CancelAction, State> {
+    let state = task
+        .status
+        .as_ref()
+        .map(|status| status.state())
+        .unwrap_or_default();
+    let creator = task
+        .metadata
+        .as_ref()
+        .map(|metadata| metadata.creator.as_str());
+    let worker = task
+        .status
+        .as_ref()
+        .map(|status| status.active_worker.as_str());
+    match state {
+        State::Done | State::Failed => Err(state),
+        State::Pending | State::Declined if creator == Some(signer) => Ok(CancelAction::Deleted),
+        State::Running if worker == Some(signer) => Ok(CancelAction::Finished),
+        _ if sudo => Ok(CancelAction::SudoDeleted),
+        _ => Err(state),
+    }
+}
+
+/// How [`TaskClient::get`] and [`TaskClient::list`] treat the stdout and stderr of tasks.
+///
+/// Tasks can store megabytes of output. Clients which don't need the output can drop it right
+/// after receiving it, and load it explicitly with [`TaskClient::fetch_stdout`] and
+/// [`TaskClient::fetch_stderr`] when needed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputLoading {
+    /// Keep the complete output.
+    #[default]
+    Full,
+    /// Keep at most the given number of bytes of each output.
+    Truncate(usize),
+    /// Drop the output.
+    Skip,
+}
+
+impl OutputLoading {
+    /// Applies the setting to the stdout and stderr of a task.
+    fn apply(&self, task: &mut crate::proto::gevulot::gevulot::Task) {
+        let Some(status) = task.status.as_mut() else {
+            return;
+        };
+        for output in [&mut status.stdout, &mut status.stderr] {
+            match *self {
+                OutputLoading::Full => {}
+                OutputLoading::Truncate(max_len) => {
+                    if output.len() > max_len {
+                        let mut len = max_len;
+                        while !output.is_char_boundary(len) {
+                            len -= 1;
+                        }
+                        output.truncate(len);
+                        output.shrink_to_fit();
+                    }
+                }
+                OutputLoading::Skip => *output = String::new(),
+            }
+        }
+    }
+}
+
+/// Event handler waking up [`TaskClient::watch`] and [`TaskClient::create_with_deadline`] when
+/// the chain emits an event for a task.
+///
+/// Register a clone with an [`crate::EventFetcher`] and another one with the task client through
+/// [`TaskClient::set_notifier`]. Watched tasks are then re-checked as soon as they change
+/// instead of on the next poll.
+#[derive(Clone, Debug)]
+pub struct TaskNotifier {
+    sender: broadcast::Sender<String>,
+}
+
+impl Default for TaskNotifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskNotifier {
+    /// Creates a new notifier.
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Self { sender }
+    }
+
+    /// Returns a receiver of the IDs of tasks with new events.
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+}
+
+impl EventHandler for TaskNotifier {
+    async fn handle_event(
+        &mut self,
+        event: &crate::Event,
+        block_height: crate::Height,
+    ) -> Result<()> {
+        if let Ok(GevulotEvent::Task(event)) = GevulotEvent::from_cosmos(event, block_height) {
+            // Nobody watching any task is fine
+            let _ = self.sender.send(event.task_id().to_string());
+        }
+        Ok(())
+    }
+}
+
+/// State of a task watched by [`TaskClient::watch`].
+struct TaskWatch {
+    client: TaskClient,
+    task_id: String,
+    events: Option<broadcast::Receiver<String>>,
+    last_state: Option<State>,
+    finished: bool,
+}
+
+impl TaskWatch {
+    /// Checks the task until its state differs from the last one seen.
+    async fn next_status(&mut self) -> Result<crate::models::TaskStatus> {
+        loop {
+            if self.last_state.is_some() {
+                self.wait_for_change().await;
+            }
+            let status = match self.client.get(&self.task_id).await {
+                Ok(task) => task.status.unwrap_or_default(),
+                Err(e) => {
+                    self.finished = true;
+                    return Err(e);
+                }
+            };
+            let state = status.state();
+            if self.last_state != Some(state) {
+                self.last_state = Some(state);
+                self.finished = matches!(state, State::Done | State::Failed);
+                return Ok(status.into());
+            }
+        }
+    }
+
+    /// Waits for an event of the task or the poll interval, whichever comes first.
+    async fn wait_for_change(&mut self) {
+        let timer = self.client.base_client.read().await.timer();
+        let Some(events) = self.events.as_mut() else {
+            timer.sleep(WATCH_POLL_INTERVAL).await;
+            return;
+        };
+        let task_id = &self.task_id;
+        let task_event = async {
+            loop {
+                match events.recv().await {
+                    Ok(id) if id == *task_id => return true,
+                    Ok(_) => {}
+                    // Missed events might have been for this task
+                    Err(broadcast::error::RecvError::Lagged(_)) => return true,
+                    Err(broadcast::error::RecvError::Closed) => return false,
+                }
+            }
+        };
+        let open = tokio::select! {
+            open = task_event => open,
+            _ = timer.sleep(WATCH_POLL_INTERVAL) => true,
+        };
+        if !open {
+            self.events = None;
+        }
+    }
+}
+
 /// Client for managing tasks in the Gevulot system.
 #[derive(Debug, Clone)]
 pub struct TaskClient {
     base_client: Arc<RwLock<BaseClient>>,
+    output_loading: OutputLoading,
+    notifier: Option<TaskNotifier>,
+    spec_defaults: Option<Arc<SpecDefaults>>,
+    origin: Option<Arc<Origin>>,
+    sudo_cancel: bool,
 }
 
 impl TaskClient {
@@ -28,11 +250,66 @@ impl TaskClient {
     ///
     /// A new instance of TaskClient.
     pub fn new(base_client: Arc<RwLock<BaseClient>>) -> Self {
-        Self { base_client }
+        Self {
+            base_client,
+            output_loading: OutputLoading::default(),
+            notifier: None,
+            spec_defaults: None,
+            origin: None,
+            sudo_cancel: false,
+        }
+    }
+
+    /// Sets how the stdout and stderr of tasks returned by `get` and `list` are treated.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_loading` - Whether to keep, truncate or drop the output.
+    pub fn set_output_loading(&mut self, output_loading: OutputLoading) {
+        self.output_loading = output_loading;
+    }
+
+    /// Sets the notifier waking up [`TaskClient::watch`] and [`TaskClient::create_with_deadline`]
+    /// on task events.
+    ///
+    /// # Arguments
+    ///
+    /// * `notifier` - A notifier registered with an event fetcher.
+    pub fn set_notifier(&mut self, notifier: TaskNotifier) {
+        self.notifier = Some(notifier);
+    }
+
+    /// Sets the defaults merged into every task created by this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec_defaults` - The defaults, None stops applying defaults.
+    pub fn set_spec_defaults(&mut self, spec_defaults: Option<Arc<SpecDefaults>>) {
+        self.spec_defaults = spec_defaults;
+    }
+
+    /// Sets the origin labels added to every task created by this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The origin, None stops adding origin labels.
+    pub fn set_origin(&mut self, origin: Option<Arc<Origin>>) {
+        self.origin = origin;
+    }
+
+    /// Sets whether [`TaskClient::cancel`] may delete tasks as the sudo authority.
+    ///
+    /// # Arguments
+    ///
+    /// * `enabled` - True if the signer is the sudo authority of the chain.
+    pub fn set_sudo_cancel(&mut self, enabled: bool) {
+        self.sudo_cancel = enabled;
     }
 
     /// Lists all tasks.
     ///
+    /// Fetches the tasks page by page until all tasks were received.
+    ///
     /// # Returns
     ///
     /// A Result containing a vector of tasks or an error.
@@ -41,15 +318,149 @@ impl TaskClient {
     ///
     /// This function will return an error if the request to the Gevulot client fails.
     pub async fn list(&mut self) -> Result<Vec<crate::proto::gevulot::gevulot::Task>> {
-        let request = crate::proto::gevulot::gevulot::QueryAllTaskRequest { pagination: None };
+        self.list_filtered(|_| true).await
+    }
+
+    /// Lists the tasks created by an account.
+    ///
+    /// The chain cannot filter tasks, so this fetches all tasks page by page and keeps the
+    /// matching ones only.
+    ///
+    /// # Arguments
+    ///
+    /// * `creator` - The address of the creator.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of tasks or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_by_creator(
+        &mut self,
+        creator: &str,
+    ) -> Result<Vec<crate::proto::gevulot::gevulot::Task>> {
+        self.list_filtered(|task| {
+            task.metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.creator == creator)
+        })
+        .await
+    }
+
+    /// Lists the tasks assigned to a worker.
+    ///
+    /// The chain cannot filter tasks, so this fetches all tasks page by page and keeps the
+    /// matching ones only.
+    ///
+    /// # Arguments
+    ///
+    /// * `worker_id` - The ID of the worker.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of tasks or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_by_worker(
+        &mut self,
+        worker_id: &str,
+    ) -> Result<Vec<crate::proto::gevulot::gevulot::Task>> {
+        self.list_filtered(|task| {
+            task.status.as_ref().is_some_and(|status| {
+                status.active_worker == worker_id
+                    || status
+                        .assigned_workers
+                        .iter()
+                        .any(|worker| worker == worker_id)
+            })
+        })
+        .await
+    }
+
+    /// Lists the tasks in a state.
+    ///
+    /// The chain cannot filter tasks, so this fetches all tasks page by page and keeps the
+    /// matching ones only.
+    ///
+    /// # Arguments
+    ///
+    /// * `state` - The state of the tasks.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of tasks or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_by_state(
+        &mut self,
+        state: State,
+    ) -> Result<Vec<crate::proto::gevulot::gevulot::Task>> {
+        self.list_filtered(|task| {
+            task.status
+                .as_ref()
+                .is_some_and(|status| status.state() == state)
+        })
+        .await
+    }
+
+    // Lists all tasks page by page, dropping the ones not matching the predicate right away
+    async fn list_filtered(
+        &mut self,
+        predicate: impl Fn(&crate::proto::gevulot::gevulot::Task) -> bool,
+    ) -> Result<Vec<crate::proto::gevulot::gevulot::Task>> {
+        let mut tasks = Vec::new();
+        let mut options = PageOptions::new().limit(DEFAULT_PAGE_LIMIT);
+        loop {
+            let (page, next) = self.list_paged(options.clone()).await?;
+            tasks.extend(page.into_iter().filter(|task| predicate(task)));
+            match next {
+                Some(token) => options.token = Some(token),
+                None => break,
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// Lists a single page of tasks.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The size and position of the page.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the tasks of the page and the token of the next page, None if this
+    /// was the last page, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_paged(
+        &mut self,
+        options: PageOptions,
+    ) -> Result<(Vec<crate::proto::gevulot::gevulot::Task>, Option<PageToken>)> {
+        let request = crate::proto::gevulot::gevulot::QueryAllTaskRequest {
+            pagination: Some(options.to_request()),
+        };
         let response = self
             .base_client
             .write()
             .await
             .gevulot_client
             .task_all(request)
-            .await?;
-        Ok(response.into_inner().task)
+            .await?
+            .into_inner();
+        let mut tasks = response.task;
+        for task in tasks.iter_mut() {
+            self.output_loading.apply(task);
+        }
+        Ok((tasks, PageToken::next(response.pagination.as_ref())))
     }
 
     /// Gets a task by its ID.
@@ -66,6 +477,47 @@ impl TaskClient {
     ///
     /// This function will return an error if the task is not found or if the request to the Gevulot client fails.
     pub async fn get(&mut self, id: &str) -> Result<crate::proto::gevulot::gevulot::Task> {
+        let mut task = self.get_full(id).await?;
+        self.output_loading.apply(&mut task);
+        Ok(task)
+    }
+
+    /// Gets a task by its ID as a model.
+    ///
+    /// Like `get`, but converts the task into a [`crate::models::Task`] with parsed resource
+    /// units, status strings and metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task to retrieve.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the task or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the task is not found or if the request to the Gevulot client fails.
+    pub async fn get_model(&mut self, id: &str) -> Result<crate::models::Task> {
+        Ok(self.get(id).await?.into())
+    }
+
+    /// Lists all tasks as models.
+    ///
+    /// Like `list`, but converts the tasks into [`crate::models::Task`]s.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of tasks or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_models(&mut self) -> Result<Vec<crate::models::Task>> {
+        Ok(self.list().await?.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_full(&mut self, id: &str) -> Result<crate::proto::gevulot::gevulot::Task> {
         let request = crate::proto::gevulot::gevulot::QueryGetTaskRequest { id: id.to_owned() };
         let response = self
             .base_client
@@ -74,7 +526,49 @@ impl TaskClient {
             .gevulot_client
             .task(request)
             .await?;
-        response.into_inner().task.ok_or(Error::NotFound)
+        response.into_inner().task.ok_or(Error::NotFound(None))
+    }
+
+    /// Fetches the complete stdout of a task, regardless of the output loading setting.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the stdout, None if the task didn't store any, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the task is not found or if the request to the Gevulot client fails.
+    pub async fn fetch_stdout(&mut self, id: &str) -> Result<Option<String>> {
+        let task = self.get_full(id).await?;
+        Ok(task
+            .status
+            .map(|status| status.stdout)
+            .filter(|stdout| !stdout.is_empty()))
+    }
+
+    /// Fetches the complete stderr of a task, regardless of the output loading setting.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the stderr, None if the task didn't store any, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the task is not found or if the request to the Gevulot client fails.
+    pub async fn fetch_stderr(&mut self, id: &str) -> Result<Option<String>> {
+        let task = self.get_full(id).await?;
+        Ok(task
+            .status
+            .map(|status| status.stderr)
+            .filter(|stderr| !stderr.is_empty()))
     }
 
     /// Creates a new task.
@@ -90,7 +584,13 @@ impl TaskClient {
     /// # Errors
     ///
     /// This function will return an error if the request to the Gevulot client fails.
-    pub async fn create(&mut self, msg: MsgCreateTask) -> Result<MsgCreateTaskResponse> {
+    pub async fn create(&mut self, mut msg: MsgCreateTask) -> Result<MsgCreateTaskResponse> {
+        if let Some(spec_defaults) = &self.spec_defaults {
+            spec_defaults.apply_to_task(&mut msg);
+        }
+        if let Some(origin) = &self.origin {
+            origin.apply(&mut msg.labels);
+        }
         let resp: MsgCreateTaskResponse = self
             .base_client
             .write()
@@ -100,6 +600,42 @@ impl TaskClient {
         Ok(resp)
     }
 
+    /// Creates several tasks in a single transaction.
+    ///
+    /// This needs a single account sequence and block inclusion for all tasks, which is a lot
+    /// faster than creating them one by one. Either all tasks are created or none.
+    ///
+    /// # Arguments
+    ///
+    /// * `msgs` - The messages containing the task details.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the IDs of the created tasks in the order of the messages, or an
+    /// error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `msgs` is empty, the transaction fails, or the
+    /// request to the Gevulot client fails.
+    pub async fn create_many(&mut self, mut msgs: Vec<MsgCreateTask>) -> Result<Vec<String>> {
+        if let Some(spec_defaults) = &self.spec_defaults {
+            msgs.iter_mut()
+                .for_each(|msg| spec_defaults.apply_to_task(msg));
+        }
+        if let Some(origin) = &self.origin {
+            msgs.iter_mut()
+                .for_each(|msg| origin.apply(&mut msg.labels));
+        }
+        let resps: Vec<MsgCreateTaskResponse> = self
+            .base_client
+            .write()
+            .await
+            .send_msgs_sync(&msgs, "")
+            .await?;
+        Ok(resps.into_iter().map(|resp| resp.id).collect())
+    }
+
     /// Deletes a task.
     ///
     /// # Arguments
@@ -123,6 +659,241 @@ impl TaskClient {
         Ok(resp)
     }
 
+    /// Stops a task, picking the action its state and the signer allow.
+    ///
+    /// The chain has no dedicated cancel message. Pending and declined tasks are deleted if the
+    /// signer created them, running tasks are finished as failed if the signer is their worker.
+    /// Any other task is deleted as the sudo authority if enabled with
+    /// [`TaskClient::set_sudo_cancel`].
+    ///
+    /// # Arguments
+    ///
+    /// * `task_id` - The ID of the task to cancel.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the action which stopped the task or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::CannotCancel` with the state of the task if the task
+    /// is already done or failed or the signer isn't allowed to stop it, or an error if the
+    /// task is not found, no signer is set or a request to the Gevulot client fails.
+    pub async fn cancel(&mut self, task_id: &str) -> Result<CancelAction> {
+        let task = self.get(task_id).await?;
+        let signer = self
+            .base_client
+            .read()
+            .await
+            .address
+            .clone()
+            .ok_or_else(|| Error::Unknown("no signer set".to_string()))?;
+        let action =
+            cancel_action(&task, &signer, self.sudo_cancel).map_err(Error::CannotCancel)?;
+        log::debug!("Cancelling task {}: {:?}", task_id, action);
+
+        match action {
+            CancelAction::Deleted => {
+                self.delete(MsgDeleteTask {
+                    creator: signer,
+                    id: task_id.to_string(),
+                })
+                .await?;
+            }
+            CancelAction::Finished => {
+                self.finish(MsgFinishTask {
+                    creator: signer,
+                    task_id: task_id.to_string(),
+                    exit_code: 1,
+                    error: "cancelled".to_string(),
+                    ..Default::default()
+                })
+                .await?;
+            }
+            CancelAction::SudoDeleted => {
+                let _: MsgSudoDeleteTaskResponse = self
+                    .base_client
+                    .write()
+                    .await
+                    .send_msg_sync(
+                        MsgSudoDeleteTask {
+                            authority: signer,
+                            id: task_id.to_string(),
+                        },
+                        "",
+                    )
+                    .await?;
+            }
+        }
+        Ok(action)
+    }
+
+    /// Creates a new task which has to be accepted by a worker before a deadline.
+    ///
+    /// After submitting the task it is re-checked whenever the notifier set with
+    /// [`TaskClient::set_notifier`] reports an event for it, and polled every second otherwise.
+    /// Whenever the assigned workers decline the task it is rescheduled. If no worker accepted
+    /// the task when the deadline passes, the task is deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message containing the task details.
+    /// * `must_start_by` - The point in time by which a worker has to accept the task.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing whether the task was accepted or cancelled, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if any request to the Gevulot client fails.
+    pub async fn create_with_deadline(
+        &mut self,
+        msg: MsgCreateTask,
+        must_start_by: Instant,
+    ) -> Result<DeadlineOutcome> {
+        let creator = msg.creator.clone();
+        // Subscribe before creating the task so no event in between is lost
+        let mut events = self.notifier.as_ref().map(TaskNotifier::subscribe);
+        let task_id = self.create(msg).await?.id;
+        let timer = self.base_client.read().await.timer();
+        let mut reschedules = 0;
+
+        loop {
+            let status = self.get(&task_id).await?.status.unwrap_or_default();
+            match status.state() {
+                State::Running | State::Done | State::Failed => {
+                    return Ok(DeadlineOutcome::Accepted {
+                        task_id,
+                        worker_id: status.active_worker,
+                        reschedules,
+                    });
+                }
+                State::Declined if timer.now() < must_start_by => {
+                    log::debug!("Task {} was declined, rescheduling", task_id);
+                    self.reschedule(MsgRescheduleTask {
+                        creator: creator.clone(),
+                        id: task_id.clone(),
+                    })
+                    .await?;
+                    reschedules += 1;
+                }
+                _ => {}
+            }
+
+            let now = timer.now();
+            if now >= must_start_by {
+                log::warn!(
+                    "Task {} was not accepted before its deadline, deleting it",
+                    task_id
+                );
+                self.delete(MsgDeleteTask {
+                    creator,
+                    id: task_id.clone(),
+                })
+                .await?;
+                return Ok(DeadlineOutcome::Cancelled {
+                    task_id,
+                    reschedules,
+                });
+            }
+            let sleep = timer.sleep(DEADLINE_POLL_INTERVAL.min(must_start_by - now));
+            let Some(receiver) = events.as_mut() else {
+                sleep.await;
+                continue;
+            };
+            let task_event = async {
+                loop {
+                    match receiver.recv().await {
+                        Ok(id) if id == task_id => return true,
+                        Ok(_) => {}
+                        // Missed events might have been for this task
+                        Err(broadcast::error::RecvError::Lagged(_)) => return true,
+                        Err(broadcast::error::RecvError::Closed) => return false,
+                    }
+                }
+            };
+            let open = tokio::select! {
+                open = task_event => open,
+                _ = sleep => true,
+            };
+            if !open {
+                events = None;
+            }
+        }
+    }
+
+    /// Watches a task move through its states.
+    ///
+    /// The task is re-checked whenever the notifier set with [`TaskClient::set_notifier`]
+    /// reports an event for it, and polled every few seconds otherwise. Every state the task is
+    /// seen in is yielded once, so quick transitions between two checks can be missed. The
+    /// stream ends after yielding the Done or Failed status, or after the first error.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_id` - The ID of the task to watch.
+    ///
+    /// # Returns
+    ///
+    /// A stream of the statuses of the task.
+    pub fn watch(&self, task_id: &str) -> impl Stream<Item = Result<crate::models::TaskStatus>> {
+        let watch = TaskWatch {
+            client: self.clone(),
+            task_id: task_id.to_string(),
+            // Subscribe before the first check so no event in between is lost
+            events: self.notifier.as_ref().map(TaskNotifier::subscribe),
+            last_state: None,
+            finished: false,
+        };
+        futures::stream::unfold(watch, |mut watch| async move {
+            if watch.finished {
+                return None;
+            }
+            let status = watch.next_status().await;
+            Some((status, watch))
+        })
+    }
+
+    /// Waits until a task is done or failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `task_id` - The ID of the task to wait for.
+    /// * `timeout` - How long to wait at most.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the final status of the task, with its exit code and output
+    /// contexts, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::Timeout` if the task didn't finish in time, or an error
+    /// if the task is not found or a request to the Gevulot client fails.
+    pub async fn wait_for_completion(
+        &self,
+        task_id: &str,
+        timeout: Duration,
+    ) -> Result<crate::models::TaskStatus> {
+        let wait = async {
+            let mut statuses = std::pin::pin!(self.watch(task_id));
+            let mut last = None;
+            while let Some(status) = statuses.next().await {
+                last = Some(status?);
+            }
+            last.ok_or(Error::NotFound(None))
+        };
+        let timer = self.base_client.read().await.timer();
+        tokio::select! {
+            status = wait => status,
+            _ = timer.sleep(timeout) => Err(Error::Timeout(format!(
+                "task {} didn't finish within {:?}",
+                task_id, timeout
+            ))),
+        }
+    }
+
     /// Accepts a task.
     ///
     /// # Arguments
@@ -213,4 +984,164 @@ impl TaskClient {
             .await?;
         Ok(resp)
     }
+
+    /// Builds a prefetch plan for the inputs of a task.
+    ///
+    /// Resolves every input context of the task to its pin and collects the workers which
+    /// acknowledged storing the data as well as the pin's fallback URLs. Workers can use the
+    /// plan to decide whether to accept or decline a task they were assigned.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the task.
+    /// * `bandwidth` - Expected download bandwidth in bytes per second.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the prefetch plan or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the task is not found or if the request to the Gevulot client fails.
+    pub async fn prefetch_plan(&mut self, id: &str, bandwidth: u64) -> Result<PrefetchPlan> {
+        let task = self.get(id).await?;
+        let input_contexts = task
+            .spec
+            .map(|spec| spec.input_contexts)
+            .unwrap_or_default();
+
+        let mut inputs = Vec::with_capacity(input_contexts.len());
+        for input in input_contexts {
+            let request = crate::proto::gevulot::gevulot::QueryGetPinRequest {
+                cid: input.source.clone(),
+            };
+            let response = self
+                .base_client
+                .write()
+                .await
+                .gevulot_client
+                .pin(request)
+                .await;
+            let pin = match response {
+                Ok(response) => response.into_inner().pin,
+                Err(status) if status.code() == tonic::Code::NotFound => None,
+                Err(status) => return Err(status.into()),
+            };
+            inputs.push((input, pin));
+        }
+
+        Ok(PrefetchPlan::new(id, &inputs, bandwidth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::gevulot::gevulot;
+
+    fn task(stdout: &str, stderr: &str) -> gevulot::Task {
+        gevulot::Task {
+            status: Some(gevulot::TaskStatus {
+                stdout: stdout.to_string(),
+                stderr: stderr.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_output_loading() {
+        let mut full = task("hello world", "oops");
+        OutputLoading::Full.apply(&mut full);
+        assert_eq!(full, task("hello world", "oops"));
+
+        // Truncation never splits a multi-byte character
+        let mut truncated = task("hello world", "äöü");
+        OutputLoading::Truncate(5).apply(&mut truncated);
+        assert_eq!(truncated, task("hello", "äö"));
+
+        let mut skipped = task("hello world", "oops");
+        OutputLoading::Skip.apply(&mut skipped);
+        assert_eq!(skipped, task("", ""));
+    }
+
+    #[test]
+    fn test_cancel_action() {
+        let task = |state: State, creator: &str, worker: &str| gevulot::Task {
+            metadata: Some(gevulot::Metadata {
+                creator: creator.to_string(),
+                ..Default::default()
+            }),
+            status: Some(gevulot::TaskStatus {
+                state: state as i32,
+                active_worker: worker.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            cancel_action(&task(State::Pending, "alice", ""), "alice", false),
+            Ok(CancelAction::Deleted)
+        );
+        assert_eq!(
+            cancel_action(&task(State::Declined, "alice", ""), "alice", false),
+            Ok(CancelAction::Deleted)
+        );
+        assert_eq!(
+            cancel_action(&task(State::Running, "alice", "bob"), "bob", false),
+            Ok(CancelAction::Finished)
+        );
+        assert_eq!(
+            cancel_action(&task(State::Running, "alice", "bob"), "alice", false),
+            Err(State::Running)
+        );
+        assert_eq!(
+            cancel_action(&task(State::Running, "alice", "bob"), "admin", true),
+            Ok(CancelAction::SudoDeleted)
+        );
+        assert_eq!(
+            cancel_action(&task(State::Done, "alice", "bob"), "admin", true),
+            Err(State::Done)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_notifier() {
+        use cosmrs::{rpc::dialect::v0_34::EventAttribute, tendermint::abci::Event};
+
+        let mut notifier = TaskNotifier::new();
+        let mut events = notifier.subscribe();
+        let event = |kind: &str, key: &[u8], value: &[u8]| {
+            Event::new(
+                kind,
+                vec![
+                    EventAttribute {
+                        index: true,
+                        key: key.to_vec(),
+                        value: value.to_vec(),
+                    },
+                    EventAttribute {
+                        index: true,
+                        key: b"worker-id".to_vec(),
+                        value: b"worker1".to_vec(),
+                    },
+                ],
+            )
+        };
+        let height = crate::Height::from(1u32);
+
+        notifier
+            .handle_event(&event("create-worker", b"worker-id", b"worker1"), height)
+            .await
+            .unwrap();
+        notifier
+            .handle_event(&event("accept-task", b"task-id", b"task1"), height)
+            .await
+            .unwrap();
+
+        assert_eq!(events.try_recv().unwrap(), "task1");
+        assert!(events.try_recv().is_err());
+    }
 }
@@ -0,0 +1,126 @@
+//! Utilities for testing code built on the clients.
+//!
+//! Set a [`ManualTimer`] on the base client to make every wait of the clients complete
+//! immediately while the time they would have taken is still accounted for, so timeouts and
+//! deadlines are reached without any real delay:
+//!
+//! ```
+//! use gevulot_rs::testing::ManualTimer;
+//! use gevulot_rs::timer::Timer;
+//! use std::time::Duration;
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let timer = ManualTimer::new();
+//! let start = timer.now();
+//! timer.sleep(Duration::from_secs(30)).await;
+//! assert_eq!(timer.now() - start, Duration::from_secs(30));
+//! assert_eq!(timer.sleeps(), vec![Duration::from_secs(30)]);
+//! # }
+//! ```
+//!
+//! Alternatively keep the default [`TokioTimer`](crate::timer::TokioTimer) and pause the tokio
+//! clock with `tokio::time::pause()` (requires tokio's `test-util` feature).
+
+use std::sync::{Arc, Mutex};
+
+use tokio::time::{Duration, Instant};
+
+use crate::timer::{Sleep, Timer};
+
+/// Timer whose time only moves when it sleeps or is advanced.
+///
+/// Clones share the same clock.
+#[derive(Clone, Debug)]
+pub struct ManualTimer {
+    start: Instant,
+    state: Arc<Mutex<ManualTimerState>>,
+}
+
+#[derive(Debug, Default)]
+struct ManualTimerState {
+    elapsed: Duration,
+    sleeps: Vec<Duration>,
+}
+
+impl Default for ManualTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualTimer {
+    /// Creates a timer starting at the current time.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Moves the clock forward without recording a sleep.
+    pub fn advance(&self, duration: Duration) {
+        self.lock().elapsed += duration;
+    }
+
+    /// Returns how far the clock moved since the timer was created.
+    pub fn elapsed(&self) -> Duration {
+        self.lock().elapsed
+    }
+
+    /// Returns the durations of all sleeps so far, in order.
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.lock().sleeps.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ManualTimerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Timer for ManualTimer {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        let mut state = self.lock();
+        state.elapsed += duration;
+        state.sleeps.push(duration);
+        // Yield so polling loops don't starve other tasks
+        Box::pin(tokio::task::yield_now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timer::TokioTimer;
+
+    #[tokio::test]
+    async fn test_manual_timer() {
+        let timer = ManualTimer::new();
+        let start = timer.now();
+        let clone = timer.clone();
+        clone.sleep(Duration::from_secs(5)).await;
+        timer.advance(Duration::from_secs(1));
+        assert_eq!(timer.now() - start, Duration::from_secs(6));
+        assert_eq!(clone.elapsed(), Duration::from_secs(6));
+        assert_eq!(timer.sleeps(), vec![Duration::from_secs(5)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokio_timer_paused() {
+        let timer = TokioTimer;
+        let start = timer.now();
+        let wait = async {
+            loop {
+                timer.sleep(Duration::from_secs(1)).await;
+            }
+        };
+        assert!(tokio::time::timeout(Duration::from_secs(3600), wait)
+            .await
+            .is_err());
+        assert!(timer.now() - start >= Duration::from_secs(3600));
+    }
+}
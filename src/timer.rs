@@ -0,0 +1,37 @@
+//! Clock used by the clients when waiting.
+//!
+//! Every client method polling the chain (waiting for blocks, transactions or task state
+//! changes) sleeps and reads the time through the [`Timer`] set on the
+//! [`BaseClient`](crate::base_client::BaseClient). The default [`TokioTimer`] follows
+//! `tokio::time`, so tests running with `tokio::time::pause()` skip the waits. The
+//! [`testing`](crate::testing) module provides a manually driven timer.
+
+use std::{future::Future, pin::Pin};
+
+use tokio::time::{Duration, Instant};
+
+/// Future returned by [`Timer::sleep`].
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Source of the current time and of sleeps.
+pub trait Timer: std::fmt::Debug + Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns a future completing after the duration has passed.
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// Timer backed by `tokio::time`, honoring `tokio::time::pause()`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioTimer;
+
+impl Timer for TokioTimer {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
@@ -0,0 +1,320 @@
+//! Pipelined submission of transactions.
+//!
+//! The methods of [`BaseClient`] sending transactions hold the client until the transaction was
+//! broadcast, and waiting for its inclusion holds it for a block more, so transactions of one
+//! account are sent one after the other. A [`TxDispatcher`] only holds the client while signing
+//! a transaction, which assigns it the next account sequence. Broadcasts follow in the order of
+//! the sequences without waiting for the previous transactions to be included, so several
+//! transactions can make it into the same block:
+//!
+//! ```no_run
+//! # use gevulot_rs::{tx_dispatcher::TxDispatcher, GevulotClient};
+//! # use gevulot_rs::proto::gevulot::gevulot::MsgAckPin;
+//! # async fn run(client: &GevulotClient, acks: Vec<MsgAckPin>) -> gevulot_rs::Result<()> {
+//! let dispatcher = TxDispatcher::new(client.base_client.clone());
+//! let mut pending = Vec::new();
+//! for ack in &acks {
+//!     pending.push(dispatcher.submit(ack, "").await?);
+//! }
+//! for receipt in futures::future::join_all(pending).await {
+//!     println!("included at height {}", receipt?.height);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Submissions wait while the queue is full. Transactions rejected because of a wrong account
+//! sequence are signed again, up to the retries set with
+//! [`BaseClient::set_sequence_retries`].
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use cosmos_sdk_proto::prost::{Message, Name};
+use tokio::sync::{mpsc, oneshot, RwLock};
+
+use crate::{
+    base_client::{BaseClient, SignedTx, TxServiceClient},
+    builders::MsgBatch,
+    compression::MeteredChannel,
+    error::{Error, Result},
+    timer::Timer,
+    tx_receipt::TxReceipt,
+};
+
+/// Number of submitted transactions waiting to be signed unless set otherwise.
+pub const DEFAULT_QUEUE_SIZE: usize = 64;
+
+/// Time a transaction may take to be included after its broadcast unless set otherwise.
+pub const DEFAULT_INCLUSION_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Interval in which a broadcast transaction is looked up until it is included
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Transaction waiting to be signed
+struct Job {
+    msgs: Vec<cosmrs::Any>,
+    memo: String,
+    inclusion_timeout: Duration,
+    reply: oneshot::Sender<Result<TxReceipt>>,
+}
+
+/// Queue of transactions which are signed one after the other and broadcast without waiting for
+/// the previous ones to be included.
+///
+/// Clones submit to the same queue. The background task working through the queue ends when
+/// all clones are dropped.
+#[derive(Clone, Debug)]
+pub struct TxDispatcher {
+    queue: mpsc::Sender<Job>,
+    inclusion_timeout: Duration,
+}
+
+impl TxDispatcher {
+    /// Creates a dispatcher sending transactions with the client, with a queue of
+    /// [`DEFAULT_QUEUE_SIZE`].
+    pub fn new(base_client: Arc<RwLock<BaseClient>>) -> Self {
+        Self::with_queue_size(base_client, DEFAULT_QUEUE_SIZE)
+    }
+
+    /// Creates a dispatcher sending transactions with the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `base_client` - The client signing and broadcasting the transactions.
+    /// * `queue_size` - The number of submitted transactions waiting to be signed, before
+    ///   further submissions wait.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `queue_size` is 0.
+    pub fn with_queue_size(base_client: Arc<RwLock<BaseClient>>, queue_size: usize) -> Self {
+        let (queue, jobs) = mpsc::channel(queue_size);
+        crate::background::spawn_named("gevulot-tx-dispatcher", dispatch(base_client, jobs));
+        Self {
+            queue,
+            inclusion_timeout: DEFAULT_INCLUSION_TIMEOUT,
+        }
+    }
+
+    /// Sets the time transactions submitted afterwards may take to be included after their
+    /// broadcast.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The timeout, [`DEFAULT_INCLUSION_TIMEOUT`] by default.
+    pub fn set_inclusion_timeout(&mut self, timeout: Duration) {
+        self.inclusion_timeout = timeout;
+    }
+
+    /// Returns the number of submitted transactions waiting to be signed.
+    pub fn queued(&self) -> usize {
+        self.queue.max_capacity() - self.queue.capacity()
+    }
+
+    /// Submits a transaction with a single message.
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The message of the transaction.
+    /// * `memo` - The memo of the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A [`PendingTx`] resolving to the receipt of the transaction once it is included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message can't be encoded or the dispatcher stopped.
+    pub async fn submit<M: Message + Name>(&self, msg: &M, memo: &str) -> Result<PendingTx> {
+        self.submit_batch(MsgBatch::new().msg(msg)?, memo).await
+    }
+
+    /// Submits a transaction with a batch of messages.
+    ///
+    /// Waits while the queue is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - The messages of the transaction.
+    /// * `memo` - The memo of the transaction.
+    ///
+    /// # Returns
+    ///
+    /// A [`PendingTx`] resolving to the receipt of the transaction once it is included.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dispatcher stopped.
+    pub async fn submit_batch(&self, batch: MsgBatch, memo: &str) -> Result<PendingTx> {
+        let (reply, receiver) = oneshot::channel();
+        let job = Job {
+            msgs: batch.into_anys(),
+            memo: memo.to_string(),
+            inclusion_timeout: self.inclusion_timeout,
+            reply,
+        };
+        self.queue
+            .send(job)
+            .await
+            .map_err(|_| Error::Unknown("tx dispatcher stopped".to_string()))?;
+        Ok(PendingTx { receiver })
+    }
+}
+
+/// Transaction submitted to a [`TxDispatcher`].
+///
+/// Resolves to the receipt of the transaction once it is included in a block, or to an error
+/// if it couldn't be signed or broadcast, failed in the block or wasn't included in time.
+#[derive(Debug)]
+pub struct PendingTx {
+    receiver: oneshot::Receiver<Result<TxReceipt>>,
+}
+
+impl Future for PendingTx {
+    type Output = Result<TxReceipt>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver).poll(cx).map(|result| {
+            result.unwrap_or_else(|_| {
+                Err(Error::Unknown(
+                    "tx dispatcher stopped before the tx was included".to_string(),
+                ))
+            })
+        })
+    }
+}
+
+// Signs and broadcasts the queued transactions in order, leaving the wait for their inclusion
+// to a task per transaction
+async fn dispatch(base_client: Arc<RwLock<BaseClient>>, mut jobs: mpsc::Receiver<Job>) {
+    while let Some(job) = jobs.recv().await {
+        let mut retry = 0;
+        let result = loop {
+            let (signed, mut tx_client, timer, retries) = {
+                let mut base_client = base_client.write().await;
+                let signed = base_client.sign_anys(job.msgs.clone(), &job.memo).await;
+                if let Ok(tx) = &signed {
+                    // The next transaction is signed before this one is broadcast
+                    base_client.account_sequence = Some(tx.sequence + 1);
+                }
+                (
+                    signed,
+                    base_client.tx_client.clone(),
+                    base_client.timer(),
+                    base_client.sequence_retries(),
+                )
+            };
+            let result = match signed {
+                Ok(tx) => broadcast(&base_client, &mut tx_client, &tx)
+                    .await
+                    .map(|hash| (hash, tx_client, timer)),
+                Err(e) => Err(e),
+            };
+            match result {
+                Err(e) if e.is_sequence_mismatch() && retry < retries => {
+                    retry += 1;
+                    log::warn!(
+                        "Account sequence mismatch, retrying ({}/{}): {}",
+                        retry,
+                        retries,
+                        e
+                    );
+                    base_client.write().await.account_sequence = e.expected_sequence();
+                }
+                result => break result,
+            }
+        };
+
+        match result {
+            Ok((hash, tx_client, timer)) => {
+                crate::background::spawn_named("gevulot-tx-inclusion", async move {
+                    let receipt =
+                        wait_for_inclusion(tx_client, timer, &hash, job.inclusion_timeout).await;
+                    let _ = job.reply.send(receipt);
+                });
+            }
+            Err(e) => {
+                let _ = job.reply.send(Err(e));
+            }
+        }
+    }
+}
+
+// Broadcasts a signed transaction, handing its sequence back if it was rejected
+async fn broadcast(
+    base_client: &RwLock<BaseClient>,
+    tx_client: &mut TxServiceClient<MeteredChannel>,
+    tx: &SignedTx,
+) -> Result<String> {
+    let result = BaseClient::broadcast_with(tx_client, tx).await;
+    if let Err(e) = &result {
+        // A rejected transaction didn't use its sequence, after other errors it is unknown
+        base_client.write().await.account_sequence = match e {
+            Error::Tx(_) => Some(tx.sequence),
+            _ => None,
+        };
+    }
+    result
+}
+
+// Looks up a broadcast transaction until it is included in a block
+async fn wait_for_inclusion(
+    mut tx_client: TxServiceClient<MeteredChannel>,
+    timer: Arc<dyn Timer>,
+    hash: &str,
+    timeout: Duration,
+) -> Result<TxReceipt> {
+    let start = timer.now();
+    loop {
+        let request = cosmos_sdk_proto::cosmos::tx::v1beta1::GetTxRequest {
+            hash: hash.to_owned(),
+        };
+        match tx_client.get_tx(request).await.map_err(Error::from) {
+            Ok(response) => {
+                if let Some(tx_response) = response.into_inner().tx_response {
+                    BaseClient::assert_tx_success(&tx_response)?;
+                    return TxReceipt::from_response(&tx_response);
+                }
+            }
+            // Not included yet
+            Err(Error::NotFound(_)) => {}
+            Err(e) if e.is_retryable() => {
+                log::debug!("Failed to look up tx {}, retrying: {}", hash, e);
+            }
+            Err(e) => return Err(e),
+        }
+        if timer.now() - start > timeout {
+            return Err(Error::Timeout(format!(
+                "tx {} not included within {}",
+                hash,
+                humantime::format_duration(timeout)
+            )));
+        }
+        timer.sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pending_tx() {
+        let (reply, receiver) = oneshot::channel();
+        reply.send(Err(Error::Timeout("tx".to_string()))).unwrap();
+        let pending = PendingTx { receiver };
+        assert!(matches!(pending.await, Err(Error::Timeout(_))));
+
+        // Dropped jobs resolve with an error
+        let (reply, receiver) = oneshot::channel::<Result<TxReceipt>>();
+        drop(reply);
+        let pending = PendingTx { receiver };
+        assert!(matches!(pending.await, Err(Error::Unknown(_))));
+    }
+}
@@ -0,0 +1,487 @@
+//! Export and import of transactions as JSON for offline signing.
+//!
+//! Transactions are written in the JSON format of the Cosmos SDK CLI (`tx --generate-only`,
+//! `tx sign`, `tx multisign`), so cosigners using other tooling can sign transactions prepared
+//! by this crate and hand them back:
+//!
+//! ```no_run
+//! # use gevulot_rs::{base_client::BaseClient, tx_json::TxJson};
+//! # use cosmos_sdk_proto::cosmos::tx::v1beta1::Tx;
+//! # async fn run(client: &mut BaseClient, msg: cosmrs::Any) -> gevulot_rs::Result<()> {
+//! let tx = client.prepare_tx(vec![msg], "", 200_000).await?;
+//! std::fs::write("unsigned.json", tx.to_sign_doc_json()?).unwrap();
+//! // Sign with other tooling, then
+//! let signed = Tx::from_json(&std::fs::read_to_string("signed.json").unwrap())?;
+//! client.broadcast_tx(&signed).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Messages are encoded as proto3 JSON with the original field names, only the messages of the
+//! Gevulot module and bank transfers are supported. Only single signers with secp256k1 keys are
+//! supported, multisig signer infos are rejected.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use cosmos_sdk_proto::{
+    cosmos::{
+        bank::v1beta1::MsgSend,
+        base::v1beta1::Coin,
+        crypto::secp256k1::PubKey,
+        tx::{
+            signing::v1beta1::SignMode,
+            v1beta1::{mode_info, AuthInfo, Fee, ModeInfo, SignerInfo, Tx, TxBody},
+        },
+    },
+    prost::{Message, Name},
+    Any,
+};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    error::{Error, Result},
+    proto::gevulot::gevulot,
+};
+
+/// JSON export and import of transactions in the format of the Cosmos SDK.
+pub trait TxJson: Sized {
+    /// Encodes the transaction as pretty printed JSON, signatures included.
+    fn to_sign_doc_json(&self) -> Result<String>;
+
+    /// Decodes a transaction from JSON, e.g. one signed by other tooling.
+    fn from_json(json: &str) -> Result<Self>;
+}
+
+impl TxJson for Tx {
+    fn to_sign_doc_json(&self) -> Result<String> {
+        let value = tx_to_json(self)?;
+        serde_json::to_string_pretty(&value).map_err(|e| Error::EncodeError(e.to_string()))
+    }
+
+    fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json).map_err(|e| Error::Parse(e.to_string()))?;
+        tx_from_json(&value)
+    }
+}
+
+// Converts the value of an Any into JSON with a known message type
+macro_rules! message_codecs {
+    ($($msg:ty),* $(,)?) => {
+        fn message_to_json(any: &Any) -> Result<Value> {
+            $(
+                if any.type_url == <$msg>::type_url() {
+                    let msg = <$msg>::decode(any.value.as_slice())?;
+                    return serde_json::to_value(&msg)
+                        .map_err(|e| Error::EncodeError(e.to_string()));
+                }
+            )*
+            Err(Error::EncodeError(format!("no JSON encoding for {}", any.type_url)))
+        }
+
+        fn message_from_json(type_url: &str, value: Value) -> Result<Any> {
+            $(
+                if type_url == <$msg>::type_url() {
+                    let msg: $msg = serde_json::from_value(value)
+                        .map_err(|e| Error::Parse(format!("{}: {}", type_url, e)))?;
+                    return Ok(Any {
+                        type_url: type_url.to_string(),
+                        value: msg.encode_to_vec(),
+                    });
+                }
+            )*
+            Err(Error::Parse(format!("no JSON decoding for {}", type_url)))
+        }
+    };
+}
+
+message_codecs!(
+    gevulot::MsgCreateWorker,
+    gevulot::MsgUpdateWorker,
+    gevulot::MsgDeleteWorker,
+    gevulot::MsgAnnounceWorkerExit,
+    gevulot::MsgCreateTask,
+    gevulot::MsgDeleteTask,
+    gevulot::MsgRescheduleTask,
+    gevulot::MsgAcceptTask,
+    gevulot::MsgDeclineTask,
+    gevulot::MsgFinishTask,
+    gevulot::MsgCreateWorkflow,
+    gevulot::MsgDeleteWorkflow,
+    gevulot::MsgCreateProof,
+    gevulot::MsgDeleteProof,
+    gevulot::MsgCreatePin,
+    gevulot::MsgDeletePin,
+    gevulot::MsgAckPin,
+    gevulot::MsgUpdateParams,
+    gevulot::MsgSudoFreezeAccount,
+    gevulot::MsgSudoDeleteWorker,
+    gevulot::MsgSudoDeletePin,
+    gevulot::MsgSudoDeleteTask,
+);
+
+fn any_to_json(any: &Any) -> Result<Value> {
+    let mut value = if any.type_url == MsgSend::type_url() {
+        let msg = MsgSend::decode(any.value.as_slice())?;
+        json!({
+            "from_address": msg.from_address,
+            "to_address": msg.to_address,
+            "amount": coins_to_json(&msg.amount),
+        })
+    } else {
+        message_to_json(any)?
+    };
+    let Value::Object(fields) = &mut value else {
+        return Err(Error::EncodeError(format!(
+            "{} is not encoded as a JSON object",
+            any.type_url
+        )));
+    };
+    // The type comes first, like in the output of the Cosmos SDK
+    let mut message = Map::new();
+    message.insert("@type".to_string(), json!(any.type_url));
+    message.append(fields);
+    Ok(Value::Object(message))
+}
+
+fn any_from_json(value: &Value) -> Result<Any> {
+    let mut fields = object(value, "message")?.clone();
+    let type_url = match fields.remove("@type") {
+        Some(Value::String(type_url)) => type_url,
+        _ => return Err(Error::Parse("message without @type".to_string())),
+    };
+    if type_url == MsgSend::type_url() {
+        let msg = MsgSend {
+            from_address: string(&fields, "from_address")?,
+            to_address: string(&fields, "to_address")?,
+            amount: coins_from_json(fields.get("amount"))?,
+        };
+        return Ok(Any {
+            type_url,
+            value: msg.encode_to_vec(),
+        });
+    }
+    message_from_json(&type_url, Value::Object(fields))
+}
+
+fn tx_to_json(tx: &Tx) -> Result<Value> {
+    let body = tx.body.clone().unwrap_or_default();
+    let auth_info = tx.auth_info.clone().unwrap_or_default();
+    let fee = auth_info.fee.unwrap_or_default();
+    let anys_to_json = |anys: &[Any]| anys.iter().map(any_to_json).collect::<Result<Vec<_>>>();
+    let signer_infos = auth_info
+        .signer_infos
+        .iter()
+        .map(signer_info_to_json)
+        .collect::<Result<Vec<_>>>()?;
+    let signatures: Vec<_> = tx.signatures.iter().map(|s| STANDARD.encode(s)).collect();
+    Ok(json!({
+        "body": {
+            "messages": anys_to_json(&body.messages)?,
+            "memo": body.memo,
+            "timeout_height": body.timeout_height.to_string(),
+            "extension_options": anys_to_json(&body.extension_options)?,
+            "non_critical_extension_options": anys_to_json(&body.non_critical_extension_options)?,
+        },
+        "auth_info": {
+            "signer_infos": signer_infos,
+            "fee": {
+                "amount": coins_to_json(&fee.amount),
+                "gas_limit": fee.gas_limit.to_string(),
+                "payer": fee.payer,
+                "granter": fee.granter,
+            },
+            "tip": null,
+        },
+        "signatures": signatures,
+    }))
+}
+
+fn tx_from_json(value: &Value) -> Result<Tx> {
+    let tx = object(value, "tx")?;
+    let body = object(tx.get("body").unwrap_or(&Value::Null), "body")?;
+    let auth_info = object(tx.get("auth_info").unwrap_or(&Value::Null), "auth_info")?;
+    let fee = match auth_info.get("fee") {
+        Some(fee) => {
+            let fee = object(fee, "fee")?;
+            Fee {
+                amount: coins_from_json(fee.get("amount"))?,
+                gas_limit: integer(fee, "gas_limit")?,
+                payer: optional_string(fee, "payer")?,
+                granter: optional_string(fee, "granter")?,
+            }
+        }
+        None => Fee::default(),
+    };
+
+    Ok(Tx {
+        body: Some(TxBody {
+            messages: anys_from_json(body.get("messages"))?,
+            memo: optional_string(body, "memo")?,
+            timeout_height: integer(body, "timeout_height")?,
+            extension_options: anys_from_json(body.get("extension_options"))?,
+            non_critical_extension_options: anys_from_json(
+                body.get("non_critical_extension_options"),
+            )?,
+        }),
+        auth_info: Some(AuthInfo {
+            signer_infos: array(auth_info.get("signer_infos"))?
+                .iter()
+                .map(signer_info_from_json)
+                .collect::<Result<_>>()?,
+            fee: Some(fee),
+            ..Default::default()
+        }),
+        signatures: array(tx.get("signatures"))?
+            .iter()
+            .map(|signature| {
+                let signature = signature
+                    .as_str()
+                    .ok_or_else(|| Error::Parse("signature is not a string".to_string()))?;
+                STANDARD
+                    .decode(signature)
+                    .map_err(|e| Error::Parse(format!("signature: {}", e)))
+            })
+            .collect::<Result<_>>()?,
+    })
+}
+
+fn signer_info_to_json(signer_info: &SignerInfo) -> Result<Value> {
+    let public_key = match &signer_info.public_key {
+        Some(any) if any.type_url == PubKey::type_url() => {
+            let key = PubKey::decode(any.value.as_slice())?;
+            json!({"@type": any.type_url, "key": STANDARD.encode(key.key)})
+        }
+        Some(any) => {
+            return Err(Error::EncodeError(format!(
+                "unsupported public key {}",
+                any.type_url
+            )))
+        }
+        None => Value::Null,
+    };
+    let mode = match signer_info.mode_info.as_ref().and_then(|m| m.sum.as_ref()) {
+        Some(mode_info::Sum::Single(single)) => SignMode::try_from(single.mode)
+            .map_err(|e| Error::EncodeError(e.to_string()))?
+            .as_str_name(),
+        _ => {
+            return Err(Error::EncodeError(
+                "only single signers are supported".to_string(),
+            ))
+        }
+    };
+    Ok(json!({
+        "public_key": public_key,
+        "mode_info": {"single": {"mode": mode}},
+        "sequence": signer_info.sequence.to_string(),
+    }))
+}
+
+fn signer_info_from_json(value: &Value) -> Result<SignerInfo> {
+    let signer_info = object(value, "signer_info")?;
+    let public_key = match signer_info.get("public_key") {
+        None | Some(Value::Null) => None,
+        Some(public_key) => {
+            let public_key = object(public_key, "public_key")?;
+            let type_url = string(public_key, "@type")?;
+            if type_url != PubKey::type_url() {
+                return Err(Error::Parse(format!("unsupported public key {}", type_url)));
+            }
+            let key = STANDARD
+                .decode(string(public_key, "key")?)
+                .map_err(|e| Error::Parse(format!("public key: {}", e)))?;
+            Some(Any {
+                type_url,
+                value: PubKey { key }.encode_to_vec(),
+            })
+        }
+    };
+    let mode = signer_info
+        .get("mode_info")
+        .and_then(|mode_info| mode_info.get("single"))
+        .and_then(|single| single.get("mode"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::Parse("only single signers are supported".to_string()))?;
+    let mode = SignMode::from_str_name(mode)
+        .ok_or_else(|| Error::Parse(format!("unknown sign mode {}", mode)))?;
+    Ok(SignerInfo {
+        public_key,
+        mode_info: Some(ModeInfo {
+            sum: Some(mode_info::Sum::Single(mode_info::Single {
+                mode: mode as i32,
+            })),
+        }),
+        sequence: integer(signer_info, "sequence")?,
+    })
+}
+
+fn coins_to_json(coins: &[Coin]) -> Value {
+    coins
+        .iter()
+        .map(|coin| json!({"denom": coin.denom, "amount": coin.amount}))
+        .collect()
+}
+
+fn coins_from_json(value: Option<&Value>) -> Result<Vec<Coin>> {
+    array(value)?
+        .iter()
+        .map(|coin| {
+            let coin = object(coin, "coin")?;
+            Ok(Coin {
+                denom: string(coin, "denom")?,
+                amount: string(coin, "amount")?,
+            })
+        })
+        .collect()
+}
+
+fn anys_from_json(value: Option<&Value>) -> Result<Vec<Any>> {
+    array(value)?.iter().map(any_from_json).collect()
+}
+
+fn object<'a>(value: &'a Value, name: &str) -> Result<&'a Map<String, Value>> {
+    value
+        .as_object()
+        .ok_or_else(|| Error::Parse(format!("{} is not an object", name)))
+}
+
+// Missing and null arrays are empty, like in proto3 JSON
+fn array(value: Option<&Value>) -> Result<&[Value]> {
+    match value {
+        None | Some(Value::Null) => Ok(&[]),
+        Some(Value::Array(values)) => Ok(values),
+        Some(_) => Err(Error::Parse("expected an array".to_string())),
+    }
+}
+
+fn string(object: &Map<String, Value>, key: &str) -> Result<String> {
+    object
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| Error::Parse(format!("missing string {}", key)))
+}
+
+fn optional_string(object: &Map<String, Value>, key: &str) -> Result<String> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(String::new()),
+        Some(_) => string(object, key),
+    }
+}
+
+// 64 bit integers are strings in proto3 JSON, numbers are accepted as well
+fn integer(object: &Map<String, Value>, key: &str) -> Result<u64> {
+    match object.get(key) {
+        None | Some(Value::Null) => Ok(0),
+        Some(Value::String(s)) => s
+            .parse()
+            .map_err(|e| Error::Parse(format!("{}: {}", key, e))),
+        Some(Value::Number(n)) => n
+            .as_u64()
+            .ok_or_else(|| Error::Parse(format!("{} is not an unsigned integer", key))),
+        Some(_) => Err(Error::Parse(format!("{} is not an integer", key))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx() -> Tx {
+        let create_task = gevulot::MsgCreateTask {
+            creator: "gvlt1creator".to_string(),
+            image: "prover:latest".to_string(),
+            cpus: 1000,
+            memory: 1024,
+            time: 60,
+            ..Default::default()
+        };
+        let send = MsgSend {
+            from_address: "gvlt1creator".to_string(),
+            to_address: "gvlt1receiver".to_string(),
+            amount: vec![Coin {
+                denom: "ucredit".to_string(),
+                amount: "100".to_string(),
+            }],
+        };
+        Tx {
+            body: Some(TxBody {
+                messages: vec![
+                    Any::from_msg(&create_task).unwrap(),
+                    Any::from_msg(&send).unwrap(),
+                ],
+                memo: "offline".to_string(),
+                ..Default::default()
+            }),
+            auth_info: Some(AuthInfo {
+                signer_infos: vec![SignerInfo {
+                    public_key: Some(Any::from_msg(&PubKey { key: vec![2; 33] }).unwrap()),
+                    mode_info: Some(ModeInfo {
+                        sum: Some(mode_info::Sum::Single(mode_info::Single {
+                            mode: SignMode::Direct as i32,
+                        })),
+                    }),
+                    sequence: 7,
+                }],
+                fee: Some(Fee {
+                    amount: vec![Coin {
+                        denom: "ucredit".to_string(),
+                        amount: "2000".to_string(),
+                    }],
+                    gas_limit: 200_000,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }),
+            signatures: vec![vec![1; 64]],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let tx = tx();
+        let json = tx.to_sign_doc_json().unwrap();
+        assert_eq!(Tx::from_json(&json).unwrap(), tx);
+
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let message = &value["body"]["messages"][0];
+        assert_eq!(message["@type"], "/gevulot.gevulot.MsgCreateTask");
+        assert_eq!(message["image"], "prover:latest");
+        assert_eq!(value["auth_info"]["fee"]["gas_limit"], "200000");
+        assert_eq!(
+            value["auth_info"]["signer_infos"][0]["mode_info"]["single"]["mode"],
+            "SIGN_MODE_DIRECT"
+        );
+    }
+
+    #[test]
+    fn test_from_cosmos_json() {
+        // As written by `tx bank send --generate-only`
+        let json = r#"{
+            "body": {
+                "messages": [{
+                    "@type": "/cosmos.bank.v1beta1.MsgSend",
+                    "from_address": "gvlt1creator",
+                    "to_address": "gvlt1receiver",
+                    "amount": [{"denom": "ucredit", "amount": "5"}]
+                }],
+                "memo": "",
+                "timeout_height": "0",
+                "extension_options": [],
+                "non_critical_extension_options": []
+            },
+            "auth_info": {
+                "signer_infos": [],
+                "fee": {"amount": [], "gas_limit": "200000", "payer": "", "granter": ""},
+                "tip": null
+            },
+            "signatures": []
+        }"#;
+        let tx = Tx::from_json(json).unwrap();
+        let body = tx.body.unwrap();
+        let send = MsgSend::decode(body.messages[0].value.as_slice()).unwrap();
+        assert_eq!(send.amount[0].amount, "5");
+        assert_eq!(tx.auth_info.unwrap().fee.unwrap().gas_limit, 200_000);
+
+        let unknown = json.replace("/cosmos.bank.v1beta1.MsgSend", "/unknown.MsgUnknown");
+        assert!(Tx::from_json(&unknown).is_err());
+    }
+}
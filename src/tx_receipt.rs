@@ -0,0 +1,280 @@
+//! Receipts of included transactions and the fees charged for them.
+//!
+//! The fee of a transaction is deducted before its messages are executed and chains with a fee
+//! market may refund part of it for unused gas afterwards. Both show up as `coin_spent` and
+//! `coin_received` events of the fee payer among the events of the messages. A [`FeeBreakdown`]
+//! tells them apart by the `msg_index` attribute the Cosmos SDK adds to the events of messages
+//! since v0.50.
+
+use std::collections::BTreeMap;
+
+use cosmos_sdk_proto::cosmos::base::abci::v1beta1::TxResponse;
+use cosmrs::Coin;
+
+use crate::error::{Error, Result};
+
+/// Fee charged for a transaction, from the events of its result.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FeeBreakdown {
+    /// Fee set in the transaction, from the `fee` attribute of the `tx` event.
+    pub fee: Vec<Coin>,
+    /// Account which paid the fee, None if the chain didn't emit it and nothing was deducted.
+    pub fee_payer: Option<String>,
+    /// Coins deducted from the fee payer before the messages were executed.
+    pub deducted: Vec<Coin>,
+    /// Coins refunded to the fee payer after the messages were executed, e.g. for unused gas.
+    pub refunded: Vec<Coin>,
+}
+
+impl FeeBreakdown {
+    /// Collects the fee of a transaction from its events.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an amount of coins can't be parsed.
+    pub fn from_events(events: &[crate::Event]) -> Result<Self> {
+        let attribute = |event: &crate::Event, key: &str| {
+            event
+                .attributes
+                .iter()
+                .find(|attr| attr.key_bytes() == key.as_bytes())
+                .and_then(|attr| attr.value_str().ok())
+                .map(str::to_string)
+        };
+        // Events of messages carry the index of their message
+        let fee_events: Vec<_> = events
+            .iter()
+            .filter(|event| attribute(event, "msg_index").is_none())
+            .collect();
+
+        let mut breakdown = Self::default();
+        for event in fee_events.iter().filter(|event| event.kind == "tx") {
+            if let Some(fee) = attribute(event, "fee") {
+                breakdown.fee = parse_coins(&fee)?;
+            }
+            if let Some(payer) = attribute(event, "fee_payer") {
+                breakdown.fee_payer = Some(payer);
+            }
+        }
+        // Older chains don't emit the fee payer, the fee is the first spending
+        if breakdown.fee_payer.is_none() {
+            breakdown.fee_payer = fee_events
+                .iter()
+                .find(|event| event.kind == "coin_spent")
+                .and_then(|event| attribute(event, "spender"));
+        }
+        let Some(payer) = breakdown.fee_payer.clone() else {
+            return Ok(breakdown);
+        };
+
+        let mut deducted = Vec::new();
+        let mut refunded = Vec::new();
+        for event in fee_events {
+            let (account, coins) = match event.kind.as_str() {
+                "coin_spent" => (attribute(event, "spender"), &mut deducted),
+                "coin_received" => (attribute(event, "receiver"), &mut refunded),
+                _ => continue,
+            };
+            if account.as_deref() == Some(payer.as_str()) {
+                if let Some(amount) = attribute(event, "amount") {
+                    coins.extend(parse_coins(&amount)?);
+                }
+            }
+        }
+        breakdown.deducted = sum_coins(deducted.iter().map(|coin| (coin, true)))?;
+        breakdown.refunded = sum_coins(refunded.iter().map(|coin| (coin, true)))?;
+        Ok(breakdown)
+    }
+
+    /// Returns the fee actually paid, the deducted coins minus the refunded ones.
+    pub fn net(&self) -> Vec<Coin> {
+        let coins = self
+            .deducted
+            .iter()
+            .map(|coin| (coin, true))
+            .chain(self.refunded.iter().map(|coin| (coin, false)));
+        // Only fails for denoms which were parsed before
+        sum_coins(coins).unwrap_or_default()
+    }
+}
+
+/// Outcome of a transaction included in a block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TxReceipt {
+    pub tx_hash: String,
+    pub height: i64,
+    /// Result code of the transaction, 0 on success.
+    pub code: u32,
+    pub gas_wanted: i64,
+    pub gas_used: i64,
+    /// The fee charged, also for failed transactions.
+    pub fee: FeeBreakdown,
+    pub events: Vec<crate::Event>,
+}
+
+impl TxReceipt {
+    /// Creates the receipt of a transaction from its response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the fee events can't be parsed.
+    pub fn from_response(tx_response: &TxResponse) -> Result<Self> {
+        let events: Vec<crate::Event> = tx_response
+            .events
+            .iter()
+            .cloned()
+            .filter_map(|event| crate::Event::try_from(event).ok())
+            .collect();
+        Ok(Self {
+            tx_hash: tx_response.txhash.clone(),
+            height: tx_response.height,
+            code: tx_response.code,
+            gas_wanted: tx_response.gas_wanted,
+            gas_used: tx_response.gas_used,
+            fee: FeeBreakdown::from_events(&events)?,
+            events,
+        })
+    }
+
+    /// Returns true if the transaction succeeded.
+    pub fn is_success(&self) -> bool {
+        self.code == 0
+    }
+}
+
+// Parses coins in the format of events, e.g. `2500ucredit,10uatom`
+fn parse_coins(coins: &str) -> Result<Vec<Coin>> {
+    coins
+        .split(',')
+        .map(str::trim)
+        .filter(|coin| !coin.is_empty())
+        .map(|coin| {
+            let split = coin
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(|| Error::Parse(format!("missing denom in coins {}", coin)))?;
+            let (amount, denom) = coin.split_at(split);
+            Ok(Coin {
+                amount: amount.parse().map_err(|e| {
+                    Error::Parse(format!("invalid amount in coins {}: {}", coin, e))
+                })?,
+                denom: denom
+                    .parse()
+                    .map_err(|e| Error::Parse(format!("invalid denom in coins {}: {}", coin, e)))?,
+            })
+        })
+        .collect()
+}
+
+// Sums coins per denom, adding the coins marked true and subtracting the others. Denoms which
+// sum up to zero or less are left out.
+fn sum_coins<'a>(coins: impl Iterator<Item = (&'a Coin, bool)>) -> Result<Vec<Coin>> {
+    let mut sums = BTreeMap::<String, i128>::new();
+    for (coin, add) in coins {
+        let amount = i128::try_from(coin.amount).unwrap_or(i128::MAX);
+        let sum = sums.entry(coin.denom.to_string()).or_default();
+        *sum = if add {
+            sum.saturating_add(amount)
+        } else {
+            sum.saturating_sub(amount)
+        };
+    }
+    sums.into_iter()
+        .filter(|(_, amount)| *amount > 0)
+        .map(|(denom, amount)| {
+            Ok(Coin {
+                denom: denom.parse()?,
+                amount: amount as u128,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmrs::{rpc::dialect::v0_34::EventAttribute, tendermint::abci::Event};
+
+    use super::*;
+
+    const PAYER: &str = "gvlt1payer";
+    const COLLECTOR: &str = "gvlt1feecollector";
+
+    fn event(kind: &str, attributes: &[(&str, &str)]) -> Event {
+        Event::new(
+            kind,
+            attributes
+                .iter()
+                .map(|(key, value)| EventAttribute {
+                    index: true,
+                    key: key.as_bytes().to_vec(),
+                    value: value.as_bytes().to_vec(),
+                })
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn coins(coins: &str) -> Vec<Coin> {
+        parse_coins(coins).unwrap()
+    }
+
+    #[test]
+    fn test_parse_coins() {
+        let parsed = coins(
+            "2500ucredit, 10ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2",
+        );
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].amount, 2500);
+        assert_eq!(parsed[0].denom.as_ref(), "ucredit");
+        assert!(coins("").is_empty());
+        assert!(parse_coins("2500").is_err());
+        assert!(parse_coins("ucredit").is_err());
+    }
+
+    #[test]
+    fn test_fee_breakdown() {
+        let events = [
+            event(
+                "coin_spent",
+                &[("spender", PAYER), ("amount", "2500ucredit")],
+            ),
+            event(
+                "coin_received",
+                &[("receiver", COLLECTOR), ("amount", "2500ucredit")],
+            ),
+            event("tx", &[("fee", "2500ucredit"), ("fee_payer", PAYER)]),
+            // A transfer of the message isn't part of the fee
+            event(
+                "coin_spent",
+                &[
+                    ("spender", PAYER),
+                    ("amount", "100ucredit"),
+                    ("msg_index", "0"),
+                ],
+            ),
+            event(
+                "coin_spent",
+                &[("spender", COLLECTOR), ("amount", "400ucredit")],
+            ),
+            event(
+                "coin_received",
+                &[("receiver", PAYER), ("amount", "400ucredit")],
+            ),
+        ];
+        let breakdown = FeeBreakdown::from_events(&events).unwrap();
+        assert_eq!(breakdown.fee, coins("2500ucredit"));
+        assert_eq!(breakdown.fee_payer.as_deref(), Some(PAYER));
+        assert_eq!(breakdown.deducted, coins("2500ucredit"));
+        assert_eq!(breakdown.refunded, coins("400ucredit"));
+        assert_eq!(breakdown.net(), coins("2100ucredit"));
+
+        // Without fee payer the first spending is the fee
+        let breakdown = FeeBreakdown::from_events(&events[..2]).unwrap();
+        assert!(breakdown.fee.is_empty());
+        assert_eq!(breakdown.fee_payer.as_deref(), Some(PAYER));
+        assert_eq!(breakdown.net(), coins("2500ucredit"));
+
+        assert_eq!(
+            FeeBreakdown::from_events(&[]).unwrap(),
+            FeeBreakdown::default()
+        );
+    }
+}
@@ -1,20 +1,182 @@
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::{sync::Arc, time::SystemTime};
+use tokio::{sync::RwLock, time::Duration};
 
 use crate::{
     base_client::BaseClient,
     error::{Error, Result},
+    label_selector::LabelSelector,
+    models::{MaintenanceWindow, ResourceVector, TaskResources, Worker},
+    origin::Origin,
+    pagination::{PageOptions, PageToken, DEFAULT_PAGE_LIMIT},
     proto::gevulot::gevulot::{
-        MsgAnnounceWorkerExit, MsgAnnounceWorkerExitResponse, MsgCreateWorker,
+        self, MsgAnnounceWorkerExit, MsgAnnounceWorkerExitResponse, MsgCreateWorker,
         MsgCreateWorkerResponse, MsgDeleteWorker, MsgDeleteWorkerResponse, MsgUpdateWorker,
         MsgUpdateWorkerResponse,
     },
 };
 
+/// Interval in which [`WorkerClient::wait_until_exit_allowed`] checks the block height.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Progress of the exit of a worker, see [`WorkerClient::exit_status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExitStatus {
+    /// Block height the exit was announced at, None if it wasn't announced.
+    pub announced_at: Option<u64>,
+    pub current_height: u64,
+    /// Number of blocks a worker has to wait after announcing its exit.
+    pub exit_delay: u64,
+    /// Number of blocks until the worker may be deleted, the full delay if the exit wasn't
+    /// announced yet.
+    pub remaining_blocks: u64,
+}
+
+impl ExitStatus {
+    fn new(announced_at: u64, current_height: u64, exit_delay: u64) -> Self {
+        let announced_at = (announced_at > 0).then_some(announced_at);
+        let remaining_blocks = match announced_at {
+            Some(height) => height
+                .saturating_add(exit_delay)
+                .saturating_sub(current_height),
+            None => exit_delay,
+        };
+        Self {
+            announced_at,
+            current_height,
+            exit_delay,
+            remaining_blocks,
+        }
+    }
+
+    /// Returns true if the worker announced its exit and waited long enough to be deleted.
+    pub fn exit_allowed(&self) -> bool {
+        self.announced_at.is_some() && self.remaining_blocks == 0
+    }
+}
+
+/// Label linking a worker registered by [`WorkerClient::transfer`] to the worker it replaces.
+pub const PREVIOUS_WORKER_LABEL: &str = "gevulot.sdk/previous-worker";
+
+/// Messages transferring a worker to a new owner.
+///
+/// Workers can't change their creator, so the worker is registered again by the new owner
+/// with the same name, resources, labels and tags, and a [`PREVIOUS_WORKER_LABEL`] pointing
+/// to the old worker. The old worker announces its exit and is deleted once it finished its
+/// tasks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkerTransfer {
+    /// Registers the worker for the new owner.
+    pub create: MsgCreateWorker,
+    /// Announces the exit of the old worker.
+    pub announce_exit: MsgAnnounceWorkerExit,
+    /// Deletes the old worker.
+    pub delete: MsgDeleteWorker,
+}
+
+impl WorkerTransfer {
+    /// Creates the messages transferring a worker.
+    ///
+    /// # Arguments
+    ///
+    /// * `worker` - The worker to transfer.
+    /// * `old_owner` - The address of the current creator of the worker.
+    /// * `new_owner` - The address of the new creator.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the worker has no ID or spec.
+    pub fn new(worker: &gevulot::Worker, old_owner: &str, new_owner: &str) -> Result<Self> {
+        let metadata = worker.metadata.clone().unwrap_or_default();
+        if metadata.id.is_empty() {
+            return Err(Error::Unknown("worker has no id".to_string()));
+        }
+        let spec = worker
+            .spec
+            .clone()
+            .ok_or_else(|| Error::Unknown(format!("worker {} has no spec", metadata.id)))?;
+        let mut labels: Vec<_> = metadata
+            .labels
+            .into_iter()
+            .filter(|label| label.key != PREVIOUS_WORKER_LABEL)
+            .collect();
+        labels.push(gevulot::Label {
+            key: PREVIOUS_WORKER_LABEL.to_string(),
+            value: metadata.id.clone(),
+        });
+        Ok(Self {
+            create: MsgCreateWorker {
+                creator: new_owner.to_string(),
+                name: metadata.name,
+                description: metadata.desc,
+                cpus: spec.cpus,
+                gpus: spec.gpus,
+                memory: spec.memory,
+                disk: spec.disk,
+                labels,
+                tags: metadata.tags,
+            },
+            announce_exit: MsgAnnounceWorkerExit {
+                creator: old_owner.to_string(),
+                worker_id: metadata.id.clone(),
+            },
+            delete: MsgDeleteWorker {
+                creator: old_owner.to_string(),
+                id: metadata.id,
+            },
+        })
+    }
+}
+
+/// Changes to a worker, fields which are None are kept.
+///
+/// Resources are in the units of the chain: cpus and gpus in millicores, memory and disk in
+/// bytes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorkerPatch {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub cpus: Option<u64>,
+    pub gpus: Option<u64>,
+    pub memory: Option<u64>,
+    pub disk: Option<u64>,
+    /// Replaces all labels.
+    pub labels: Option<Vec<gevulot::Label>>,
+    /// Replaces all tags.
+    pub tags: Option<Vec<String>>,
+}
+
+impl WorkerPatch {
+    /// Creates the message updating a worker with the changes applied.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the worker has no spec.
+    pub fn apply(&self, worker: gevulot::Worker, creator: &str) -> Result<MsgUpdateWorker> {
+        let metadata = worker.metadata.unwrap_or_default();
+        let spec = worker
+            .spec
+            .ok_or_else(|| Error::Unknown(format!("worker {} has no spec", metadata.id)))?;
+        let patch = self.clone();
+        Ok(MsgUpdateWorker {
+            creator: creator.to_string(),
+            id: metadata.id,
+            name: patch.name.unwrap_or(metadata.name),
+            description: patch.description.unwrap_or(metadata.desc),
+            cpus: patch.cpus.unwrap_or(spec.cpus),
+            gpus: patch.gpus.unwrap_or(spec.gpus),
+            memory: patch.memory.unwrap_or(spec.memory),
+            disk: patch.disk.unwrap_or(spec.disk),
+            labels: patch.labels.unwrap_or(metadata.labels),
+            tags: patch.tags.unwrap_or(metadata.tags),
+        })
+    }
+}
+
 /// Client for managing workers in the Gevulot system.
 #[derive(Debug, Clone)]
 pub struct WorkerClient {
     base_client: Arc<RwLock<BaseClient>>,
+    origin: Option<Arc<Origin>>,
 }
 
 impl WorkerClient {
@@ -28,11 +190,25 @@ impl WorkerClient {
     ///
     /// A new instance of WorkerClient.
     pub fn new(base_client: Arc<RwLock<BaseClient>>) -> Self {
-        Self { base_client }
+        Self {
+            base_client,
+            origin: None,
+        }
+    }
+
+    /// Sets the origin labels added to every worker created by this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The origin, None stops adding origin labels.
+    pub fn set_origin(&mut self, origin: Option<Arc<Origin>>) {
+        self.origin = origin;
     }
 
     /// Lists all workers.
     ///
+    /// Fetches the workers page by page until all workers were received.
+    ///
     /// # Returns
     ///
     /// A Result containing a vector of workers or an error.
@@ -41,15 +217,225 @@ impl WorkerClient {
     ///
     /// This function will return an error if the request to the Gevulot client fails.
     pub async fn list(&mut self) -> Result<Vec<crate::proto::gevulot::gevulot::Worker>> {
-        let request = crate::proto::gevulot::gevulot::QueryAllWorkerRequest { pagination: None };
+        let mut workers = Vec::new();
+        let mut options = PageOptions::new().limit(DEFAULT_PAGE_LIMIT);
+        loop {
+            let (page, next) = self.list_paged(options.clone()).await?;
+            workers.extend(page);
+            match next {
+                Some(token) => options.token = Some(token),
+                None => break,
+            }
+        }
+        Ok(workers)
+    }
+
+    /// Lists a single page of workers.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The size and position of the page.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the workers of the page and the token of the next page, None if this
+    /// was the last page, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_paged(
+        &mut self,
+        options: PageOptions,
+    ) -> Result<(
+        Vec<crate::proto::gevulot::gevulot::Worker>,
+        Option<PageToken>,
+    )> {
+        let request = crate::proto::gevulot::gevulot::QueryAllWorkerRequest {
+            pagination: Some(options.to_request()),
+        };
         let response = self
             .base_client
             .write()
             .await
             .gevulot_client
             .worker_all(request)
-            .await?;
-        Ok(response.into_inner().worker)
+            .await?
+            .into_inner();
+        Ok((
+            response.worker,
+            PageToken::next(response.pagination.as_ref()),
+        ))
+    }
+
+    /// Returns how far a worker is in its exit.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the worker.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the exit status or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the worker is not found or if any request to the
+    /// Gevulot client fails.
+    pub async fn exit_status(&mut self, id: &str) -> Result<ExitStatus> {
+        let worker = self.get(id).await?;
+        let mut base_client = self.base_client.write().await;
+        let exit_delay = base_client
+            .gevulot_client
+            .params(crate::proto::gevulot::gevulot::QueryParamsRequest {})
+            .await?
+            .into_inner()
+            .params
+            .unwrap_or_default()
+            .worker_exit_delay;
+        let current_height = base_client
+            .current_block()
+            .await?
+            .header
+            .ok_or("Header not found")?
+            .height;
+        Ok(ExitStatus::new(
+            worker.status.unwrap_or_default().exit_announced_at,
+            u64::try_from(current_height).unwrap_or_default(),
+            exit_delay,
+        ))
+    }
+
+    /// Waits until a worker which announced its exit may be deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the worker.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the final exit status or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the worker didn't announce its exit or if any
+    /// request to the Gevulot client fails.
+    pub async fn wait_until_exit_allowed(&mut self, id: &str) -> Result<ExitStatus> {
+        let timer = self.base_client.read().await.timer();
+        loop {
+            let status = self.exit_status(id).await?;
+            if status.announced_at.is_none() {
+                return Err(Error::Unknown(format!(
+                    "worker {} didn't announce its exit",
+                    id
+                )));
+            }
+            if status.exit_allowed() {
+                return Ok(status);
+            }
+            log::debug!(
+                "Worker {} may exit in {} blocks",
+                id,
+                status.remaining_blocks
+            );
+            timer.sleep(EXIT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Transfers a worker of this client's signer to the signer of another client.
+    ///
+    /// The worker is registered for the new owner first so it can take tasks right away, then
+    /// the old worker announces its exit. The old worker has to be deleted with
+    /// [`WorkerTransfer::delete`] after it finished its remaining tasks. See
+    /// [`WorkerTransfer`] for how the worker is re-registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `new_owner` - A client signing with the key of the new owner.
+    /// * `worker_id` - The ID of the worker to transfer.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the ID of the new worker and the messages of the transfer, or an
+    /// error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a client has no signer or if any request to the
+    /// Gevulot client fails. If the exit announcement fails the new worker stays registered.
+    pub async fn transfer(
+        &mut self,
+        new_owner: &mut WorkerClient,
+        worker_id: &str,
+    ) -> Result<(String, WorkerTransfer)> {
+        let old_address = signer_address(&self.base_client).await?;
+        let new_address = signer_address(&new_owner.base_client).await?;
+        let worker = self.get(worker_id).await?;
+        let transfer = WorkerTransfer::new(&worker, &old_address, &new_address)?;
+
+        let new_id = new_owner.create(transfer.create.clone()).await?.id;
+        log::info!("Registered worker {} as {}", worker_id, new_id);
+        self.announce_exit(transfer.announce_exit.clone()).await?;
+        Ok((new_id, transfer))
+    }
+
+    /// Lists the workers matching a label selector.
+    ///
+    /// All workers are fetched and the selector is evaluated on the client.
+    ///
+    /// # Arguments
+    ///
+    /// * `selector` - The requirements on the labels and tags of the workers.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the matching workers or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_filtered(&mut self, selector: &LabelSelector) -> Result<Vec<Worker>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|worker| worker.spec.is_some())
+            .map(Worker::from)
+            .filter(|worker| selector.matches(&worker.metadata))
+            .collect())
+    }
+
+    /// Lists the workers with enough free resources for a task.
+    ///
+    /// The free resources of a worker are its capacity minus the resources it uses. Workers
+    /// which announced their exit or are in their maintenance window, see
+    /// [`MaintenanceWindow`], are skipped. The workers with the most free resources come
+    /// first, compared by cpus, then gpus, then memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `requirements` - The resources the task requests.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the available workers or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a resource unit of the requirements is invalid or
+    /// if the request to the Gevulot client fails.
+    pub async fn find_available(&mut self, requirements: &TaskResources) -> Result<Vec<Worker>> {
+        let required = ResourceVector::from_resources(requirements)?;
+        let workers = self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|worker| worker.spec.is_some())
+            .map(Worker::from)
+            .collect();
+        // Maintenance windows are compared with the chain time
+        let now = self.base_client.write().await.chain_time().await?;
+        Ok(available_workers(workers, &required, now))
     }
 
     /// Gets a worker by its ID.
@@ -74,7 +460,7 @@ impl WorkerClient {
             .gevulot_client
             .worker(request)
             .await?;
-        response.into_inner().worker.ok_or(Error::NotFound)
+        response.into_inner().worker.ok_or(Error::NotFound(None))
     }
 
     /// Creates a new worker.
@@ -90,7 +476,10 @@ impl WorkerClient {
     /// # Errors
     ///
     /// This function will return an error if the request to the Gevulot client fails.
-    pub async fn create(&mut self, msg: MsgCreateWorker) -> Result<MsgCreateWorkerResponse> {
+    pub async fn create(&mut self, mut msg: MsgCreateWorker) -> Result<MsgCreateWorkerResponse> {
+        if let Some(origin) = &self.origin {
+            origin.apply(&mut msg.labels);
+        }
         let resp: MsgCreateWorkerResponse = self
             .base_client
             .write()
@@ -123,6 +512,72 @@ impl WorkerClient {
         Ok(resp)
     }
 
+    /// Updates only some fields of a worker.
+    ///
+    /// The current worker is fetched and the patch applied to it, so fields not set in the
+    /// patch keep their values instead of being reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the worker to update.
+    /// * `patch` - The changes to the worker.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the response or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no signer is set or if any request to the Gevulot
+    /// client fails.
+    pub async fn patch(
+        &mut self,
+        id: &str,
+        patch: &WorkerPatch,
+    ) -> Result<MsgUpdateWorkerResponse> {
+        let creator = signer_address(&self.base_client).await?;
+        let worker = self.get(id).await?;
+        self.update(patch.apply(worker, &creator)?).await
+    }
+
+    /// Declares or clears the maintenance window of a worker in its labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the worker.
+    /// * `window` - The maintenance window, None clears a declared window.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the response or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no signer is set, if the worker is not found or
+    /// if the request to the Gevulot client fails.
+    pub async fn set_maintenance(
+        &mut self,
+        id: &str,
+        window: Option<MaintenanceWindow>,
+    ) -> Result<MsgUpdateWorkerResponse> {
+        let creator = signer_address(&self.base_client).await?;
+        let worker = self.get(id).await?;
+        let mut labels = worker
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.labels.clone())
+            .unwrap_or_default();
+        match window {
+            Some(window) => window.apply(&mut labels),
+            None => MaintenanceWindow::clear(&mut labels),
+        }
+        let patch = WorkerPatch {
+            labels: Some(labels),
+            ..Default::default()
+        };
+        self.update(patch.apply(worker, &creator)?).await
+    }
+
     /// Deletes a worker.
     ///
     /// # Arguments
@@ -172,3 +627,171 @@ impl WorkerClient {
         Ok(resp)
     }
 }
+
+async fn signer_address(base_client: &RwLock<BaseClient>) -> Result<String> {
+    base_client
+        .read()
+        .await
+        .address
+        .clone()
+        .ok_or_else(|| Error::Unknown("Address not set".to_string()))
+}
+
+/// Keeps the workers with enough free resources, those with the most free resources first.
+fn available_workers(
+    workers: Vec<Worker>,
+    required: &ResourceVector,
+    now: SystemTime,
+) -> Vec<Worker> {
+    let mut available: Vec<_> = workers
+        .into_iter()
+        .filter(|worker| {
+            worker
+                .status
+                .as_ref()
+                .map_or(true, |status| status.exit_announced_at == 0)
+        })
+        .filter(|worker| !worker.in_maintenance(now))
+        .filter_map(|worker| {
+            let free = ResourceVector::from_worker_free(&worker).ok()?;
+            required.fits_within_vector(&free).then_some((free, worker))
+        })
+        .collect();
+    available.sort_by_key(|(free, _)| std::cmp::Reverse((free.cpus, free.gpus, free.memory)));
+    available.into_iter().map(|(_, worker)| worker).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn worker(id: &str, cpus_used: &str, exit_announced_at: i64) -> Worker {
+        serde_json::from_value(json!({
+            "kind": "Worker",
+            "version": "v0",
+            "metadata": {
+                "id": id, "name": id, "description": "", "tags": [], "labels": []
+            },
+            "spec": {"cpus": 4, "gpus": 0, "memory": "16GiB", "disk": "100GiB"},
+            "status": {
+                "cpusUsed": cpus_used, "gpusUsed": 0, "memoryUsed": "4GiB", "diskUsed": 0,
+                "exitAnnouncedAt": exit_announced_at
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_worker_transfer() {
+        let worker = gevulot::Worker {
+            metadata: Some(gevulot::Metadata {
+                id: "w1".to_string(),
+                name: "prover-01".to_string(),
+                tags: vec!["gpu".to_string()],
+                labels: vec![gevulot::Label {
+                    key: "region".to_string(),
+                    value: "eu-west".to_string(),
+                }],
+                ..Default::default()
+            }),
+            spec: Some(gevulot::WorkerSpec {
+                cpus: 8000,
+                gpus: 1000,
+                memory: 1 << 34,
+                disk: 1 << 36,
+            }),
+            ..Default::default()
+        };
+        let transfer = WorkerTransfer::new(&worker, "old", "new").unwrap();
+        assert_eq!(transfer.create.creator, "new");
+        assert_eq!(transfer.create.cpus, 8000);
+        assert_eq!(transfer.create.tags, vec!["gpu"]);
+        let labels: Vec<_> = transfer
+            .create
+            .labels
+            .iter()
+            .map(|l| (l.key.as_str(), l.value.as_str()))
+            .collect();
+        assert_eq!(
+            labels,
+            vec![("region", "eu-west"), (PREVIOUS_WORKER_LABEL, "w1")]
+        );
+        assert_eq!(transfer.announce_exit.creator, "old");
+        assert_eq!(transfer.delete.id, "w1");
+
+        assert!(WorkerTransfer::new(&gevulot::Worker::default(), "old", "new").is_err());
+    }
+
+    #[test]
+    fn test_exit_status() {
+        let status = ExitStatus::new(0, 500, 100);
+        assert_eq!(status.announced_at, None);
+        assert_eq!(status.remaining_blocks, 100);
+        assert!(!status.exit_allowed());
+
+        let status = ExitStatus::new(450, 500, 100);
+        assert_eq!(status.remaining_blocks, 50);
+        assert!(!status.exit_allowed());
+        assert!(ExitStatus::new(400, 500, 100).exit_allowed());
+        assert!(ExitStatus::new(300, 500, 100).exit_allowed());
+    }
+
+    #[test]
+    fn test_worker_patch() {
+        let worker = gevulot::Worker {
+            metadata: Some(gevulot::Metadata {
+                id: "w1".to_string(),
+                name: "prover-01".to_string(),
+                desc: "A100 prover".to_string(),
+                tags: vec!["gpu".to_string()],
+                ..Default::default()
+            }),
+            spec: Some(gevulot::WorkerSpec {
+                cpus: 8000,
+                gpus: 1000,
+                memory: 1 << 34,
+                disk: 1 << 36,
+            }),
+            ..Default::default()
+        };
+        let patch = WorkerPatch {
+            cpus: Some(16000),
+            tags: Some(Vec::new()),
+            ..Default::default()
+        };
+        let msg = patch.apply(worker, "alice").unwrap();
+        assert_eq!(msg.id, "w1");
+        assert_eq!(msg.name, "prover-01");
+        assert_eq!(msg.description, "A100 prover");
+        assert_eq!((msg.cpus, msg.gpus, msg.memory), (16000, 1000, 1 << 34));
+        assert!(msg.tags.is_empty());
+    }
+
+    #[test]
+    fn test_available_workers() {
+        let requirements = serde_json::from_value::<TaskResources>(json!({
+            "cpus": 2, "gpus": 0, "memory": "8GiB", "time": "1h"
+        }))
+        .unwrap();
+        let required = ResourceVector::from_resources(&requirements).unwrap();
+        let mut workers = vec![
+            worker("busy", "3", 0),
+            worker("half", "2", 0),
+            worker("idle", "0", 0),
+            worker("leaving", "0", 1700000000),
+            worker("maintained", "0", 0),
+        ];
+        let now = SystemTime::now();
+        let mut labels = Vec::new();
+        MaintenanceWindow::new(now - Duration::from_secs(60), now + Duration::from_secs(60))
+            .unwrap()
+            .apply(&mut labels);
+        workers[4].metadata.labels = labels.into_iter().map(Into::into).collect();
+        let ids: Vec<_> = available_workers(workers, &required, now)
+            .into_iter()
+            .map(|worker| worker.metadata.id.unwrap())
+            .collect();
+        assert_eq!(ids, vec!["idle", "half"]);
+    }
+}
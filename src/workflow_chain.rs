@@ -0,0 +1,467 @@
+//! Chaining of workflows on the client side.
+//!
+//! A [`WorkflowChain`] holds follow-up workflows which are submitted once the workflow they
+//! depend on finished successfully. Outputs of the tasks in the final stage of the finished
+//! workflow can be mapped to inputs of the follow-up workflow's first stage.
+//!
+//! The chain state can be persisted to a JSON file so pending links survive restarts. After a
+//! restart [`WorkflowChain::poll`] picks up workflows which finished in the meantime.
+//!
+//! Follow-up workflows are submitted at most once. A link is marked as submitting in the state
+//! file before its workflow is created, if the process stops before the result is recorded the
+//! link is moved to the failed links instead of being submitted again.
+
+use std::path::{Path, PathBuf};
+
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    event_fetcher::EventHandler,
+    events::{GevulotEvent, WorkflowEvent},
+    gevulot_client::GevulotClient,
+    proto::gevulot::gevulot::{self, workflow_status::State},
+    task_client::TaskClient,
+    workflow_client::WorkflowClient,
+};
+
+/// Maps an output of a task in the final stage of a finished workflow to an input of the
+/// follow-up workflow.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputMapping {
+    /// Index of the task within the final stage.
+    pub task: usize,
+    /// Index of the output context of that task.
+    pub output: usize,
+    /// Path the output is mounted at in every task of the follow-up workflow's first stage.
+    pub target: String,
+}
+
+/// A follow-up workflow waiting for another workflow to finish.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainLink {
+    /// ID of the workflow which has to finish first.
+    pub after: String,
+    /// Hex encoded `MsgCreateWorkflow` of the follow-up workflow.
+    pub next: String,
+    /// Outputs of the finished workflow passed to the follow-up workflow.
+    pub mappings: Vec<OutputMapping>,
+    /// Set while the follow-up workflow is being created.
+    #[serde(default)]
+    pub submitting: bool,
+}
+
+impl ChainLink {
+    fn next_msg(&self) -> Result<gevulot::MsgCreateWorkflow> {
+        let bytes = hex::decode(&self.next)?;
+        Ok(gevulot::MsgCreateWorkflow::decode(bytes.as_slice())?)
+    }
+}
+
+/// A link whose follow-up workflow was submitted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubmittedLink {
+    /// ID of the workflow which finished.
+    pub after: String,
+    /// ID of the submitted follow-up workflow.
+    pub workflow_id: String,
+}
+
+/// Persistent state of a [`WorkflowChain`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainState {
+    /// Links waiting for their workflow to finish.
+    pub pending: Vec<ChainLink>,
+    /// Links whose follow-up workflow was submitted.
+    pub submitted: Vec<SubmittedLink>,
+    /// Links dropped because the workflow they waited for failed, or because their follow-up
+    /// workflow could not be submitted. Links with the submitting flag set may have been
+    /// submitted before a restart.
+    pub failed: Vec<ChainLink>,
+}
+
+/// Submits follow-up workflows once the workflows they depend on finished.
+///
+/// The chain can be used as an [`EventHandler`] with the `EventFetcher` to react to
+/// finish-workflow events, or driven manually with [`WorkflowChain::poll`].
+#[derive(Debug)]
+pub struct WorkflowChain {
+    workflows: WorkflowClient,
+    tasks: TaskClient,
+    state: ChainState,
+    state_path: Option<PathBuf>,
+}
+
+impl WorkflowChain {
+    /// Creates a new chain without persistence.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to query and submit workflows.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of WorkflowChain.
+    pub fn new(client: &GevulotClient) -> Self {
+        Self {
+            workflows: client.workflows.clone(),
+            tasks: client.tasks.clone(),
+            state: ChainState::default(),
+            state_path: None,
+        }
+    }
+
+    /// Creates a chain persisting its state to a JSON file, loading the state if the file exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to query and submit workflows.
+    /// * `path` - The file the chain state is stored in.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the new instance of WorkflowChain or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state file exists but cannot be read or parsed.
+    pub fn with_state_file(client: &GevulotClient, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let state = if path.exists() {
+            let data = std::fs::read(&path).map_err(|e| Error::Unknown(e.to_string()))?;
+            serde_json::from_slice(&data).map_err(|e| Error::DecodeError(e.to_string()))?
+        } else {
+            ChainState::default()
+        };
+        Ok(Self {
+            state,
+            state_path: Some(path),
+            ..Self::new(client)
+        })
+    }
+
+    /// Returns the current state of the chain.
+    pub fn state(&self) -> &ChainState {
+        &self.state
+    }
+
+    /// Adds a follow-up workflow to submit once another workflow finished successfully.
+    ///
+    /// # Arguments
+    ///
+    /// * `after` - The ID of the workflow which has to finish first.
+    /// * `next` - The message creating the follow-up workflow.
+    /// * `mappings` - Outputs of the first workflow passed to the follow-up workflow.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the state cannot be persisted.
+    pub fn add(
+        &mut self,
+        after: &str,
+        next: gevulot::MsgCreateWorkflow,
+        mappings: Vec<OutputMapping>,
+    ) -> Result<()> {
+        self.state.pending.push(ChainLink {
+            after: after.to_string(),
+            next: hex::encode(next.encode_to_vec()),
+            mappings,
+            submitting: false,
+        });
+        self.save()
+    }
+
+    /// Checks all pending links and submits the follow-up workflows which are ready.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the IDs of the submitted workflows or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a request to the Gevulot client fails.
+    pub async fn poll(&mut self) -> Result<Vec<String>> {
+        let mut after: Vec<String> = self
+            .state
+            .pending
+            .iter()
+            .map(|link| link.after.clone())
+            .collect();
+        after.sort();
+        after.dedup();
+
+        let mut submitted = Vec::new();
+        for workflow_id in after {
+            submitted.extend(self.on_workflow_finished(&workflow_id).await?);
+        }
+        Ok(submitted)
+    }
+
+    /// Submits the follow-up workflows of a workflow if it finished successfully.
+    ///
+    /// Links whose follow-up workflow can't be submitted are moved to the failed links, the
+    /// remaining links are still submitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - The ID of the workflow which might have finished.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the IDs of the submitted workflows or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if querying the finished workflow fails or if the
+    /// state cannot be persisted.
+    pub async fn on_workflow_finished(&mut self, workflow_id: &str) -> Result<Vec<String>> {
+        if !self
+            .state
+            .pending
+            .iter()
+            .any(|link| link.after == workflow_id)
+        {
+            return Ok(Vec::new());
+        }
+
+        let workflow = self.workflows.get(workflow_id).await?;
+        let status = workflow.status.unwrap_or_default();
+        match status.state() {
+            State::Done => {}
+            State::Failed => {
+                log::warn!(
+                    "Workflow {} failed, dropping its follow-up workflows",
+                    workflow_id
+                );
+                let (failed, pending) = std::mem::take(&mut self.state.pending)
+                    .into_iter()
+                    .partition(|link| link.after == workflow_id);
+                self.state.pending = pending;
+                self.state.failed.extend::<Vec<_>>(failed);
+                self.save()?;
+                return Ok(Vec::new());
+            }
+            // Still running, a later event or poll will pick it up
+            _ => return Ok(Vec::new()),
+        }
+
+        let mut outputs = Vec::new();
+        if let Some(final_stage) = status.stages.last() {
+            for task_id in final_stage.task_ids.iter() {
+                let task = self.tasks.get(task_id).await?;
+                outputs.push(task.status.unwrap_or_default().output_contexts);
+            }
+        }
+
+        let mut submitted = Vec::new();
+        while let Some(index) = self
+            .state
+            .pending
+            .iter()
+            .position(|link| link.after == workflow_id)
+        {
+            let mut link = self.state.pending.remove(index);
+            if link.submitting {
+                log::warn!(
+                    "Submitting the follow-up workflow of {} was interrupted, it may have been \
+                     submitted already",
+                    workflow_id
+                );
+                self.state.failed.push(link);
+                self.save()?;
+                continue;
+            }
+            let next = link.next_msg().and_then(|mut next| {
+                if let Some(spec) = next.spec.as_mut() {
+                    apply_mappings(spec, &outputs, &link.mappings)?;
+                }
+                Ok(next)
+            });
+            let next = match next {
+                Ok(next) => next,
+                Err(e) => {
+                    log::error!(
+                        "Failed to prepare the follow-up workflow of {}: {}",
+                        workflow_id,
+                        e
+                    );
+                    self.state.failed.push(link);
+                    self.save()?;
+                    continue;
+                }
+            };
+
+            // Persist the marker first so a crash during creation doesn't submit twice
+            link.submitting = true;
+            self.state.pending.insert(index, link.clone());
+            self.save()?;
+            let created = self.workflows.create(next).await;
+            self.state.pending.remove(index);
+            match created {
+                Ok(response) => {
+                    log::info!(
+                        "Workflow {} finished, submitted workflow {}",
+                        workflow_id,
+                        response.id
+                    );
+                    self.state.submitted.push(SubmittedLink {
+                        after: link.after,
+                        workflow_id: response.id.clone(),
+                    });
+                    submitted.push(response.id);
+                }
+                Err(e) => {
+                    log::error!(
+                        "Failed to submit the follow-up workflow of {}: {}",
+                        workflow_id,
+                        e
+                    );
+                    self.state.failed.push(link);
+                }
+            }
+            self.save()?;
+        }
+        Ok(submitted)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+        let data = serde_json::to_vec_pretty(&self.state)
+            .map_err(|e| Error::EncodeError(e.to_string()))?;
+        // Write to a temporary file first so a crash never leaves a truncated state behind
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, data).map_err(|e| Error::Unknown(e.to_string()))?;
+        std::fs::rename(&tmp_path, path).map_err(|e| Error::Unknown(e.to_string()))
+    }
+}
+
+impl EventHandler for WorkflowChain {
+    async fn handle_event(
+        &mut self,
+        event: &crate::Event,
+        block_height: crate::Height,
+    ) -> Result<()> {
+        if let Ok(GevulotEvent::Workflow(WorkflowEvent::Finish(finish))) =
+            GevulotEvent::from_cosmos(event, block_height)
+        {
+            self.on_workflow_finished(&finish.workflow_id).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Adds the mapped outputs as input contexts to every task of the first stage.
+fn apply_mappings(
+    spec: &mut gevulot::WorkflowSpec,
+    outputs: &[Vec<String>],
+    mappings: &[OutputMapping],
+) -> Result<()> {
+    let mut inputs = Vec::with_capacity(mappings.len());
+    for mapping in mappings {
+        let source = outputs
+            .get(mapping.task)
+            .and_then(|task_outputs| task_outputs.get(mapping.output))
+            .ok_or_else(|| {
+                Error::Unknown(format!(
+                    "output {} of task {} in the final stage does not exist",
+                    mapping.output, mapping.task
+                ))
+            })?;
+        inputs.push(gevulot::InputContext {
+            source: source.clone(),
+            target: mapping.target.clone(),
+        });
+    }
+    if let Some(stage) = spec.stages.first_mut() {
+        for task in stage.tasks.iter_mut() {
+            task.input_contexts.extend(inputs.iter().cloned());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_mappings() {
+        let mut spec = gevulot::WorkflowSpec {
+            stages: vec![gevulot::workflow_spec::Stage {
+                tasks: vec![gevulot::TaskSpec::default(), gevulot::TaskSpec::default()],
+            }],
+        };
+        let outputs = vec![
+            vec!["cid-a".to_string()],
+            vec!["cid-b".to_string(), "cid-c".to_string()],
+        ];
+        let mappings = vec![
+            OutputMapping {
+                task: 1,
+                output: 1,
+                target: "/input/c".to_string(),
+            },
+            OutputMapping {
+                task: 0,
+                output: 0,
+                target: "/input/a".to_string(),
+            },
+        ];
+
+        apply_mappings(&mut spec, &outputs, &mappings).unwrap();
+        for task in spec.stages[0].tasks.iter() {
+            assert_eq!(
+                task.input_contexts,
+                vec![
+                    gevulot::InputContext {
+                        source: "cid-c".to_string(),
+                        target: "/input/c".to_string(),
+                    },
+                    gevulot::InputContext {
+                        source: "cid-a".to_string(),
+                        target: "/input/a".to_string(),
+                    },
+                ]
+            );
+        }
+
+        let missing = vec![OutputMapping {
+            task: 2,
+            output: 0,
+            target: "/input".to_string(),
+        }];
+        assert!(apply_mappings(&mut spec, &outputs, &missing).is_err());
+    }
+
+    #[test]
+    fn test_chain_link_round_trip() {
+        let msg = gevulot::MsgCreateWorkflow {
+            creator: "creator".to_string(),
+            spec: Some(gevulot::WorkflowSpec { stages: vec![] }),
+        };
+        let link = ChainLink {
+            after: "wf1".to_string(),
+            next: hex::encode(msg.encode_to_vec()),
+            mappings: vec![],
+            submitting: false,
+        };
+        let state = ChainState {
+            pending: vec![link],
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        let state: ChainState = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.pending[0].next_msg().unwrap(), msg);
+    }
+
+    #[test]
+    fn test_state_without_submitting_marker() {
+        let json =
+            r#"{"pending":[{"after":"wf1","next":"","mappings":[]}],"submitted":[],"failed":[]}"#;
+        let state: ChainState = serde_json::from_str(json).unwrap();
+        assert!(!state.pending[0].submitting);
+    }
+}
@@ -1,18 +1,96 @@
-use std::sync::Arc;
+use cosmos_sdk_proto::prost::Message;
+use futures::Stream;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::RwLock;
 
 use crate::{
     base_client::BaseClient,
     error::{Error, Result},
+    event_bus::{BusMessage, EventBus, EventSubscription},
+    events::{GevulotEvent, TaskEvent, WorkflowEvent},
+    models::{ResourcePrices, WorkflowPreview, WorkflowProgress, WorkflowSpec, WorkflowStatus},
+    pagination::{PageOptions, PageToken, DEFAULT_PAGE_LIMIT},
     proto::gevulot::gevulot::{
-        MsgCreateWorkflow, MsgCreateWorkflowResponse, MsgDeleteWorkflow, MsgDeleteWorkflowResponse,
+        task_status::State, MsgCreateWorkflow, MsgCreateWorkflowResponse, MsgDeleteWorkflow,
+        MsgDeleteWorkflowResponse,
     },
+    spec_defaults::SpecDefaults,
 };
 
+/// Default limit of the encoded size of a workflow creation message, the default maximum
+/// transaction size of CometBFT minus room for the signature, fee and memo.
+pub const DEFAULT_MAX_MSG_BYTES: usize = 1024 * 1024 - 16 * 1024;
+
+// Interval in which watched workflows are polled when no event arrives
+const WATCH_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A change of a workflow reported by [`WorkflowClient::watch`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorkflowUpdate {
+    /// The tasks of a stage were created.
+    StageStarted { stage: usize, task_ids: Vec<String> },
+    /// An event of a task of the workflow.
+    Task { stage: usize, event: TaskEvent },
+    /// All tasks of a stage finished.
+    StageFinished { stage: usize },
+    /// The workflow is done or failed.
+    WorkflowFinished { state: String },
+}
+
+impl WorkflowUpdate {
+    /// Returns the updates between two statuses of a workflow, in stage order.
+    ///
+    /// # Arguments
+    ///
+    /// * `previous` - The status seen before, None if the workflow wasn't seen yet.
+    /// * `current` - The current status.
+    pub fn between(previous: Option<&WorkflowStatus>, current: &WorkflowStatus) -> Vec<Self> {
+        let started = |status: &WorkflowStatus, stage: usize| {
+            status
+                .stages
+                .get(stage)
+                .is_some_and(|s| !s.task_ids.is_empty())
+        };
+        let finished = |status: &WorkflowStatus, stage: usize| {
+            status.stages.get(stage).is_some_and(|s| {
+                !s.task_ids.is_empty()
+                    && (s.finished_tasks >= s.task_ids.len() as u64
+                        || status.current_stage > stage as u64)
+            })
+        };
+        let mut updates = Vec::new();
+        for (stage, stage_status) in current.stages.iter().enumerate() {
+            if started(current, stage) && !previous.is_some_and(|p| started(p, stage)) {
+                updates.push(WorkflowUpdate::StageStarted {
+                    stage,
+                    task_ids: stage_status.task_ids.clone(),
+                });
+            }
+            if finished(current, stage) && !previous.is_some_and(|p| finished(p, stage)) {
+                updates.push(WorkflowUpdate::StageFinished { stage });
+            }
+        }
+        let done = |status: &WorkflowStatus| matches!(status.state.as_str(), "Done" | "Failed");
+        if done(current) && !previous.is_some_and(done) {
+            updates.push(WorkflowUpdate::WorkflowFinished {
+                state: current.state.clone(),
+            });
+        }
+        updates
+    }
+}
+
 /// Client for managing workflows in the Gevulot system.
 #[derive(Debug, Clone)]
 pub struct WorkflowClient {
     base_client: Arc<RwLock<BaseClient>>,
+    spec_defaults: Option<Arc<SpecDefaults>>,
+    max_msg_bytes: usize,
+    event_bus: Option<EventBus>,
 }
 
 impl WorkflowClient {
@@ -26,11 +104,48 @@ impl WorkflowClient {
     ///
     /// A new instance of WorkflowClient.
     pub fn new(base_client: Arc<RwLock<BaseClient>>) -> Self {
-        Self { base_client }
+        Self {
+            base_client,
+            spec_defaults: None,
+            max_msg_bytes: DEFAULT_MAX_MSG_BYTES,
+            event_bus: None,
+        }
+    }
+
+    /// Sets the limit of the encoded size of workflow creation messages.
+    ///
+    /// Should match the maximum transaction size of the chain, larger workflows are rejected
+    /// by [`WorkflowClient::create`] before any gas is spent on them.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_msg_bytes` - The limit in bytes, [`DEFAULT_MAX_MSG_BYTES`] by default.
+    pub fn set_max_msg_bytes(&mut self, max_msg_bytes: usize) {
+        self.max_msg_bytes = max_msg_bytes;
+    }
+
+    /// Sets the defaults merged into every workflow created by this client.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec_defaults` - The defaults, None stops applying defaults.
+    pub fn set_spec_defaults(&mut self, spec_defaults: Option<Arc<SpecDefaults>>) {
+        self.spec_defaults = spec_defaults;
+    }
+
+    /// Sets the event bus waking up [`WorkflowClient::watch`] on task and workflow events.
+    ///
+    /// # Arguments
+    ///
+    /// * `event_bus` - A bus registered with an event fetcher.
+    pub fn set_event_bus(&mut self, event_bus: EventBus) {
+        self.event_bus = Some(event_bus);
     }
 
     /// Lists all workflows.
     ///
+    /// Fetches the workflows page by page until all workflows were received.
+    ///
     /// # Returns
     ///
     /// A Result containing a vector of workflows or an error.
@@ -39,15 +154,55 @@ impl WorkflowClient {
     ///
     /// This function will return an error if the request to the Gevulot client fails.
     pub async fn list(&mut self) -> Result<Vec<crate::proto::gevulot::gevulot::Workflow>> {
-        let request = crate::proto::gevulot::gevulot::QueryAllWorkflowRequest { pagination: None };
+        let mut workflows = Vec::new();
+        let mut options = PageOptions::new().limit(DEFAULT_PAGE_LIMIT);
+        loop {
+            let (page, next) = self.list_paged(options.clone()).await?;
+            workflows.extend(page);
+            match next {
+                Some(token) => options.token = Some(token),
+                None => break,
+            }
+        }
+        Ok(workflows)
+    }
+
+    /// Lists a single page of workflows.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The size and position of the page.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the workflows of the page and the token of the next page, None if this
+    /// was the last page, or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_paged(
+        &mut self,
+        options: PageOptions,
+    ) -> Result<(
+        Vec<crate::proto::gevulot::gevulot::Workflow>,
+        Option<PageToken>,
+    )> {
+        let request = crate::proto::gevulot::gevulot::QueryAllWorkflowRequest {
+            pagination: Some(options.to_request()),
+        };
         let response = self
             .base_client
             .write()
             .await
             .gevulot_client
             .workflow_all(request)
-            .await?;
-        Ok(response.into_inner().workflow)
+            .await?
+            .into_inner();
+        Ok((
+            response.workflow,
+            PageToken::next(response.pagination.as_ref()),
+        ))
     }
 
     /// Gets a workflow by its ID.
@@ -72,7 +227,125 @@ impl WorkflowClient {
             .gevulot_client
             .workflow(request)
             .await?;
-        response.into_inner().workflow.ok_or(Error::NotFound)
+        response.into_inner().workflow.ok_or(Error::NotFound(None))
+    }
+
+    /// Lists all workflows as models.
+    ///
+    /// Like `list`, but converts the workflows into [`crate::models::Workflow`]s.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing a vector of workflows or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the request to the Gevulot client fails.
+    pub async fn list_models(&mut self) -> Result<Vec<crate::models::Workflow>> {
+        Ok(self.list().await?.into_iter().map(Into::into).collect())
+    }
+
+    /// Gets a workflow by its ID as a model.
+    ///
+    /// Like `get`, but converts the workflow into a [`crate::models::Workflow`] with parsed
+    /// units, status strings and metadata.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the workflow to retrieve.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the workflow or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the workflow is not found or if the request to the Gevulot client fails.
+    pub async fn get_model(&mut self, id: &str) -> Result<crate::models::Workflow> {
+        Ok(self.get(id).await?.into())
+    }
+
+    /// Summarizes the progress of a workflow.
+    ///
+    /// The workflow status only counts the finished tasks of each stage, so the tasks created
+    /// by the workflow are fetched to find the failed ones. Tasks which don't exist anymore are
+    /// not counted as failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the workflow.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the progress or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the workflow is not found or if a request to the Gevulot client fails.
+    pub async fn progress(&mut self, id: &str) -> Result<WorkflowProgress> {
+        let workflow = self.get_model(id).await?;
+        let task_ids = workflow
+            .status
+            .iter()
+            .flat_map(|status| status.stages.iter())
+            .flat_map(|stage| stage.task_ids.iter());
+
+        let mut failed = HashSet::new();
+        for task_id in task_ids {
+            let request = crate::proto::gevulot::gevulot::QueryGetTaskRequest {
+                id: task_id.clone(),
+            };
+            let response = self
+                .base_client
+                .write()
+                .await
+                .gevulot_client
+                .task(request)
+                .await;
+            let task = match response {
+                Ok(response) => response.into_inner().task,
+                Err(status) if status.code() == tonic::Code::NotFound => None,
+                Err(status) => return Err(status.into()),
+            };
+            let state = task
+                .and_then(|task| task.status)
+                .map(|status| status.state());
+            if state == Some(State::Failed) {
+                failed.insert(task_id.as_str());
+            }
+        }
+        Ok(WorkflowProgress::new(&workflow, &|id| failed.contains(id)))
+    }
+
+    /// Watches a workflow move through its stages.
+    ///
+    /// The workflow is re-checked whenever the event bus set with
+    /// [`WorkflowClient::set_event_bus`] reports an event of the workflow or one of its tasks,
+    /// and polled every few seconds otherwise. Task events are correlated to their stage through
+    /// the task IDs of the stage status, tasks of stages not started yet are unknown. The stream
+    /// ends after yielding [`WorkflowUpdate::WorkflowFinished`], or after the first error.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the workflow to watch.
+    ///
+    /// # Returns
+    ///
+    /// A stream of the updates of the workflow.
+    pub fn watch(&self, id: &str) -> impl Stream<Item = Result<WorkflowUpdate>> {
+        let watch = WorkflowWatch {
+            client: self.clone(),
+            workflow_id: id.to_string(),
+            // Subscribe before the first check so no event in between is lost
+            events: self.event_bus.as_ref().map(EventBus::subscribe),
+            last_status: None,
+            pending: VecDeque::new(),
+            finished: false,
+        };
+        futures::stream::unfold(watch, |mut watch| async move {
+            let update = watch.next_update().await?;
+            Some((update, watch))
+        })
     }
 
     /// Creates a new workflow.
@@ -87,8 +360,17 @@ impl WorkflowClient {
     ///
     /// # Errors
     ///
-    /// This function will return an error if the request to the Gevulot client fails.
-    pub async fn create(&mut self, msg: MsgCreateWorkflow) -> Result<MsgCreateWorkflowResponse> {
+    /// This function will return `Error::TooLarge` if the encoded message exceeds the limit set
+    /// with [`WorkflowClient::set_max_msg_bytes`], or an error if the request to the Gevulot
+    /// client fails.
+    pub async fn create(
+        &mut self,
+        mut msg: MsgCreateWorkflow,
+    ) -> Result<MsgCreateWorkflowResponse> {
+        if let Some(spec_defaults) = &self.spec_defaults {
+            spec_defaults.apply_to_workflow(&mut msg);
+        }
+        check_size(&msg, self.max_msg_bytes)?;
         let resp: MsgCreateWorkflowResponse = self
             .base_client
             .write()
@@ -120,4 +402,270 @@ impl WorkflowClient {
             .await?;
         Ok(resp)
     }
+
+    /// Expands the sub-workflows of a spec into a flat spec which can be created on chain.
+    ///
+    /// Sub-workflows referenced by ID are fetched from the chain and their specs reused, see
+    /// [`SubWorkflow`](crate::models::SubWorkflow) for how the stages are merged.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The workflow spec to expand.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the expanded spec or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a referenced workflow is not found, a resource
+    /// unit of a task is invalid or if the request to the Gevulot client fails.
+    pub async fn expand(
+        &mut self,
+        spec: WorkflowSpec,
+    ) -> Result<crate::proto::gevulot::gevulot::WorkflowSpec> {
+        let mut resolved = HashMap::new();
+        for id in spec.sub_workflow_ids() {
+            if !resolved.contains_key(id) {
+                let workflow = self.get(id).await?;
+                resolved.insert(id.to_string(), workflow.spec.unwrap_or_default());
+            }
+        }
+        spec.expand(&mut |id: &str| resolved.get(id).cloned().ok_or(Error::NotFound(None)))
+    }
+
+    /// Previews the tasks a workflow would create without submitting it.
+    ///
+    /// Expands every stage into its tasks with all resource units resolved and estimates the
+    /// cost of each task from the resource prices in the chain parameters.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The workflow spec to preview.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the preview or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a resource unit of a task is invalid or if the request to the Gevulot client fails.
+    pub async fn preview(&mut self, spec: &WorkflowSpec) -> Result<WorkflowPreview> {
+        let request = crate::proto::gevulot::gevulot::QueryParamsRequest {};
+        let response = self
+            .base_client
+            .write()
+            .await
+            .gevulot_client
+            .params(request)
+            .await?;
+        let prices = response
+            .into_inner()
+            .params
+            .as_ref()
+            .map(ResourcePrices::try_from)
+            .transpose()?;
+        WorkflowPreview::new(spec, prices.as_ref())
+    }
+}
+
+/// State of a workflow watched by [`WorkflowClient::watch`].
+struct WorkflowWatch {
+    client: WorkflowClient,
+    workflow_id: String,
+    events: Option<EventSubscription>,
+    last_status: Option<WorkflowStatus>,
+    pending: VecDeque<WorkflowUpdate>,
+    finished: bool,
+}
+
+impl WorkflowWatch {
+    /// Checks the workflow until there is an update, None once the workflow finished.
+    async fn next_update(&mut self) -> Option<Result<WorkflowUpdate>> {
+        loop {
+            if let Some(update) = self.pending.pop_front() {
+                return Some(Ok(update));
+            }
+            if self.finished {
+                return None;
+            }
+            if self.last_status.is_some() {
+                self.wait_for_change().await;
+                if !self.pending.is_empty() {
+                    continue;
+                }
+            }
+            let status = match self.client.get_model(&self.workflow_id).await {
+                Ok(workflow) => workflow.status.unwrap_or_else(|| WorkflowStatus {
+                    state: "Pending".to_string(),
+                    current_stage: 0,
+                    stages: Vec::new(),
+                }),
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            };
+            let updates = WorkflowUpdate::between(self.last_status.as_ref(), &status);
+            self.finished = updates
+                .iter()
+                .any(|update| matches!(update, WorkflowUpdate::WorkflowFinished { .. }));
+            self.pending.extend(updates);
+            self.last_status = Some(status);
+        }
+    }
+
+    // Returns the stage of a task of the workflow
+    fn stage_of(&self, task_id: &str) -> Option<usize> {
+        self.last_status
+            .as_ref()?
+            .stages
+            .iter()
+            .position(|stage| stage.task_ids.iter().any(|id| id == task_id))
+    }
+
+    /// Waits for an event of the workflow or its tasks, or the poll interval, whichever comes
+    /// first. Task events are queued as updates.
+    async fn wait_for_change(&mut self) {
+        let timer = self.client.base_client.read().await.timer();
+        let Some(mut events) = self.events.take() else {
+            timer.sleep(WATCH_POLL_INTERVAL).await;
+            return;
+        };
+        let relevant_event = async {
+            let lagged = events.lagged();
+            while let Some(message) = events.recv().await {
+                // Missed events might have been for this workflow
+                if events.lagged() != lagged {
+                    return true;
+                }
+                let BusMessage::Event {
+                    parsed: Some(event),
+                    ..
+                } = &*message
+                else {
+                    continue;
+                };
+                match event {
+                    GevulotEvent::Task(event) => {
+                        if let Some(stage) = self.stage_of(event.task_id()) {
+                            self.pending.push_back(WorkflowUpdate::Task {
+                                stage,
+                                event: event.clone(),
+                            });
+                            return true;
+                        }
+                    }
+                    GevulotEvent::Workflow(event) if workflow_id(event) == self.workflow_id => {
+                        return true;
+                    }
+                    _ => {}
+                }
+            }
+            false
+        };
+        let open = tokio::select! {
+            open = relevant_event => open,
+            _ = timer.sleep(WATCH_POLL_INTERVAL) => true,
+        };
+        if open {
+            self.events = Some(events);
+        }
+    }
+}
+
+fn workflow_id(event: &WorkflowEvent) -> &str {
+    match event {
+        WorkflowEvent::Create(event) => &event.workflow_id,
+        WorkflowEvent::Delete(event) => &event.workflow_id,
+        WorkflowEvent::Progress(event) => &event.workflow_id,
+        WorkflowEvent::Finish(event) => &event.workflow_id,
+    }
+}
+
+// Checks the size of a message wrapped into an Any as it is sent in a transaction
+fn check_size(msg: &MsgCreateWorkflow, limit: usize) -> Result<()> {
+    let size = cosmrs::Any::from_msg(msg)?.encoded_len();
+    if size > limit {
+        return Err(Error::TooLarge { size, limit });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::gevulot::gevulot::{workflow_spec::Stage, TaskSpec};
+
+    #[test]
+    fn test_check_size() {
+        let msg = MsgCreateWorkflow {
+            creator: "alice".to_string(),
+            spec: Some(crate::proto::gevulot::gevulot::WorkflowSpec {
+                stages: vec![Stage {
+                    tasks: vec![
+                        TaskSpec {
+                            image: "prover:latest".to_string(),
+                            ..Default::default()
+                        };
+                        100
+                    ],
+                }],
+            }),
+        };
+        assert!(check_size(&msg, DEFAULT_MAX_MSG_BYTES).is_ok());
+        let Err(Error::TooLarge { size, limit }) = check_size(&msg, 1000) else {
+            panic!("message should be too large");
+        };
+        assert!(size > 1000);
+        assert_eq!(limit, 1000);
+    }
+
+    #[test]
+    fn test_workflow_updates() {
+        use crate::models::WorkflowStageStatus;
+
+        let status = |state: &str, current_stage, stages: &[(usize, u64)]| WorkflowStatus {
+            state: state.to_string(),
+            current_stage,
+            stages: stages
+                .iter()
+                .enumerate()
+                .map(|(stage, &(tasks, finished_tasks))| WorkflowStageStatus {
+                    task_ids: (0..tasks).map(|i| format!("t{}-{}", stage, i)).collect(),
+                    finished_tasks,
+                })
+                .collect(),
+        };
+
+        let first = status("Running", 0, &[(2, 1)]);
+        assert_eq!(
+            WorkflowUpdate::between(None, &first),
+            vec![WorkflowUpdate::StageStarted {
+                stage: 0,
+                task_ids: vec!["t0-0".to_string(), "t0-1".to_string()],
+            }]
+        );
+        assert!(WorkflowUpdate::between(Some(&first), &first).is_empty());
+
+        let second = status("Running", 1, &[(2, 2), (1, 0)]);
+        let updates = WorkflowUpdate::between(Some(&first), &second);
+        assert_eq!(updates[0], WorkflowUpdate::StageFinished { stage: 0 });
+        assert!(matches!(
+            updates[1],
+            WorkflowUpdate::StageStarted { stage: 1, .. }
+        ));
+        assert_eq!(updates.len(), 2);
+
+        let done = status("Done", 1, &[(2, 2), (1, 1)]);
+        assert_eq!(
+            WorkflowUpdate::between(Some(&second), &done),
+            vec![
+                WorkflowUpdate::StageFinished { stage: 1 },
+                WorkflowUpdate::WorkflowFinished {
+                    state: "Done".to_string()
+                },
+            ]
+        );
+    }
 }
@@ -0,0 +1,394 @@
+//! Construction of workflows from task dependencies.
+//!
+//! Workflows on chain are a list of stages which run one after another. A [`WorkflowDag`]
+//! instead describes the tasks of a workflow by name, the outputs each task produces and the
+//! outputs of other tasks it consumes. [`WorkflowDag::into_spec`] sorts the tasks topologically
+//! and places every task in the first stage after all tasks it depends on, so independent tasks
+//! run in parallel.
+//!
+//! The chain can't refer to the output of a task which doesn't exist yet, so inputs consuming
+//! the output of another task get a source of the form `output://<task>/<output>`, see
+//! [`output_ref`], which is resolved to the CID of the output once the task finished.
+//!
+//! ```
+//! use gevulot_rs::models::TaskSpec;
+//! use gevulot_rs::workflow_dag::{DagTask, WorkflowDag};
+//!
+//! let spec = |image: &str| -> TaskSpec {
+//!     serde_json::from_value(serde_json::json!({
+//!         "image": image,
+//!         "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}
+//!     }))
+//!     .unwrap()
+//! };
+//! let workflow = WorkflowDag::new()
+//!     .task(DagTask::new("prove", spec("prover")).output("proof", "/output/proof.bin", 3600))
+//!     .task(DagTask::new("verify", spec("verifier")).input("prove", "proof", "/input/proof.bin"))
+//!     .into_spec()
+//!     .unwrap();
+//! assert_eq!(workflow.stages.len(), 2);
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::{Error, Result},
+    models::{InputContext, OutputContext, TaskSpec, WorkflowSpec, WorkflowStage},
+};
+
+/// Scheme of the input sources referring to outputs of other tasks of the workflow.
+pub const OUTPUT_REF_SCHEME: &str = "output://";
+
+/// Returns the input source referring to the named output of a task of the workflow.
+pub fn output_ref(task: &str, output: &str) -> String {
+    format!("{}{}/{}", OUTPUT_REF_SCHEME, task, output)
+}
+
+/// Problem found when sorting a [`WorkflowDag`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum DagError {
+    #[error("task {0} is declared more than once")]
+    DuplicateTask(String),
+    #[error("task {task} depends on unknown task {dependency}")]
+    UnknownTask { task: String, dependency: String },
+    #[error("task {task} consumes unknown output {output} of task {dependency}")]
+    UnknownOutput {
+        task: String,
+        dependency: String,
+        output: String,
+    },
+    #[error("tasks {} depend on each other", .0.join(", "))]
+    Cycle(Vec<String>),
+}
+
+/// An output a [`DagTask`] produces.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DagOutput {
+    /// Name other tasks refer to the output by.
+    pub name: String,
+    /// Path of the output in the task.
+    pub source: String,
+    /// How long to retain the output in seconds.
+    pub retention_period: i64,
+}
+
+/// An output of another task a [`DagTask`] consumes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DagInput {
+    /// Name of the producing task.
+    pub task: String,
+    /// Name of the output of the producing task.
+    pub output: String,
+    /// Path the output is mounted at in the consuming task.
+    pub target: String,
+}
+
+/// A named task of a [`WorkflowDag`].
+#[derive(Debug)]
+pub struct DagTask {
+    pub name: String,
+    pub spec: TaskSpec,
+    pub outputs: Vec<DagOutput>,
+    pub inputs: Vec<DagInput>,
+    /// Names of tasks which have to finish first without passing any output.
+    pub after: Vec<String>,
+}
+
+impl DagTask {
+    /// Creates a task without outputs and dependencies.
+    pub fn new(name: impl Into<String>, spec: TaskSpec) -> Self {
+        Self {
+            name: name.into(),
+            spec,
+            outputs: Vec::new(),
+            inputs: Vec::new(),
+            after: Vec::new(),
+        }
+    }
+
+    /// Declares a named output, added to the output contexts of the task.
+    pub fn output(
+        mut self,
+        name: impl Into<String>,
+        source: impl Into<String>,
+        retention_period: i64,
+    ) -> Self {
+        self.outputs.push(DagOutput {
+            name: name.into(),
+            source: source.into(),
+            retention_period,
+        });
+        self
+    }
+
+    /// Consumes the named output of another task, mounted at the target path.
+    pub fn input(
+        mut self,
+        task: impl Into<String>,
+        output: impl Into<String>,
+        target: impl Into<String>,
+    ) -> Self {
+        self.inputs.push(DagInput {
+            task: task.into(),
+            output: output.into(),
+            target: target.into(),
+        });
+        self
+    }
+
+    /// Runs the task only after another task finished.
+    pub fn after(mut self, task: impl Into<String>) -> Self {
+        self.after.push(task.into());
+        self
+    }
+
+    // Converts the task into its spec with the outputs and inputs added
+    fn into_spec(self) -> TaskSpec {
+        let mut spec = self.spec;
+        spec.output_contexts
+            .extend(self.outputs.into_iter().map(|output| OutputContext {
+                source: output.source,
+                retention_period: output.retention_period,
+            }));
+        spec.input_contexts
+            .extend(self.inputs.into_iter().map(|input| InputContext {
+                source: output_ref(&input.task, &input.output),
+                target: input.target,
+            }));
+        spec
+    }
+
+    // Names of all tasks this task depends on
+    fn dependencies(&self) -> impl Iterator<Item = &str> {
+        self.inputs
+            .iter()
+            .map(|input| input.task.as_str())
+            .chain(self.after.iter().map(String::as_str))
+    }
+}
+
+/// Tasks of a workflow connected by their outputs.
+#[derive(Debug, Default)]
+pub struct WorkflowDag {
+    tasks: Vec<DagTask>,
+}
+
+impl WorkflowDag {
+    /// Creates an empty DAG.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a task.
+    pub fn task(mut self, task: DagTask) -> Self {
+        self.tasks.push(task);
+        self
+    }
+
+    /// Returns the task names stage by stage, in the order the tasks were added.
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::Dag` if a task is declared twice, depends on an unknown
+    /// task or output, or the dependencies form a cycle.
+    pub fn stages(&self) -> Result<Vec<Vec<&str>>> {
+        self.check()?;
+        let mut stage_of: HashMap<&str, usize> = HashMap::new();
+        let mut stages: Vec<Vec<&str>> = Vec::new();
+        while stage_of.len() < self.tasks.len() {
+            let ready: Vec<_> = self
+                .tasks
+                .iter()
+                .filter(|task| !stage_of.contains_key(task.name.as_str()))
+                .filter(|task| task.dependencies().all(|dep| stage_of.contains_key(dep)))
+                .map(|task| task.name.as_str())
+                .collect();
+            if ready.is_empty() {
+                let cycle = self
+                    .tasks
+                    .iter()
+                    .map(|task| task.name.clone())
+                    .filter(|name| !stage_of.contains_key(name.as_str()))
+                    .collect();
+                return Err(Error::Dag(DagError::Cycle(cycle)));
+            }
+            for name in &ready {
+                stage_of.insert(*name, stages.len());
+            }
+            stages.push(ready);
+        }
+        Ok(stages)
+    }
+
+    /// Sorts the tasks into stages and converts them into a workflow spec.
+    ///
+    /// The outputs of every task are appended to its output contexts and the consumed outputs
+    /// to its input contexts, with sources referring to the producing task, see [`output_ref`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return `Error::Dag` if the tasks can't be sorted, see
+    /// [`WorkflowDag::stages`].
+    pub fn into_spec(self) -> Result<WorkflowSpec> {
+        let stages: Vec<Vec<String>> = self
+            .stages()?
+            .into_iter()
+            .map(|stage| stage.into_iter().map(str::to_string).collect())
+            .collect();
+        let mut tasks: HashMap<String, DagTask> = self
+            .tasks
+            .into_iter()
+            .map(|task| (task.name.clone(), task))
+            .collect();
+
+        let stages = stages
+            .into_iter()
+            .map(|names| WorkflowStage {
+                tasks: names
+                    .iter()
+                    .filter_map(|name| tasks.remove(name))
+                    .map(DagTask::into_spec)
+                    .collect(),
+                workflows: Vec::new(),
+                retry: None,
+            })
+            .collect();
+        Ok(WorkflowSpec {
+            stages,
+            retry: None,
+        })
+    }
+
+    // Checks that names are unique and all dependencies exist
+    fn check(&self) -> Result<()> {
+        let mut outputs: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for task in &self.tasks {
+            let names = task.outputs.iter().map(|output| output.name.as_str());
+            if outputs
+                .insert(task.name.as_str(), names.collect())
+                .is_some()
+            {
+                return Err(Error::Dag(DagError::DuplicateTask(task.name.clone())));
+            }
+        }
+        for task in &self.tasks {
+            for dependency in task.dependencies() {
+                if !outputs.contains_key(dependency) {
+                    return Err(Error::Dag(DagError::UnknownTask {
+                        task: task.name.clone(),
+                        dependency: dependency.to_string(),
+                    }));
+                }
+            }
+            for input in &task.inputs {
+                if !outputs[input.task.as_str()].contains(input.output.as_str()) {
+                    return Err(Error::Dag(DagError::UnknownOutput {
+                        task: task.name.clone(),
+                        dependency: input.task.clone(),
+                        output: input.output.clone(),
+                    }));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(image: &str) -> TaskSpec {
+        serde_json::from_value(serde_json::json!({
+            "image": image,
+            "resources": {"cpus": 1, "gpus": 0, "memory": 512, "time": 60}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_into_spec() {
+        // fetch -> prove-a, prove-b -> aggregate, notify runs after aggregate
+        let workflow = WorkflowDag::new()
+            .task(
+                DagTask::new("aggregate", spec("aggregator"))
+                    .input("prove-a", "proof", "/input/a")
+                    .input("prove-b", "proof", "/input/b")
+                    .output("proof", "/output/proof", 3600),
+            )
+            .task(
+                DagTask::new("prove-a", spec("prover"))
+                    .input("fetch", "block", "/input/block")
+                    .output("proof", "/output/proof", 3600),
+            )
+            .task(DagTask::new("fetch", spec("fetcher")).output("block", "/output/block", 600))
+            .task(
+                DagTask::new("prove-b", spec("prover"))
+                    .input("fetch", "block", "/input/block")
+                    .output("proof", "/output/proof", 3600),
+            )
+            .task(DagTask::new("notify", spec("notifier")).after("aggregate"));
+
+        assert_eq!(
+            workflow.stages().unwrap(),
+            vec![
+                vec!["fetch"],
+                vec!["prove-a", "prove-b"],
+                vec!["aggregate"],
+                vec!["notify"],
+            ]
+        );
+
+        let spec = workflow.into_spec().unwrap();
+        assert_eq!(spec.stages.len(), 4);
+        assert_eq!(spec.stages[0].tasks[0].image, "fetcher");
+        assert_eq!(
+            spec.stages[0].tasks[0].output_contexts[0].source,
+            "/output/block"
+        );
+        let aggregate = &spec.stages[2].tasks[0];
+        assert_eq!(aggregate.input_contexts.len(), 2);
+        assert_eq!(aggregate.input_contexts[0].source, "output://prove-a/proof");
+        assert_eq!(aggregate.input_contexts[1].target, "/input/b");
+        assert!(spec.stages[3].tasks[0].input_contexts.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_dag() {
+        let err = WorkflowDag::new()
+            .task(DagTask::new("a", spec("a")).input("b", "out", "/in"))
+            .task(
+                DagTask::new("b", spec("b"))
+                    .input("a", "out", "/in")
+                    .output("out", "/o", 1),
+            )
+            .task(DagTask::new("c", spec("c")))
+            .into_spec()
+            .unwrap_err();
+        assert!(matches!(err, Error::Dag(DagError::UnknownOutput { .. })));
+
+        let err = WorkflowDag::new()
+            .task(DagTask::new("a", spec("a")).after("b"))
+            .task(DagTask::new("b", spec("b")).after("a"))
+            .task(DagTask::new("c", spec("c")))
+            .stages()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid workflow dag: tasks a, b depend on each other"
+        );
+
+        let err = WorkflowDag::new()
+            .task(DagTask::new("a", spec("a")))
+            .task(DagTask::new("a", spec("a")))
+            .stages()
+            .unwrap_err();
+        assert!(matches!(err, Error::Dag(DagError::DuplicateTask(name)) if name == "a"));
+
+        let err = WorkflowDag::new()
+            .task(DagTask::new("a", spec("a")).after("missing"))
+            .stages()
+            .unwrap_err();
+        assert!(matches!(err, Error::Dag(DagError::UnknownTask { .. })));
+    }
+}
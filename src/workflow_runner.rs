@@ -0,0 +1,215 @@
+//! Client-side retries of failed workflow tasks.
+//!
+//! The chain doesn't retry the tasks of a workflow. A [`WorkflowRunner`] polls the progress of
+//! a workflow and reschedules failed tasks with `MsgRescheduleTask` according to the
+//! [`RetryPolicy`](crate::models::RetryPolicy) of their stage, see
+//! [`WorkflowSpec::retry_policy`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use tokio::{sync::RwLock, time::Instant};
+
+use crate::{
+    base_client::BaseClient,
+    error::{Error, Result},
+    gevulot_client::GevulotClient,
+    models::{WorkflowProgress, WorkflowSpec},
+    proto::gevulot::gevulot::MsgRescheduleTask,
+    task_client::TaskClient,
+    workflow_client::WorkflowClient,
+};
+
+/// Default interval in which the progress of a workflow is checked.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Result of a workflow run by a [`WorkflowRunner`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkflowRun {
+    /// The progress of the workflow once it was done or failed.
+    pub progress: WorkflowProgress,
+    /// Number of times each rescheduled task was retried.
+    pub retries: BTreeMap<String, u32>,
+}
+
+/// Reschedules failed tasks of workflows according to the retry policies of their specs.
+#[derive(Debug)]
+pub struct WorkflowRunner {
+    base_client: Arc<RwLock<BaseClient>>,
+    workflows: WorkflowClient,
+    tasks: TaskClient,
+    poll_interval: Duration,
+}
+
+impl WorkflowRunner {
+    /// Creates a new runner.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client used to query workflows and reschedule tasks.
+    ///
+    /// # Returns
+    ///
+    /// A new instance of WorkflowRunner.
+    pub fn new(client: &GevulotClient) -> Self {
+        Self {
+            base_client: client.base_client.clone(),
+            workflows: client.workflows.clone(),
+            tasks: client.tasks.clone(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+        }
+    }
+
+    /// Sets the interval in which the progress of a workflow is checked.
+    ///
+    /// # Arguments
+    ///
+    /// * `poll_interval` - The interval, [`DEFAULT_POLL_INTERVAL`] by default.
+    pub fn set_poll_interval(&mut self, poll_interval: Duration) {
+        self.poll_interval = poll_interval;
+    }
+
+    /// Runs a workflow until it is done or failed, rescheduling failed tasks.
+    ///
+    /// Tasks are only retried while the workflow is running. A failed task is rescheduled once
+    /// the backoff of its retry policy passed, tasks of stages without a policy or which used
+    /// up their retries are left failed.
+    ///
+    /// # Arguments
+    ///
+    /// * `workflow_id` - The ID of the workflow.
+    /// * `spec` - The spec the workflow was created from, holding the retry policies.
+    ///
+    /// # Returns
+    ///
+    /// A Result containing the final progress and the retries of the workflow or an error.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no signer is set, if a backoff is invalid or if a
+    /// request to the Gevulot client fails.
+    pub async fn run(&mut self, workflow_id: &str, spec: &WorkflowSpec) -> Result<WorkflowRun> {
+        let (creator, timer) = {
+            let base_client = self.base_client.read().await;
+            let creator = base_client
+                .address
+                .clone()
+                .ok_or_else(|| Error::Unknown("no signer set".to_string()))?;
+            (creator, base_client.timer())
+        };
+
+        let mut retries = BTreeMap::new();
+        let mut scheduled = HashMap::new();
+        loop {
+            let progress = self.workflows.progress(workflow_id).await?;
+            if progress.state == "Done" || progress.state == "Failed" {
+                return Ok(WorkflowRun { progress, retries });
+            }
+
+            let due = due_retries(&progress, spec, &retries, &mut scheduled, timer.now())?;
+            for task_id in due {
+                log::info!(
+                    "Rescheduling failed task {} of workflow {}",
+                    task_id,
+                    workflow_id
+                );
+                self.tasks
+                    .reschedule(MsgRescheduleTask {
+                        creator: creator.clone(),
+                        id: task_id.clone(),
+                    })
+                    .await?;
+                *retries.entry(task_id).or_insert(0) += 1;
+            }
+            timer.sleep(self.poll_interval).await;
+        }
+    }
+}
+
+// Returns the failed tasks whose backoff passed, the retry time of the other failed tasks with
+// retries left is recorded in scheduled
+fn due_retries(
+    progress: &WorkflowProgress,
+    spec: &WorkflowSpec,
+    retries: &BTreeMap<String, u32>,
+    scheduled: &mut HashMap<String, Instant>,
+    now: Instant,
+) -> Result<Vec<String>> {
+    let mut due = Vec::new();
+    for (index, stage) in progress.stages.iter().enumerate() {
+        let Some(policy) = spec.retry_policy(index) else {
+            continue;
+        };
+        for task_id in &stage.failed_task_ids {
+            let retry = retries.get(task_id).copied().unwrap_or(0);
+            if retry >= policy.max_retries {
+                continue;
+            }
+            let at = match scheduled.get(task_id) {
+                Some(at) => *at,
+                None => {
+                    let at = now + policy.delay(retry)?;
+                    scheduled.insert(task_id.clone(), at);
+                    at
+                }
+            };
+            if at <= now {
+                scheduled.remove(task_id);
+                due.push(task_id.clone());
+            }
+        }
+    }
+    Ok(due)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::StageProgress;
+
+    #[test]
+    fn test_due_retries() {
+        let spec = serde_json::from_value::<WorkflowSpec>(serde_json::json!({
+            "stages": [
+                {"tasks": [], "retry": {"maxRetries": 2, "backoff": "10s"}},
+                {"tasks": []}
+            ]
+        }))
+        .unwrap();
+        let stage = |failed: &[&str]| StageProgress {
+            total_tasks: 2,
+            finished_tasks: failed.len() as u64,
+            percent: 0.0,
+            failed_task_ids: failed.iter().map(|id| id.to_string()).collect(),
+        };
+        let progress = WorkflowProgress {
+            state: "Running".to_string(),
+            current_stage: 0,
+            stages: vec![stage(&["t1", "t2"]), stage(&["t3"])],
+            percent: 0.0,
+            remaining_stages: 2,
+        };
+
+        let start = Instant::now();
+        let mut retries = BTreeMap::from([("t2".to_string(), 2)]);
+        let mut scheduled = HashMap::new();
+        let due = due_retries(&progress, &spec, &retries, &mut scheduled, start).unwrap();
+        // t2 used up its retries, the second stage has no policy
+        assert!(due.is_empty());
+        assert_eq!(scheduled.len(), 1);
+
+        let later = start + Duration::from_secs(10);
+        let due = due_retries(&progress, &spec, &retries, &mut scheduled, later).unwrap();
+        assert_eq!(due, vec!["t1"]);
+        assert!(scheduled.is_empty());
+
+        // The second retry waits twice as long
+        retries.insert("t1".to_string(), 1);
+        let due = due_retries(&progress, &spec, &retries, &mut scheduled, later).unwrap();
+        assert!(due.is_empty());
+        assert_eq!(scheduled["t1"], later + Duration::from_secs(20));
+    }
+}